@@ -461,6 +461,8 @@ pub(crate) async fn expand_schema(
             .await
         }
         Format::Parquet(_) => Ok(schema),
+        Format::Csv(_) => Ok(schema),
+        Format::ArrowIpc(_) => Ok(schema),
         Format::RawString(_) => Ok(schema),
         Format::RawBytes(_) => Ok(schema),
         Format::Protobuf(_) => {
@@ -501,7 +503,17 @@ async fn expand_avro_schema(
                     )));
                 }
 
-                schema.definition = Some(SchemaDefinition::AvroSchema(schema_response.schema));
+                match &schema.definition {
+                    // a schema provided with the table is used as the reader schema, so it must
+                    // be able to read data written with the registry's latest schema
+                    Some(SchemaDefinition::AvroSchema(reader_schema)) => {
+                        check_avro_compatibility(&schema_response.schema, reader_schema)?;
+                    }
+                    _ => {
+                        schema.definition =
+                            Some(SchemaDefinition::AvroSchema(schema_response.schema));
+                    }
+                }
             }
             ConnectionType::Sink => {
                 // don't fetch schemas for sinks for now
@@ -540,6 +552,24 @@ async fn expand_avro_schema(
     Ok(schema)
 }
 
+fn check_avro_compatibility(writer_schema: &str, reader_schema: &str) -> Result<(), ErrorResp> {
+    let writer_schema = apache_avro::Schema::parse_str(writer_schema).map_err(|e| {
+        bad_request(format!(
+            "Avro schema from the schema registry is invalid: {:?}",
+            e
+        ))
+    })?;
+    let reader_schema = apache_avro::Schema::parse_str(reader_schema)
+        .map_err(|e| bad_request(format!("Avro schema is invalid: {:?}", e)))?;
+
+    avro::resolution::check_compatibility(&writer_schema, &reader_schema).map_err(|e| {
+        bad_request(format!(
+            "Avro schema is not compatible with the latest schema in the schema registry: {}",
+            e
+        ))
+    })
+}
+
 async fn expand_proto_schema(
     connector: &str,
     connection_type: ConnectionType,
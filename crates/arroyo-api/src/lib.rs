@@ -293,12 +293,19 @@ impl IntoResponse for HttpError {
         AvroFormat,
         ProtobufFormat,
         ParquetFormat,
+        CsvFormat,
+        ArrowIpcFormat,
         RawStringFormat,
         RawBytesFormat,
         TimestampFormat,
+        CdcEnvelope,
         Framing,
         FramingMethod,
         NewlineDelimitedFraming,
+        OctetCountingFraming,
+        LengthPrefixedFraming,
+        DelimitedFraming,
+        Endianness,
         PaginationQueryParams,
         CheckpointEventSpan,
         CheckpointSpanType,
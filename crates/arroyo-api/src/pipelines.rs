@@ -310,6 +310,12 @@ pub(crate) async fn create_pipeline_int<'a>(
     let mut compiled =
         compile_sql(query.clone(), &udfs, parallelism as usize, &auth, false, db).await?;
 
+    if compiled.explain.is_some() {
+        return Err(bad_request(
+            "EXPLAIN queries can't be run; use /v1/pipelines/validate_query to see their plan",
+        ));
+    }
+
     if compiled.program.graph.node_count() > auth.org_metadata.max_operators as usize {
         return Err(bad_request(
             format!("This pipeline is too large to create under your plan, which only allows pipelines up to {} nodes;
@@ -526,13 +532,17 @@ pub async fn validate_query(
     )
     .await
     {
-        Ok(CompiledSql { program, .. }) => QueryValidationResult {
+        Ok(CompiledSql {
+            program, explain, ..
+        }) => QueryValidationResult {
             graph: Some(program.try_into().map_err(log_and_map)?),
             errors: vec![],
+            explain,
         },
         Err(e) => QueryValidationResult {
             graph: None,
             errors: vec![e.message],
+            explain: None,
         },
     };
 
@@ -0,0 +1,235 @@
+mod operator;
+#[cfg(test)]
+mod test;
+
+use anyhow::{anyhow, bail};
+use arroyo_operator::connector::{Connection, Connector};
+use arroyo_operator::operator::OperatorNode;
+use arroyo_rpc::api_types::connections::{
+    ConnectionProfile, ConnectionSchema, ConnectionType, TestSourceMessage,
+};
+use arroyo_rpc::df::ArroyoSchema;
+use arroyo_rpc::OperatorConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use typify::import_types;
+
+use crate::datagen::operator::{DatagenSourceFunc, RowGenerator};
+use crate::{pull_opt, pull_option_to_i64, EmptyConfig};
+
+const TABLE_SCHEMA: &str = include_str!("./table.json");
+const ICON: &str = include_str!("./datagen.svg");
+
+import_types!(schema = "src/datagen/table.json");
+
+pub struct DatagenConnector {}
+
+impl DatagenConnector {
+    /// Parses per-field generator options, which are of the form `fields.<field>.<property>`
+    fn fields_from_options(
+        options: &mut HashMap<String, String>,
+    ) -> anyhow::Result<HashMap<String, FieldGenerator>> {
+        fn parse<T: FromStr>(key: &str, value: &str) -> anyhow::Result<T> {
+            value
+                .parse()
+                .map_err(|_| anyhow!("invalid value '{}' for option '{}'", value, key))
+        }
+
+        let keys: Vec<_> = options
+            .keys()
+            .filter(|k| k.starts_with("fields."))
+            .cloned()
+            .collect();
+
+        let mut fields: HashMap<String, FieldGenerator> = HashMap::new();
+        for key in keys {
+            let value = options.remove(&key).unwrap();
+            let Some((field, property)) = key["fields.".len()..].rsplit_once('.') else {
+                bail!(
+                    "invalid option '{}'; expected 'fields.<field>.<property>'",
+                    key
+                );
+            };
+
+            let generator = fields
+                .entry(field.to_string())
+                .or_insert_with(|| FieldGenerator {
+                    kind: None,
+                    min: None,
+                    max: None,
+                    start: None,
+                    end: None,
+                    length: None,
+                    values: vec![],
+                    null_ratio: None,
+                });
+
+            match property {
+                "kind" => {
+                    generator.kind = Some(match value.as_str() {
+                        "random" => GeneratorKind::Random,
+                        "sequence" => GeneratorKind::Sequence,
+                        "enum" => GeneratorKind::Enum,
+                        other => bail!(
+                            "invalid value for {} '{}'; expected one of 'random', 'sequence', or 'enum'",
+                            key,
+                            other
+                        ),
+                    })
+                }
+                "min" => generator.min = Some(parse(&key, &value)?),
+                "max" => generator.max = Some(parse(&key, &value)?),
+                "start" => generator.start = Some(parse(&key, &value)?),
+                "end" => generator.end = Some(parse(&key, &value)?),
+                "length" => generator.length = Some(parse(&key, &value)?),
+                "values" => generator.values = value.split(',').map(|v| v.to_string()).collect(),
+                "null_ratio" => generator.null_ratio = Some(parse(&key, &value)?),
+                other => bail!("unknown property '{}' in option '{}'", other, key),
+            }
+        }
+
+        Ok(fields)
+    }
+}
+
+impl Connector for DatagenConnector {
+    type ProfileT = EmptyConfig;
+    type TableT = DatagenTable;
+
+    fn name(&self) -> &'static str {
+        "datagen"
+    }
+
+    fn metadata(&self) -> arroyo_rpc::api_types::connections::Connector {
+        arroyo_rpc::api_types::connections::Connector {
+            id: "datagen".to_string(),
+            name: "Datagen".to_string(),
+            icon: ICON.to_string(),
+            description: "Generates synthetic data matching a schema".to_string(),
+            enabled: true,
+            source: true,
+            sink: false,
+            testing: false,
+            hidden: false,
+            custom_schemas: true,
+            connection_config: None,
+            table_config: TABLE_SCHEMA.to_string(),
+        }
+    }
+
+    fn table_type(&self, _: Self::ProfileT, _: Self::TableT) -> ConnectionType {
+        ConnectionType::Source
+    }
+
+    fn test(
+        &self,
+        _: &str,
+        _: Self::ProfileT,
+        _: Self::TableT,
+        _: Option<&ConnectionSchema>,
+        tx: tokio::sync::mpsc::Sender<TestSourceMessage>,
+    ) {
+        tokio::task::spawn(async move {
+            let message = TestSourceMessage {
+                error: false,
+                done: true,
+                message: "Successfully validated connection".to_string(),
+            };
+            tx.send(message).await.unwrap();
+        });
+    }
+
+    fn from_options(
+        &self,
+        name: &str,
+        options: &mut HashMap<String, String>,
+        schema: Option<&ConnectionSchema>,
+        _profile: Option<&ConnectionProfile>,
+    ) -> anyhow::Result<Connection> {
+        let event_rate = f64::from_str(&pull_opt("event_rate", options)?)
+            .map_err(|_| anyhow!("invalid value for event_rate; expected float"))?;
+
+        let message_count = pull_option_to_i64("message_count", options)?;
+        let seed = pull_option_to_i64("seed", options)?;
+        let event_time_interval = pull_option_to_i64("event_time_interval", options)?;
+        let max_out_of_orderness = pull_option_to_i64("max_out_of_orderness", options)?;
+        let fields = Self::fields_from_options(options)?;
+
+        self.from_config(
+            None,
+            name,
+            EmptyConfig {},
+            DatagenTable {
+                event_rate,
+                message_count,
+                seed,
+                event_time_interval,
+                max_out_of_orderness,
+                fields,
+            },
+            schema,
+        )
+    }
+
+    fn from_config(
+        &self,
+        id: Option<i64>,
+        name: &str,
+        config: Self::ProfileT,
+        table: Self::TableT,
+        schema: Option<&ConnectionSchema>,
+    ) -> anyhow::Result<Connection> {
+        let schema = schema
+            .map(|s| s.to_owned())
+            .ok_or_else(|| anyhow!("No schema defined for datagen source"))?;
+
+        if schema.fields.is_empty() {
+            bail!("datagen source requires a schema with at least one field");
+        }
+
+        // validate the generators against the schema
+        RowGenerator::new(&ArroyoSchema::from(schema.clone()), &table)?;
+
+        let description = format!(
+            "{}Datagen<{} eps>",
+            if table.message_count.is_some() {
+                "Bounded"
+            } else {
+                ""
+            },
+            table.event_rate
+        );
+
+        let config = OperatorConfig {
+            connection: serde_json::to_value(config).unwrap(),
+            table: serde_json::to_value(table).unwrap(),
+            rate_limit: None,
+            format: None,
+            bad_data: None,
+            framing: None,
+            metadata_fields: vec![],
+        };
+
+        Ok(Connection {
+            id,
+            connector: self.name(),
+            name: name.to_string(),
+            connection_type: ConnectionType::Source,
+            schema,
+            config: serde_json::to_string(&config).unwrap(),
+            description,
+        })
+    }
+
+    fn make_operator(
+        &self,
+        _: Self::ProfileT,
+        table: Self::TableT,
+        _: OperatorConfig,
+    ) -> anyhow::Result<OperatorNode> {
+        Ok(OperatorNode::from_source(Box::new(DatagenSourceFunc::new(
+            table,
+        ))))
+    }
+}
@@ -0,0 +1,651 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use anyhow::bail;
+use arrow::array::builder::{
+    ArrayBuilder, BinaryBuilder, BooleanBuilder, Float32Builder, Float64Builder, Int32Builder,
+    Int64Builder, StringBuilder, TimestampMicrosecondBuilder, TimestampMillisecondBuilder,
+    TimestampNanosecondBuilder, TimestampSecondBuilder, UInt32Builder, UInt64Builder,
+};
+use arrow::array::RecordBatch;
+use arrow::datatypes::{DataType, Field, SchemaRef, TimeUnit};
+use arroyo_operator::context::ArrowContext;
+use arroyo_operator::operator::SourceOperator;
+use arroyo_operator::SourceFinishType;
+use arroyo_rpc::df::ArroyoSchema;
+use arroyo_rpc::grpc::rpc::{StopMode, TableConfig};
+use arroyo_rpc::{ControlMessage, TIMESTAMP_FIELD};
+use arroyo_types::{to_nanos, ArroyoExtensionType};
+use async_trait::async_trait;
+use bincode::{Decode, Encode};
+use rand::distributions::Alphanumeric;
+use rand::{Rng, RngCore};
+use tracing::{debug, info};
+
+use super::{DatagenTable, FieldGenerator, GeneratorKind};
+
+const DEFAULT_LENGTH: usize = 10;
+
+#[derive(Encode, Decode, Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DatagenSourceState {
+    pub counter: u64,
+    pub start_time: SystemTime,
+}
+
+/// SplitMix64, seeded from the table seed and the global index of a row. Every row gets its own
+/// stream so that it can be generated without knowing the rows before it; subtask `i` of `n`
+/// generates the rows with indices `i`, `i + n`, `i + 2n`, and so on. A pipeline with the same
+/// seed and parallelism generates the same values, including after a restore, but a restore with
+/// a different parallelism maps the subtasks' counters to different indices, so rows may be
+/// repeated or skipped. Event times follow the wall clock unless `event_time_interval` is set.
+pub(crate) struct RowRng(u64);
+
+impl RowRng {
+    pub fn new(seed: u64, index: u64) -> Self {
+        let mut rng = Self(seed ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        // mix the initial state so that neighboring rows don't produce correlated values
+        rng.next_u64();
+        rng
+    }
+}
+
+impl RngCore for RowRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+enum Generator {
+    EventTime,
+    Sequence { start: i64, len: Option<u64> },
+    Int { min: i64, max: i64 },
+    UInt { min: u64, max: u64 },
+    Float { min: f64, max: f64 },
+    Bool,
+    Text { length: usize },
+    Json { length: usize },
+    Bytes { length: usize },
+    Values(Vec<String>),
+}
+
+enum Value {
+    Null,
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    Bytes(Vec<u8>),
+    Time(SystemTime),
+}
+
+impl Value {
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            Value::UInt(u) => Some(*u as i64),
+            _ => None,
+        }
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Int(i) => Some(*i as u64),
+            Value::UInt(u) => Some(*u),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    fn as_nanos(&self) -> Option<i64> {
+        match self {
+            Value::Time(t) => Some(to_nanos(*t) as i64),
+            _ => None,
+        }
+    }
+}
+
+struct ColumnGenerator {
+    index: usize,
+    data_type: DataType,
+    generator: Generator,
+    null_ratio: f64,
+}
+
+impl ColumnGenerator {
+    fn new(index: usize, field: &Field, spec: Option<&FieldGenerator>) -> anyhow::Result<Self> {
+        let name = field.name();
+        let data_type = field.data_type();
+
+        let null_ratio = spec.and_then(|s| s.null_ratio).unwrap_or(0.0);
+        if !(0.0..=1.0).contains(&null_ratio) {
+            bail!(
+                "null_ratio for field '{}' must be between 0 and 1, but is {}",
+                name,
+                null_ratio
+            );
+        }
+        if null_ratio > 0.0 && !field.is_nullable() {
+            bail!(
+                "field '{}' has a null_ratio configured, but is NOT NULL",
+                name
+            );
+        }
+
+        let length = match spec.and_then(|s| s.length) {
+            Some(length) if length < 0 => {
+                bail!("length for field '{}' must not be negative", name);
+            }
+            Some(length) => length as usize,
+            None => DEFAULT_LENGTH,
+        };
+
+        let kind = match spec.and_then(|s| s.kind.as_ref()) {
+            Some(kind) => kind,
+            None if spec.is_some_and(|s| !s.values.is_empty()) => &GeneratorKind::Enum,
+            None => &GeneratorKind::Random,
+        };
+
+        let generator = match kind {
+            GeneratorKind::Sequence => {
+                if !matches!(
+                    data_type,
+                    DataType::Int32
+                        | DataType::Int64
+                        | DataType::UInt32
+                        | DataType::UInt64
+                        | DataType::Utf8
+                ) {
+                    bail!(
+                        "sequence generator for field '{}' requires an integer or TEXT field, but it has type {}",
+                        name,
+                        data_type
+                    );
+                }
+
+                let start = spec.and_then(|s| s.start).unwrap_or(0);
+                let len = match spec.and_then(|s| s.end) {
+                    Some(end) if end < start => {
+                        bail!(
+                            "sequence for field '{}' has end {} before its start {}",
+                            name,
+                            end,
+                            start
+                        );
+                    }
+                    Some(end) => {
+                        Some(u64::try_from(end as i128 - start as i128 + 1).unwrap_or(u64::MAX))
+                    }
+                    None => None,
+                };
+
+                Generator::Sequence { start, len }
+            }
+            GeneratorKind::Enum => {
+                let values = spec.map(|s| s.values.clone()).unwrap_or_default();
+                if values.is_empty() {
+                    bail!("enum generator for field '{}' requires values", name);
+                }
+                if *data_type != DataType::Utf8 {
+                    bail!(
+                        "enum generator for field '{}' requires a TEXT field, but it has type {}",
+                        name,
+                        data_type
+                    );
+                }
+                Generator::Values(values)
+            }
+            GeneratorKind::Random => {
+                let min = spec.and_then(|s| s.min);
+                let max = spec.and_then(|s| s.max);
+
+                let generator = match data_type {
+                    DataType::Boolean => Generator::Bool,
+                    DataType::Int32 => Generator::Int {
+                        min: min.map(|m| m as i32 as i64).unwrap_or(i32::MIN as i64),
+                        max: max.map(|m| m as i32 as i64).unwrap_or(i32::MAX as i64),
+                    },
+                    DataType::Int64 => Generator::Int {
+                        min: min.map(|m| m as i64).unwrap_or(i64::MIN),
+                        max: max.map(|m| m as i64).unwrap_or(i64::MAX),
+                    },
+                    DataType::UInt32 => Generator::UInt {
+                        min: min.map(|m| m as u32 as u64).unwrap_or(0),
+                        max: max.map(|m| m as u32 as u64).unwrap_or(u32::MAX as u64),
+                    },
+                    DataType::UInt64 => Generator::UInt {
+                        min: min.map(|m| m as u64).unwrap_or(0),
+                        max: max.map(|m| m as u64).unwrap_or(u64::MAX),
+                    },
+                    DataType::Float32 | DataType::Float64 => Generator::Float {
+                        min: min.unwrap_or(0.0),
+                        max: max.unwrap_or(1.0),
+                    },
+                    DataType::Utf8 => {
+                        if ArroyoExtensionType::from_map(field.metadata())
+                            == Some(ArroyoExtensionType::JSON)
+                        {
+                            Generator::Json { length }
+                        } else {
+                            Generator::Text { length }
+                        }
+                    }
+                    DataType::Binary => Generator::Bytes { length },
+                    DataType::Timestamp(_, _) => Generator::EventTime,
+                    dt => {
+                        bail!("datagen does not support field '{}' of type {}", name, dt);
+                    }
+                };
+
+                if let (Some(min), Some(max)) = (min, max) {
+                    if min > max {
+                        bail!(
+                            "min for field '{}' ({}) is greater than its max ({})",
+                            name,
+                            min,
+                            max
+                        );
+                    }
+                }
+
+                generator
+            }
+        };
+
+        Ok(Self {
+            index,
+            data_type: data_type.clone(),
+            generator,
+            null_ratio,
+        })
+    }
+
+    fn generate(&self, rng: &mut RowRng, index: u64, event_time: SystemTime) -> Value {
+        if self.null_ratio > 0.0 && rng.gen_bool(self.null_ratio) {
+            return Value::Null;
+        }
+
+        match &self.generator {
+            Generator::EventTime => Value::Time(event_time),
+            Generator::Sequence { start, len } => {
+                let offset = match len {
+                    Some(len) => index % len,
+                    None => index,
+                };
+                let value = start.wrapping_add(offset as i64);
+                if self.data_type == DataType::Utf8 {
+                    Value::Str(value.to_string())
+                } else {
+                    Value::Int(value)
+                }
+            }
+            Generator::Int { min, max } => Value::Int(rng.gen_range(*min..=*max)),
+            Generator::UInt { min, max } => Value::UInt(rng.gen_range(*min..=*max)),
+            Generator::Float { min, max } => Value::Float(min + rng.gen::<f64>() * (max - min)),
+            Generator::Bool => Value::Bool(rng.gen()),
+            Generator::Text { length } => Value::Str(random_string(rng, *length)),
+            Generator::Json { length } => {
+                Value::Str(serde_json::Value::String(random_string(rng, *length)).to_string())
+            }
+            Generator::Bytes { length } => {
+                let mut bytes = vec![0; *length];
+                rng.fill_bytes(&mut bytes);
+                Value::Bytes(bytes)
+            }
+            Generator::Values(values) => Value::Str(values[rng.gen_range(0..values.len())].clone()),
+        }
+    }
+
+    fn append(&self, builder: &mut dyn ArrayBuilder, value: Value) {
+        macro_rules! builder {
+            ($t:ty) => {
+                builder
+                    .as_any_mut()
+                    .downcast_mut::<$t>()
+                    .expect("datagen builder has incorrect type")
+            };
+        }
+
+        match &self.data_type {
+            DataType::Boolean => builder!(BooleanBuilder).append_option(value.as_bool()),
+            DataType::Int32 => {
+                builder!(Int32Builder).append_option(value.as_i64().map(|v| v as i32))
+            }
+            DataType::Int64 => builder!(Int64Builder).append_option(value.as_i64()),
+            DataType::UInt32 => {
+                builder!(UInt32Builder).append_option(value.as_u64().map(|v| v as u32))
+            }
+            DataType::UInt64 => builder!(UInt64Builder).append_option(value.as_u64()),
+            DataType::Float32 => {
+                builder!(Float32Builder).append_option(value.as_f64().map(|v| v as f32))
+            }
+            DataType::Float64 => builder!(Float64Builder).append_option(value.as_f64()),
+            DataType::Utf8 => builder!(StringBuilder).append_option(value.as_str()),
+            DataType::Binary => builder!(BinaryBuilder).append_option(value.as_bytes()),
+            DataType::Timestamp(unit, _) => {
+                let nanos = value.as_nanos();
+                match unit {
+                    TimeUnit::Second => builder!(TimestampSecondBuilder)
+                        .append_option(nanos.map(|n| n / 1_000_000_000)),
+                    TimeUnit::Millisecond => builder!(TimestampMillisecondBuilder)
+                        .append_option(nanos.map(|n| n / 1_000_000)),
+                    TimeUnit::Microsecond => builder!(TimestampMicrosecondBuilder)
+                        .append_option(nanos.map(|n| n / 1_000)),
+                    TimeUnit::Nanosecond => {
+                        builder!(TimestampNanosecondBuilder).append_option(nanos)
+                    }
+                }
+            }
+            dt => unreachable!("unsupported datagen type {}", dt),
+        }
+    }
+}
+
+fn random_string(rng: &mut RowRng, length: usize) -> String {
+    (0..length)
+        .map(|_| rng.sample(Alphanumeric) as char)
+        .collect()
+}
+
+/// Generates rows for a schema; a row's values depend only on the seed, its global index and the
+/// base time it's generated at
+pub(crate) struct RowGenerator {
+    schema: SchemaRef,
+    timestamp_index: usize,
+    seed: u64,
+    max_out_of_orderness: Option<Duration>,
+    columns: Vec<ColumnGenerator>,
+}
+
+impl RowGenerator {
+    pub fn new(schema: &ArroyoSchema, table: &DatagenTable) -> anyhow::Result<Self> {
+        for name in table.fields.keys() {
+            if name == TIMESTAMP_FIELD || schema.schema.field_with_name(name).is_err() {
+                bail!(
+                    "datagen generator is configured for field '{}', which is not in the schema",
+                    name
+                );
+            }
+        }
+
+        let columns = schema
+            .schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != schema.timestamp_index)
+            .map(|(i, f)| ColumnGenerator::new(i, f, table.fields.get(f.name())))
+            .collect::<anyhow::Result<_>>()?;
+
+        let max_out_of_orderness = match table.max_out_of_orderness {
+            Some(micros) if micros < 0 => {
+                bail!("max_out_of_orderness must not be negative");
+            }
+            Some(micros) => Some(Duration::from_micros(micros as u64)),
+            None => None,
+        };
+
+        Ok(Self {
+            schema: schema.schema.clone(),
+            timestamp_index: schema.timestamp_index,
+            seed: table.seed.unwrap_or(0) as u64,
+            max_out_of_orderness,
+            columns,
+        })
+    }
+
+    pub fn builders(&self) -> Vec<Box<dyn ArrayBuilder>> {
+        self.schema
+            .fields()
+            .iter()
+            .map(|f| arrow::array::make_builder(f.data_type(), 16))
+            .collect()
+    }
+
+    pub fn generate(
+        &self,
+        builders: &mut [Box<dyn ArrayBuilder>],
+        index: u64,
+        base_time: SystemTime,
+    ) {
+        let mut rng = RowRng::new(self.seed, index);
+
+        let event_time = match self.max_out_of_orderness {
+            Some(max) if !max.is_zero() => {
+                base_time - Duration::from_nanos(rng.gen_range(0..=max.as_nanos() as u64))
+            }
+            _ => base_time,
+        };
+
+        for column in &self.columns {
+            let value = column.generate(&mut rng, index, event_time);
+            column.append(builders[column.index].as_mut(), value);
+        }
+
+        builders[self.timestamp_index]
+            .as_any_mut()
+            .downcast_mut::<TimestampNanosecondBuilder>()
+            .expect("_timestamp column has incorrect type")
+            .append_value(to_nanos(event_time) as i64);
+    }
+
+    pub fn finish(&self, builders: &mut [Box<dyn ArrayBuilder>]) -> RecordBatch {
+        RecordBatch::try_new(
+            self.schema.clone(),
+            builders.iter_mut().map(|b| b.finish()).collect(),
+        )
+        .unwrap()
+    }
+}
+
+pub struct DatagenSourceFunc {
+    table: DatagenTable,
+    state: DatagenSourceState,
+}
+
+impl DatagenSourceFunc {
+    pub fn new(table: DatagenTable) -> Self {
+        Self {
+            table,
+            state: DatagenSourceState {
+                counter: 0,
+                start_time: SystemTime::now(),
+            },
+        }
+    }
+
+    async fn run(&mut self, ctx: &mut ArrowContext) -> SourceFinishType {
+        let generator = RowGenerator::new(ctx.out_schema.as_ref().unwrap(), &self.table)
+            .expect("invalid datagen configuration");
+
+        let parallelism = ctx.task_info.parallelism as u64;
+        let task_index = ctx.task_info.task_index as u64;
+
+        // each subtask generates the rows whose global index is congruent to its task index
+        let limit = self
+            .table
+            .message_count
+            .map(|c| c.max(0) as u64)
+            .unwrap_or(u64::MAX);
+        let limit = limit / parallelism + u64::from(task_index < limit % parallelism);
+
+        let delay = if self.table.event_rate > 0.0 {
+            Duration::from_secs_f64(parallelism as f64 / self.table.event_rate)
+        } else {
+            Duration::ZERO
+        };
+
+        let interval = self
+            .table
+            .event_time_interval
+            .map(|i| Duration::from_micros(i.max(0) as u64));
+
+        info!(
+            "Starting datagen source with delay {:?} and limit {}, restored counter {}",
+            delay, limit, self.state.counter
+        );
+
+        let batch_size = if delay.is_zero() {
+            8192
+        } else {
+            (Duration::from_millis(100).as_micros() / delay.as_micros().max(1)).clamp(1, 8192)
+                as usize
+        };
+
+        let start_time = SystemTime::now() - delay.mul_f64(self.state.counter as f64);
+        let mut builders = generator.builders();
+        let mut items = 0;
+
+        while self.state.counter < limit {
+            let base_time = interval
+                .map(|d| {
+                    self.state.start_time
+                        + Duration::from_nanos(
+                            (d.as_nanos() as u64).saturating_mul(self.state.counter),
+                        )
+                })
+                .unwrap_or_else(SystemTime::now);
+
+            generator.generate(
+                &mut builders,
+                self.state.counter * parallelism + task_index,
+                base_time,
+            );
+            items += 1;
+            self.state.counter += 1;
+
+            if items == batch_size {
+                ctx.collect(generator.finish(&mut builders)).await;
+                items = 0;
+            }
+
+            match ctx.control_rx.try_recv() {
+                Ok(ControlMessage::Checkpoint(c)) => {
+                    debug!("starting checkpointing {}", ctx.task_info.task_index);
+                    if items > 0 {
+                        ctx.collect(generator.finish(&mut builders)).await;
+                        items = 0;
+                    }
+                    ctx.table_manager
+                        .get_global_keyed_state("d")
+                        .await
+                        .unwrap()
+                        .insert(ctx.task_info.task_index, self.state)
+                        .await;
+                    if self.start_checkpoint(c, ctx).await {
+                        return SourceFinishType::Immediate;
+                    }
+                }
+                Ok(ControlMessage::Stop { mode }) => {
+                    info!("Stopping datagen source {:?}", mode);
+
+                    match mode {
+                        StopMode::Graceful => {
+                            return SourceFinishType::Graceful;
+                        }
+                        StopMode::Immediate => {
+                            return SourceFinishType::Immediate;
+                        }
+                    }
+                }
+                Ok(ControlMessage::Commit { .. }) => {
+                    unreachable!("sources shouldn't receive commit messages");
+                }
+                Ok(ControlMessage::LoadCompacted { compacted }) => {
+                    ctx.load_compacted(compacted).await;
+                }
+                Ok(ControlMessage::NoOp) => {}
+                Err(_) => {
+                    // no messages
+                }
+            }
+
+            if !delay.is_zero() {
+                let next_sleep = start_time + delay.mul_f64(self.state.counter as f64);
+                if let Ok(sleep_time) = next_sleep.duration_since(SystemTime::now()) {
+                    tokio::time::sleep(sleep_time).await;
+                }
+            }
+        }
+
+        if items > 0 {
+            ctx.collect(generator.finish(&mut builders)).await;
+        }
+
+        SourceFinishType::Final
+    }
+}
+
+#[async_trait]
+impl SourceOperator for DatagenSourceFunc {
+    fn name(&self) -> String {
+        "datagen-source".to_string()
+    }
+
+    fn tables(&self) -> HashMap<String, TableConfig> {
+        arroyo_state::global_table_config("d", "datagen source state")
+    }
+
+    async fn on_start(&mut self, ctx: &mut ArrowContext) {
+        let s = ctx
+            .table_manager
+            .get_global_keyed_state("d")
+            .await
+            .expect("should have table d in datagen source");
+
+        if let Some(state) = s.get(&ctx.task_info.task_index) {
+            self.state = *state;
+        }
+    }
+
+    async fn run(&mut self, ctx: &mut ArrowContext) -> SourceFinishType {
+        self.run(ctx).await
+    }
+}
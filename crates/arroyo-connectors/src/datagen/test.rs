@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use arrow::array::{AsArray, RecordBatch};
+use arrow::datatypes::{DataType, Field, Float64Type, Int64Type, TimestampNanosecondType};
+use arroyo_rpc::df::ArroyoSchema;
+
+use crate::datagen::operator::RowGenerator;
+use crate::datagen::{DatagenConnector, DatagenTable, FieldGenerator, GeneratorKind};
+
+fn schema() -> ArroyoSchema {
+    ArroyoSchema::from_fields(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("status", DataType::Utf8, true),
+        Field::new("amount", DataType::Float64, false),
+    ])
+}
+
+fn generator(kind: Option<GeneratorKind>) -> FieldGenerator {
+    FieldGenerator {
+        kind,
+        min: None,
+        max: None,
+        start: None,
+        end: None,
+        length: None,
+        values: vec![],
+        null_ratio: None,
+    }
+}
+
+fn datagen_table(seed: i64, max_out_of_orderness: Option<i64>) -> DatagenTable {
+    let mut fields = HashMap::new();
+    fields.insert(
+        "id".to_string(),
+        FieldGenerator {
+            start: Some(10),
+            end: Some(14),
+            ..generator(Some(GeneratorKind::Sequence))
+        },
+    );
+    fields.insert(
+        "status".to_string(),
+        FieldGenerator {
+            values: vec!["open".to_string(), "closed".to_string()],
+            null_ratio: Some(0.5),
+            ..generator(None)
+        },
+    );
+    fields.insert(
+        "amount".to_string(),
+        FieldGenerator {
+            min: Some(5.0),
+            max: Some(10.0),
+            ..generator(Some(GeneratorKind::Random))
+        },
+    );
+
+    DatagenTable {
+        event_rate: 100.0,
+        message_count: None,
+        seed: Some(seed),
+        event_time_interval: None,
+        max_out_of_orderness,
+        fields,
+    }
+}
+
+fn generate(table: &DatagenTable, indices: impl Iterator<Item = u64>) -> RecordBatch {
+    let generator = RowGenerator::new(&schema(), table).unwrap();
+    let mut builders = generator.builders();
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+    for i in indices {
+        generator.generate(&mut builders, i, time);
+    }
+    generator.finish(&mut builders)
+}
+
+#[test]
+fn test_generated_values() {
+    let batch = generate(&datagen_table(1, None), 0..100);
+    assert_eq!(batch.num_rows(), 100);
+
+    let ids = batch.column(0).as_primitive::<Int64Type>();
+    for (i, id) in ids.iter().enumerate() {
+        assert_eq!(id, Some(10 + (i as i64 % 5)));
+    }
+
+    let statuses = batch.column(1).as_string::<i32>();
+    assert!(statuses.null_count() > 0 && statuses.null_count() < 100);
+    assert!(statuses
+        .iter()
+        .flatten()
+        .all(|s| s == "open" || s == "closed"));
+
+    let amounts = batch.column(2).as_primitive::<Float64Type>();
+    assert!(amounts.values().iter().all(|a| (5.0..=10.0).contains(a)));
+}
+
+#[test]
+fn test_deterministic() {
+    // rows only depend on the seed and their index, so generating them in a different order
+    // (as happens with different parallelism) produces the same values
+    let a = generate(&datagen_table(7, Some(1_000_000)), 0..50);
+    let b = generate(&datagen_table(7, Some(1_000_000)), 0..50);
+    assert_eq!(a, b);
+
+    let reordered = generate(&datagen_table(7, Some(1_000_000)), (0..50).rev());
+    for i in 0..50 {
+        assert_eq!(
+            a.slice(i, 1),
+            reordered.slice(49 - i, 1),
+            "row {} differs",
+            i
+        );
+    }
+
+    let other_seed = generate(&datagen_table(8, Some(1_000_000)), 0..50);
+    assert_ne!(a, other_seed);
+}
+
+#[test]
+fn test_out_of_orderness() {
+    let batch = generate(&datagen_table(1, Some(1_000_000)), 0..100);
+    let base = 1_000_000 * 1_000_000_000i64;
+    let timestamps = batch.column(3).as_primitive::<TimestampNanosecondType>();
+    assert!(timestamps
+        .values()
+        .iter()
+        .all(|t| (base - 1_000_000_000..=base).contains(t)));
+    assert!(timestamps.values().iter().any(|t| *t != base));
+}
+
+#[test]
+fn test_validation() {
+    let mut options = HashMap::new();
+    options.insert("fields.status.null_ratio".to_string(), "0.1".to_string());
+    options.insert("fields.id.kind".to_string(), "sequence".to_string());
+    options.insert("fields.id.end".to_string(), "100".to_string());
+    let fields = DatagenConnector::fields_from_options(&mut options).unwrap();
+    assert!(options.is_empty());
+    assert_eq!(fields.get("id").unwrap().end, Some(100));
+
+    let mut table = datagen_table(1, None);
+    table.fields.insert("missing".to_string(), generator(None));
+    assert!(RowGenerator::new(&schema(), &table).is_err());
+
+    let mut table = datagen_table(1, None);
+    table.fields.get_mut("amount").unwrap().null_ratio = Some(0.1);
+    assert!(RowGenerator::new(&schema(), &table).is_err());
+
+    let mut table = datagen_table(1, None);
+    table.fields.get_mut("amount").unwrap().kind = Some(GeneratorKind::Enum);
+    assert!(RowGenerator::new(&schema(), &table).is_err());
+}
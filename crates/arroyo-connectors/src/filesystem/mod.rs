@@ -11,7 +11,7 @@ use typify::import_types;
 
 use arroyo_operator::connector::Connection;
 use arroyo_rpc::api_types::connections::{
-    ConnectionProfile, ConnectionSchema, ConnectionType, TestSourceMessage,
+    ConnectionProfile, ConnectionSchema, ConnectionType, FieldType, TestSourceMessage,
 };
 use arroyo_rpc::formats::Format;
 use arroyo_rpc::OperatorConfig;
@@ -24,7 +24,9 @@ use arroyo_operator::connector::Connector;
 use arroyo_operator::operator::OperatorNode;
 
 use self::sink::{
-    JsonFileSystemSink, LocalJsonFileSystemSink, LocalParquetFileSystemSink, ParquetFileSystemSink,
+    AvroFileSystemSink, CsvFileSystemSink, JsonFileSystemSink, LocalAvroFileSystemSink,
+    LocalCsvFileSystemSink, LocalJsonFileSystemSink, LocalParquetFileSystemSink,
+    ParquetFileSystemSink,
 };
 
 const TABLE_SCHEMA: &str = include_str!("./table.json");
@@ -107,6 +109,10 @@ impl Connector for FileSystemConnector {
         }
     }
 
+    fn supports_csv(&self, connection_type: &ConnectionType) -> bool {
+        *connection_type == ConnectionType::Sink
+    }
+
     fn from_config(
         &self,
         id: Option<i64>,
@@ -145,6 +151,12 @@ impl Connector for FileSystemConnector {
                         "LocalFileSystem<JSON>".to_string()
                     }
                     (Some(FormatSettings::Json { .. }), false) => "FileSystem<JSON>".to_string(),
+                    (Some(FormatSettings::Avro { .. }), true) => {
+                        "LocalFileSystem<Avro>".to_string()
+                    }
+                    (Some(FormatSettings::Avro { .. }), false) => "FileSystem<Avro>".to_string(),
+                    (Some(FormatSettings::Csv { .. }), true) => "LocalFileSystem<CSV>".to_string(),
+                    (Some(FormatSettings::Csv { .. }), false) => "FileSystem<CSV>".to_string(),
                     (None, _) => bail!("have to have some format settings"),
                 };
                 (description, ConnectionType::Sink)
@@ -161,6 +173,10 @@ impl Connector for FileSystemConnector {
             .map(|t| t.to_owned())
             .ok_or_else(|| anyhow!("'format' must be set for FileSystem connection"))?;
 
+        if let Format::ArrowIpc(_) = format {
+            bail!("arrow_ipc is not supported by the FileSystem connector");
+        }
+
         let config = OperatorConfig {
             connection: serde_json::to_value(config).unwrap(),
             table: serde_json::to_value(table).unwrap(),
@@ -267,6 +283,22 @@ impl Connector for FileSystemConnector {
                     (Some(FormatSettings::Json { .. }), false) => Ok(OperatorNode::from_operator(
                         Box::new(JsonFileSystemSink::new(table, config)),
                     )),
+                    (Some(FormatSettings::Avro { .. }), true) => {
+                        Ok(OperatorNode::from_operator(Box::new(
+                            LocalAvroFileSystemSink::new(write_path.to_string(), table, config),
+                        )))
+                    }
+                    (Some(FormatSettings::Avro { .. }), false) => Ok(OperatorNode::from_operator(
+                        Box::new(AvroFileSystemSink::new(table, config)),
+                    )),
+                    (Some(FormatSettings::Csv { .. }), true) => {
+                        Ok(OperatorNode::from_operator(Box::new(
+                            LocalCsvFileSystemSink::new(write_path.to_string(), table, config),
+                        )))
+                    }
+                    (Some(FormatSettings::Csv { .. }), false) => Ok(OperatorNode::from_operator(
+                        Box::new(CsvFileSystemSink::new(table, config)),
+                    )),
                     (None, _) => bail!("have to have some format settings"),
                 }
             }
@@ -348,7 +380,7 @@ pub fn file_system_sink_from_options(
     });
 
     let format_settings = match schema.as_ref().unwrap().format.as_ref().ok_or(anyhow!(
-        "filesystem sink requires a format, such as json or parquet"
+        "filesystem sink requires a format, such as json, parquet, avro or csv"
     ))? {
         Format::Parquet(..) => {
             let compression = opts
@@ -370,6 +402,54 @@ pub fn file_system_sink_from_options(
         Format::Json(..) => Some(FormatSettings::Json {
             json_format: JsonFormat::Json,
         }),
+        Format::Avro(..) => {
+            let codec = opts
+                .remove("avro_codec")
+                .map(|value| {
+                    AvroCodec::try_from(&value)
+                        .map_err(|_err| anyhow!("{} is not a valid avro_codec argument", value))
+                })
+                .transpose()?;
+            Some(FormatSettings::Avro {
+                avro_format: AvroFormat::Avro,
+                codec,
+            })
+        }
+        Format::Csv(..) => {
+            let delimiter = opts.remove("csv_delimiter");
+            if let Some(delimiter) = &delimiter {
+                if delimiter.len() != 1 {
+                    bail!(
+                        "csv_delimiter must be a single ASCII character, not '{}'",
+                        delimiter
+                    );
+                }
+            }
+            let include_header = opts
+                .remove("csv_include_header")
+                .map(|value| {
+                    value.parse::<bool>().map_err(|_err| {
+                        anyhow!("{} is not a valid csv_include_header argument", value)
+                    })
+                })
+                .transpose()?;
+            if let Some(field) = schema
+                .unwrap()
+                .fields
+                .iter()
+                .find(|f| !matches!(f.field_type.r#type, FieldType::Primitive(_)))
+            {
+                bail!(
+                    "csv can only be used with primitive fields, but '{}' is a struct or list",
+                    field.field_name
+                );
+            }
+            Some(FormatSettings::Csv {
+                csv_format: CsvFormat::Csv,
+                delimiter,
+                include_header,
+            })
+        }
         other => bail!("Unsupported format: {:?}", other),
     };
     Ok(FileSystemTable {
@@ -0,0 +1,198 @@
+use std::{fs::File, io::Write, time::Instant};
+
+use apache_avro::Codec;
+use arrow::record_batch::RecordBatch;
+use arroyo_formats::{avro::ocf::ContainerWriter, ser::ArrowSerializer};
+use arroyo_rpc::{df::ArroyoSchemaRef, formats::Format};
+
+use super::{
+    local::{CurrentFileRecovery, FilePreCommit, LocalWriter},
+    parquet::representitive_timestamp,
+    BatchBufferingWriter, FileSettings, MultiPartWriterStats, TableType,
+};
+use crate::filesystem::{AvroCodec, FileSystemTable, FormatSettings};
+
+fn container_writer(config: &FileSystemTable, schema: &ArroyoSchemaRef) -> ContainerWriter {
+    let codec = if let TableType::Sink {
+        format_settings: Some(FormatSettings::Avro {
+            codec: Some(codec), ..
+        }),
+        ..
+    } = &config.table_type
+    {
+        match codec {
+            AvroCodec::Null => Codec::Null,
+            AvroCodec::Deflate => Codec::Deflate,
+            AvroCodec::Snappy => Codec::Snappy,
+            AvroCodec::Zstandard => Codec::Zstandard,
+            AvroCodec::Bzip2 => Codec::Bzip2,
+            AvroCodec::Xz => Codec::Xz,
+        }
+    } else {
+        Codec::Null
+    };
+
+    ContainerWriter::new(ArrowSerializer::avro_schema(&schema.schema), codec)
+}
+
+pub struct AvroWriter {
+    writer: ContainerWriter,
+    target_part_size: usize,
+    schema: ArroyoSchemaRef,
+}
+
+impl BatchBufferingWriter for AvroWriter {
+    fn new(config: &FileSystemTable, _format: Option<Format>, schema: ArroyoSchemaRef) -> Self {
+        let target_part_size = if let TableType::Sink {
+            file_settings:
+                Some(FileSettings {
+                    target_part_size: Some(target_part_size),
+                    ..
+                }),
+            ..
+        } = config.table_type
+        {
+            target_part_size as usize
+        } else {
+            5 * 1024 * 1024
+        };
+        Self {
+            writer: container_writer(config, &schema),
+            target_part_size,
+            schema,
+        }
+    }
+
+    fn suffix() -> String {
+        "avro".to_string()
+    }
+
+    fn add_batch_data(&mut self, mut batch: RecordBatch) -> Option<Vec<u8>> {
+        self.schema.remove_timestamp_column(&mut batch);
+        self.writer.write_batch(&batch);
+        if self.buffer_length() > self.target_part_size {
+            Some(self.evict_current_buffer())
+        } else {
+            None
+        }
+    }
+
+    fn buffer_length(&self) -> usize {
+        self.writer.buffered_len()
+    }
+
+    fn evict_current_buffer(&mut self) -> Vec<u8> {
+        // this ends the current block, so every part finishes with a sync marker
+        self.writer.take()
+    }
+
+    fn get_trailing_bytes_for_checkpoint(&mut self) -> Option<Vec<u8>> {
+        let buffered = self.writer.buffered();
+        if buffered.is_empty() {
+            None
+        } else {
+            Some(buffered.to_vec())
+        }
+    }
+
+    fn close(&mut self, final_batch: Option<RecordBatch>) -> Option<Vec<u8>> {
+        if let Some(final_batch) = final_batch {
+            if let Some(final_batch) = self.add_batch_data(final_batch) {
+                return Some(final_batch);
+            }
+        }
+        let buffer = self.evict_current_buffer();
+        if buffer.is_empty() {
+            None
+        } else {
+            Some(buffer)
+        }
+    }
+}
+
+pub struct AvroLocalWriter {
+    tmp_path: String,
+    final_path: String,
+    file: File,
+    writer: ContainerWriter,
+    stats: Option<MultiPartWriterStats>,
+    schema: ArroyoSchemaRef,
+}
+
+impl LocalWriter for AvroLocalWriter {
+    fn new(
+        tmp_path: String,
+        final_path: String,
+        table_properties: &FileSystemTable,
+        _format: Option<Format>,
+        schema: ArroyoSchemaRef,
+    ) -> Self {
+        let file = File::create(&tmp_path).unwrap();
+        AvroLocalWriter {
+            tmp_path,
+            final_path,
+            file,
+            writer: container_writer(table_properties, &schema),
+            stats: None,
+            schema,
+        }
+    }
+
+    fn file_suffix() -> &'static str {
+        "avro"
+    }
+
+    fn write_batch(&mut self, mut batch: RecordBatch) -> anyhow::Result<()> {
+        if self.stats.is_none() {
+            self.stats = Some(MultiPartWriterStats {
+                bytes_written: 0,
+                parts_written: 0,
+                first_write_at: Instant::now(),
+                last_write_at: Instant::now(),
+                representative_timestamp: representitive_timestamp(
+                    batch.column(self.schema.timestamp_index),
+                )?,
+            });
+        } else {
+            self.stats.as_mut().unwrap().last_write_at = Instant::now();
+        }
+        self.schema.remove_timestamp_column(&mut batch);
+        self.writer.write_batch(&batch);
+        Ok(())
+    }
+
+    fn sync(&mut self) -> anyhow::Result<usize> {
+        // ending the block here means the file is always valid up to the synced length
+        self.file.write_all(&self.writer.take())?;
+        self.file.flush()?;
+        let size = self.file.metadata()?.len() as usize;
+        self.stats.as_mut().unwrap().bytes_written = size;
+        Ok(size)
+    }
+
+    fn close(&mut self) -> anyhow::Result<FilePreCommit> {
+        LocalWriter::sync(self)?;
+        Ok(FilePreCommit {
+            tmp_file: self.tmp_path.clone(),
+            destination: self.final_path.clone(),
+        })
+    }
+
+    fn checkpoint(&mut self) -> anyhow::Result<Option<CurrentFileRecovery>> {
+        let bytes_written = LocalWriter::sync(self)?;
+        if bytes_written > 0 {
+            Ok(Some(CurrentFileRecovery {
+                tmp_file: self.tmp_path.clone(),
+                bytes_written,
+                suffix: None,
+                destination: self.final_path.clone(),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn stats(&self) -> MultiPartWriterStats {
+        self.stats.clone().unwrap()
+    }
+}
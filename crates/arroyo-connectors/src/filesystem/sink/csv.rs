@@ -0,0 +1,218 @@
+use std::{fs::File, io::Write, time::Instant};
+
+use arrow::{csv::WriterBuilder, record_batch::RecordBatch};
+use arroyo_rpc::{df::ArroyoSchemaRef, formats::Format};
+
+use super::{
+    local::{CurrentFileRecovery, FilePreCommit, LocalWriter},
+    parquet::representitive_timestamp,
+    BatchBufferingWriter, FileSettings, MultiPartWriterStats, TableType,
+};
+use crate::filesystem::{FileSystemTable, FormatSettings};
+
+/// Encodes batches as CSV, writing the header (if enabled) only before the first batch of a file
+struct CsvEncoder {
+    delimiter: u8,
+    include_header: bool,
+    wrote_header: bool,
+}
+
+impl CsvEncoder {
+    fn new(config: &FileSystemTable) -> Self {
+        let (delimiter, include_header) = if let TableType::Sink {
+            format_settings:
+                Some(FormatSettings::Csv {
+                    delimiter,
+                    include_header,
+                    ..
+                }),
+            ..
+        } = &config.table_type
+        {
+            (
+                delimiter.as_ref().map(|d| d.as_bytes()[0]).unwrap_or(b','),
+                include_header.unwrap_or(true),
+            )
+        } else {
+            (b',', true)
+        };
+
+        Self {
+            delimiter,
+            include_header,
+            wrote_header: false,
+        }
+    }
+
+    fn encode(&mut self, batch: &RecordBatch, out: &mut Vec<u8>) {
+        let mut writer = WriterBuilder::new()
+            .with_delimiter(self.delimiter)
+            .with_header(self.include_header && !self.wrote_header)
+            .build(out);
+        writer.write(batch).expect("failed to write CSV");
+        self.wrote_header = true;
+    }
+}
+
+pub struct CsvWriter {
+    current_buffer: Vec<u8>,
+    encoder: CsvEncoder,
+    target_part_size: usize,
+    schema: ArroyoSchemaRef,
+}
+
+impl BatchBufferingWriter for CsvWriter {
+    fn new(config: &FileSystemTable, _format: Option<Format>, schema: ArroyoSchemaRef) -> Self {
+        let target_part_size = if let TableType::Sink {
+            file_settings:
+                Some(FileSettings {
+                    target_part_size: Some(target_part_size),
+                    ..
+                }),
+            ..
+        } = config.table_type
+        {
+            target_part_size as usize
+        } else {
+            5 * 1024 * 1024
+        };
+        Self {
+            current_buffer: Vec::new(),
+            encoder: CsvEncoder::new(config),
+            target_part_size,
+            schema,
+        }
+    }
+
+    fn suffix() -> String {
+        "csv".to_string()
+    }
+
+    fn add_batch_data(&mut self, mut batch: RecordBatch) -> Option<Vec<u8>> {
+        self.schema.remove_timestamp_column(&mut batch);
+        self.encoder.encode(&batch, &mut self.current_buffer);
+        if self.buffer_length() > self.target_part_size {
+            Some(self.evict_current_buffer())
+        } else {
+            None
+        }
+    }
+
+    fn buffer_length(&self) -> usize {
+        self.current_buffer.len()
+    }
+
+    fn evict_current_buffer(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.current_buffer)
+    }
+
+    fn get_trailing_bytes_for_checkpoint(&mut self) -> Option<Vec<u8>> {
+        if self.current_buffer.is_empty() {
+            None
+        } else {
+            Some(self.current_buffer.clone())
+        }
+    }
+
+    fn close(&mut self, final_batch: Option<RecordBatch>) -> Option<Vec<u8>> {
+        if let Some(final_batch) = final_batch {
+            if let Some(final_batch) = self.add_batch_data(final_batch) {
+                return Some(final_batch);
+            }
+        }
+        if self.current_buffer.is_empty() {
+            None
+        } else {
+            Some(self.evict_current_buffer())
+        }
+    }
+}
+
+pub struct CsvLocalWriter {
+    tmp_path: String,
+    final_path: String,
+    file: File,
+    encoder: CsvEncoder,
+    buffer: Vec<u8>,
+    stats: Option<MultiPartWriterStats>,
+    schema: ArroyoSchemaRef,
+}
+
+impl LocalWriter for CsvLocalWriter {
+    fn new(
+        tmp_path: String,
+        final_path: String,
+        table_properties: &FileSystemTable,
+        _format: Option<Format>,
+        schema: ArroyoSchemaRef,
+    ) -> Self {
+        let file = File::create(&tmp_path).unwrap();
+        CsvLocalWriter {
+            tmp_path,
+            final_path,
+            file,
+            encoder: CsvEncoder::new(table_properties),
+            buffer: Vec::new(),
+            stats: None,
+            schema,
+        }
+    }
+
+    fn file_suffix() -> &'static str {
+        "csv"
+    }
+
+    fn write_batch(&mut self, mut batch: RecordBatch) -> anyhow::Result<()> {
+        if self.stats.is_none() {
+            self.stats = Some(MultiPartWriterStats {
+                bytes_written: 0,
+                parts_written: 0,
+                first_write_at: Instant::now(),
+                last_write_at: Instant::now(),
+                representative_timestamp: representitive_timestamp(
+                    batch.column(self.schema.timestamp_index),
+                )?,
+            });
+        } else {
+            self.stats.as_mut().unwrap().last_write_at = Instant::now();
+        }
+        self.schema.remove_timestamp_column(&mut batch);
+        self.encoder.encode(&batch, &mut self.buffer);
+        self.file.write_all(&self.buffer)?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    fn sync(&mut self) -> anyhow::Result<usize> {
+        self.file.flush()?;
+        let size = self.file.metadata()?.len() as usize;
+        self.stats.as_mut().unwrap().bytes_written = size;
+        Ok(size)
+    }
+
+    fn close(&mut self) -> anyhow::Result<FilePreCommit> {
+        LocalWriter::sync(self)?;
+        Ok(FilePreCommit {
+            tmp_file: self.tmp_path.clone(),
+            destination: self.final_path.clone(),
+        })
+    }
+
+    fn checkpoint(&mut self) -> anyhow::Result<Option<CurrentFileRecovery>> {
+        let bytes_written = LocalWriter::sync(self)?;
+        if bytes_written > 0 {
+            Ok(Some(CurrentFileRecovery {
+                tmp_file: self.tmp_path.clone(),
+                bytes_written,
+                suffix: None,
+                destination: self.final_path.clone(),
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn stats(&self) -> MultiPartWriterStats {
+        self.stats.clone().unwrap()
+    }
+}
@@ -44,6 +44,8 @@ use uuid::Uuid;
 
 use arroyo_types::*;
 pub mod arrow;
+pub mod avro;
+pub mod csv;
 mod delta;
 pub mod json;
 pub mod local;
@@ -51,6 +53,8 @@ pub mod parquet;
 mod two_phase_committer;
 
 use self::{
+    avro::{AvroLocalWriter, AvroWriter},
+    csv::{CsvLocalWriter, CsvWriter},
     json::{JsonLocalWriter, JsonWriter},
     local::LocalFileSystemWriter,
     parquet::{
@@ -82,6 +86,14 @@ pub type LocalParquetFileSystemSink = LocalFileSystemWriter<ParquetLocalWriter>;
 
 pub type LocalJsonFileSystemSink = LocalFileSystemWriter<JsonLocalWriter>;
 
+pub type AvroFileSystemSink = FileSystemSink<BatchMultipartWriter<AvroWriter>>;
+
+pub type LocalAvroFileSystemSink = LocalFileSystemWriter<AvroLocalWriter>;
+
+pub type CsvFileSystemSink = FileSystemSink<BatchMultipartWriter<CsvWriter>>;
+
+pub type LocalCsvFileSystemSink = LocalFileSystemWriter<CsvLocalWriter>;
+
 impl<R: MultiPartWriter + Send + 'static> FileSystemSink<R> {
     pub fn create_and_start(
         table: FileSystemTable,
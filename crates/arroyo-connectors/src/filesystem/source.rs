@@ -315,6 +315,10 @@ impl FileSystemSourceFunc {
                 self.read_parquet_file(ctx, record_batch_stream, obj_key, records_read)
                     .await
             }
+            Format::Csv(_) => unreachable!("csv is rejected for sources at planning time"),
+            Format::ArrowIpc(_) => {
+                unreachable!("arrow_ipc is rejected for the filesystem connector at planning time")
+            }
             Format::RawString(_) => todo!(),
             Format::RawBytes(_) => todo!(),
             Format::Protobuf(_) => todo!("Protobuf not supported"),
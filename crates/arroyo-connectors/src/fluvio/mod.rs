@@ -195,7 +195,8 @@ impl Connector for FluvioConnector {
                     config
                         .format
                         .ok_or_else(|| anyhow!("format required for fluvio sink"))?,
-                ),
+                )
+                .with_framing(config.framing),
             }))),
         }
     }
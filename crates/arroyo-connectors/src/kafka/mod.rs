@@ -1,5 +1,5 @@
 use anyhow::{anyhow, bail};
-use arrow::datatypes::DataType;
+use arrow::datatypes::{DataType, Fields};
 use arroyo_formats::de::ArrowDeserializer;
 use arroyo_formats::ser::ArrowSerializer;
 use arroyo_operator::connector::{Connection, MetadataDef};
@@ -129,6 +129,15 @@ impl KafkaConnector {
                     },
                     timestamp_field: options.remove("sink.timestamp_field"),
                     key_field: options.remove("sink.key_field"),
+                    topic_field: options.remove("sink.topic_field"),
+                    allowed_topics: options
+                        .remove("sink.allowed_topics")
+                        .map(|topics| split_list(&topics))
+                        .unwrap_or_default(),
+                    header_fields: options
+                        .remove("sink.header_fields")
+                        .map(|fields| split_list(&fields))
+                        .unwrap_or_default(),
                 }
             }
             _ => {
@@ -182,6 +191,50 @@ impl Connector for KafkaConnector {
         (*config.bootstrap_servers).clone()
     }
 
+    fn check_sink_fields(&self, table: &KafkaTable, fields: &Fields) -> anyhow::Result<()> {
+        if let TableType::Sink {
+            topic_field: Some(topic_field),
+            ..
+        } = &table.type_
+        {
+            match fields.find(topic_field) {
+                None => bail!(
+                    "Kafka sink configured with topic_field '{}', but that does not appear in \
+                    the schema",
+                    topic_field
+                ),
+                Some((_, f)) if *f.data_type() != DataType::Utf8 => bail!(
+                    "Kafka sink configured with topic_field '{}', but it has type {}, not TEXT",
+                    topic_field,
+                    f.data_type()
+                ),
+                Some(_) => {}
+            }
+        }
+
+        if let TableType::Sink { header_fields, .. } = &table.type_ {
+            for header_field in header_fields {
+                match fields.find(header_field) {
+                    None => bail!(
+                        "Kafka sink configured with header field '{}', but that does not appear \
+                        in the schema",
+                        header_field
+                    ),
+                    Some((_, f)) if !matches!(f.data_type(), DataType::Utf8 | DataType::Binary) => {
+                        bail!(
+                            "Kafka sink configured with header field '{}', but it has type {}, \
+                            not TEXT or BYTEA",
+                            header_field,
+                            f.data_type()
+                        )
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn from_config(
         &self,
         id: Option<i64>,
@@ -190,6 +243,21 @@ impl Connector for KafkaConnector {
         table: KafkaTable,
         schema: Option<&ConnectionSchema>,
     ) -> anyhow::Result<Connection> {
+        if let TableType::Sink {
+            topic_field: Some(topic_field),
+            allowed_topics,
+            ..
+        } = &table.type_
+        {
+            if allowed_topics.is_empty() {
+                bail!(
+                    "Kafka sink with topic_field '{}' must set allowed_topics to the topics or \
+                    topic prefixes it may write to",
+                    topic_field
+                );
+            }
+        }
+
         let (typ, desc) = match table.type_ {
             TableType::Source { .. } => (
                 ConnectionType::Source,
@@ -326,6 +394,10 @@ impl Connector for KafkaConnector {
                 name: "timestamp",
                 data_type: DataType::Int64,
             },
+            MetadataDef {
+                name: "headers.*",
+                data_type: DataType::Utf8,
+            },
         ]
     }
 
@@ -413,6 +485,9 @@ impl Connector for KafkaConnector {
                 commit_mode,
                 key_field,
                 timestamp_field,
+                topic_field,
+                allowed_topics,
+                header_fields,
             } => Ok(OperatorNode::from_operator(Box::new(KafkaSinkFunc {
                 bootstrap_servers: profile.bootstrap_servers.to_string(),
                 producer: None,
@@ -421,12 +496,18 @@ impl Connector for KafkaConnector {
                 timestamp_col: None,
                 key_field: key_field.clone(),
                 key_col: None,
+                topic_field: topic_field.clone(),
+                topic_col: None,
+                allowed_topics: allowed_topics.clone(),
+                header_fields: header_fields.clone(),
+                header_cols: vec![],
                 write_futures: vec![],
                 client_config: client_configs(&profile, &table),
                 topic: table.topic,
                 serializer: ArrowSerializer::new(
                     config.format.expect("Format must be defined for KafkaSink"),
-                ),
+                )
+                .with_framing(config.framing),
             }))),
         }
     }
@@ -688,6 +769,9 @@ impl KafkaTester {
             Format::Parquet(_) => {
                 unreachable!()
             }
+            Format::Csv(_) => {
+                bail!("csv format is not supported by the kafka connector");
+            }
             Format::RawString(_) => {
                 String::from_utf8(msg).map_err(|e|
                     anyhow!("Failed to parse message as UTF-8: {:?}. Ensure that the format and schema type are correct.", e))?;
@@ -695,6 +779,18 @@ impl KafkaTester {
             Format::RawBytes(_) => {
                 // all bytes are valid
             }
+            Format::ArrowIpc(_) => {
+                let aschema: ArroyoSchema = schema.clone().into();
+                let batches = arroyo_formats::ipc::read_stream(&msg).map_err(|e| {
+                    anyhow!("Failed to parse message as an Arrow IPC stream: {:?}. Ensure that the format and schema type are correct.", e)
+                })?;
+                for batch in batches {
+                    arroyo_formats::ipc::project(&batch, &aschema.schema_without_timestamp())
+                        .map_err(|e| {
+                            anyhow!("Arrow IPC message does not match the schema: {}", e)
+                        })?;
+                }
+            }
             Format::Protobuf(_) => {
                 let aschema: ArroyoSchema = schema.clone().into();
                 let mut deserializer =
@@ -903,6 +999,13 @@ impl SourceOffset {
     }
 }
 
+fn split_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty())
+        .collect()
+}
+
 pub fn client_configs(connection: &KafkaConfig, table: &KafkaTable) -> HashMap<String, String> {
     let mut client_configs: HashMap<String, String> = HashMap::new();
 
@@ -8,6 +8,7 @@ use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use tracing::{error, warn};
 
+use rdkafka::message::{Header, OwnedHeaders};
 use rdkafka::producer::{DeliveryFuture, FutureProducer, FutureRecord, Producer};
 use rdkafka::util::Timeout;
 
@@ -15,6 +16,7 @@ use rdkafka::ClientConfig;
 
 use super::SinkCommitMode;
 use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::compute::cast;
 use arrow::datatypes::{DataType, TimeUnit};
 use arroyo_formats::ser::ArrowSerializer;
 use arroyo_operator::context::ArrowContext;
@@ -37,6 +39,11 @@ pub struct KafkaSinkFunc {
     pub timestamp_col: Option<usize>,
     pub key_field: Option<String>,
     pub key_col: Option<usize>,
+    pub topic_field: Option<String>,
+    pub topic_col: Option<usize>,
+    pub allowed_topics: Vec<String>,
+    pub header_fields: Vec<String>,
+    pub header_cols: Vec<(String, usize)>,
     pub producer: Option<FutureProducer>,
     pub write_futures: Vec<DeliveryFuture>,
     pub client_config: HashMap<String, String>,
@@ -125,6 +132,52 @@ impl KafkaSinkFunc {
         }
     }
 
+    fn set_topic_col(&mut self, schema: &ArroyoSchema) {
+        if let Some(f) = &self.topic_field {
+            // the field is checked when the table is created and planned
+            self.topic_col = Some(
+                schema
+                    .schema
+                    .index_of(f)
+                    .expect("topic_field should be in the schema"),
+            );
+        }
+    }
+
+    /// Whether a topic selected by the topic field may be written to; it must be a legal Kafka
+    /// topic name and match one of the allowed topics
+    fn is_topic_allowed(&self, topic: &str) -> bool {
+        let legal = !topic.is_empty()
+            && topic.len() <= 249
+            && topic != "."
+            && topic != ".."
+            && topic
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+
+        legal
+            && self
+                .allowed_topics
+                .iter()
+                .any(|allowed| match allowed.strip_suffix('*') {
+                    Some(prefix) => topic.starts_with(prefix),
+                    None => allowed == topic,
+                })
+    }
+
+    fn set_header_cols(&mut self, schema: &ArroyoSchema) {
+        self.header_cols =
+            self.header_fields
+                .iter()
+                .map(|f| {
+                    let index = schema.schema.index_of(f).expect(
+                        "header fields should have been checked when the table was created",
+                    );
+                    (f.clone(), index)
+                })
+                .collect();
+    }
+
     fn init_producer(&mut self, task_info: &TaskInfo) -> Result<()> {
         let mut client_config = ClientConfig::new();
         client_config.set("bootstrap.servers", &self.bootstrap_servers);
@@ -182,19 +235,24 @@ impl KafkaSinkFunc {
 
     async fn publish(
         &mut self,
+        topic: &str,
         ts: Option<i64>,
         k: Option<Vec<u8>>,
         v: Vec<u8>,
+        headers: Option<OwnedHeaders>,
         ctx: &mut ArrowContext,
     ) {
         let mut rec = {
-            let mut rec = FutureRecord::<Vec<u8>, Vec<u8>>::to(&self.topic);
+            let mut rec = FutureRecord::<Vec<u8>, Vec<u8>>::to(topic);
             if let Some(ts) = ts {
                 rec = rec.timestamp(ts);
             }
             if let Some(k) = k.as_ref() {
                 rec = rec.key(k);
             }
+            if let Some(headers) = headers {
+                rec = rec.headers(headers);
+            }
 
             rec.payload(&v)
         };
@@ -244,6 +302,9 @@ impl ArrowOperator for KafkaSinkFunc {
                     AsDisplayable::Debug(&self.timestamp_field),
                 ),
                 ("key_field", AsDisplayable::Debug(&self.key_field)),
+                ("topic_field", AsDisplayable::Debug(&self.topic_field)),
+                ("allowed_topics", AsDisplayable::Debug(&self.allowed_topics)),
+                ("header_fields", AsDisplayable::Debug(&self.header_fields)),
                 ("client_config", AsDisplayable::Debug(&self.client_config)),
             ],
         }
@@ -271,6 +332,8 @@ impl ArrowOperator for KafkaSinkFunc {
     async fn on_start(&mut self, ctx: &mut ArrowContext) {
         self.set_timestamp_col(&ctx.in_schemas[0]);
         self.set_key_col(&ctx.in_schemas[0]);
+        self.set_topic_col(&ctx.in_schemas[0]);
+        self.set_header_cols(&ctx.in_schemas[0]);
 
         self.init_producer(&ctx.task_info)
             .expect("Producer creation failed");
@@ -287,8 +350,38 @@ impl ArrowOperator for KafkaSinkFunc {
             .downcast_ref::<arrow::array::TimestampNanosecondArray>();
 
         let keys = self.key_col.map(|i| batch.column(i).as_string::<i32>());
+        let topics = self.topic_col.map(|i| batch.column(i).as_string::<i32>());
+        let default_topic = self.topic.clone();
+
+        let header_columns: Vec<_> = self
+            .header_cols
+            .iter()
+            .map(|(name, i)| {
+                // headers are bytes, so TEXT columns are written as their UTF-8 encoding
+                let column = cast(batch.column(*i), &DataType::Binary)
+                    .expect("header fields should be TEXT or BYTEA");
+                (name.clone(), column.as_binary::<i32>().clone())
+            })
+            .collect();
+
+        // rows that select a disallowed topic are dropped and reported once per batch
+        let mut dropped = 0;
+        let mut disallowed_topic = None;
 
         for (i, v) in values.enumerate() {
+            let topic = match topics {
+                Some(topics) if !topics.is_null(i) => {
+                    let topic = topics.value(i);
+                    if !self.is_topic_allowed(topic) {
+                        dropped += 1;
+                        disallowed_topic.get_or_insert(topic);
+                        continue;
+                    }
+                    topic
+                }
+                _ => default_topic.as_str(),
+            };
+
             // kafka timestamp as unix millis
             let timestamp = timestamps.map(|ts| {
                 if ts.is_null(i) {
@@ -299,7 +392,32 @@ impl ArrowOperator for KafkaSinkFunc {
             });
             // TODO: this copy should be unnecessary but likely needs a custom trait impl
             let key = keys.map(|k| k.value(i).as_bytes().to_vec());
-            self.publish(timestamp, key, v, ctx).await;
+
+            let headers = (!header_columns.is_empty()).then(|| {
+                header_columns
+                    .iter()
+                    .filter(|(_, col)| !col.is_null(i))
+                    .fold(OwnedHeaders::new(), |headers, (name, col)| {
+                        headers.insert(Header {
+                            key: name,
+                            value: Some(col.value(i)),
+                        })
+                    })
+            });
+
+            self.publish(topic, timestamp, key, v, headers, ctx).await;
+        }
+
+        if let Some(topic) = disallowed_topic {
+            ctx.report_error(
+                "Kafka sink dropped rows with a disallowed topic",
+                format!(
+                    "dropped {} rows; row selected topic '{}', which is invalid or not in \
+                    allowed_topics {:?}",
+                    dropped, topic, self.allowed_topics
+                ),
+            )
+            .await;
         }
     }
 
@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
-use arrow::array::{RecordBatch, UInt32Array};
+use arrow::array::{RecordBatch, StringArray, UInt32Array};
 use arrow::datatypes::Field;
 use arrow::datatypes::{DataType, Schema, SchemaRef};
 use arroyo_formats::ser::ArrowSerializer;
@@ -12,15 +12,17 @@ use arroyo_operator::context::ArrowContext;
 use arroyo_operator::operator::ArrowOperator;
 use arroyo_rpc::df::ArroyoSchema;
 use arroyo_rpc::formats::{Format, JsonFormat};
+use arroyo_rpc::ControlResp;
 use arroyo_types::CheckpointBarrier;
 use arroyo_types::*;
 use itertools::Itertools;
 use rdkafka::admin::{AdminClient, AdminOptions, NewTopic};
 use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::message::Headers;
 use rdkafka::producer::Producer;
 use rdkafka::{ClientConfig, Message};
 use serde::Deserialize;
-use tokio::sync::mpsc::channel;
+use tokio::sync::mpsc::{channel, Receiver};
 
 use super::{ConsistencyMode, KafkaSinkFunc};
 
@@ -70,22 +72,16 @@ impl KafkaTopicTester {
     }
 
     async fn get_sink_with_writes(&self) -> KafkaSinkWithWrites {
-        let mut kafka = KafkaSinkFunc {
-            topic: self.topic.to_string(),
-            bootstrap_servers: self.server.to_string(),
-            producer: None,
-            consistency_mode: ConsistencyMode::AtLeastOnce,
-            timestamp_field: None,
-            timestamp_col: None,
-            key_field: None,
-            write_futures: vec![],
-            client_config: HashMap::new(),
-            serializer: ArrowSerializer::new(Format::Json(JsonFormat::default())),
-            key_col: None,
-        };
+        self.start_sink(self.sink_func(), schema()).await
+    }
+
+    fn sink_func(&self) -> KafkaSinkFunc {
+        sink_func(&self.topic, &self.server)
+    }
 
+    async fn start_sink(&self, mut kafka: KafkaSinkFunc, schema: SchemaRef) -> KafkaSinkWithWrites {
         let (_, control_rx) = channel(128);
-        let (command_tx, _) = channel(128);
+        let (command_tx, command_rx) = channel(128);
 
         let task_info = get_test_task_info();
 
@@ -95,7 +91,7 @@ impl KafkaTopicTester {
             control_rx,
             command_tx,
             1,
-            vec![ArroyoSchema::new_unkeyed(schema(), 0)],
+            vec![ArroyoSchema::new_unkeyed(schema, 0)],
             None,
             None,
             vec![vec![]],
@@ -105,10 +101,18 @@ impl KafkaTopicTester {
 
         kafka.on_start(&mut ctx).await;
 
-        KafkaSinkWithWrites { sink: kafka, ctx }
+        KafkaSinkWithWrites {
+            sink: kafka,
+            ctx,
+            command_rx,
+        }
     }
 
     fn get_consumer(&mut self, job_id: &str) -> StreamConsumer {
+        self.get_consumer_for(job_id, &[&self.topic.clone()])
+    }
+
+    fn get_consumer_for(&mut self, job_id: &str, topics: &[&str]) -> StreamConsumer {
         let base_consumer: StreamConsumer = ClientConfig::new()
             .set("bootstrap.servers", self.server.to_string())
             .set("enable.auto.commit", "false")
@@ -119,11 +123,32 @@ impl KafkaTopicTester {
             .create()
             .expect("Consumer creation failed");
 
-        base_consumer.subscribe(&[&self.topic]).expect("success");
+        base_consumer.subscribe(topics).expect("success");
         base_consumer
     }
 }
 
+fn sink_func(topic: &str, server: &str) -> KafkaSinkFunc {
+    KafkaSinkFunc {
+        topic: topic.to_string(),
+        bootstrap_servers: server.to_string(),
+        producer: None,
+        consistency_mode: ConsistencyMode::AtLeastOnce,
+        timestamp_field: None,
+        timestamp_col: None,
+        key_field: None,
+        topic_field: None,
+        topic_col: None,
+        allowed_topics: vec![],
+        header_fields: vec![],
+        header_cols: vec![],
+        write_futures: vec![],
+        client_config: HashMap::new(),
+        serializer: ArrowSerializer::new(Format::Json(JsonFormat::default())),
+        key_col: None,
+    }
+}
+
 async fn get_data(consumer: &mut StreamConsumer) -> String {
     let owned_message = consumer
         .recv()
@@ -137,6 +162,7 @@ async fn get_data(consumer: &mut StreamConsumer) -> String {
 struct KafkaSinkWithWrites {
     sink: KafkaSinkFunc,
     ctx: ArrowContext,
+    command_rx: Receiver<ControlResp>,
 }
 
 #[tokio::test]
@@ -208,3 +234,162 @@ async fn test_kafka() {
         assert_eq!(message, result.value);
     }
 }
+
+#[tokio::test]
+async fn test_kafka_header_fields() {
+    let mut kafka_topic_tester = KafkaTopicTester {
+        topic: "arroyo-sink-headers".to_string(),
+        server: "0.0.0.0:9092".to_string(),
+    };
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("value", DataType::UInt32, false),
+        Field::new("trace_id", DataType::Utf8, true),
+    ]));
+
+    kafka_topic_tester.create_topic("headers", 1).await;
+    let mut sink = kafka_topic_tester.sink_func();
+    sink.header_fields = vec!["trace_id".to_string()];
+    let mut sink_with_writes = kafka_topic_tester.start_sink(sink, schema.clone()).await;
+    let mut consumer = kafka_topic_tester.get_consumer("2");
+
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(UInt32Array::from_iter_values(0..4)),
+            Arc::new(StringArray::from(vec![
+                Some("trace-0"),
+                None,
+                Some("trace-2"),
+                None,
+            ])),
+        ],
+    )
+    .unwrap();
+
+    sink_with_writes
+        .sink
+        .process_batch(batch, &mut sink_with_writes.ctx)
+        .await;
+    sink_with_writes
+        .sink
+        .producer
+        .as_ref()
+        .unwrap()
+        .flush(Duration::from_secs(3))
+        .unwrap();
+
+    for i in 0..4 {
+        let message = consumer
+            .recv()
+            .await
+            .expect("shouldn't have errored")
+            .detach();
+        let result: TestData = serde_json::from_slice(message.payload().unwrap()).unwrap();
+        assert_eq!(i, result.value);
+
+        let trace_id = message.headers().and_then(|headers| {
+            headers
+                .iter()
+                .find(|header| header.key == "trace_id")
+                .map(|header| String::from_utf8(header.value.unwrap().to_vec()).unwrap())
+        });
+        let expected = (i % 2 == 0).then(|| format!("trace-{}", i));
+        assert_eq!(expected, trace_id);
+    }
+}
+
+#[test]
+fn test_is_topic_allowed() {
+    let mut sink = sink_func("default", "0.0.0.0:9092");
+    assert!(!sink.is_topic_allowed("customer-1"));
+
+    sink.allowed_topics = vec!["events".to_string(), "customer-*".to_string()];
+    assert!(sink.is_topic_allowed("events"));
+    assert!(sink.is_topic_allowed("customer-1"));
+    assert!(sink.is_topic_allowed("customer-"));
+    assert!(!sink.is_topic_allowed("events-2"));
+    assert!(!sink.is_topic_allowed("other"));
+
+    // topics must also be legal kafka topic names
+    assert!(!sink.is_topic_allowed("customer-a/b"));
+    assert!(!sink.is_topic_allowed(&format!("customer-{}", "a".repeat(249))));
+
+    sink.allowed_topics = vec!["*".to_string()];
+    assert!(sink.is_topic_allowed("anything"));
+    assert!(!sink.is_topic_allowed(""));
+    assert!(!sink.is_topic_allowed(".."));
+}
+
+#[tokio::test]
+async fn test_kafka_topic_field() {
+    let mut kafka_topic_tester = KafkaTopicTester {
+        topic: "arroyo-sink-routing".to_string(),
+        server: "0.0.0.0:9092".to_string(),
+    };
+    let routed_tester = KafkaTopicTester {
+        topic: "arroyo-sink-routing-customer-1".to_string(),
+        server: "0.0.0.0:9092".to_string(),
+    };
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("value", DataType::UInt32, false),
+        Field::new("topic", DataType::Utf8, true),
+    ]));
+
+    kafka_topic_tester.create_topic("routing", 1).await;
+    routed_tester.create_topic("routing", 1).await;
+    let mut sink = kafka_topic_tester.sink_func();
+    sink.topic_field = Some("topic".to_string());
+    sink.allowed_topics = vec!["arroyo-sink-routing-customer-*".to_string()];
+    let mut sink_with_writes = kafka_topic_tester.start_sink(sink, schema.clone()).await;
+
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(UInt32Array::from_iter_values(0..5)),
+            Arc::new(StringArray::from(vec![
+                Some("arroyo-sink-routing-customer-1"),
+                None,
+                Some("arroyo-sink-routing-customer-1"),
+                None,
+                Some("arroyo-sink-routing-other"),
+            ])),
+        ],
+    )
+    .unwrap();
+
+    sink_with_writes
+        .sink
+        .process_batch(batch, &mut sink_with_writes.ctx)
+        .await;
+    sink_with_writes
+        .sink
+        .producer
+        .as_ref()
+        .unwrap()
+        .flush(Duration::from_secs(3))
+        .unwrap();
+
+    // the row for a topic outside allowed_topics is dropped and reported
+    match sink_with_writes.command_rx.try_recv().unwrap() {
+        ControlResp::Error { details, .. } => {
+            assert!(details.contains("arroyo-sink-routing-other"), "{}", details);
+        }
+        _ => panic!("expected an error to be reported"),
+    }
+
+    // rows with a null topic go to the configured topic
+    let mut consumer = kafka_topic_tester.get_consumer("3");
+    for expected in [1, 3] {
+        let result: TestData = serde_json::from_str(&get_data(&mut consumer).await).unwrap();
+        assert_eq!(expected, result.value);
+    }
+
+    let mut consumer =
+        kafka_topic_tester.get_consumer_for("4", &["arroyo-sink-routing-customer-1"]);
+    for expected in [0, 2] {
+        let result: TestData = serde_json::from_str(&get_data(&mut consumer).await).unwrap();
+        assert_eq!(expected, result.value);
+    }
+}
@@ -12,6 +12,7 @@ use async_trait::async_trait;
 use bincode::{Decode, Encode};
 use governor::{Quota, RateLimiter as GovernorRateLimiter};
 use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::{BorrowedMessage, Headers};
 use rdkafka::{ClientConfig, Message as KMessage, Offset, TopicPartitionList};
 use std::collections::HashMap;
 use std::num::NonZeroU32;
@@ -190,7 +191,12 @@ impl KafkaSourceFunc {
                                             "partition" => FieldValueType::Int32(msg.partition()),
                                             "topic" => FieldValueType::String(topic),
                                             "timestamp" => FieldValueType::Int64(timestamp),
-                                            k => unreachable!("Invalid metadata key '{}'", k),
+                                            k => {
+                                                let Some(header) = k.strip_prefix("headers.") else {
+                                                    unreachable!("Invalid metadata key '{}'", k);
+                                                };
+                                                FieldValueType::NullableString(header_value(&msg, header))
+                                            }
                                         });
                                     }
                                     Some(connector_metadata)
@@ -198,7 +204,8 @@ impl KafkaSourceFunc {
                                     None
                                 };
 
-                                ctx.deserialize_slice(v, from_millis(timestamp.max(0) as u64), connector_metadata.as_ref()).await?;
+                                ctx.deserialize_slice_from_partition(v, from_millis(timestamp.max(0) as u64), connector_metadata.as_ref(),
+                                    topic, msg.partition(), msg.offset()).await?;
 
 
                                 if ctx.should_flush() {
@@ -273,6 +280,15 @@ impl KafkaSourceFunc {
     }
 }
 
+/// Returns the value of the last header with the given name, if it is present and valid UTF-8
+fn header_value<'a>(msg: &'a BorrowedMessage<'_>, name: &str) -> Option<&'a str> {
+    msg.headers()?
+        .iter()
+        .filter(|h| h.key == name)
+        .last()
+        .and_then(|h| std::str::from_utf8(h.value?).ok())
+}
+
 #[async_trait]
 impl SourceOperator for KafkaSourceFunc {
     async fn run(&mut self, ctx: &mut ArrowContext) -> SourceFinishType {
@@ -5,7 +5,8 @@ use arroyo_state::tables::ErasedTable;
 use arroyo_state::{BackingStore, StateBackend};
 use rand::random;
 
-use arrow::array::{Array, StringArray};
+use arrow::array::{Array, AsArray, StringArray};
+use arrow::datatypes::Int64Type;
 use arrow::datatypes::TimeUnit;
 use std::collections::{HashMap, VecDeque};
 use std::num::NonZeroU32;
@@ -23,6 +24,7 @@ use arroyo_types::{
     single_item_hash_map, to_micros, ArrowMessage, CheckpointBarrier, SignalMessage, TaskInfo,
 };
 use rdkafka::admin::{AdminClient, AdminOptions, NewTopic};
+use rdkafka::message::{Header, OwnedHeaders};
 use rdkafka::producer::{BaseProducer, BaseRecord};
 use rdkafka::ClientConfig;
 use serde::{Deserialize, Serialize};
@@ -165,6 +167,20 @@ impl KafkaTopicProducer {
             .send(BaseRecord::<(), String>::to(&self.topic).payload(&json))
             .expect("could not send message")
     }
+
+    fn send_data_with_headers(&mut self, data: TestData, trace_id: Option<&str>) {
+        let json = serde_json::to_string(&data).unwrap();
+        let mut record = BaseRecord::<(), String>::to(&self.topic).payload(&json);
+        if let Some(trace_id) = trace_id {
+            record = record.headers(OwnedHeaders::new().insert(Header {
+                key: "trace_id",
+                value: Some(trace_id),
+            }));
+        }
+        self.base_producer
+            .send(record)
+            .expect("could not send message")
+    }
 }
 
 struct KafkaSourceWithReads {
@@ -358,10 +374,16 @@ async fn test_kafka_with_metadata_fields() {
     kafka_topic_tester.create_topic().await;
 
     // Prepare metadata fields
-    let metadata_fields = vec![MetadataField {
-        field_name: "offset".to_string(),
-        key: "offset_id".to_string(),
-    }];
+    let metadata_fields = vec![
+        MetadataField {
+            field_name: "offset".to_string(),
+            key: "offset_id".to_string(),
+        },
+        MetadataField {
+            field_name: "trace_id".to_string(),
+            key: "headers.trace_id".to_string(),
+        },
+    ];
 
     // Set metadata fields in KafkaSourceFunc
     let mut kafka = KafkaSourceFunc {
@@ -379,9 +401,9 @@ async fn test_kafka_with_metadata_fields() {
         metadata_fields,
     };
 
-    let (_to_control_tx, control_rx) = channel(128);
+    let (to_control_tx, control_rx) = channel(128);
     let (command_tx, _from_control_rx) = channel(128);
-    let (data_tx, _recv) = batch_bounded(128);
+    let (data_tx, mut recv) = batch_bounded(128);
 
     let checkpoint_metadata = None;
 
@@ -401,6 +423,7 @@ async fn test_kafka_with_metadata_fields() {
                 ),
                 Field::new("value", DataType::Utf8, false),
                 Field::new("offset", DataType::Int64, false),
+                Field::new("trace_id", DataType::Utf8, true),
             ])),
             0,
         )),
@@ -414,27 +437,42 @@ async fn test_kafka_with_metadata_fields() {
         kafka.run(&mut ctx).await;
     });
 
-    let mut reader = kafka_topic_tester
-        .get_source_with_reader(task_info.clone(), None)
-        .await;
     let mut producer = kafka_topic_tester.get_producer();
 
-    // Send test data
-    let expected_messages: Vec<_> = (1u64..=21)
-        .map(|i| {
-            let data = TestData { i };
-            producer.send_data(data.clone());
-            serde_json::to_string(&data).unwrap()
-        })
-        .collect();
+    // every other message has a trace_id header
+    let mut expected = VecDeque::new();
+    for i in 0u64..10 {
+        let data = TestData { i };
+        let trace_id = (i % 2 == 0).then(|| format!("trace-{}", i));
+        producer.send_data_with_headers(data.clone(), trace_id.as_deref());
+        expected.push_back((serde_json::to_string(&data).unwrap(), i as i64, trace_id));
+    }
 
-    // Verify received messages
-    reader
-        .assert_next_message_record_values(expected_messages.into())
-        .await;
+    while !expected.is_empty() {
+        let batch = match recv.recv().await {
+            Some(ArrowMessage::Data(batch)) => batch,
+            Some(_) => continue,
+            None => unreachable!("source should still be running"),
+        };
+        let values = batch.column_by_name("value").unwrap().as_string::<i32>();
+        let offsets = batch
+            .column_by_name("offset")
+            .unwrap()
+            .as_primitive::<Int64Type>();
+        let trace_ids = batch.column_by_name("trace_id").unwrap().as_string::<i32>();
+        for row in 0..batch.num_rows() {
+            let (value, offset, trace_id) =
+                expected.pop_front().expect("found more rows than expected");
+            assert_eq!(value, values.value(row));
+            assert_eq!(offset, offsets.value(row));
+            assert_eq!(
+                trace_id.as_deref(),
+                trace_ids.is_valid(row).then(|| trace_ids.value(row))
+            );
+        }
+    }
 
-    reader
-        .to_control_tx
+    to_control_tx
         .send(ControlMessage::Stop {
             mode: arroyo_rpc::grpc::rpc::StopMode::Graceful,
         })
@@ -210,7 +210,8 @@ impl Connector for KinesisConnector {
                         config
                             .format
                             .ok_or_else(|| anyhow!("Format must be defined for KinesisSink"))?,
-                    ),
+                    )
+                    .with_framing(config.framing),
                     flush_config,
                 })))
             }
@@ -5,6 +5,7 @@ use crate::kinesis::KinesisConnector;
 use crate::mqtt::MqttConnector;
 use crate::polling_http::PollingHTTPConnector;
 use crate::preview::PreviewConnector;
+use crate::pubsub::PubSubConnector;
 use crate::redis::RedisConnector;
 use crate::single_file::SingleFileConnector;
 use crate::stdout::StdoutConnector;
@@ -18,6 +19,7 @@ use arroyo_rpc::primitive_to_sql;
 use arroyo_rpc::var_str::VarStr;
 use arroyo_types::string_to_map;
 use blackhole::BlackholeConnector;
+use datagen::DatagenConnector;
 use fluvio::FluvioConnector;
 use impulse::ImpulseConnector;
 use nats::NatsConnector;
@@ -25,6 +27,7 @@ use nexmark::NexmarkConnector;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use socket::SocketConnector;
 use sse::SSEConnector;
 use std::collections::HashMap;
 use std::time::Duration;
@@ -36,6 +39,7 @@ use self::kafka::KafkaConnector;
 
 pub mod blackhole;
 pub mod confluent;
+pub mod datagen;
 pub mod filesystem;
 pub mod fluvio;
 pub mod impulse;
@@ -46,8 +50,10 @@ pub mod nats;
 pub mod nexmark;
 pub mod polling_http;
 pub mod preview;
+pub mod pubsub;
 pub mod redis;
 pub mod single_file;
+pub mod socket;
 pub mod sse;
 pub mod stdout;
 pub mod webhook;
@@ -57,6 +63,7 @@ pub fn connectors() -> HashMap<&'static str, Box<dyn ErasedConnector>> {
     let connectors: Vec<Box<dyn ErasedConnector>> = vec![
         Box::new(BlackholeConnector {}),
         Box::new(ConfluentConnector {}),
+        Box::new(DatagenConnector {}),
         Box::new(DeltaLakeConnector {}),
         Box::new(FileSystemConnector {}),
         Box::new(FluvioConnector {}),
@@ -68,8 +75,10 @@ pub fn connectors() -> HashMap<&'static str, Box<dyn ErasedConnector>> {
         Box::new(NexmarkConnector {}),
         Box::new(PollingHTTPConnector {}),
         Box::new(PreviewConnector {}),
+        Box::new(PubSubConnector {}),
         Box::new(RedisConnector {}),
         Box::new(SingleFileConnector {}),
+        Box::new(SocketConnector {}),
         Box::new(SSEConnector {}),
         Box::new(StdoutConnector {}),
         Box::new(WebhookConnector {}),
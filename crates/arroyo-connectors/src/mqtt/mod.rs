@@ -302,7 +302,8 @@ impl Connector for MqttConnector {
                     config
                         .format
                         .ok_or_else(|| anyhow!("format is required for mqtt sink"))?,
-                ),
+                )
+                .with_framing(config.framing),
                 stopped: Arc::new(AtomicBool::new(false)),
                 client: None,
             })),
@@ -375,7 +375,8 @@ impl Connector for NatsConnector {
                     publisher: None,
                     serializer: ArrowSerializer::new(
                         config.format.expect("Format must be set for NATS source"),
-                    ),
+                    )
+                    .with_framing(config.framing),
                 }))
             }
         })
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail};
+use arrow::datatypes::DataType;
+use arroyo_formats::ser::ArrowSerializer;
+use arroyo_operator::connector::{Connection, Connector, MetadataDef};
+use arroyo_operator::operator::OperatorNode;
+use arroyo_rpc::api_types::connections::{
+    ConnectionProfile, ConnectionSchema, ConnectionType, TestSourceMessage,
+};
+use arroyo_rpc::OperatorConfig;
+use google_cloud_gax::conn::Environment;
+use google_cloud_pubsub::client::{Client, ClientConfig};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot::Receiver;
+use typify::import_types;
+
+use crate::pubsub::sink::PubSubSinkFunc;
+use crate::pubsub::source::PubSubSourceFunc;
+use crate::{pull_opt, pull_option_to_i64};
+
+const CONFIG_SCHEMA: &str = include_str!("./profile.json");
+const TABLE_SCHEMA: &str = include_str!("./table.json");
+const ICON: &str = include_str!("./pubsub.svg");
+
+pub mod sink;
+pub mod source;
+
+import_types!(schema = "src/pubsub/profile.json");
+import_types!(schema = "src/pubsub/table.json");
+
+const DEFAULT_ACK_DEADLINE_SECONDS: i64 = 60;
+const DEFAULT_MAX_OUTSTANDING_MESSAGES: i64 = 100_000;
+
+pub struct PubSubConnector {}
+
+impl PubSubConnector {
+    pub fn connection_from_options(
+        options: &mut HashMap<String, String>,
+    ) -> anyhow::Result<PubSubConfig> {
+        let emulator = options
+            .remove("emulator")
+            .map(|s| {
+                s.parse::<bool>()
+                    .map_err(|_| anyhow!("'emulator' must be either 'true' or 'false'"))
+            })
+            .transpose()?;
+
+        Ok(PubSubConfig {
+            project_id: pull_opt("project_id", options)?,
+            endpoint: options.remove("endpoint"),
+            emulator,
+        })
+    }
+
+    pub fn table_from_options(
+        options: &mut HashMap<String, String>,
+    ) -> anyhow::Result<PubSubTable> {
+        let typ = pull_opt("type", options)?;
+
+        let table_type = match typ.as_str() {
+            "source" => TableType::Source {
+                subscription: pull_opt("subscription", options)?,
+                ack_deadline_seconds: pull_option_to_i64("source.ack_deadline_seconds", options)?,
+                max_outstanding_messages: pull_option_to_i64(
+                    "source.max_outstanding_messages",
+                    options,
+                )?,
+            },
+            "sink" => TableType::Sink {
+                topic: pull_opt("topic", options)?,
+                ordering_key_field: options.remove("sink.ordering_key_field"),
+            },
+            _ => {
+                bail!("type must be one of 'source' or 'sink'")
+            }
+        };
+
+        Ok(PubSubTable { type_: table_type })
+    }
+}
+
+impl Connector for PubSubConnector {
+    type ProfileT = PubSubConfig;
+    type TableT = PubSubTable;
+
+    fn name(&self) -> &'static str {
+        "pubsub"
+    }
+
+    fn metadata(&self) -> arroyo_rpc::api_types::connections::Connector {
+        arroyo_rpc::api_types::connections::Connector {
+            id: "pubsub".to_string(),
+            name: "Google Cloud Pub/Sub".to_string(),
+            icon: ICON.to_string(),
+            description: "Read from subscriptions and write to topics in Google Cloud Pub/Sub"
+                .to_string(),
+            enabled: true,
+            source: true,
+            sink: true,
+            testing: true,
+            hidden: false,
+            custom_schemas: true,
+            connection_config: Some(CONFIG_SCHEMA.to_string()),
+            table_config: TABLE_SCHEMA.to_string(),
+        }
+    }
+
+    fn config_description(&self, config: Self::ProfileT) -> String {
+        match config.endpoint {
+            Some(endpoint) => format!("{} ({})", config.project_id, endpoint),
+            None => config.project_id,
+        }
+    }
+
+    fn table_type(&self, _: Self::ProfileT, table: Self::TableT) -> ConnectionType {
+        match table.type_ {
+            TableType::Source { .. } => ConnectionType::Source,
+            TableType::Sink { .. } => ConnectionType::Sink,
+        }
+    }
+
+    fn metadata_defs(&self) -> &'static [MetadataDef] {
+        &[
+            MetadataDef {
+                name: "message_id",
+                data_type: DataType::Utf8,
+            },
+            MetadataDef {
+                name: "ordering_key",
+                data_type: DataType::Utf8,
+            },
+            MetadataDef {
+                name: "publish_time",
+                data_type: DataType::Int64,
+            },
+            MetadataDef {
+                name: "attributes.*",
+                data_type: DataType::Utf8,
+            },
+        ]
+    }
+
+    fn test_profile(&self, profile: Self::ProfileT) -> Option<Receiver<TestSourceMessage>> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        tokio::spawn(async move {
+            let message = match create_client(&profile).await {
+                Ok(_) => TestSourceMessage::done("Successfully created Pub/Sub client"),
+                Err(e) => {
+                    TestSourceMessage::fail(format!("Failed to create Pub/Sub client: {}", e))
+                }
+            };
+
+            tx.send(message).unwrap();
+        });
+
+        Some(rx)
+    }
+
+    fn test(
+        &self,
+        _: &str,
+        config: Self::ProfileT,
+        table: Self::TableT,
+        _: Option<&ConnectionSchema>,
+        tx: Sender<TestSourceMessage>,
+    ) {
+        tokio::task::spawn(async move {
+            let resp = match test_inner(config, table, tx.clone()).await {
+                Ok(c) => TestSourceMessage::done(c),
+                Err(e) => TestSourceMessage::fail(e.to_string()),
+            };
+
+            tx.send(resp).await.unwrap();
+        });
+    }
+
+    fn from_options(
+        &self,
+        name: &str,
+        options: &mut HashMap<String, String>,
+        schema: Option<&ConnectionSchema>,
+        profile: Option<&ConnectionProfile>,
+    ) -> anyhow::Result<Connection> {
+        let connection = profile
+            .map(|p| {
+                serde_json::from_value(p.config.clone()).map_err(|e| {
+                    anyhow!("invalid config for profile '{}' in database: {}", p.id, e)
+                })
+            })
+            .unwrap_or_else(|| Self::connection_from_options(options))?;
+
+        let table = Self::table_from_options(options)?;
+
+        Self::from_config(self, None, name, connection, table, schema)
+    }
+
+    fn from_config(
+        &self,
+        id: Option<i64>,
+        name: &str,
+        config: Self::ProfileT,
+        table: Self::TableT,
+        schema: Option<&ConnectionSchema>,
+    ) -> anyhow::Result<Connection> {
+        if config.emulator == Some(true) && config.endpoint.is_none() {
+            bail!("'endpoint' must be set to the emulator address when 'emulator' is enabled");
+        }
+
+        let (typ, desc) = match &table.type_ {
+            TableType::Source {
+                subscription,
+                ack_deadline_seconds,
+                max_outstanding_messages,
+            } => {
+                if let Some(d) = ack_deadline_seconds {
+                    if !(10..=600).contains(d) {
+                        bail!("'source.ack_deadline_seconds' must be between 10 and 600");
+                    }
+                }
+                if max_outstanding_messages.is_some_and(|m| m < 1) {
+                    bail!("'source.max_outstanding_messages' must be positive");
+                }
+
+                (
+                    ConnectionType::Source,
+                    format!("PubSubSource<{}>", subscription),
+                )
+            }
+            TableType::Sink { topic, .. } => {
+                (ConnectionType::Sink, format!("PubSubSink<{}>", topic))
+            }
+        };
+
+        let schema = schema
+            .map(|s| s.to_owned())
+            .ok_or_else(|| anyhow!("No schema defined for Pub/Sub connection"))?;
+
+        let format = schema
+            .format
+            .as_ref()
+            .map(|t| t.to_owned())
+            .ok_or_else(|| anyhow!("'format' must be set for Pub/Sub connection"))?;
+
+        let config = OperatorConfig {
+            connection: serde_json::to_value(config).unwrap(),
+            table: serde_json::to_value(table).unwrap(),
+            rate_limit: None,
+            format: Some(format),
+            bad_data: schema.bad_data.clone(),
+            framing: schema.framing.clone(),
+            metadata_fields: schema.metadata_fields(),
+        };
+
+        Ok(Connection {
+            id,
+            connector: self.name(),
+            name: name.to_string(),
+            connection_type: typ,
+            schema,
+            config: serde_json::to_string(&config).unwrap(),
+            description: desc,
+        })
+    }
+
+    fn make_operator(
+        &self,
+        profile: Self::ProfileT,
+        table: Self::TableT,
+        config: OperatorConfig,
+    ) -> anyhow::Result<OperatorNode> {
+        Ok(match table.type_ {
+            TableType::Source {
+                subscription,
+                ack_deadline_seconds,
+                max_outstanding_messages,
+            } => OperatorNode::from_source(Box::new(PubSubSourceFunc::new(
+                profile,
+                subscription,
+                ack_deadline_seconds.unwrap_or(DEFAULT_ACK_DEADLINE_SECONDS) as i32,
+                max_outstanding_messages.unwrap_or(DEFAULT_MAX_OUTSTANDING_MESSAGES),
+                config
+                    .format
+                    .ok_or_else(|| anyhow!("format is required for pubsub source"))?,
+                config.framing,
+                config.bad_data,
+                config.metadata_fields,
+            ))),
+            TableType::Sink {
+                topic,
+                ordering_key_field,
+            } => OperatorNode::from_operator(Box::new(PubSubSinkFunc::new(
+                profile,
+                topic,
+                ordering_key_field,
+                ArrowSerializer::new(
+                    config
+                        .format
+                        .ok_or_else(|| anyhow!("format is required for pubsub sink"))?,
+                )
+                .with_framing(config.framing),
+            ))),
+        })
+    }
+}
+
+async fn test_inner(
+    c: PubSubConfig,
+    t: PubSubTable,
+    tx: Sender<TestSourceMessage>,
+) -> anyhow::Result<String> {
+    tx.send(TestSourceMessage::info("Connecting to Pub/Sub"))
+        .await
+        .unwrap();
+
+    let client = create_client(&c).await?;
+
+    match t.type_ {
+        TableType::Source { subscription, .. } => {
+            if !client.subscription(&subscription).exists(None).await? {
+                bail!("Subscription '{}' does not exist", subscription);
+            }
+            Ok(format!("Found subscription '{}'", subscription))
+        }
+        TableType::Sink { topic, .. } => {
+            if !client.topic(&topic).exists(None).await? {
+                bail!("Topic '{}' does not exist", topic);
+            }
+            Ok(format!("Found topic '{}'", topic))
+        }
+    }
+}
+
+/// Creates a Pub/Sub client for the configured project. Unless connecting to the emulator, this
+/// uses application default credentials.
+pub(crate) async fn create_client(c: &PubSubConfig) -> anyhow::Result<Client> {
+    let config = match (&c.endpoint, c.emulator.unwrap_or(false)) {
+        (Some(endpoint), true) => ClientConfig {
+            project_id: Some(c.project_id.clone()),
+            environment: Environment::Emulator(endpoint.clone()),
+            ..Default::default()
+        },
+        (None, true) => bail!("'endpoint' must be set when connecting to the Pub/Sub emulator"),
+        (endpoint, false) => {
+            let mut config = ClientConfig::default()
+                .with_auth()
+                .await
+                .map_err(|e| anyhow!("failed to load Google Cloud credentials: {}", e))?;
+            config.project_id = Some(c.project_id.clone());
+            if let Some(endpoint) = endpoint {
+                config.endpoint = endpoint.clone();
+            }
+            config
+        }
+    };
+
+    Client::new(config)
+        .await
+        .map_err(|e| anyhow!("failed to create Pub/Sub client: {}", e))
+}
@@ -0,0 +1,141 @@
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::datatypes::DataType;
+use arroyo_formats::ser::ArrowSerializer;
+use arroyo_operator::context::ArrowContext;
+use arroyo_operator::operator::ArrowOperator;
+use arroyo_rpc::df::ArroyoSchema;
+use arroyo_types::{CheckpointBarrier, SignalMessage};
+use async_trait::async_trait;
+use google_cloud_googleapis::pubsub::v1::PubsubMessage;
+use google_cloud_pubsub::publisher::{Awaiter, Publisher};
+use tracing::warn;
+
+use crate::pubsub::{create_client, PubSubConfig};
+
+/// The number of unconfirmed publishes after which the sink waits for them to complete
+const MAX_OUTSTANDING_PUBLISHES: usize = 10_000;
+
+pub struct PubSubSinkFunc {
+    pub config: PubSubConfig,
+    pub topic: String,
+    pub ordering_key_field: Option<String>,
+    pub ordering_key_col: Option<usize>,
+    pub serializer: ArrowSerializer,
+    publisher: Option<Publisher>,
+    outstanding: Vec<Awaiter>,
+}
+
+impl PubSubSinkFunc {
+    pub fn new(
+        config: PubSubConfig,
+        topic: String,
+        ordering_key_field: Option<String>,
+        serializer: ArrowSerializer,
+    ) -> Self {
+        Self {
+            config,
+            topic,
+            ordering_key_field,
+            ordering_key_col: None,
+            serializer,
+            publisher: None,
+            outstanding: vec![],
+        }
+    }
+
+    fn set_ordering_key_col(&mut self, schema: &ArroyoSchema) {
+        if let Some(f) = &self.ordering_key_field {
+            if let Ok(f) = schema.schema.field_with_name(f) {
+                if matches!(f.data_type(), DataType::Utf8) {
+                    self.ordering_key_col = Some(schema.schema.index_of(f.name()).unwrap());
+                } else {
+                    warn!(
+                        "Pub/Sub sink configured with ordering_key_field '{f}', but it has type \
+                {}, not TEXT... ignoring",
+                        f.data_type()
+                    );
+                }
+            } else {
+                warn!(
+                    "Pub/Sub sink configured with ordering_key_field '{f}', but that \
+                does not appear in the schema... ignoring"
+                );
+            }
+        }
+    }
+
+    /// Waits for all outstanding messages to be confirmed by Pub/Sub
+    async fn flush(&mut self, ctx: &mut ArrowContext) {
+        for awaiter in self.outstanding.drain(..) {
+            if let Err(e) = awaiter.get().await {
+                ctx.report_error("Could not write to Pub/Sub", e.to_string())
+                    .await;
+
+                panic!("Could not write to Pub/Sub: {:?}", e);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ArrowOperator for PubSubSinkFunc {
+    fn name(&self) -> String {
+        format!("pubsub-producer-{}", self.topic)
+    }
+
+    async fn on_start(&mut self, ctx: &mut ArrowContext) {
+        self.set_ordering_key_col(&ctx.in_schemas[0]);
+
+        match create_client(&self.config).await {
+            Ok(client) => {
+                // publishes with the same ordering key are sent in order by the publisher
+                self.publisher = Some(client.topic(&self.topic).new_publisher(None));
+            }
+            Err(e) => {
+                ctx.report_error("Failed to connect to Pub/Sub", e.to_string())
+                    .await;
+
+                panic!("Failed to connect to Pub/Sub: {:?}", e);
+            }
+        }
+    }
+
+    async fn process_batch(&mut self, batch: RecordBatch, ctx: &mut ArrowContext) {
+        let ordering_keys = self
+            .ordering_key_col
+            .map(|i| batch.column(i).as_string::<i32>());
+
+        let publisher = self.publisher.as_ref().expect("publisher should be set");
+        let mut messages = vec![];
+        for (i, data) in self.serializer.serialize(&batch).enumerate() {
+            let ordering_key = ordering_keys
+                .filter(|keys| keys.is_valid(i))
+                .map(|keys| keys.value(i).to_string())
+                .unwrap_or_default();
+
+            messages.push(PubsubMessage {
+                data,
+                ordering_key,
+                ..Default::default()
+            });
+        }
+
+        self.outstanding
+            .extend(publisher.publish_bulk(messages).await);
+
+        if self.outstanding.len() >= MAX_OUTSTANDING_PUBLISHES {
+            self.flush(ctx).await;
+        }
+    }
+
+    async fn handle_checkpoint(&mut self, _: CheckpointBarrier, ctx: &mut ArrowContext) {
+        self.flush(ctx).await;
+    }
+
+    async fn on_close(&mut self, _: &Option<SignalMessage>, ctx: &mut ArrowContext) {
+        self.flush(ctx).await;
+        if let Some(mut publisher) = self.publisher.take() {
+            publisher.shutdown().await;
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use arroyo_formats::de::FieldValueType;
+use arroyo_operator::context::ArrowContext;
+use arroyo_operator::operator::SourceOperator;
+use arroyo_operator::SourceFinishType;
+use arroyo_rpc::formats::{BadData, Format, Framing};
+use arroyo_rpc::grpc::rpc::{
+    GlobalKeyedTableConfig, StopMode, TableConfig, TableEnum, TaskCheckpointEventType,
+};
+use arroyo_rpc::{CheckpointEvent, ControlMessage, ControlResp, MetadataField};
+use arroyo_types::{from_nanos, UserError};
+use async_trait::async_trait;
+use futures::StreamExt;
+use google_cloud_googleapis::pubsub::v1::PubsubMessage;
+use google_cloud_pubsub::subscriber::{ReceivedMessage, SubscriberConfig};
+use google_cloud_pubsub::subscription::{SubscribeConfig, Subscription};
+use prost::Message;
+use tokio::select;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, warn};
+
+use crate::pubsub::{create_client, PubSubConfig};
+
+#[cfg(test)]
+mod test;
+
+/// The maximum number of ack ids sent in a single acknowledge request
+const ACK_BATCH_SIZE: usize = 2500;
+
+/// Reads from a Pub/Sub subscription using streaming pull. Messages are only acknowledged once
+/// the checkpoint that contains them has been committed; until then their ack deadlines are
+/// periodically extended so that they aren't redelivered. Messages that were received but not
+/// committed before a failure are redelivered by Pub/Sub, so the source is at-least-once.
+pub struct PubSubSourceFunc {
+    pub config: PubSubConfig,
+    pub subscription: String,
+    pub ack_deadline_seconds: i32,
+    pub max_outstanding_messages: i64,
+    pub format: Format,
+    pub framing: Option<Framing>,
+    pub bad_data: Option<BadData>,
+    pub metadata_fields: Vec<MetadataField>,
+    pub subscribed: Arc<AtomicBool>,
+    client: Option<Subscription>,
+    /// messages received since the last checkpoint
+    pending: Vec<ReceivedMessage>,
+    /// messages that are part of a checkpoint that hasn't been committed yet, by epoch
+    uncommitted: BTreeMap<u32, Vec<ReceivedMessage>>,
+    awaiting_final_commit: bool,
+}
+
+#[async_trait]
+impl SourceOperator for PubSubSourceFunc {
+    fn name(&self) -> String {
+        format!("pubsub-{}", self.subscription)
+    }
+
+    fn tables(&self) -> HashMap<String, TableConfig> {
+        // the table holds no data; it's used to receive a commit once each checkpoint completes
+        let mut tables = HashMap::new();
+        tables.insert(
+            "p".to_string(),
+            TableConfig {
+                table_type: TableEnum::GlobalKeyValue.into(),
+                config: GlobalKeyedTableConfig {
+                    table_name: "p".to_string(),
+                    description: "pubsub acknowledgements".to_string(),
+                    uses_two_phase_commit: true,
+                }
+                .encode_to_vec(),
+            },
+        );
+        tables
+    }
+
+    async fn run(&mut self, ctx: &mut ArrowContext) -> SourceFinishType {
+        match self.run_int(ctx).await {
+            Ok(r) => r,
+            Err(e) => {
+                ctx.report_user_error(e.clone()).await;
+
+                panic!("{}: {}", e.name, e.details);
+            }
+        }
+    }
+
+    async fn on_close(&mut self, ctx: &mut ArrowContext) {
+        if !self.awaiting_final_commit {
+            return;
+        }
+
+        if let Some(ControlMessage::Commit { epoch, .. }) = ctx.control_rx.recv().await {
+            self.commit(epoch, ctx).await;
+        } else {
+            warn!("no commit message received, not acknowledging messages");
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+impl PubSubSourceFunc {
+    pub fn new(
+        config: PubSubConfig,
+        subscription: String,
+        ack_deadline_seconds: i32,
+        max_outstanding_messages: i64,
+        format: Format,
+        framing: Option<Framing>,
+        bad_data: Option<BadData>,
+        metadata_fields: Vec<MetadataField>,
+    ) -> Self {
+        Self {
+            config,
+            subscription,
+            ack_deadline_seconds,
+            max_outstanding_messages,
+            format,
+            framing,
+            bad_data,
+            metadata_fields,
+            subscribed: Arc::new(AtomicBool::new(false)),
+            client: None,
+            pending: vec![],
+            uncommitted: BTreeMap::new(),
+            awaiting_final_commit: false,
+        }
+    }
+
+    fn connector_metadata<'a>(
+        metadata_fields: &'a [MetadataField],
+        message: &'a PubsubMessage,
+        publish_time: i64,
+    ) -> Option<HashMap<&'a String, FieldValueType<'a>>> {
+        if metadata_fields.is_empty() {
+            return None;
+        }
+
+        let mut connector_metadata = HashMap::new();
+        for mf in metadata_fields {
+            connector_metadata.insert(
+                &mf.field_name,
+                match mf.key.as_str() {
+                    "message_id" => FieldValueType::String(&message.message_id),
+                    "ordering_key" => FieldValueType::String(&message.ordering_key),
+                    "publish_time" => FieldValueType::Int64(publish_time),
+                    k => {
+                        let Some(attribute) = k.strip_prefix("attributes.") else {
+                            unreachable!("invalid metadata key '{}' for pubsub", k);
+                        };
+                        FieldValueType::NullableString(
+                            message.attributes.get(attribute).map(|s| s.as_str()),
+                        )
+                    }
+                },
+            );
+        }
+        Some(connector_metadata)
+    }
+
+    /// Pushes back the ack deadlines of all unacknowledged messages
+    async fn extend_leases(&self) {
+        let deadline = self.ack_deadline_seconds;
+        let failures = futures::stream::iter(
+            self.pending
+                .iter()
+                .chain(self.uncommitted.values().flatten()),
+        )
+        .map(|m| m.modify_ack_deadline(deadline))
+        .buffer_unordered(32)
+        .filter(|r| futures::future::ready(r.is_err()))
+        .count()
+        .await;
+
+        if failures > 0 {
+            warn!(
+                "failed to extend the ack deadline of {} messages from {}; they may be redelivered",
+                failures, self.subscription
+            );
+        }
+    }
+
+    /// Acknowledges all messages from checkpoints up to and including `epoch`
+    async fn commit(&mut self, epoch: u32, ctx: &mut ArrowContext) {
+        let rest = self.uncommitted.split_off(&(epoch + 1));
+        let committed = std::mem::replace(&mut self.uncommitted, rest);
+
+        let ack_ids: Vec<String> = committed
+            .into_values()
+            .flatten()
+            .map(|m| m.ack_id().to_string())
+            .collect();
+
+        debug!(
+            "acknowledging {} messages from {} for epoch {}",
+            ack_ids.len(),
+            self.subscription,
+            epoch
+        );
+
+        let subscription = self.client.as_ref().expect("subscription should be set");
+        for chunk in ack_ids.chunks(ACK_BATCH_SIZE) {
+            if let Err(e) = subscription.ack(chunk.to_vec()).await {
+                // unacknowledged messages will be redelivered once their deadlines expire
+                ctx.report_error("Failed to acknowledge Pub/Sub messages", e.to_string())
+                    .await;
+            }
+        }
+
+        ctx.control_tx
+            .send(ControlResp::CheckpointEvent(CheckpointEvent {
+                checkpoint_epoch: epoch,
+                operator_id: ctx.task_info.operator_id.clone(),
+                subtask_index: ctx.task_info.task_index as u32,
+                time: SystemTime::now(),
+                event_type: TaskCheckpointEventType::FinishedCommit,
+            }))
+            .await
+            .expect("sent commit event");
+    }
+
+    async fn run_int(&mut self, ctx: &mut ArrowContext) -> Result<SourceFinishType, UserError> {
+        ctx.initialize_deserializer(
+            self.format.clone(),
+            self.framing.clone(),
+            self.bad_data.clone(),
+        );
+
+        let client = create_client(&self.config)
+            .await
+            .map_err(|e| UserError::new("PubSubSourceError", e.to_string()))?;
+
+        let subscription = client.subscription(&self.subscription);
+        let config = SubscribeConfig::default().with_subscriber_config(SubscriberConfig {
+            stream_ack_deadline_seconds: self.ack_deadline_seconds,
+            max_outstanding_messages: self.max_outstanding_messages,
+            ..Default::default()
+        });
+
+        let mut stream = subscription.subscribe(Some(config)).await.map_err(|e| {
+            UserError::new(
+                "PubSubSourceError",
+                format!("Failed to subscribe to {}: {}", self.subscription, e),
+            )
+        })?;
+        self.client = Some(subscription);
+        self.subscribed.store(true, Ordering::Relaxed);
+
+        let mut flush_ticker = tokio::time::interval(Duration::from_millis(50));
+        flush_ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let lease_period = Duration::from_secs(self.ack_deadline_seconds as u64 / 2);
+        let mut lease_ticker =
+            tokio::time::interval_at(tokio::time::Instant::now() + lease_period, lease_period);
+        lease_ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            select! {
+                message = stream.next() => {
+                    let Some(mut message) = message else {
+                        return Err(UserError::new(
+                            "PubSubSourceError",
+                            format!(
+                                "Subscription stream for {} closed unexpectedly",
+                                self.subscription
+                            ),
+                        ));
+                    };
+
+                    let data = std::mem::take(&mut message.message.data);
+                    let publish_time = message
+                        .message
+                        .publish_time
+                        .as_ref()
+                        .map(|t| t.seconds * 1_000_000_000 + t.nanos as i64);
+                    let timestamp = publish_time
+                        .map(|t| from_nanos(t as u128))
+                        .unwrap_or_else(SystemTime::now);
+
+                    let connector_metadata = Self::connector_metadata(
+                        &self.metadata_fields,
+                        &message.message,
+                        publish_time.unwrap_or_default() / 1_000_000,
+                    );
+                    ctx.deserialize_slice(&data, timestamp, connector_metadata.as_ref()).await?;
+                    self.pending.push(message);
+                }
+                _ = flush_ticker.tick() => {
+                    if ctx.should_flush() {
+                        ctx.flush_buffer().await?;
+                    }
+                }
+                _ = lease_ticker.tick() => {
+                    self.extend_leases().await;
+                }
+                control_message = ctx.control_rx.recv() => {
+                    match control_message {
+                        Some(ControlMessage::Checkpoint(c)) => {
+                            debug!("starting checkpointing {}", ctx.task_info.task_index);
+                            self.uncommitted.insert(c.epoch, std::mem::take(&mut self.pending));
+                            ctx.table_manager
+                                .insert_committing_data("p", vec![])
+                                .await
+                                .expect("sent commit data");
+                            if self.start_checkpoint(c, ctx).await {
+                                self.awaiting_final_commit = true;
+                                return Ok(SourceFinishType::Immediate);
+                            }
+                        },
+                        Some(ControlMessage::Stop { mode }) => {
+                            info!("Stopping Pub/Sub source: {:?}", mode);
+
+                            match mode {
+                                StopMode::Graceful => {
+                                    return Ok(SourceFinishType::Graceful);
+                                }
+                                StopMode::Immediate => {
+                                    return Ok(SourceFinishType::Immediate);
+                                }
+                            }
+                        }
+                        Some(ControlMessage::Commit { epoch, .. }) => {
+                            self.commit(epoch, ctx).await;
+                        }
+                        Some(ControlMessage::LoadCompacted { compacted }) => {
+                            ctx.load_compacted(compacted).await;
+                        }
+                        Some(ControlMessage::NoOp) => {}
+                        None => {}
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use arrow::array::UInt64Array;
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arroyo_operator::context::{batch_bounded, ArrowContext, BatchReceiver};
+use arroyo_operator::operator::SourceOperator;
+use arroyo_rpc::df::ArroyoSchema;
+use arroyo_rpc::formats::{Format, JsonFormat};
+use arroyo_rpc::grpc::rpc::{StopMode, TaskCheckpointEventType};
+use arroyo_rpc::{ControlMessage, ControlResp};
+use arroyo_types::{ArrowMessage, CheckpointBarrier, TaskInfo};
+use google_cloud_googleapis::pubsub::v1::PubsubMessage;
+use google_cloud_pubsub::client::Client;
+use google_cloud_pubsub::subscription::SubscriptionConfig;
+use rand::random;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+use super::PubSubSourceFunc;
+use crate::pubsub::{create_client, PubSubConfig};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct TestData {
+    value: u64,
+}
+
+struct PubSubSourceWithReads {
+    to_control_tx: Sender<ControlMessage>,
+    from_control_rx: Receiver<ControlResp>,
+    data_recv: BatchReceiver,
+}
+
+impl PubSubSourceWithReads {
+    async fn read_values(&mut self, count: usize) -> Vec<u64> {
+        let mut values = vec![];
+        while values.len() < count {
+            let item = tokio::time::timeout(Duration::from_secs(10), self.data_recv.recv())
+                .await
+                .expect("timed out waiting for data")
+                .expect("option shouldn't be missing");
+
+            let ArrowMessage::Data(record) = item else {
+                unreachable!("expected data, got {:?}", item);
+            };
+
+            let a = record.columns()[1]
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .unwrap();
+            values.extend(a.iter().map(|v| v.unwrap()));
+        }
+
+        values
+    }
+
+    async fn wait_for_event(&mut self, event: TaskCheckpointEventType) {
+        loop {
+            let resp = tokio::time::timeout(Duration::from_secs(10), self.from_control_rx.recv())
+                .await
+                .expect("timed out waiting for control response")
+                .expect("control channel closed");
+
+            if let ControlResp::CheckpointEvent(c) = resp {
+                if c.event_type == event {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+struct PubSubTester {
+    topic: String,
+    subscription: String,
+}
+
+impl PubSubTester {
+    fn get_config(&self) -> PubSubConfig {
+        PubSubConfig {
+            project_id: "arroyo-test".to_string(),
+            endpoint: Some("localhost:8085".to_string()),
+            emulator: Some(true),
+        }
+    }
+
+    async fn setup(&self) -> Client {
+        let client = create_client(&self.get_config())
+            .await
+            .expect("failed to connect to the Pub/Sub emulator");
+
+        let topic = client.topic(&self.topic);
+        topic.create(None, None).await.unwrap();
+        client
+            .subscription(&self.subscription)
+            .create(
+                topic.fully_qualified_name(),
+                SubscriptionConfig {
+                    ack_deadline_seconds: 10,
+                    ..Default::default()
+                },
+                None,
+            )
+            .await
+            .unwrap();
+
+        client
+    }
+
+    async fn get_source_with_reader(&self, task_info: TaskInfo) -> PubSubSourceWithReads {
+        let mut source = PubSubSourceFunc::new(
+            self.get_config(),
+            self.subscription.clone(),
+            10,
+            1000,
+            Format::Json(JsonFormat::default()),
+            None,
+            None,
+            vec![],
+        );
+
+        let (to_control_tx, control_rx) = channel(128);
+        let (command_tx, from_control_rx) = channel(128);
+        let (data_tx, recv) = batch_bounded(128);
+
+        let mut ctx = ArrowContext::new(
+            task_info,
+            None,
+            control_rx,
+            command_tx,
+            1,
+            vec![],
+            Some(ArroyoSchema::new_unkeyed(
+                Arc::new(Schema::new(vec![
+                    Field::new(
+                        "_timestamp",
+                        DataType::Timestamp(TimeUnit::Nanosecond, None),
+                        false,
+                    ),
+                    Field::new("value", DataType::UInt64, false),
+                ])),
+                0,
+            )),
+            None,
+            vec![vec![data_tx]],
+            source.tables(),
+        )
+        .await;
+
+        let subscribed = source.subscribed.clone();
+        tokio::spawn(async move {
+            source.on_start(&mut ctx).await;
+            source.run(&mut ctx).await;
+            source.on_close(&mut ctx).await;
+        });
+
+        while !subscribed.load(Ordering::Relaxed) {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        PubSubSourceWithReads {
+            to_control_tx,
+            from_control_rx,
+            data_recv: recv,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_pubsub_source() {
+    let id = random::<u32>();
+    let tester = PubSubTester {
+        topic: format!("arroyo-source-{}", id),
+        subscription: format!("arroyo-source-{}-sub", id),
+    };
+
+    let client = tester.setup().await;
+
+    let mut task_info = arroyo_types::get_test_task_info();
+    task_info.job_id = format!("pubsub-job-{}", id);
+
+    let mut reader = tester.get_source_with_reader(task_info.clone()).await;
+
+    let publisher = client.topic(&tester.topic).new_publisher(None);
+    let messages = (1u64..20)
+        .map(|value| PubsubMessage {
+            data: serde_json::to_vec(&TestData { value }).unwrap(),
+            ..Default::default()
+        })
+        .collect();
+    for awaiter in publisher.publish_bulk(messages).await {
+        awaiter.get().await.expect("failed to publish message");
+    }
+
+    let mut values = reader.read_values(19).await;
+    values.sort();
+    assert_eq!(values, (1u64..20).collect::<Vec<_>>());
+
+    // the messages are acknowledged once the checkpoint containing them is committed
+    reader
+        .to_control_tx
+        .send(ControlMessage::Checkpoint(CheckpointBarrier {
+            epoch: 1,
+            min_epoch: 0,
+            timestamp: SystemTime::now(),
+            then_stop: false,
+        }))
+        .await
+        .unwrap();
+    reader
+        .wait_for_event(TaskCheckpointEventType::FinishedSync)
+        .await;
+
+    reader
+        .to_control_tx
+        .send(ControlMessage::Commit {
+            epoch: 1,
+            commit_data: HashMap::new(),
+        })
+        .await
+        .unwrap();
+    reader
+        .wait_for_event(TaskCheckpointEventType::FinishedCommit)
+        .await;
+
+    reader
+        .to_control_tx
+        .send(ControlMessage::Stop {
+            mode: StopMode::Immediate,
+        })
+        .await
+        .unwrap();
+
+    // acknowledged messages aren't redelivered to a new reader after the ack deadline passes
+    tokio::time::sleep(Duration::from_secs(11)).await;
+    task_info.job_id = format!("pubsub-job-{}-2", id);
+    let mut reader = tester.get_source_with_reader(task_info).await;
+    assert!(
+        tokio::time::timeout(Duration::from_secs(2), reader.data_recv.recv())
+            .await
+            .is_err(),
+        "committed messages were redelivered"
+    );
+}
@@ -7,6 +7,7 @@ use arroyo_operator::connector::Connection;
 use arroyo_rpc::api_types::connections::{
     ConnectionProfile, ConnectionSchema, ConnectionType, TestSourceMessage,
 };
+use arroyo_rpc::formats::{Framing, FramingMethod, NewlineDelimitedFraming};
 use arroyo_rpc::OperatorConfig;
 use serde::{Deserialize, Serialize};
 
@@ -174,7 +175,13 @@ impl Connector for SingleFileConnector {
                     config
                         .format
                         .expect("Format must be set for Single File Sink"),
-                ),
+                )
+                // records are written as a stream, so default to newline-delimited
+                .with_framing(Some(config.framing.unwrap_or(Framing {
+                    method: FramingMethod::Newline(NewlineDelimitedFraming {
+                        max_line_length: None,
+                    }),
+                }))),
             }))),
         }
     }
@@ -36,7 +36,6 @@ impl ArrowOperator for SingleFileSink {
         let file = self.file.as_mut().unwrap();
         for value in values {
             file.write_all(&value).await.unwrap();
-            file.write_all(b"\n").await.unwrap();
         }
     }
 
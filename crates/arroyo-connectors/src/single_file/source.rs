@@ -4,17 +4,74 @@ use arroyo_operator::context::ArrowContext;
 use arroyo_operator::operator::SourceOperator;
 use arroyo_operator::SourceFinishType;
 use arroyo_rpc::{
-    formats::{BadData, Format, Framing},
+    formats::{BadData, Format, Framing, FramingMethod},
     grpc::rpc::{StopMode, TableConfig},
     ControlMessage,
 };
 use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
 use tokio::{
     fs::File,
-    io::{AsyncBufReadExt, BufReader},
+    io::{AsyncBufReadExt, AsyncReadExt, BufReader, Lines},
 };
 use tracing::info;
 
+use crate::socket::FrameDecoder;
+
+/// Reads the records of the file, either line by line or, for the binary-safe framings,
+/// by splitting the byte stream with a [FrameDecoder]
+enum Records {
+    Lines(Lines<BufReader<File>>),
+    Frames {
+        file: File,
+        decoder: FrameDecoder,
+        buf: BytesMut,
+        eof: bool,
+    },
+}
+
+impl Records {
+    fn new(file: File, framing: Option<&Framing>) -> Self {
+        match framing.map(|f| &f.method) {
+            None | Some(FramingMethod::Newline(_)) => Records::Lines(BufReader::new(file).lines()),
+            Some(method) => Records::Frames {
+                file,
+                decoder: FrameDecoder::new(Some(method.clone())),
+                buf: BytesMut::new(),
+                eof: false,
+            },
+        }
+    }
+
+    async fn next(&mut self) -> Option<Bytes> {
+        match self {
+            Records::Lines(lines) => lines.next_line().await.unwrap().map(Bytes::from),
+            Records::Frames {
+                file,
+                decoder,
+                buf,
+                eof,
+            } => loop {
+                let frame = if *eof {
+                    decoder.decode_eof(buf)
+                } else {
+                    decoder.decode(buf)
+                };
+
+                if let Some(frame) = frame.unwrap_or_else(|e| panic!("invalid input file: {}", e)) {
+                    return Some(frame);
+                }
+
+                if *eof {
+                    return None;
+                }
+
+                *eof = file.read_buf(buf).await.unwrap() == 0;
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SingleFileSourceFunc {
     pub input_file: String,
@@ -30,28 +87,29 @@ impl SingleFileSourceFunc {
         if ctx.task_info.task_index != 0 {
             return SourceFinishType::Final;
         }
-        ctx.initialize_deserializer(
-            self.format.clone(),
-            self.framing.clone(),
-            self.bad_data.clone(),
-        );
+        let file = File::open(&self.input_file).await.expect(&self.input_file);
+        let mut records = Records::new(file, self.framing.as_ref());
+
+        // records that were split from the byte stream are passed to the deserializer whole
+        let framing = match records {
+            Records::Lines(_) => self.framing.clone(),
+            Records::Frames { .. } => None,
+        };
+        ctx.initialize_deserializer(self.format.clone(), framing, self.bad_data.clone());
 
         let state: &mut arroyo_state::tables::global_keyed_map::GlobalKeyedView<String, usize> =
             ctx.table_manager.get_global_keyed_state("f").await.unwrap();
 
         self.lines_read = state.get(&self.input_file).copied().unwrap_or_default();
 
-        let file = File::open(&self.input_file).await.expect(&self.input_file);
-        let mut lines = BufReader::new(file).lines();
-
         let mut i = 0;
 
-        while let Some(s) = lines.next_line().await.unwrap() {
+        while let Some(record) = records.next().await {
             if i < self.lines_read {
                 i += 1;
                 continue;
             }
-            ctx.deserialize_slice(s.as_bytes(), SystemTime::now(), None)
+            ctx.deserialize_slice(&record, SystemTime::now(), None)
                 .await
                 .unwrap();
             if ctx.should_flush() {
@@ -0,0 +1,231 @@
+mod operator;
+mod syslog;
+#[cfg(test)]
+mod test;
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use arroyo_operator::connector::{Connection, Connector};
+use arroyo_operator::operator::OperatorNode;
+use arroyo_rpc::api_types::connections::{
+    ConnectionProfile, ConnectionSchema, ConnectionType, FieldType, PrimitiveType, SourceField,
+    TestSourceMessage,
+};
+use arroyo_rpc::formats::{Format, JsonFormat};
+use arroyo_rpc::OperatorConfig;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
+use typify::import_types;
+
+pub(crate) use crate::socket::operator::FrameDecoder;
+use crate::socket::operator::SocketSourceFunc;
+use crate::{pull_opt, source_field, EmptyConfig};
+
+const TABLE_SCHEMA: &str = include_str!("./table.json");
+const ICON: &str = include_str!("./socket.svg");
+
+import_types!(schema = "src/socket/table.json");
+
+/// The fields produced by the syslog parser; tables may declare any subset of these
+pub fn syslog_fields() -> Vec<SourceField> {
+    let nullable = |name: &str, t: PrimitiveType| SourceField {
+        nullable: true,
+        ..source_field(name, FieldType::Primitive(t))
+    };
+
+    vec![
+        source_field("facility", FieldType::Primitive(PrimitiveType::Int32)),
+        source_field("severity", FieldType::Primitive(PrimitiveType::Int32)),
+        nullable("version", PrimitiveType::Int32),
+        nullable("timestamp", PrimitiveType::DateTime),
+        nullable("hostname", PrimitiveType::String),
+        nullable("app_name", PrimitiveType::String),
+        nullable("proc_id", PrimitiveType::String),
+        nullable("msg_id", PrimitiveType::String),
+        nullable("structured_data", PrimitiveType::Json),
+        nullable("message", PrimitiveType::String),
+    ]
+}
+
+pub struct SocketConnector {}
+
+impl Connector for SocketConnector {
+    type ProfileT = EmptyConfig;
+    type TableT = SocketTable;
+
+    fn name(&self) -> &'static str {
+        "socket"
+    }
+
+    fn metadata(&self) -> arroyo_rpc::api_types::connections::Connector {
+        arroyo_rpc::api_types::connections::Connector {
+            id: "socket".to_string(),
+            name: "Socket".to_string(),
+            icon: ICON.to_string(),
+            description: "Receive messages over TCP or UDP, including syslog".to_string(),
+            enabled: true,
+            source: true,
+            sink: false,
+            testing: true,
+            hidden: false,
+            custom_schemas: true,
+            connection_config: None,
+            table_config: TABLE_SCHEMA.to_string(),
+        }
+    }
+
+    fn table_type(&self, _: Self::ProfileT, _: Self::TableT) -> ConnectionType {
+        ConnectionType::Source
+    }
+
+    fn test(
+        &self,
+        _: &str,
+        _: Self::ProfileT,
+        table: Self::TableT,
+        _: Option<&ConnectionSchema>,
+        tx: Sender<TestSourceMessage>,
+    ) {
+        tokio::task::spawn(async move {
+            let message = match SocketAddr::from_str(&table.address) {
+                Ok(_) => TestSourceMessage {
+                    error: false,
+                    done: true,
+                    message: "Successfully validated connection".to_string(),
+                },
+                Err(e) => TestSourceMessage {
+                    error: true,
+                    done: true,
+                    message: format!("Invalid address '{}': {}", table.address, e),
+                },
+            };
+            tx.send(message).await.unwrap();
+        });
+    }
+
+    fn from_options(
+        &self,
+        name: &str,
+        options: &mut HashMap<String, String>,
+        schema: Option<&ConnectionSchema>,
+        _profile: Option<&ConnectionProfile>,
+    ) -> anyhow::Result<Connection> {
+        let protocol = Protocol::try_from(pull_opt("protocol", options)?)
+            .map_err(|e| anyhow!("invalid value for 'protocol': {e}"))?;
+        let address = pull_opt("address", options)?;
+        let syslog = options
+            .remove("syslog")
+            .map(|s| {
+                SyslogParser::try_from(s).map_err(|e| anyhow!("invalid value for 'syslog': {e}"))
+            })
+            .transpose()?;
+
+        self.from_config(
+            None,
+            name,
+            EmptyConfig {},
+            SocketTable {
+                protocol,
+                address,
+                syslog,
+            },
+            schema,
+        )
+    }
+
+    fn from_config(
+        &self,
+        id: Option<i64>,
+        name: &str,
+        config: Self::ProfileT,
+        table: Self::TableT,
+        schema: Option<&ConnectionSchema>,
+    ) -> anyhow::Result<Connection> {
+        SocketAddr::from_str(&table.address)
+            .map_err(|e| anyhow!("invalid address '{}': {}", table.address, e))?;
+
+        let mut schema = schema
+            .map(|s| s.to_owned())
+            .ok_or_else(|| anyhow!("no schema defined for socket source"))?;
+
+        if table.syslog.is_some() {
+            if schema.format.is_some() {
+                bail!("'format' cannot be set for a socket source that parses syslog");
+            }
+
+            let syslog_fields = syslog_fields();
+            if schema.fields.is_empty() {
+                schema.fields = syslog_fields;
+                schema.inferred = None;
+            } else if let Some(f) = schema
+                .fields
+                .iter()
+                .find(|f| !syslog_fields.iter().any(|s| s.field_name == f.field_name))
+            {
+                bail!(
+                    "field '{}' is not produced by the syslog parser; valid fields are {}",
+                    f.field_name,
+                    syslog_fields
+                        .iter()
+                        .map(|f| f.field_name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+        } else if schema.format.is_none() {
+            bail!("'format' must be set for socket source unless 'syslog' is set");
+        }
+
+        let protocol = match table.protocol {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+        };
+        let description = format!("SocketSource<{}://{}>", protocol, table.address);
+
+        let config = OperatorConfig {
+            connection: serde_json::to_value(config).unwrap(),
+            table: serde_json::to_value(table).unwrap(),
+            rate_limit: None,
+            format: schema.format.clone(),
+            bad_data: schema.bad_data.clone(),
+            framing: schema.framing.clone(),
+            metadata_fields: schema.metadata_fields(),
+        };
+
+        Ok(Connection {
+            id,
+            connector: self.name(),
+            name: name.to_string(),
+            connection_type: ConnectionType::Source,
+            schema,
+            config: serde_json::to_string(&config).unwrap(),
+            description,
+        })
+    }
+
+    fn make_operator(
+        &self,
+        _: Self::ProfileT,
+        table: Self::TableT,
+        config: OperatorConfig,
+    ) -> anyhow::Result<OperatorNode> {
+        // parsed syslog messages are passed to the deserializer as JSON
+        let format = match (table.syslog, config.format) {
+            (Some(_), _) => Format::Json(JsonFormat::default()),
+            (None, Some(format)) => format,
+            (None, None) => bail!("format required for socket source"),
+        };
+
+        Ok(OperatorNode::from_source(Box::new(SocketSourceFunc {
+            protocol: table.protocol,
+            address: table.address,
+            syslog: table.syslog,
+            format,
+            framing: config.framing,
+            bad_data: config.bad_data,
+        })))
+    }
+}
@@ -0,0 +1,505 @@
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
+
+use arroyo_operator::context::ArrowContext;
+use arroyo_operator::operator::SourceOperator;
+use arroyo_operator::SourceFinishType;
+use arroyo_rpc::formats::{BadData, Format, Framing, FramingMethod, NewlineDelimitedFraming};
+use arroyo_rpc::{grpc::rpc::StopMode, ControlMessage};
+use arroyo_types::{ArrowMessage, SignalMessage, SourceError, UserError, Watermark};
+use async_trait::async_trait;
+use bytes::{Buf, Bytes, BytesMut};
+use chrono::Utc;
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::select;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tracing::{debug, info, warn};
+
+use crate::socket::{syslog, Protocol, SyslogParser};
+
+// the maximum size of a UDP datagram
+const MAX_DATAGRAM_SIZE: usize = 65_535;
+
+/// Splits a stream of bytes into messages according to the configured framing. Messages longer
+/// than the configured maximum are truncated, as with the deserializer's framing.
+pub struct FrameDecoder {
+    method: Option<FramingMethod>,
+    // whether we're skipping the rest of a delimited message that was too long
+    discarding: bool,
+    // the number of bytes remaining in a length-counted message that was too long
+    skip: usize,
+}
+
+impl FrameDecoder {
+    /// Without a framing method, messages are only delimited by the end of the input
+    pub fn new(method: Option<FramingMethod>) -> Self {
+        Self {
+            method,
+            discarding: false,
+            skip: 0,
+        }
+    }
+
+    /// Returns the next complete message from the buffer, or None if more data is needed
+    pub fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, String> {
+        match &self.method {
+            None => Ok(None),
+            Some(FramingMethod::Newline(newline)) => {
+                let max = newline.max_line_length.unwrap_or(u64::MAX) as usize;
+                loop {
+                    let Some(end) = buf.iter().position(|b| *b == b'\n') else {
+                        if buf.len() > max {
+                            let line = buf.split_to(max);
+                            buf.clear();
+                            if !std::mem::replace(&mut self.discarding, true) {
+                                return Ok(Some(line.freeze()));
+                            }
+                        }
+                        return Ok(None);
+                    };
+
+                    let mut line = buf.split_to(end + 1);
+                    if std::mem::take(&mut self.discarding) {
+                        continue;
+                    }
+
+                    line.truncate(end);
+                    if line.last() == Some(&b'\r') {
+                        line.truncate(end - 1);
+                    }
+                    line.truncate(max);
+
+                    if !line.is_empty() {
+                        return Ok(Some(line.freeze()));
+                    }
+                }
+            }
+            Some(FramingMethod::Delimited(delimited)) => {
+                let delimiter = delimited.delimiter.as_slice();
+                let max = delimited.max_message_length.unwrap_or(u64::MAX) as usize;
+                loop {
+                    let Some(end) = buf.windows(delimiter.len()).position(|w| w == delimiter)
+                    else {
+                        if buf.len() > max {
+                            let message = buf.split_to(max);
+                            // keep enough of the tail to find a delimiter split across reads
+                            buf.advance(buf.len().saturating_sub(delimiter.len() - 1));
+                            if !std::mem::replace(&mut self.discarding, true) {
+                                return Ok(Some(message.freeze()));
+                            }
+                        }
+                        return Ok(None);
+                    };
+
+                    let mut message = buf.split_to(end + delimiter.len());
+                    if std::mem::take(&mut self.discarding) {
+                        continue;
+                    }
+
+                    message.truncate(end.min(max));
+
+                    if !message.is_empty() {
+                        return Ok(Some(message.freeze()));
+                    }
+                }
+            }
+            Some(FramingMethod::OctetCounting(octet_counting)) => {
+                if !self.skip_excess(buf) {
+                    return Ok(None);
+                }
+
+                let Some(space) = buf.iter().position(|b| *b == b' ') else {
+                    if buf.len() > 20 || !buf.iter().all(|b| b.is_ascii_digit()) {
+                        return Err("invalid octet-counting frame header".to_string());
+                    }
+                    return Ok(None);
+                };
+
+                let len: usize = std::str::from_utf8(&buf[..space])
+                    .ok()
+                    .filter(|s| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()))
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| "invalid octet-counting frame header".to_string())?;
+
+                let frame_len =
+                    len.min(octet_counting.max_message_length.unwrap_or(u64::MAX) as usize);
+                if buf.len() < space + 1 + frame_len {
+                    return Ok(None);
+                }
+
+                buf.advance(space + 1);
+                let frame = buf.split_to(frame_len).freeze();
+                self.skip = len - frame_len;
+                Ok(Some(frame))
+            }
+            Some(FramingMethod::LengthPrefixed(length_prefixed)) => {
+                if !self.skip_excess(buf) {
+                    return Ok(None);
+                }
+
+                let header_len = length_prefixed.prefix_bytes as usize;
+                if buf.len() < header_len {
+                    return Ok(None);
+                }
+
+                let len = usize::try_from(length_prefixed.read_length(buf)).unwrap_or(usize::MAX);
+                let frame_len =
+                    len.min(length_prefixed.max_message_length.unwrap_or(u64::MAX) as usize);
+                if buf.len() - header_len < frame_len {
+                    return Ok(None);
+                }
+
+                buf.advance(header_len);
+                let frame = buf.split_to(frame_len).freeze();
+                self.skip = len - frame_len;
+                Ok(Some(frame))
+            }
+        }
+    }
+
+    /// Drops the remainder of a truncated length-counted message from the buffer, returning
+    /// whether it has been fully skipped
+    fn skip_excess(&mut self, buf: &mut BytesMut) -> bool {
+        let n = self.skip.min(buf.len());
+        buf.advance(n);
+        self.skip -= n;
+        self.skip == 0
+    }
+
+    /// Like `decode`, but for when no more data will be added to the buffer
+    pub fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, String> {
+        if let Some(frame) = self.decode(buf)? {
+            return Ok(Some(frame));
+        }
+
+        if buf.is_empty() {
+            return Ok(None);
+        }
+
+        match &self.method {
+            None => Ok(Some(buf.split().freeze())),
+            Some(FramingMethod::Newline(_) | FramingMethod::Delimited(_)) => {
+                let line = buf.split();
+                if std::mem::take(&mut self.discarding) {
+                    Ok(None)
+                } else {
+                    Ok(Some(line.freeze()))
+                }
+            }
+            Some(FramingMethod::OctetCounting(_) | FramingMethod::LengthPrefixed(_)) => {
+                buf.clear();
+                if std::mem::take(&mut self.skip) > 0 {
+                    Ok(None)
+                } else {
+                    Err("input ended in the middle of a message".to_string())
+                }
+            }
+        }
+    }
+}
+
+pub struct SocketSourceFunc {
+    pub protocol: Protocol,
+    pub address: String,
+    pub syslog: Option<SyslogParser>,
+    pub format: Format,
+    pub framing: Option<Framing>,
+    pub bad_data: Option<BadData>,
+}
+
+#[async_trait]
+impl SourceOperator for SocketSourceFunc {
+    fn name(&self) -> String {
+        "SocketSource".to_string()
+    }
+
+    async fn on_start(&mut self, ctx: &mut ArrowContext) {
+        // framing is handled by the FrameDecoder, so messages are passed to the deserializer whole
+        ctx.initialize_deserializer(self.format.clone(), None, self.bad_data.clone());
+    }
+
+    async fn run(&mut self, ctx: &mut ArrowContext) -> SourceFinishType {
+        match self.run_int(ctx).await {
+            Ok(r) => r,
+            Err(e) => {
+                ctx.report_error(e.name.clone(), e.details.clone()).await;
+
+                panic!("{}: {}", e.name, e.details);
+            }
+        }
+    }
+}
+
+impl SocketSourceFunc {
+    async fn our_handle_control_message(
+        &mut self,
+        ctx: &mut ArrowContext,
+        msg: Option<ControlMessage>,
+    ) -> Option<SourceFinishType> {
+        match msg? {
+            ControlMessage::Checkpoint(c) => {
+                debug!("starting checkpointing {}", ctx.task_info.task_index);
+                if self.start_checkpoint(c, ctx).await {
+                    return Some(SourceFinishType::Immediate);
+                }
+            }
+            ControlMessage::Stop { mode } => {
+                info!("Stopping socket source: {:?}", mode);
+
+                match mode {
+                    StopMode::Graceful => {
+                        return Some(SourceFinishType::Graceful);
+                    }
+                    StopMode::Immediate => {
+                        return Some(SourceFinishType::Immediate);
+                    }
+                }
+            }
+            ControlMessage::Commit { .. } => {
+                unreachable!("sources shouldn't receive commit messages");
+            }
+            ControlMessage::LoadCompacted { compacted } => {
+                ctx.load_compacted(compacted).await;
+            }
+            ControlMessage::NoOp => {}
+        }
+        None
+    }
+
+    async fn handle_message(
+        &mut self,
+        msg: &[u8],
+        ctx: &mut ArrowContext,
+    ) -> Result<(), UserError> {
+        match self.syslog {
+            Some(parser) => {
+                let line = String::from_utf8_lossy(msg);
+                match syslog::parse(parser, &line, Utc::now()) {
+                    Ok(message) => {
+                        ctx.deserialize_slice(
+                            message.to_json().to_string().as_bytes(),
+                            SystemTime::now(),
+                            None,
+                        )
+                        .await?
+                    }
+                    Err(e) => {
+                        ctx.collect_source_errors(vec![SourceError::bad_data(format!(
+                            "invalid syslog message: {}",
+                            e
+                        ))])
+                        .await?
+                    }
+                }
+            }
+            None => ctx.deserialize_slice(msg, SystemTime::now(), None).await?,
+        }
+
+        if ctx.should_flush() {
+            ctx.flush_buffer().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Binds the socket and starts a task that sends received messages to the returned channel
+    async fn listen(&self) -> Result<Receiver<Bytes>, UserError> {
+        let addr: SocketAddr = self.address.parse().map_err(|e| {
+            UserError::new(
+                "Invalid socket address",
+                format!("'{}': {:?}", self.address, e),
+            )
+        })?;
+
+        let bind_error = |e: std::io::Error| {
+            UserError::new(
+                "Failed to bind socket",
+                format!("could not listen on {}: {}", addr, e),
+            )
+        };
+
+        let (tx, rx) = channel(1024);
+        match self.protocol {
+            Protocol::Tcp => {
+                let listener = TcpListener::bind(addr).await.map_err(bind_error)?;
+                // streams need to be delimited, so default to the non-transparent (newline)
+                // framing of RFC 6587
+                let method = self.framing.as_ref().map(|f| f.method.clone()).unwrap_or(
+                    FramingMethod::Newline(NewlineDelimitedFraming {
+                        max_line_length: None,
+                    }),
+                );
+                tokio::spawn(accept_connections(listener, method, tx));
+            }
+            Protocol::Udp => {
+                let socket = UdpSocket::bind(addr).await.map_err(bind_error)?;
+                // each datagram contains at least one whole message
+                let method = self.framing.as_ref().map(|f| f.method.clone());
+                tokio::spawn(receive_datagrams(socket, method, tx));
+            }
+        }
+
+        info!("Listening on {}", addr);
+        Ok(rx)
+    }
+
+    async fn run_int(&mut self, ctx: &mut ArrowContext) -> Result<SourceFinishType, UserError> {
+        // only one subtask can listen on the address
+        if ctx.task_info.task_index != 0 {
+            ctx.broadcast(ArrowMessage::Signal(SignalMessage::Watermark(
+                Watermark::Idle,
+            )))
+            .await;
+            loop {
+                let msg = ctx.control_rx.recv().await;
+                if let Some(r) = self.our_handle_control_message(ctx, msg).await {
+                    return Ok(r);
+                }
+            }
+        }
+
+        let mut rx = self.listen().await?;
+
+        let mut flush_ticker = tokio::time::interval(Duration::from_millis(50));
+        flush_ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            select! {
+                msg = rx.recv() => {
+                    match msg {
+                        Some(msg) => self.handle_message(&msg, ctx).await?,
+                        None => {
+                            return Err(UserError::new(
+                                "Socket closed",
+                                format!("stopped listening on {}", self.address),
+                            ));
+                        }
+                    }
+                }
+                _ = flush_ticker.tick() => {
+                    if ctx.should_flush() {
+                        ctx.flush_buffer().await?;
+                    }
+                }
+                control_message = ctx.control_rx.recv() => {
+                    if let Some(r) = self.our_handle_control_message(ctx, control_message).await {
+                        return Ok(r);
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn accept_connections(listener: TcpListener, method: FramingMethod, tx: Sender<Bytes>) {
+    loop {
+        select! {
+            _ = tx.closed() => return,
+            conn = listener.accept() => {
+                match conn {
+                    Ok((stream, peer)) => {
+                        debug!("Accepted connection from {}", peer);
+                        tokio::spawn(read_connection(
+                            stream,
+                            peer,
+                            FrameDecoder::new(Some(method.clone())),
+                            tx.clone(),
+                        ));
+                    }
+                    Err(e) => {
+                        warn!("Failed to accept connection: {:?}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn read_connection(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    mut decoder: FrameDecoder,
+    tx: Sender<Bytes>,
+) {
+    let mut buf = BytesMut::with_capacity(8 * 1024);
+    loop {
+        loop {
+            match decoder.decode(&mut buf) {
+                Ok(Some(frame)) => {
+                    if tx.send(frame).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Closing connection from {}: {}", peer, e);
+                    return;
+                }
+            }
+        }
+
+        let read = select! {
+            _ = tx.closed() => return,
+            read = stream.read_buf(&mut buf) => read,
+        };
+
+        match read {
+            Ok(0) => {
+                debug!("Connection from {} closed", peer);
+                loop {
+                    match decoder.decode_eof(&mut buf) {
+                        Ok(Some(frame)) => {
+                            if tx.send(frame).await.is_err() {
+                                return;
+                            }
+                        }
+                        Ok(None) => return,
+                        Err(e) => {
+                            warn!("Dropping data from {}: {}", peer, e);
+                            return;
+                        }
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!("Error reading from {}: {:?}", peer, e);
+                return;
+            }
+        }
+    }
+}
+
+async fn receive_datagrams(socket: UdpSocket, method: Option<FramingMethod>, tx: Sender<Bytes>) {
+    let mut datagram = vec![0; MAX_DATAGRAM_SIZE];
+    loop {
+        let (len, peer) = select! {
+            _ = tx.closed() => return,
+            r = socket.recv_from(&mut datagram) => match r {
+                Ok(r) => r,
+                Err(e) => {
+                    warn!("Error receiving datagram: {:?}", e);
+                    continue;
+                }
+            },
+        };
+
+        let mut buf = BytesMut::from(&datagram[..len]);
+        let mut decoder = FrameDecoder::new(method.clone());
+        loop {
+            match decoder.decode_eof(&mut buf) {
+                Ok(Some(frame)) => {
+                    if tx.send(frame).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Dropping datagram from {}: {}", peer, e);
+                    break;
+                }
+            }
+        }
+    }
+}
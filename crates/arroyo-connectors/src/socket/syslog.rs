@@ -0,0 +1,295 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+
+use crate::socket::SyslogParser;
+
+/// A syslog message parsed according to RFC 5424 or RFC 3164. Fields that are nil or not
+/// present in the message are `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyslogMessage<'a> {
+    pub facility: u8,
+    pub severity: u8,
+    pub version: Option<u32>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub hostname: Option<&'a str>,
+    pub app_name: Option<&'a str>,
+    pub proc_id: Option<&'a str>,
+    pub msg_id: Option<&'a str>,
+    pub structured_data: Option<Map<String, Value>>,
+    pub message: Option<&'a str>,
+}
+
+impl SyslogMessage<'_> {
+    /// Converts the message into a JSON object with the fields from
+    /// [`syslog_fields`](crate::socket::syslog_fields)
+    pub fn to_json(&self) -> Value {
+        let timestamp = self
+            .timestamp
+            .map(|t| t.to_rfc3339_opts(SecondsFormat::AutoSi, true));
+        let structured_data = self
+            .structured_data
+            .as_ref()
+            .map(|d| Value::Object(d.clone()).to_string());
+
+        json!({
+            "facility": self.facility,
+            "severity": self.severity,
+            "version": self.version,
+            "timestamp": timestamp,
+            "hostname": self.hostname,
+            "app_name": self.app_name,
+            "proc_id": self.proc_id,
+            "msg_id": self.msg_id,
+            "structured_data": structured_data,
+            "message": self.message,
+        })
+    }
+}
+
+/// Parses a single syslog message. `now` is used to infer the year of RFC 3164 timestamps,
+/// which don't include one.
+pub fn parse(
+    parser: SyslogParser,
+    line: &str,
+    now: DateTime<Utc>,
+) -> Result<SyslogMessage, String> {
+    let line = line.trim_end_matches(['\r', '\n', '\0']);
+
+    match parser {
+        SyslogParser::Rfc5424 => {
+            let (facility, severity, rest) = parse_priority(line)?;
+            parse_rfc5424(facility, severity, rest)
+        }
+        SyslogParser::Rfc3164 => Ok(parse_rfc3164(line, now)),
+        SyslogParser::Auto => match parse_priority(line) {
+            // RFC 5424 messages have a version number directly after the priority
+            Ok((facility, severity, rest))
+                if rest.starts_with(|c: char| ('1'..='9').contains(&c))
+                    && rest
+                        .split_once(' ')
+                        .is_some_and(|(v, _)| v.bytes().all(|b| b.is_ascii_digit())) =>
+            {
+                parse_rfc5424(facility, severity, rest)
+            }
+            _ => Ok(parse_rfc3164(line, now)),
+        },
+    }
+}
+
+/// Parses the `<PRI>` prefix, returning the facility, severity, and the rest of the message
+fn parse_priority(line: &str) -> Result<(u8, u8, &str), String> {
+    let invalid = || format!("invalid priority in '{}'", truncate(line));
+
+    let rest = line.strip_prefix('<').ok_or_else(invalid)?;
+    let end = rest
+        .find('>')
+        .filter(|i| (1..=3).contains(i))
+        .ok_or_else(invalid)?;
+    let priority: u8 = rest[..end]
+        .parse()
+        .ok()
+        .filter(|p| *p <= 191)
+        .ok_or_else(invalid)?;
+
+    Ok((priority >> 3, priority & 7, &rest[end + 1..]))
+}
+
+fn parse_rfc5424(facility: u8, severity: u8, line: &str) -> Result<SyslogMessage, String> {
+    let mut rest = line;
+
+    let version = next_token(&mut rest, "version")?;
+    let version = version
+        .parse()
+        .map_err(|_| format!("invalid version '{}'", version))?;
+
+    let timestamp = nil(next_token(&mut rest, "timestamp")?)
+        .map(|t| {
+            DateTime::parse_from_rfc3339(t)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| format!("invalid timestamp '{}': {}", t, e))
+        })
+        .transpose()?;
+
+    let hostname = nil(next_token(&mut rest, "hostname")?);
+    let app_name = nil(next_token(&mut rest, "app name")?);
+    let proc_id = nil(next_token(&mut rest, "proc id")?);
+    let msg_id = nil(next_token(&mut rest, "msg id")?);
+
+    let (structured_data, rest) = parse_structured_data(rest)?;
+
+    let message = match rest.strip_prefix(' ') {
+        Some(message) => Some(message.strip_prefix('\u{feff}').unwrap_or(message)),
+        None if rest.is_empty() => None,
+        None => return Err(format!("invalid structured data '{}'", truncate(rest))),
+    };
+
+    Ok(SyslogMessage {
+        facility,
+        severity,
+        version: Some(version),
+        timestamp,
+        hostname,
+        app_name,
+        proc_id,
+        msg_id,
+        structured_data,
+        message: message.filter(|m| !m.is_empty()),
+    })
+}
+
+/// Parses the structured data section of an RFC 5424 message into a map from SD-IDs to their
+/// parameters, returning the rest of the message
+fn parse_structured_data(s: &str) -> Result<(Option<Map<String, Value>>, &str), String> {
+    if let Some(rest) = s.strip_prefix('-') {
+        return Ok((None, rest));
+    }
+
+    let invalid = || format!("invalid structured data '{}'", truncate(s));
+
+    let mut data = Map::new();
+    let mut rest = s;
+    while let Some(element) = rest.strip_prefix('[') {
+        let id_end = element.find([' ', ']']).ok_or_else(invalid)?;
+        let id = &element[..id_end];
+        if id.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut params = Map::new();
+        rest = &element[id_end..];
+        loop {
+            if let Some(r) = rest.strip_prefix(']') {
+                rest = r;
+                break;
+            }
+
+            let (name, value) = rest
+                .strip_prefix(' ')
+                .and_then(|r| r.split_once("=\""))
+                .ok_or_else(invalid)?;
+
+            // values are terminated by an unescaped quote; '"', '\' and ']' are escaped by '\'
+            let mut parsed = String::new();
+            let mut chars = value.char_indices();
+            let end = loop {
+                match chars.next().ok_or_else(invalid)? {
+                    (_, '\\') => match chars.next().ok_or_else(invalid)? {
+                        (_, c @ ('"' | '\\' | ']')) => parsed.push(c),
+                        (_, c) => {
+                            parsed.push('\\');
+                            parsed.push(c);
+                        }
+                    },
+                    (i, '"') => break i,
+                    (_, c) => parsed.push(c),
+                }
+            };
+
+            params.insert(name.to_string(), Value::String(parsed));
+            rest = &value[end + 1..];
+        }
+
+        data.insert(id.to_string(), Value::Object(params));
+    }
+
+    if data.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok((Some(data), rest))
+}
+
+/// RFC 3164 only describes common practice, so parsing is best-effort: anything that can't be
+/// recognized is left in the message. Messages without a priority are treated as user.notice,
+/// as recommended by the RFC.
+fn parse_rfc3164(line: &str, now: DateTime<Utc>) -> SyslogMessage {
+    let (facility, severity, rest) = parse_priority(line).unwrap_or((1, 5, line));
+
+    let (timestamp, rest) = match rest.get(..15).and_then(|t| parse_rfc3164_timestamp(t, now)) {
+        Some(timestamp) => (Some(timestamp), rest[15..].trim_start_matches(' ')),
+        None => (None, rest),
+    };
+
+    // the hostname is only present after a timestamp, and is sometimes omitted by relays
+    let (hostname, rest) = match timestamp.and_then(|_| rest.split_once(' ')) {
+        Some((hostname, r)) if !hostname.ends_with(':') && !hostname.contains('[') => {
+            (Some(hostname), r)
+        }
+        _ => (None, rest),
+    };
+
+    let (app_name, proc_id, message) = parse_tag(rest);
+
+    SyslogMessage {
+        facility,
+        severity,
+        version: None,
+        timestamp,
+        hostname,
+        app_name,
+        proc_id,
+        msg_id: None,
+        structured_data: None,
+        message: Some(message).filter(|m| !m.is_empty()),
+    }
+}
+
+/// Parses timestamps like `Oct 11 22:14:15`, which have no year or timezone. They're assumed to
+/// be in UTC and from the most recent year that doesn't put them more than a day in the future.
+fn parse_rfc3164_timestamp(s: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let parse = |year: i32| {
+        NaiveDateTime::parse_from_str(&format!("{} {}", year, s), "%Y %b %e %H:%M:%S")
+            .ok()
+            .map(|t| t.and_utc())
+    };
+
+    let timestamp = parse(now.year())?;
+    if timestamp > now + Duration::days(1) {
+        parse(now.year() - 1)
+    } else {
+        Some(timestamp)
+    }
+}
+
+/// Parses a tag of the form `app[pid]:` or `app:`, returning the app name, proc id, and message
+fn parse_tag(s: &str) -> (Option<&str>, Option<&str>, &str) {
+    let end = s
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "-_./".contains(c)))
+        .unwrap_or(s.len());
+    if end == 0 {
+        return (None, None, s);
+    }
+
+    let (app_name, rest) = s.split_at(end);
+    let (proc_id, rest) = match rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+        Some((proc_id, rest)) => (Some(proc_id), rest),
+        None => (None, rest),
+    };
+
+    match rest.strip_prefix(':') {
+        Some(message) => (Some(app_name), proc_id, message.trim_start_matches(' ')),
+        None => (None, None, s),
+    }
+}
+
+fn next_token<'a>(s: &mut &'a str, name: &str) -> Result<&'a str, String> {
+    let (token, rest) = s
+        .split_once(' ')
+        .ok_or_else(|| format!("message ended before {}", name))?;
+    if token.is_empty() {
+        return Err(format!("missing {}", name));
+    }
+    *s = rest;
+    Ok(token)
+}
+
+fn nil(s: &str) -> Option<&str> {
+    (s != "-").then_some(s)
+}
+
+fn truncate(s: &str) -> &str {
+    match s.char_indices().nth(64) {
+        Some((i, _)) => &s[..i],
+        None => s,
+    }
+}
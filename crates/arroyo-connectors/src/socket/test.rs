@@ -0,0 +1,265 @@
+use arroyo_rpc::formats::{
+    DelimitedFraming, Endianness, FramingMethod, LengthPrefixedFraming, NewlineDelimitedFraming,
+    OctetCountingFraming,
+};
+use bytes::BytesMut;
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::json;
+
+use crate::socket::operator::FrameDecoder;
+use crate::socket::syslog::parse;
+use crate::socket::SyslogParser;
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap()
+}
+
+fn decode_all(decoder: &mut FrameDecoder, buf: &mut BytesMut, eof: bool) -> Vec<String> {
+    let mut frames = vec![];
+    loop {
+        let frame = if eof {
+            decoder.decode_eof(buf)
+        } else {
+            decoder.decode(buf)
+        };
+
+        match frame.unwrap() {
+            Some(frame) => frames.push(String::from_utf8(frame.to_vec()).unwrap()),
+            None => return frames,
+        }
+    }
+}
+
+#[test]
+fn test_rfc5424() {
+    let message = parse(
+        SyslogParser::Rfc5424,
+        "<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 \
+        [exampleSDID@32473 iut=\"3\" eventSource=\"Application\" eventID=\"1011\"]\
+        [examplePriority@32473 class=\"high\\]\"] \u{feff}An application event log entry...\n",
+        now(),
+    )
+    .unwrap();
+
+    assert_eq!(message.facility, 20);
+    assert_eq!(message.severity, 5);
+    assert_eq!(message.version, Some(1));
+    assert_eq!(
+        message.timestamp,
+        Some(Utc.timestamp_millis_opt(1065910455003).unwrap())
+    );
+    assert_eq!(message.hostname, Some("mymachine.example.com"));
+    assert_eq!(message.app_name, Some("evntslog"));
+    assert_eq!(message.proc_id, None);
+    assert_eq!(message.msg_id, Some("ID47"));
+    assert_eq!(
+        serde_json::Value::Object(message.structured_data.clone().unwrap()),
+        json!({
+            "exampleSDID@32473": {"iut": "3", "eventSource": "Application", "eventID": "1011"},
+            "examplePriority@32473": {"class": "high]"},
+        })
+    );
+    assert_eq!(message.message, Some("An application event log entry..."));
+
+    let json = message.to_json();
+    assert_eq!(json["timestamp"], json!("2003-10-11T22:14:15.003Z"));
+    assert!(json["structured_data"]
+        .as_str()
+        .unwrap()
+        .contains("eventSource"));
+
+    let message = parse(SyslogParser::Rfc5424, "<34>1 - - - - - -", now()).unwrap();
+    assert_eq!((message.facility, message.severity), (4, 2));
+    assert_eq!(message.timestamp, None);
+    assert_eq!(message.hostname, None);
+    assert_eq!(message.structured_data, None);
+    assert_eq!(message.message, None);
+
+    assert!(parse(SyslogParser::Rfc5424, "<34>1 - host", now()).is_err());
+    assert!(parse(SyslogParser::Rfc5424, "<192>1 - - - - - -", now()).is_err());
+    assert!(parse(
+        SyslogParser::Rfc5424,
+        "<34>1 - - - - - [id x=\"y] msg",
+        now()
+    )
+    .is_err());
+    assert!(parse(SyslogParser::Rfc5424, "Oct 11 22:14:15 host su: hi", now()).is_err());
+}
+
+#[test]
+fn test_rfc3164() {
+    let message = parse(
+        SyslogParser::Rfc3164,
+        "<34>Oct 11 22:14:15 mymachine su[123]: 'su root' failed for lonvick on /dev/pts/8",
+        now(),
+    )
+    .unwrap();
+
+    assert_eq!((message.facility, message.severity), (4, 2));
+    assert_eq!(message.version, None);
+    // the year is inferred so that the timestamp isn't in the future
+    assert_eq!(
+        message.timestamp,
+        Some(Utc.with_ymd_and_hms(2023, 10, 11, 22, 14, 15).unwrap())
+    );
+    assert_eq!(message.hostname, Some("mymachine"));
+    assert_eq!(message.app_name, Some("su"));
+    assert_eq!(message.proc_id, Some("123"));
+    assert_eq!(
+        message.message,
+        Some("'su root' failed for lonvick on /dev/pts/8")
+    );
+
+    let message = parse(
+        SyslogParser::Rfc3164,
+        "<13>Jan  1 10:00:00 cron: job ran",
+        now(),
+    )
+    .unwrap();
+    assert_eq!(
+        message.timestamp,
+        Some(Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap())
+    );
+    assert_eq!(message.hostname, None);
+    assert_eq!(message.app_name, Some("cron"));
+    assert_eq!(message.message, Some("job ran"));
+
+    // anything that can't be parsed ends up in the message
+    let message = parse(SyslogParser::Rfc3164, "just some text", now()).unwrap();
+    assert_eq!((message.facility, message.severity), (1, 5));
+    assert_eq!(message.timestamp, None);
+    assert_eq!(message.app_name, None);
+    assert_eq!(message.message, Some("just some text"));
+}
+
+#[test]
+fn test_auto_detect() {
+    let message = parse(SyslogParser::Auto, "<165>1 - host app - - - hello", now()).unwrap();
+    assert_eq!(message.version, Some(1));
+    assert_eq!(message.hostname, Some("host"));
+    assert_eq!(message.message, Some("hello"));
+
+    let message = parse(
+        SyslogParser::Auto,
+        "<165>Oct 11 22:14:15 host app: hello",
+        now(),
+    )
+    .unwrap();
+    assert_eq!(message.version, None);
+    assert_eq!(message.hostname, Some("host"));
+    assert_eq!(message.message, Some("hello"));
+}
+
+#[test]
+fn test_newline_frame_decoder() {
+    let mut decoder = FrameDecoder::new(Some(FramingMethod::Newline(NewlineDelimitedFraming {
+        max_line_length: Some(8),
+    })));
+
+    let mut buf = BytesMut::from("one\r\ntw");
+    assert_eq!(decode_all(&mut decoder, &mut buf, false), vec!["one"]);
+
+    buf.extend_from_slice(b"o\n\nthree is too long");
+    assert_eq!(
+        decode_all(&mut decoder, &mut buf, false),
+        vec!["two", "three is"]
+    );
+
+    // the rest of the long line is skipped
+    buf.extend_from_slice(b" still\nfour");
+    assert_eq!(
+        decode_all(&mut decoder, &mut buf, false),
+        Vec::<String>::new()
+    );
+    assert_eq!(decode_all(&mut decoder, &mut buf, true), vec!["four"]);
+}
+
+#[test]
+fn test_octet_counting_frame_decoder() {
+    let mut decoder = FrameDecoder::new(Some(FramingMethod::OctetCounting(OctetCountingFraming {
+        max_message_length: Some(5),
+    })));
+
+    let mut buf = BytesMut::from("3 one9 two\nthree1");
+    assert_eq!(
+        decode_all(&mut decoder, &mut buf, false),
+        vec!["one", "two\nt"]
+    );
+
+    // the rest of the long message is skipped, even across reads
+    buf.extend_from_slice(b"3 four and");
+    assert_eq!(decode_all(&mut decoder, &mut buf, false), vec!["four "]);
+    buf.extend_from_slice(b" more5 five!");
+    assert_eq!(decode_all(&mut decoder, &mut buf, true), vec!["five!"]);
+
+    let mut buf = BytesMut::from("abc def");
+    assert!(decoder.decode(&mut buf).is_err());
+
+    let mut buf = BytesMut::from("10 abc");
+    assert!(decoder.decode_eof(&mut buf).is_err());
+}
+
+#[test]
+fn test_length_prefixed_frame_decoder() {
+    let mut decoder =
+        FrameDecoder::new(Some(FramingMethod::LengthPrefixed(LengthPrefixedFraming {
+            prefix_bytes: 2,
+            endianness: Endianness::Big,
+            max_message_length: Some(4),
+        })));
+
+    let mut buf = BytesMut::from(&b"\x00\x03one\x00\x06tw"[..]);
+    assert_eq!(decode_all(&mut decoder, &mut buf, false), vec!["one"]);
+
+    // the rest of the long message is skipped
+    buf.extend_from_slice(b"o!!!\x00\x02hi");
+    assert_eq!(
+        decode_all(&mut decoder, &mut buf, false),
+        vec!["two!", "hi"]
+    );
+
+    let mut buf = BytesMut::from(&b"\x00"[..]);
+    assert!(decoder.decode_eof(&mut buf).is_err());
+}
+
+#[test]
+fn test_delimited_frame_decoder() {
+    let mut decoder = FrameDecoder::new(Some(FramingMethod::Delimited(DelimitedFraming {
+        delimiter: b"||".to_vec(),
+        max_message_length: Some(5),
+    })));
+
+    let mut buf = BytesMut::from("one||tw");
+    assert_eq!(decode_all(&mut decoder, &mut buf, false), vec!["one"]);
+
+    buf.extend_from_slice(b"o|");
+    assert_eq!(
+        decode_all(&mut decoder, &mut buf, false),
+        Vec::<String>::new()
+    );
+
+    buf.extend_from_slice(b"|||too long message|");
+    assert_eq!(
+        decode_all(&mut decoder, &mut buf, false),
+        vec!["two", "too l"]
+    );
+
+    // the rest of the long message is skipped, including a delimiter split across reads
+    buf.extend_from_slice(b"|four");
+    assert_eq!(
+        decode_all(&mut decoder, &mut buf, false),
+        Vec::<String>::new()
+    );
+    assert_eq!(decode_all(&mut decoder, &mut buf, true), vec!["four"]);
+}
+
+#[test]
+fn test_unframed_decoder() {
+    let mut decoder = FrameDecoder::new(None);
+    let mut buf = BytesMut::from("one\ntwo");
+    assert_eq!(
+        decode_all(&mut decoder, &mut buf, false),
+        Vec::<String>::new()
+    );
+    assert_eq!(decode_all(&mut decoder, &mut buf, true), vec!["one\ntwo"]);
+}
@@ -18,7 +18,7 @@ use crate::EmptyConfig;
 use crate::stdout::operator::StdoutSink;
 use arroyo_operator::connector::Connector;
 use arroyo_operator::operator::OperatorNode;
-use arroyo_rpc::formats::{Format, JsonFormat};
+use arroyo_rpc::formats::{Format, Framing, FramingMethod, JsonFormat, NewlineDelimitedFraming};
 
 pub struct StdoutConnector {}
 
@@ -132,7 +132,14 @@ impl Connector for StdoutConnector {
             .unwrap_or_else(|| Format::Json(JsonFormat::default()));
         Ok(OperatorNode::from_operator(Box::new(StdoutSink {
             stdout: BufWriter::new(tokio::io::stdout()),
-            serializer: ArrowSerializer::new(format),
+            // records are written as a stream, so default to newline-delimited
+            serializer: ArrowSerializer::new(format).with_framing(Some(c.framing.unwrap_or(
+                Framing {
+                    method: FramingMethod::Newline(NewlineDelimitedFraming {
+                        max_line_length: None,
+                    }),
+                },
+            ))),
         })))
     }
 }
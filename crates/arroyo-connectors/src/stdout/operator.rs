@@ -20,7 +20,6 @@ impl ArrowOperator for StdoutSink {
     async fn process_batch(&mut self, batch: RecordBatch, _: &mut ArrowContext) {
         for value in self.serializer.serialize(&batch) {
             self.stdout.write_all(&value).await.unwrap();
-            self.stdout.write_u8(b'\n').await.unwrap();
         }
         self.stdout.flush().await.unwrap();
     }
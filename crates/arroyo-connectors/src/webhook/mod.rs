@@ -227,7 +227,8 @@ impl Connector for WebhookConnector {
                 config
                     .format
                     .expect("No format configured for webhook sink"),
-            ),
+            )
+            .with_framing(config.framing),
             last_reported_error_at: Arc::new(Mutex::new(SystemTime::UNIX_EPOCH)),
         })))
     }
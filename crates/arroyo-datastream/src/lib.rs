@@ -54,6 +54,7 @@ pub struct WasmUDF {
 pub enum WindowType {
     Tumbling { width: Duration },
     Sliding { width: Duration, slide: Duration },
+    Cumulative { step: Duration, max_size: Duration },
     Instant,
     Session { gap: Duration },
 }
@@ -90,6 +91,14 @@ impl Debug for WindowType {
                     format_duration(*slide)
                 )
             }
+            Self::Cumulative { step, max_size } => {
+                write!(
+                    f,
+                    "CumulativeWindow(step: {}, max size: {})",
+                    format_duration(*step),
+                    format_duration(*max_size)
+                )
+            }
             Self::Instant => {
                 write!(f, "InstantWindow")
             }
@@ -1,5 +1,6 @@
 use datafusion_proto::protobuf::ArrowType;
 
+use crate::{format_duration, WindowType};
 use anyhow::anyhow;
 use arrow_schema::DataType;
 use arroyo_rpc::api_types::pipelines::{PipelineEdge, PipelineGraph, PipelineNode};
@@ -16,9 +17,10 @@ use rand::prelude::SmallRng;
 use rand::{Rng, SeedableRng};
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
-use std::fmt::{Debug, Display, Formatter};
+use std::fmt::{Debug, Display, Formatter, Write};
 use std::hash::Hasher;
 use std::sync::Arc;
+use std::time::Duration;
 use strum::{Display, EnumString};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, EnumString, Display)]
@@ -29,9 +31,14 @@ pub enum OperatorName {
     AsyncUdf,
     Join,
     InstantJoin,
+    TemporalJoin,
     WindowFunction,
+    TopN,
+    Dedup,
+    MatchRecognize,
     TumblingWindowAggregate,
     SlidingWindowAggregate,
+    CumulativeWindowAggregate,
     SessionWindowAggregate,
     UpdatingAggregate,
     ConnectorSource,
@@ -44,6 +51,20 @@ pub enum LogicalEdgeType {
     Shuffle,
     LeftJoin,
     RightJoin,
+    /// carries records that a source failed to deserialize to its dead-letter sink
+    DeadLetter,
+    /// carries records that arrived after their windows closed to the late-records sink
+    LateRecords,
+}
+
+impl LogicalEdgeType {
+    /// Side outputs carry records other than the operator's results, with their own schemas
+    pub fn is_side_output(&self) -> bool {
+        matches!(
+            self,
+            LogicalEdgeType::DeadLetter | LogicalEdgeType::LateRecords
+        )
+    }
 }
 
 impl Display for LogicalEdgeType {
@@ -53,6 +74,8 @@ impl Display for LogicalEdgeType {
             LogicalEdgeType::Shuffle => write!(f, "⤨"),
             LogicalEdgeType::LeftJoin => write!(f, "-[left]⤨"),
             LogicalEdgeType::RightJoin => write!(f, "-[right]⤨"),
+            LogicalEdgeType::DeadLetter => write!(f, "-[dead letter]⤨"),
+            LogicalEdgeType::LateRecords => write!(f, "-[late records]⤨"),
         }
     }
 }
@@ -65,6 +88,8 @@ impl From<arroyo_rpc::grpc::api::EdgeType> for LogicalEdgeType {
             EdgeType::Shuffle => LogicalEdgeType::Shuffle,
             EdgeType::LeftJoin => LogicalEdgeType::LeftJoin,
             EdgeType::RightJoin => LogicalEdgeType::RightJoin,
+            EdgeType::DeadLetter => LogicalEdgeType::DeadLetter,
+            EdgeType::LateRecords => LogicalEdgeType::LateRecords,
         }
     }
 }
@@ -76,6 +101,8 @@ impl From<LogicalEdgeType> for api::EdgeType {
             LogicalEdgeType::Shuffle => EdgeType::Shuffle,
             LogicalEdgeType::LeftJoin => EdgeType::LeftJoin,
             LogicalEdgeType::RightJoin => EdgeType::RightJoin,
+            LogicalEdgeType::DeadLetter => EdgeType::DeadLetter,
+            LogicalEdgeType::LateRecords => EdgeType::LateRecords,
         }
     }
 }
@@ -174,6 +201,231 @@ impl Debug for LogicalNode {
     }
 }
 
+impl LogicalNode {
+    fn explain_name(&self) -> String {
+        match self.operator_name {
+            OperatorName::ConnectorSource | OperatorName::ConnectorSink => {
+                match ConnectorOp::decode(&self.operator_config[..]) {
+                    Ok(op) => format!("{}({})", self.operator_name, op.connector),
+                    Err(_) => self.operator_name.to_string(),
+                }
+            }
+            op => op.to_string(),
+        }
+    }
+
+    /// The windowing, TTLs and state tables of the operator, as lines for EXPLAIN; these mirror
+    /// the tables that the operators register in the worker
+    fn explain_details(&self) -> Vec<String> {
+        let config = &self.operator_config[..];
+        let micros = Duration::from_micros;
+        let timestamp_table = |name: &str, retention: Duration| {
+            format!(
+                "state: {} (time-keyed, retention {})",
+                name,
+                format_duration(retention)
+            )
+        };
+        let global_table = |name: &str| format!("state: {} (global)", name);
+
+        match self.operator_name {
+            OperatorName::TumblingWindowAggregate => {
+                let Ok(c) = api::TumblingWindowAggregateOperator::decode(config) else {
+                    return vec![];
+                };
+                let width = micros(c.width_micros);
+                let allowed_lateness = micros(c.allowed_lateness_micros);
+                let mut details = vec![format!("window: {:?}", WindowType::Tumbling { width })];
+                if c.early_fire_micros > 0 {
+                    details.push(format!(
+                        "early fire: {}",
+                        format_duration(micros(c.early_fire_micros))
+                    ));
+                }
+                if !allowed_lateness.is_zero() {
+                    details.push(format!(
+                        "allowed lateness: {}",
+                        format_duration(allowed_lateness)
+                    ));
+                }
+                details.push(timestamp_table("t", width + allowed_lateness));
+                if c.early_fire_micros > 0 || !allowed_lateness.is_zero() {
+                    details.push(timestamp_table("f", width + allowed_lateness));
+                }
+                details
+            }
+            OperatorName::SlidingWindowAggregate => {
+                let Ok(c) = api::SlidingWindowAggregateOperator::decode(config) else {
+                    return vec![];
+                };
+                let width = micros(c.width_micros);
+                let allowed_lateness = micros(c.allowed_lateness_micros);
+                let mut details = vec![format!(
+                    "window: {:?}",
+                    WindowType::Sliding {
+                        width,
+                        slide: micros(c.slide_micros)
+                    }
+                )];
+                if c.early_fire_micros > 0 {
+                    details.push(format!(
+                        "early fire: {}",
+                        format_duration(micros(c.early_fire_micros))
+                    ));
+                }
+                if !allowed_lateness.is_zero() {
+                    details.push(format!(
+                        "allowed lateness: {}",
+                        format_duration(allowed_lateness)
+                    ));
+                }
+                details.push(timestamp_table("t", width + allowed_lateness));
+                if c.early_fire_micros > 0 || !allowed_lateness.is_zero() {
+                    details.push(timestamp_table("f", width + allowed_lateness));
+                }
+                details
+            }
+            OperatorName::CumulativeWindowAggregate => {
+                let Ok(c) = api::CumulativeWindowAggregateOperator::decode(config) else {
+                    return vec![];
+                };
+                let max_size = micros(c.max_size_micros);
+                vec![
+                    format!(
+                        "window: {:?}",
+                        WindowType::Cumulative {
+                            step: micros(c.step_micros),
+                            max_size,
+                        }
+                    ),
+                    timestamp_table("t", max_size),
+                ]
+            }
+            OperatorName::SessionWindowAggregate => {
+                let Ok(c) = api::SessionWindowAggregateOperator::decode(config) else {
+                    return vec![];
+                };
+                let gap = micros(c.gap_micros);
+                vec![
+                    format!("window: {:?}", WindowType::Session { gap }),
+                    global_table("e"),
+                    timestamp_table("s", gap * 100),
+                ]
+            }
+            OperatorName::UpdatingAggregate => {
+                let Ok(c) = api::UpdatingAggregateOperator::decode(config) else {
+                    return vec![];
+                };
+                let ttl = micros(c.ttl_micros);
+                vec![
+                    format!("ttl: {}", format_duration(ttl)),
+                    format!(
+                        "flush interval: {}",
+                        format_duration(micros(c.flush_interval_micros))
+                    ),
+                    timestamp_table("f", ttl),
+                    timestamp_table("p", ttl),
+                ]
+            }
+            OperatorName::Join => {
+                let Ok(c) = api::JoinOperator::decode(config) else {
+                    return vec![];
+                };
+                // a zero TTL means the default of one day
+                let ttl = match c.ttl_micros.map(micros) {
+                    Some(ttl) if !ttl.is_zero() => ttl,
+                    _ => Duration::from_secs(24 * 60 * 60),
+                };
+                vec![
+                    format!("ttl: {}", format_duration(ttl)),
+                    timestamp_table("left", ttl),
+                    timestamp_table("right", ttl),
+                ]
+            }
+            OperatorName::InstantJoin => vec![
+                format!("window: {:?}", WindowType::Instant),
+                timestamp_table("left", Duration::ZERO),
+                timestamp_table("right", Duration::ZERO),
+            ],
+            OperatorName::TemporalJoin => {
+                let Ok(c) = api::TemporalJoinOperator::decode(config) else {
+                    return vec![];
+                };
+                let ttl = micros(c.ttl_micros);
+                vec![
+                    format!("ttl: {}", format_duration(ttl)),
+                    format!("left outer: {}", c.left_outer),
+                    timestamp_table("left", Duration::ZERO),
+                    timestamp_table("right", ttl),
+                ]
+            }
+            OperatorName::WindowFunction => vec![timestamp_table("input", Duration::ZERO)],
+            OperatorName::TopN => {
+                let Ok(c) = api::TopNOperator::decode(config) else {
+                    return vec![];
+                };
+                vec![
+                    format!("limit: {}", c.limit),
+                    timestamp_table("input", Duration::ZERO),
+                ]
+            }
+            OperatorName::Dedup => {
+                let Ok(c) = api::DedupOperator::decode(config) else {
+                    return vec![];
+                };
+                let ttl = micros(c.ttl_micros);
+                vec![
+                    format!("ttl: {}", format_duration(ttl)),
+                    timestamp_table("s", ttl),
+                ]
+            }
+            OperatorName::MatchRecognize => {
+                let Ok(c) = api::MatchRecognizeOperator::decode(config) else {
+                    return vec![];
+                };
+                let within = micros(c.within_micros);
+                vec![
+                    format!("within: {}", format_duration(within)),
+                    format!("nfa states: {}", c.states.len()),
+                    timestamp_table("i", within),
+                ]
+            }
+            OperatorName::ExpressionWatermark => {
+                let Ok(c) = api::ExpressionWatermarkConfig::decode(config) else {
+                    return vec![];
+                };
+                let mut details = vec![format!(
+                    "period: {}",
+                    format_duration(micros(c.period_micros))
+                )];
+                if let Some(idle) = c.idle_time_micros {
+                    details.push(format!("idle time: {}", format_duration(micros(idle))));
+                }
+                details.push(global_table("s"));
+                details
+            }
+            OperatorName::AsyncUdf => {
+                let Ok(c) = api::AsyncUdfOperator::decode(config) else {
+                    return vec![];
+                };
+                vec![
+                    format!(
+                        "ordering: {:?}, max concurrency: {}, timeout: {}",
+                        c.ordering(),
+                        c.max_concurrency,
+                        format_duration(micros(c.timeout_micros))
+                    ),
+                    global_table("a"),
+                ]
+            }
+            OperatorName::ArrowValue
+            | OperatorName::ArrowKey
+            | OperatorName::ConnectorSource
+            | OperatorName::ConnectorSink => vec![],
+        }
+    }
+}
+
 pub type LogicalGraph = DiGraph<LogicalNode, LogicalEdge>;
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
@@ -228,6 +480,71 @@ impl LogicalProgram {
         }
     }
 
+    /// Renders the program as text for EXPLAIN: each operator in topological order with its
+    /// parallelism, windowing and state tables, followed by its outgoing edges and their key
+    /// columns. Verbose output also includes operator descriptions and edge schemas.
+    pub fn explain(&self, verbose: bool) -> String {
+        let mut out = String::new();
+
+        let order = petgraph::algo::toposort(&self.graph, None)
+            .unwrap_or_else(|_| self.graph.node_indices().collect());
+
+        for idx in order {
+            let node = &self.graph[idx];
+            writeln!(
+                out,
+                "{} [{}] parallelism={}",
+                node.operator_id,
+                node.explain_name(),
+                node.parallelism
+            )
+            .unwrap();
+
+            if verbose {
+                writeln!(out, "    description: {}", node.description).unwrap();
+            }
+
+            for detail in node.explain_details() {
+                writeln!(out, "    {}", detail).unwrap();
+            }
+
+            for edge in self.graph.edges_directed(idx, Direction::Outgoing) {
+                let LogicalEdge {
+                    edge_type, schema, ..
+                } = edge.weight();
+                write!(
+                    out,
+                    "  {} {} ({:?})",
+                    edge_type,
+                    self.graph[edge.target()].operator_id,
+                    edge_type
+                )
+                .unwrap();
+
+                if let Some(keys) = schema.key_indices.as_ref().filter(|k| !k.is_empty()) {
+                    let keys: Vec<_> = keys
+                        .iter()
+                        .map(|i| schema.schema.field(*i).name().as_str())
+                        .collect();
+                    write!(out, " keys=({})", keys.join(", ")).unwrap();
+                }
+                writeln!(out).unwrap();
+
+                if verbose {
+                    let fields: Vec<_> = schema
+                        .schema
+                        .fields()
+                        .iter()
+                        .map(|f| format!("{}: {}", f.name(), f.data_type()))
+                        .collect();
+                    writeln!(out, "      schema: ({})", fields.join(", ")).unwrap();
+                }
+            }
+        }
+
+        out
+    }
+
     pub fn dot(&self) -> String {
         format!("{:?}", Dot::with_config(&self.graph, &[]))
     }
@@ -284,11 +601,18 @@ impl LogicalProgram {
                 | OperatorName::ArrowKey => continue,
                 OperatorName::Join => "join-with-expiration".to_string(),
                 OperatorName::InstantJoin => "windowed-join".to_string(),
+                OperatorName::TemporalJoin => "temporal-join".to_string(),
                 OperatorName::WindowFunction => "sql-window-function".to_string(),
+                OperatorName::TopN => "sql-top-n".to_string(),
+                OperatorName::Dedup => "sql-dedup".to_string(),
+                OperatorName::MatchRecognize => "sql-match-recognize".to_string(),
                 OperatorName::TumblingWindowAggregate => {
                     "sql-tumbling-window-aggregate".to_string()
                 }
                 OperatorName::SlidingWindowAggregate => "sql-sliding-window-aggregate".to_string(),
+                OperatorName::CumulativeWindowAggregate => {
+                    "sql-cumulative-window-aggregate".to_string()
+                }
                 OperatorName::SessionWindowAggregate => "sql-session-window-aggregate".to_string(),
                 OperatorName::UpdatingAggregate => "sql-updating-aggregate".to_string(),
                 OperatorName::ConnectorSource => {
@@ -1,21 +1,87 @@
+use crate::avro::resolution::{deref, named_types, SchemaResolution};
+use crate::columnar::{
+    check_int_range, convert_time_unit, error, push_offset, Column, ColumnValues, ColumnarDecoder,
+};
 use crate::float_to_json;
+use apache_avro::schema::SchemaKind;
 use apache_avro::types::{Value, Value as AvroValue};
 use apache_avro::{from_avro_datum, AvroResult, Reader, Schema};
+use arrow::compute::kernels::cast_utils::string_to_timestamp_nanos;
+use arrow_schema::{DataType, FieldRef, Fields, TimeUnit};
 use arroyo_rpc::formats::AvroFormat;
 use arroyo_rpc::schema_resolver::SchemaResolver;
 use arroyo_types::SourceError;
 use serde_json::{json, Value as JsonValue};
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing::info;
+use tracing::{info, warn};
+
+/// A writer schema loaded from the schema resolver, or the reason it can't be used to read data
+/// for this table
+pub(crate) type WriterSchemas = HashMap<u32, Result<WriterSchema, String>>;
+
+pub(crate) enum WriterSchema {
+    /// a writer schema whose records are resolved into the table's reader schema
+    Resolved(SchemaResolution),
+    /// a writer schema that's used as-is, when the table doesn't have a reader schema
+    Unresolved(Schema),
+}
+
+impl WriterSchema {
+    /// Fails if records written with `writer` can't be read into the reader schema, or into the
+    /// table's `fields`
+    fn new(writer: Schema, reader: Option<&Schema>, fields: &Fields) -> Result<Self, String> {
+        let writer_schema = match reader {
+            Some(reader) => Self::Resolved(SchemaResolution::new(writer, reader.clone())?),
+            None => Self::Unresolved(writer),
+        };
+
+        // records are decoded in the shape of the reader schema, if there is one
+        let schema = reader.unwrap_or_else(|| writer_schema.schema());
+        check_decimal_scales(
+            &named_types(schema),
+            schema,
+            &DataType::Struct(fields.clone()),
+            "",
+        )?;
+
+        Ok(writer_schema)
+    }
+
+    fn schema(&self) -> &Schema {
+        match self {
+            WriterSchema::Resolved(resolution) => resolution.writer(),
+            WriterSchema::Unresolved(schema) => schema,
+        }
+    }
+
+    fn resolve(&self, value: AvroResult<Value>) -> Result<Value, SourceError> {
+        let value = value.map_err(|e| {
+            SourceError::bad_data(format!("failed to deserialize from avro: {:?}", e))
+        })?;
+
+        match self {
+            WriterSchema::Resolved(resolution) => resolution.resolve(value).map_err(|e| {
+                SourceError::bad_data(format!(
+                    "failed to resolve avro record into the table's schema: {}",
+                    e
+                ))
+            }),
+            WriterSchema::Unresolved(_) => Ok(value),
+        }
+    }
+}
 
 pub(crate) async fn avro_messages(
     format: &AvroFormat,
-    schema_registry: &Arc<Mutex<HashMap<u32, Schema>>>,
+    fields: &Fields,
+    schema_registry: &Arc<Mutex<WriterSchemas>>,
     resolver: &Arc<dyn SchemaResolver + Sync>,
     mut msg: &[u8],
-) -> Result<Vec<AvroResult<Value>>, SourceError> {
+) -> Result<Vec<Result<Value, SourceError>>, SourceError> {
     let id = if format.confluent_schema_registry {
         let magic_byte = msg[0];
         if magic_byte != 0 {
@@ -35,53 +101,131 @@ pub(crate) async fn avro_messages(
         0
     };
 
+    let reader_schema = format.reader_schema.as_ref().map(|s| &s.0);
+
     let mut registry = schema_registry.lock().await;
 
     let messages = if format.raw_datums || format.confluent_schema_registry {
-        let schema = if let std::collections::hash_map::Entry::Vacant(e) = registry.entry(id) {
-            let new_schema = resolver
-                .resolve_schema(id)
-                .await
-                .map_err(|e| SourceError::other("schema registry error", e))?
-                .ok_or_else(|| {
-                    SourceError::bad_data(format!(
-                        "could not resolve schema for message with id {}",
-                        id
-                    ))
-                })?;
+        let writer_schema = match registry.entry(id) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                let new_schema = resolver
+                    .resolve_schema(id)
+                    .await
+                    .map_err(|e| SourceError::other("schema registry error", e))?
+                    .ok_or_else(|| {
+                        SourceError::bad_data(format!(
+                            "could not resolve schema for message with id {}",
+                            id
+                        ))
+                    })?;
 
-            let new_schema = Schema::parse_str(&new_schema).map_err(|e| {
-                SourceError::other(
-                    "schema registry error",
-                    format!(
-                        "schema from Confluent Schema registry is not valid: {:?}",
-                        e
-                    ),
-                )
-            })?;
+                let new_schema = Schema::parse_str(&new_schema).map_err(|e| {
+                    SourceError::other(
+                        "schema registry error",
+                        format!(
+                            "schema from Confluent Schema registry is not valid: {:?}",
+                            e
+                        ),
+                    )
+                })?;
 
-            info!("Loaded new schema with id {} from Schema Registry", id);
-            e.insert(new_schema);
+                info!("Loaded new schema with id {} from Schema Registry", id);
+                let writer_schema = WriterSchema::new(new_schema, reader_schema, fields);
+                if let Err(e) = &writer_schema {
+                    warn!(
+                        "Schema with id {} is incompatible with the table's schema: {}",
+                        id, e
+                    );
+                }
 
-            registry.get(&id).unwrap()
-        } else {
-            registry.get(&id).unwrap()
+                e.insert(writer_schema)
+            }
         };
 
+        // every record written with an incompatible schema fails with the same error
+        let writer_schema = writer_schema.as_ref().map_err(|e| {
+            SourceError::bad_data(format!(
+                "writer schema with id {} is incompatible with the table's schema: {}",
+                id, e
+            ))
+        })?;
+
         let mut buf = msg;
-        vec![from_avro_datum(
-            schema,
-            &mut buf,
-            format.reader_schema.as_ref().map(|t| t.into()),
-        )]
+        vec![writer_schema.resolve(from_avro_datum(writer_schema.schema(), &mut buf, None))]
     } else {
-        Reader::new(msg)
-            .map_err(|e| SourceError::bad_data(format!("invalid Avro schema in message: {:?}", e)))?
-            .collect()
+        let reader = Reader::new(msg).map_err(|e| {
+            SourceError::bad_data(format!("invalid Avro schema in message: {:?}", e))
+        })?;
+
+        let writer_schema =
+            WriterSchema::new(reader.writer_schema().clone(), reader_schema, fields).map_err(
+                |e| {
+                    SourceError::bad_data(format!(
+                        "schema of the Avro data is incompatible with the table's schema: {}",
+                        e
+                    ))
+                },
+            )?;
+
+        reader.map(|v| writer_schema.resolve(v)).collect()
     };
     Ok(messages)
 }
 
+/// Checks that the Avro decimals read into Arrow decimals have the same scale, as they're read
+/// as unscaled integers
+fn check_decimal_scales(
+    names: &HashMap<String, Schema>,
+    schema: &Schema,
+    data_type: &DataType,
+    path: &str,
+) -> Result<(), String> {
+    let check = |schema: &Schema, data_type: &DataType, path: &str| {
+        check_decimal_scales(names, schema, data_type, path)
+    };
+
+    match (deref(names, schema), data_type) {
+        (Schema::Union(union), _) => union
+            .variants()
+            .iter()
+            .try_for_each(|v| check(v, data_type, path)),
+        (Schema::Decimal(decimal), DataType::Decimal128(_, scale))
+            if decimal.scale as i64 != *scale as i64 =>
+        {
+            Err(format!(
+                "field '{}' has decimal scale {}, which can't be read as decimal scale {}",
+                path, decimal.scale, scale
+            ))
+        }
+        (Schema::Record(record), DataType::Struct(fields)) => fields.iter().try_for_each(|f| {
+            match record.fields.iter().find(|rf| rf.name == *f.name()) {
+                Some(rf) => check(&rf.schema, f.data_type(), &field_path(path, f.name())),
+                None => Ok(()),
+            }
+        }),
+        (Schema::Map(values), DataType::Struct(fields)) => fields
+            .iter()
+            .try_for_each(|f| check(values, f.data_type(), &field_path(path, f.name()))),
+        (Schema::Array(items), DataType::List(item) | DataType::LargeList(item)) => {
+            check(items, item.data_type(), path)
+        }
+        (Schema::Map(values), DataType::Map(entries, _)) => match entries.data_type() {
+            DataType::Struct(kv) if kv.len() == 2 => check(values, kv[1].data_type(), path),
+            _ => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+fn field_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
 fn encode_vec(v: Vec<u8>) -> JsonValue {
     JsonValue::String(v.into_iter().map(char::from).collect())
 }
@@ -128,17 +272,255 @@ pub(crate) fn avro_to_json(value: AvroValue) -> JsonValue {
     }
 }
 
+impl ColumnarDecoder {
+    /// Decodes an Avro record directly into the Arrow columns of the target schema, without
+    /// going through JSON. Values are converted to the types of the target fields where that's
+    /// unambiguous (for example, Avro logical timestamps are converted to the unit of the target
+    /// timestamp), and fields with the JSON extension type receive the JSON encoding of their
+    /// values.
+    ///
+    /// Decimals are read as unscaled integers; writer schemas whose decimals have a different
+    /// scale than the target field are rejected when they're loaded.
+    pub fn decode_avro(&mut self, value: AvroValue) -> Result<(), SourceError> {
+        let mut fields = match value {
+            Value::Record(fields) => fields,
+            Value::Union(_, v) => match *v {
+                Value::Record(fields) => fields,
+                v => return Err(SourceError::bad_data(unexpected_value("record", &v))),
+            },
+            v => return Err(SourceError::bad_data(unexpected_value("record", &v))),
+        };
+
+        self.append_row(|columns| {
+            for (i, column) in columns.iter_mut().enumerate() {
+                let value = take_field(&mut fields, i, column.field.name());
+                append_avro(column, value)?;
+            }
+            Ok(())
+        })
+        .map_err(|e| SourceError::bad_data(format!("failed to deserialize from avro: {}", e)))
+    }
+}
+
+/// Takes the value of a record field, checking the expected position first as records usually
+/// have the same field order as the target schema. Missing fields are null.
+fn take_field(fields: &mut [(String, Value)], i: usize, name: &str) -> Value {
+    let field = match fields.get_mut(i) {
+        Some((n, v)) if n.as_str() == name => Some(v),
+        _ => fields
+            .iter_mut()
+            .find(|(n, _)| n.as_str() == name)
+            .map(|(_, v)| v),
+    };
+
+    field
+        .map(|v| std::mem::replace(v, Value::Null))
+        .unwrap_or(Value::Null)
+}
+
+fn unexpected_value(expected: impl Display, value: &Value) -> String {
+    format!("expected {}, found {:?}", expected, SchemaKind::from(value))
+}
+
+fn append_avro(column: &mut Column, value: Value) -> Result<(), String> {
+    let value = match value {
+        Value::Union(_, v) => *v,
+        v => v,
+    };
+
+    if matches!(value, Value::Null) {
+        return column.append_null();
+    }
+
+    let field = &column.field;
+    let json = column.json;
+    let data_type = field.data_type();
+    match &mut column.values {
+        ColumnValues::Boolean(values) => match value {
+            Value::Boolean(b) => values.push(b),
+            v => return Err(invalid(field, &v)),
+        },
+        ColumnValues::Int(values) => {
+            let v = to_int(data_type, value).map_err(|e| error(field, e))?;
+            values.push(v);
+        }
+        ColumnValues::UInt64(values) => {
+            let v = match value {
+                Value::Int(i) => u64::try_from(i).ok(),
+                Value::Long(i) => u64::try_from(i).ok(),
+                v => return Err(invalid(field, &v)),
+            };
+            values.push(v.ok_or_else(|| error(field, "negative value for UInt64"))?);
+        }
+        ColumnValues::Float(values) => values.push(match value {
+            Value::Float(f) => f as f64,
+            Value::Double(f) => f,
+            Value::Int(i) => i as f64,
+            Value::Long(i) => i as f64,
+            v => return Err(invalid(field, &v)),
+        }),
+        ColumnValues::Decimal(values) => {
+            let DataType::Decimal128(_, scale) = data_type else {
+                unreachable!("decimal column with type {}", data_type);
+            };
+            let v = to_decimal(*scale, value).map_err(|e| error(field, e))?;
+            values.push(v);
+        }
+        ColumnValues::Bytes { offsets, data } => {
+            match (data_type, value) {
+                (DataType::Utf8 | DataType::LargeUtf8, v) if json => {
+                    data.extend_from_slice(avro_to_json(v).to_string().as_bytes());
+                }
+                (DataType::Utf8 | DataType::LargeUtf8, Value::String(s) | Value::Enum(_, s)) => {
+                    data.extend_from_slice(s.as_bytes());
+                }
+                (DataType::Utf8 | DataType::LargeUtf8, v) => match avro_to_json(v) {
+                    JsonValue::String(s) => data.extend_from_slice(s.as_bytes()),
+                    v => data.extend_from_slice(v.to_string().as_bytes()),
+                },
+                (_, Value::Bytes(b) | Value::Fixed(_, b)) => data.extend_from_slice(&b),
+                (_, Value::String(s)) => data.extend_from_slice(s.as_bytes()),
+                (_, Value::Decimal(d)) => {
+                    let b: Vec<u8> = d.try_into().map_err(|e| error(field, e))?;
+                    data.extend_from_slice(&b);
+                }
+                (_, v) => return Err(invalid(field, &v)),
+            }
+            push_offset(field, offsets, data)?;
+        }
+        ColumnValues::List { offsets, item } => {
+            let Value::Array(items) = value else {
+                return Err(invalid(field, &value));
+            };
+            for v in items {
+                append_avro(item, v)?;
+            }
+            offsets.push(item.len() as i32);
+        }
+        ColumnValues::Struct(columns) => match value {
+            Value::Record(mut fields) => {
+                for (i, column) in columns.iter_mut().enumerate() {
+                    let value = take_field(&mut fields, i, column.field.name());
+                    append_avro(column, value)?;
+                }
+            }
+            Value::Map(mut fields) => {
+                for column in columns.iter_mut() {
+                    let value = fields.remove(column.field.name()).unwrap_or(Value::Null);
+                    append_avro(column, value)?;
+                }
+            }
+            v => return Err(invalid(field, &v)),
+        },
+        ColumnValues::Map {
+            offsets,
+            keys,
+            values,
+        } => {
+            let Value::Map(entries) = value else {
+                return Err(invalid(field, &value));
+            };
+            for (k, v) in entries {
+                append_avro(keys, Value::String(k))?;
+                append_avro(values, v)?;
+            }
+            offsets.push(keys.len() as i32);
+        }
+    }
+
+    column.validity.push(true);
+    Ok(())
+}
+
+fn invalid(field: &FieldRef, value: &Value) -> String {
+    error(field, unexpected_value(field.data_type(), value))
+}
+
+/// Converts an integer-like Avro value to the integer representation of the target type
+fn to_int(data_type: &DataType, value: Value) -> Result<i64, String> {
+    let v = match (data_type, value) {
+        (
+            DataType::Timestamp(unit, _),
+            Value::TimestampMillis(v) | Value::LocalTimestampMillis(v),
+        ) => convert_time_unit(v, TimeUnit::Millisecond, unit)?,
+        (
+            DataType::Timestamp(unit, _),
+            Value::TimestampMicros(v) | Value::LocalTimestampMicros(v),
+        ) => convert_time_unit(v, TimeUnit::Microsecond, unit)?,
+        (DataType::Timestamp(unit, _), Value::Date(d)) => {
+            convert_time_unit(d as i64 * 86_400, TimeUnit::Second, unit)?
+        }
+        (DataType::Timestamp(unit, _), Value::String(s)) => {
+            let nanos = string_to_timestamp_nanos(&s).map_err(|e| e.to_string())?;
+            convert_time_unit(nanos, TimeUnit::Nanosecond, unit)?
+        }
+        (DataType::Date32, Value::Date(d)) => d as i64,
+        (DataType::Date64, Value::Date(d)) => d as i64 * 86_400_000,
+        (DataType::Time32(unit) | DataType::Time64(unit), Value::TimeMillis(v)) => {
+            convert_time_unit(v as i64, TimeUnit::Millisecond, unit)?
+        }
+        (DataType::Time32(unit) | DataType::Time64(unit), Value::TimeMicros(v)) => {
+            convert_time_unit(v, TimeUnit::Microsecond, unit)?
+        }
+        (_, Value::Int(i)) => i as i64,
+        (_, Value::Long(i)) => i,
+        (_, v) => return Err(unexpected_value(data_type, &v)),
+    };
+
+    check_int_range(data_type, v)
+}
+
+/// Converts a decimal-like Avro value to an unscaled i128 with the given scale
+fn to_decimal(scale: i8, value: Value) -> Result<i128, String> {
+    let factor = 10i128.pow(scale.max(0) as u32);
+    match value {
+        Value::Decimal(d) => {
+            let bytes: Vec<u8> = d.try_into().map_err(|e| format!("{:?}", e))?;
+            if bytes.len() > 16 {
+                return Err("decimal is too large for a 128-bit decimal".to_string());
+            }
+
+            // the bytes are a big-endian two's complement integer, which we sign-extend
+            let negative = bytes.first().is_some_and(|b| b & 0x80 != 0);
+            let mut buf = [if negative { 0xff } else { 0 }; 16];
+            buf[16 - bytes.len()..].copy_from_slice(&bytes);
+            Ok(i128::from_be_bytes(buf))
+        }
+        Value::Int(i) => (i as i128)
+            .checked_mul(factor)
+            .ok_or_else(|| format!("{} is out of range", i)),
+        Value::Long(i) => (i as i128)
+            .checked_mul(factor)
+            .ok_or_else(|| format!("{} is out of range", i)),
+        Value::Float(f) => Ok((f as f64 * factor as f64).round() as i128),
+        Value::Double(f) => Ok((f * factor as f64).round() as i128),
+        v => Err(unexpected_value(
+            format!("decimal with scale {}", scale),
+            &v,
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::avro::de::WriterSchema;
     use crate::avro::schema::to_arrow;
+    use crate::columnar::ColumnarDecoder;
     use crate::de::ArrowDeserializer;
+    use apache_avro::types::Value;
+    use apache_avro::Decimal;
     use arrow_array::builder::{make_builder, ArrayBuilder};
-    use arrow_array::RecordBatch;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{
+        Decimal128Type, Float64Type, Int32Type, Int64Type, TimestampNanosecondType,
+    };
+    use arrow_array::{Array, RecordBatch};
     use arrow_json::writer::record_batch_to_vec;
-    use arrow_schema::{DataType, Field, Schema, TimeUnit};
+    use arrow_schema::{DataType, Field, Fields, Schema, TimeUnit};
     use arroyo_rpc::df::ArroyoSchema;
     use arroyo_rpc::formats::{AvroFormat, BadData, Format};
     use arroyo_rpc::schema_resolver::{FailingSchemaResolver, FixedSchemaResolver, SchemaResolver};
+    use arroyo_types::ArroyoExtensionType;
     use serde_json::json;
     use std::sync::Arc;
     use std::time::SystemTime;
@@ -455,6 +837,152 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_incompatible_writer_schema() {
+        let reader_schema = r#"{"namespace": "example.avro",
+            "type": "record",
+            "name": "User",
+            "fields": [
+                {"name": "name", "type": "string"},
+                {"name": "favorite_number", "type": "int"},
+                {"name": "country", "type": "string"}
+            ]
+        }"#;
+
+        let writer_schema = r#"{"namespace": "example.avro",
+            "type": "record",
+            "name": "User",
+            "fields": [
+                {"name": "name", "type": "string"},
+                {"name": "favorite_number", "type": "int"}
+            ]
+        }"#;
+
+        let schema = apache_avro::Schema::parse_str(writer_schema).unwrap();
+        let mut value = apache_avro::types::Record::new(&schema).unwrap();
+        value.put(
+            "name",
+            apache_avro::types::Value::String("Alyssa".to_string()),
+        );
+        value.put("favorite_number", apache_avro::types::Value::Int(256));
+
+        let mut bytes = vec![0, 0, 0, 0, 1];
+        bytes.extend_from_slice(&apache_avro::to_avro_datum(&schema, value).unwrap());
+
+        let mut format = AvroFormat::new(true, false, false);
+        format.add_reader_schema(apache_avro::Schema::parse_str(reader_schema).unwrap());
+
+        let (mut deserializer, mut builders, _) =
+            deserializer_with_schema(format, Some(writer_schema));
+
+        // each record written with the incompatible schema fails with the same error
+        for _ in 0..2 {
+            let errors = deserializer
+                .deserialize_slice(&mut builders, &bytes, SystemTime::now(), None)
+                .await;
+            assert_eq!(errors.len(), 1);
+            assert_eq!(
+                errors[0].details(),
+                "writer schema with id 1 is incompatible with the table's schema: \
+                field 'country': missing from the writer's schema, and has no default"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_debezium_envelope() {
+        let writer_schema = r#"{"namespace": "shop.orders",
+            "type": "record",
+            "name": "Envelope",
+            "fields": [
+                {"name": "before", "default": null, "type": ["null", {
+                    "type": "record",
+                    "name": "Value",
+                    "fields": [
+                        {"name": "id", "type": "long"},
+                        {"name": "status", "type": "string"}
+                    ]
+                }]},
+                {"name": "after", "type": ["null", "Value"], "default": null},
+                {"name": "source", "type": {
+                    "type": "record",
+                    "name": "Source",
+                    "fields": [{"name": "db", "type": "string"}]
+                }},
+                {"name": "op", "type": "string"},
+                {"name": "ts_ms", "type": ["null", "long"], "default": null}
+            ]
+        }"#;
+
+        // the table only needs the parts of the envelope that describe the change
+        let reader_schema = r#"{"namespace": "shop.orders",
+            "type": "record",
+            "name": "Envelope",
+            "fields": [
+                {"name": "before", "default": null, "type": ["null", {
+                    "type": "record",
+                    "name": "Value",
+                    "fields": [
+                        {"name": "id", "type": "long"},
+                        {"name": "status", "type": "string"}
+                    ]
+                }]},
+                {"name": "after", "type": ["null", "Value"], "default": null},
+                {"name": "op", "type": "string"}
+            ]
+        }"#;
+
+        let row = |status: &str| {
+            apache_avro::types::Value::Union(
+                1,
+                Box::new(apache_avro::types::Value::Record(vec![
+                    ("id".to_string(), apache_avro::types::Value::Long(1)),
+                    (
+                        "status".to_string(),
+                        apache_avro::types::Value::String(status.to_string()),
+                    ),
+                ])),
+            )
+        };
+
+        let schema = apache_avro::Schema::parse_str(writer_schema).unwrap();
+        let mut value = apache_avro::types::Record::new(&schema).unwrap();
+        value.put("before", row("new"));
+        value.put("after", row("shipped"));
+        value.put(
+            "source",
+            apache_avro::types::Value::Record(vec![(
+                "db".to_string(),
+                apache_avro::types::Value::String("shop".to_string()),
+            )]),
+        );
+        value.put("op", apache_avro::types::Value::String("u".to_string()));
+        value.put(
+            "ts_ms",
+            apache_avro::types::Value::Union(
+                1,
+                Box::new(apache_avro::types::Value::Long(1700000000000)),
+            ),
+        );
+
+        let mut bytes = vec![0, 0, 0, 0, 1];
+        bytes.extend_from_slice(&apache_avro::to_avro_datum(&schema, value).unwrap());
+
+        let mut format = AvroFormat::new(true, false, false);
+        format.debezium = true;
+        format.add_reader_schema(apache_avro::Schema::parse_str(reader_schema).unwrap());
+
+        let v = deserialize_with_schema(format, Some(writer_schema), &bytes).await;
+        assert_eq!(
+            serde_json::to_value(v).unwrap(),
+            json!([{
+                "before": {"id": 1, "status": "new"},
+                "after": {"id": 1, "status": "shipped"},
+                "op": "u"
+            }])
+        );
+    }
+
     #[tokio::test]
     async fn test_embedded() {
         let data = [
@@ -520,4 +1048,205 @@ mod tests {
             expected
         );
     }
+
+    fn native_schema() -> Schema {
+        let entries = Field::new(
+            "entries",
+            DataType::Struct(Fields::from(vec![
+                Field::new("keys", DataType::Utf8, false),
+                Field::new("values", DataType::Int64, true),
+            ])),
+            false,
+        );
+
+        Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("amount", DataType::Decimal128(10, 2), true),
+            Field::new(
+                "created",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            Field::new("tags", DataType::Map(Arc::new(entries), false), true),
+            Field::new(
+                "nested",
+                DataType::Struct(Fields::from(vec![
+                    Field::new("a", DataType::Utf8, true),
+                    Field::new_list("b", Field::new_list_field(DataType::Float64, true), true),
+                ])),
+                true,
+            ),
+            ArroyoExtensionType::add_metadata(
+                Some(ArroyoExtensionType::JSON),
+                Field::new("extra", DataType::Utf8, true),
+            ),
+        ])
+    }
+
+    fn record(fields: Vec<(&str, Value)>) -> Value {
+        Value::Record(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_native_decoding() {
+        let mut decoder = ColumnarDecoder::new(&native_schema()).unwrap();
+
+        decoder
+            .decode_avro(record(vec![
+                ("id", Value::Int(1)),
+                (
+                    "amount",
+                    Value::Union(1, Box::new(Value::Decimal(Decimal::from(vec![0x30, 0x39])))),
+                ),
+                ("created", Value::TimestampMillis(1_000)),
+                (
+                    "tags",
+                    Value::Union(
+                        1,
+                        Box::new(Value::Map(
+                            [("x".to_string(), Value::Long(5))].into_iter().collect(),
+                        )),
+                    ),
+                ),
+                (
+                    "nested",
+                    Value::Union(
+                        1,
+                        Box::new(record(vec![
+                            ("a", Value::String("hi".to_string())),
+                            (
+                                "b",
+                                Value::Array(vec![Value::Double(1.5), Value::Float(2.0)]),
+                            ),
+                        ])),
+                    ),
+                ),
+                (
+                    "extra",
+                    Value::Array(vec![Value::Int(1), Value::String("a".to_string())]),
+                ),
+            ]))
+            .unwrap();
+
+        // fields may be in any order, and missing fields are null
+        decoder
+            .decode_avro(record(vec![
+                ("created", Value::TimestampMicros(2_000)),
+                ("id", Value::Long(2)),
+                ("amount", Value::Union(0, Box::new(Value::Null))),
+                ("tags", Value::Null),
+                ("nested", Value::Null),
+            ]))
+            .unwrap();
+
+        let columns = decoder.flush();
+        assert!(decoder.is_empty());
+
+        let ids = columns[0].as_primitive::<Int32Type>();
+        assert_eq!(ids.values().to_vec(), vec![1, 2]);
+
+        let amounts = columns[1].as_primitive::<Decimal128Type>();
+        assert_eq!(amounts.value(0), 12345);
+        assert_eq!(amounts.value_as_string(0), "123.45");
+        assert!(amounts.is_null(1));
+
+        let created = columns[2].as_primitive::<TimestampNanosecondType>();
+        assert_eq!(created.values().to_vec(), vec![1_000_000_000, 2_000_000]);
+
+        let tags = columns[3].as_map();
+        assert!(tags.is_null(1));
+        let entries = tags.value(0);
+        assert_eq!(entries.column(0).as_string::<i32>().value(0), "x");
+        assert_eq!(entries.column(1).as_primitive::<Int64Type>().value(0), 5);
+
+        let nested = columns[4].as_struct();
+        assert!(nested.is_null(1));
+        assert_eq!(nested.column(0).as_string::<i32>().value(0), "hi");
+        let b = nested.column(1).as_list::<i32>().value(0);
+        assert_eq!(
+            b.as_primitive::<Float64Type>().values().to_vec(),
+            vec![1.5, 2.0]
+        );
+
+        let extra = columns[5].as_string::<i32>();
+        assert_eq!(extra.value(0), r#"[1,"a"]"#);
+        assert!(extra.is_null(1));
+    }
+
+    #[test]
+    fn test_decimal_scale_mismatch() {
+        let schema = |scale| {
+            apache_avro::Schema::parse_str(&format!(
+                r#"{{"type": "record", "name": "r", "fields": [
+                    {{"name": "id", "type": "int"}},
+                    {{"name": "amount", "type": ["null", {{"type": "bytes",
+                        "logicalType": "decimal", "precision": 10, "scale": {}}}]}}
+                ]}}"#,
+                scale
+            ))
+            .unwrap()
+        };
+        let fields = native_schema().fields;
+
+        assert!(WriterSchema::new(schema(2), None, &fields).is_ok());
+
+        let Err(e) = WriterSchema::new(schema(3), None, &fields) else {
+            panic!("writer schema with a different decimal scale should be rejected");
+        };
+        assert!(e.contains("'amount'"), "{}", e);
+    }
+
+    #[test]
+    fn test_native_decoding_errors() {
+        let mut decoder = ColumnarDecoder::new(&native_schema()).unwrap();
+
+        let valid = |id| {
+            record(vec![
+                ("id", Value::Int(id)),
+                ("created", Value::TimestampMillis(0)),
+            ])
+        };
+
+        decoder.decode_avro(valid(1)).unwrap();
+
+        // the error comes after several columns have been appended to, which must be rolled back
+        let err = decoder
+            .decode_avro(record(vec![
+                ("id", Value::Int(2)),
+                ("created", Value::TimestampMillis(0)),
+                (
+                    "tags",
+                    Value::Map(
+                        [("x".to_string(), Value::String("five".to_string()))]
+                            .into_iter()
+                            .collect(),
+                    ),
+                ),
+            ]))
+            .unwrap_err();
+        assert!(err.details().contains("values"), "{:?}", err);
+
+        assert!(decoder
+            .decode_avro(record(vec![("id", Value::Long(i64::MAX))]))
+            .is_err());
+        assert!(decoder
+            .decode_avro(record(vec![("created", Value::TimestampMillis(0))]))
+            .is_err());
+        assert!(decoder.decode_avro(Value::Int(1)).is_err());
+
+        decoder.decode_avro(valid(3)).unwrap();
+
+        let columns = decoder.flush();
+        assert!(columns.iter().all(|c| c.len() == 2));
+        assert_eq!(
+            columns[0].as_primitive::<Int32Type>().values().to_vec(),
+            vec![1, 3]
+        );
+        assert_eq!(columns[3].as_map().entries().len(), 0);
+    }
 }
@@ -1,3 +1,5 @@
 pub mod de;
+pub mod ocf;
+pub mod resolution;
 pub mod schema;
 pub mod ser;
@@ -0,0 +1,174 @@
+use crate::avro;
+use apache_avro::types::Value;
+use apache_avro::{Codec, Schema};
+use arrow_array::RecordBatch;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const MAGIC: &[u8] = b"Obj\x01";
+
+/// Records are grouped into blocks of roughly this many (uncompressed) bytes
+const TARGET_BLOCK_SIZE: usize = 64 * 1024;
+
+/// Incrementally writes an Avro object container file. Unlike [`apache_avro::Writer`], encoded
+/// bytes can be taken out of the writer as the file is written, and the current block can be
+/// ended at any point (for example at the end of an upload part) so that every chunk of the
+/// file that's taken ends with a sync marker.
+pub struct ContainerWriter {
+    schema: Schema,
+    codec: Codec,
+    sync_marker: [u8; 16],
+    /// encoded bytes that haven't been taken yet, starting with the header
+    buffer: Vec<u8>,
+    /// the uncompressed records of the current block
+    block: Vec<u8>,
+    block_records: usize,
+}
+
+impl ContainerWriter {
+    pub fn new(schema: Schema, codec: Codec) -> Self {
+        let sync_marker = *Uuid::new_v4().as_bytes();
+
+        // the full JSON form of the schema is used rather than the canonical form, which would
+        // drop logical types
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            "avro.schema".to_string(),
+            Value::Bytes(serde_json::to_vec(&schema).unwrap()),
+        );
+        let codec_name: &'static str = codec.into();
+        metadata.insert(
+            "avro.codec".to_string(),
+            Value::Bytes(codec_name.as_bytes().to_vec()),
+        );
+
+        let mut buffer = MAGIC.to_vec();
+        buffer.extend(
+            apache_avro::to_avro_datum(&Schema::Map(Box::new(Schema::Bytes)), Value::Map(metadata))
+                .expect("avro metadata is valid"),
+        );
+        buffer.extend(sync_marker);
+
+        Self {
+            schema,
+            codec,
+            sync_marker,
+            buffer,
+            block: vec![],
+            block_records: 0,
+        }
+    }
+
+    /// Appends the rows of the batch, whose columns must match the writer's schema
+    pub fn write_batch(&mut self, batch: &RecordBatch) {
+        for value in avro::ser::serialize(&self.schema, batch) {
+            self.block.extend(
+                apache_avro::to_avro_datum(&self.schema, value).expect("avro serialization failed"),
+            );
+            self.block_records += 1;
+
+            if self.block.len() >= TARGET_BLOCK_SIZE {
+                self.end_block();
+            }
+        }
+    }
+
+    /// Writes out the records of the current block, followed by a sync marker
+    pub fn end_block(&mut self) {
+        if self.block_records == 0 {
+            return;
+        }
+
+        let mut block = std::mem::take(&mut self.block);
+        self.codec
+            .compress(&mut block)
+            .expect("avro compression failed");
+
+        encode_long(self.block_records as i64, &mut self.buffer);
+        encode_long(block.len() as i64, &mut self.buffer);
+        self.buffer.extend(block);
+        self.buffer.extend(self.sync_marker);
+        self.block_records = 0;
+    }
+
+    /// The number of bytes buffered, including the records of the current block (before
+    /// compression)
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len() + self.block.len()
+    }
+
+    /// Ends the current block and takes all of the bytes written since the last call
+    pub fn take(&mut self) -> Vec<u8> {
+        self.end_block();
+        std::mem::take(&mut self.buffer)
+    }
+
+    /// Ends the current block and returns the bytes written since the last call to `take`,
+    /// leaving them in the writer
+    pub fn buffered(&mut self) -> &[u8] {
+        self.end_block();
+        &self.buffer
+    }
+}
+
+/// Avro's zig-zag variable-length encoding for longs
+fn encode_long(n: i64, buf: &mut Vec<u8>) {
+    let mut z = ((n << 1) ^ (n >> 63)) as u64;
+    while z & !0x7f != 0 {
+        buf.push((z & 0x7f | 0x80) as u8);
+        z >>= 7;
+    }
+    buf.push(z as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ContainerWriter;
+    use crate::avro::schema::to_avro;
+    use apache_avro::types::Value;
+    use apache_avro::{Codec, Reader};
+    use arrow_array::{Int64Array, RecordBatch, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    fn batch(start: i64) -> RecordBatch {
+        RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("name", DataType::Utf8, true),
+            ])),
+            vec![
+                Arc::new(Int64Array::from(vec![start, start + 1])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_chunks_form_a_container_file() {
+        for codec in [Codec::Null, Codec::Deflate, Codec::Snappy] {
+            let schema = to_avro("Row", &batch(0).schema().fields);
+            let mut writer = ContainerWriter::new(schema, codec);
+
+            // each chunk ends on a block boundary, so they can be concatenated
+            let mut file = vec![];
+            writer.write_batch(&batch(0));
+            file.extend(writer.take());
+            writer.write_batch(&batch(2));
+            assert!(!writer.buffered().is_empty());
+            writer.write_batch(&batch(4));
+            file.extend(writer.take());
+
+            let ids: Vec<_> = Reader::new(&file[..])
+                .unwrap()
+                .map(|r| match r.unwrap() {
+                    Value::Record(fields) => fields[0].1.clone(),
+                    v => panic!("expected a record, got {:?}", v),
+                })
+                .collect();
+
+            assert_eq!(ids, (0..6).map(Value::Long).collect::<Vec<_>>());
+        }
+    }
+}
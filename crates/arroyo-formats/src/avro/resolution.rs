@@ -0,0 +1,551 @@
+use apache_avro::schema::{RecordField, Schema, SchemaKind};
+use apache_avro::types::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Reads data written with one Avro schema (the writer schema) as another (the reader schema),
+/// following the Avro schema resolution rules: record fields are matched by name or by one of the
+/// reader field's aliases, fields that are missing from the writer take the reader's default,
+/// fields that are missing from the reader are dropped, and numeric values are promoted to the
+/// reader's type.
+///
+/// Records are matched structurally, so the names of records in the two schemas don't need to
+/// agree.
+pub struct SchemaResolution {
+    writer: Schema,
+    reader: Schema,
+    writer_names: HashMap<String, Schema>,
+    reader_names: HashMap<String, Schema>,
+}
+
+impl SchemaResolution {
+    /// Fails with a description of the incompatibility if data written with `writer` can't be
+    /// read as `reader`
+    pub fn new(writer: Schema, reader: Schema) -> Result<Self, String> {
+        let resolution = Self {
+            writer_names: named_types(&writer),
+            reader_names: named_types(&reader),
+            writer,
+            reader,
+        };
+
+        resolution.check(
+            &resolution.writer,
+            &resolution.reader,
+            "",
+            &mut HashSet::new(),
+        )?;
+
+        Ok(resolution)
+    }
+
+    pub fn writer(&self) -> &Schema {
+        &self.writer
+    }
+
+    /// Resolves a value that was decoded with the writer schema into the reader schema
+    pub fn resolve(&self, value: Value) -> Result<Value, String> {
+        self.resolve_value(value, &self.writer, &self.reader, "")
+    }
+
+    fn check(
+        &self,
+        writer: &Schema,
+        reader: &Schema,
+        path: &str,
+        seen: &mut HashSet<(String, String)>,
+    ) -> Result<(), String> {
+        let writer = deref(&self.writer_names, writer);
+        let reader = deref(&self.reader_names, reader);
+
+        // every variant of a writer union must be readable, as any of them may be written
+        if let Schema::Union(union) = writer {
+            return union
+                .variants()
+                .iter()
+                .try_for_each(|v| self.check(v, reader, path, seen));
+        }
+
+        if let Schema::Union(union) = reader {
+            let Some((_, variant)) = self.select_variant(writer, union.variants()) else {
+                return Err(format!(
+                    "{}: {} can't be read as any of the types of the reader's union",
+                    location(path),
+                    kind(writer)
+                ));
+            };
+            return self.check(writer, variant, path, seen);
+        }
+
+        match (writer, reader) {
+            (Schema::Record(w), Schema::Record(r)) => {
+                // recursive types only need to be checked once
+                if !seen.insert((w.name.fullname(None), r.name.fullname(None))) {
+                    return Ok(());
+                }
+
+                for field in &r.fields {
+                    let path = field_path(path, &field.name);
+                    match writer_field(&w.fields, field) {
+                        Some((_, wf)) => self.check(&wf.schema, &field.schema, &path, seen)?,
+                        None if field.default.is_some() => {}
+                        None => {
+                            return Err(format!(
+                                "{}: missing from the writer's schema, and has no default",
+                                location(&path)
+                            ));
+                        }
+                    }
+                }
+                Ok(())
+            }
+            (Schema::Enum(w), Schema::Enum(r)) => {
+                if r.default.is_some() {
+                    return Ok(());
+                }
+
+                match w.symbols.iter().find(|s| !r.symbols.contains(s)) {
+                    Some(symbol) => Err(format!(
+                        "{}: symbol '{}' is missing from the reader's enum, which has no default",
+                        location(path),
+                        symbol
+                    )),
+                    None => Ok(()),
+                }
+            }
+            (Schema::Fixed(w), Schema::Fixed(r)) => {
+                if w.size != r.size {
+                    return Err(format!(
+                        "{}: fixed size {} can't be read as fixed size {}",
+                        location(path),
+                        w.size,
+                        r.size
+                    ));
+                }
+                Ok(())
+            }
+            (Schema::Decimal(w), Schema::Decimal(r)) => {
+                if (w.precision, w.scale) != (r.precision, r.scale) {
+                    return Err(format!(
+                        "{}: decimal({}, {}) can't be read as decimal({}, {})",
+                        location(path),
+                        w.precision,
+                        w.scale,
+                        r.precision,
+                        r.scale
+                    ));
+                }
+                Ok(())
+            }
+            (Schema::Array(w), Schema::Array(r)) | (Schema::Map(w), Schema::Map(r)) => {
+                self.check(w, r, path, seen)
+            }
+            (w, r) if promotable(w, r) => Ok(()),
+            (w, r) => Err(format!(
+                "{}: {} can't be read as {}",
+                location(path),
+                kind(w),
+                kind(r)
+            )),
+        }
+    }
+
+    fn resolve_value(
+        &self,
+        value: Value,
+        writer: &Schema,
+        reader: &Schema,
+        path: &str,
+    ) -> Result<Value, String> {
+        let writer = deref(&self.writer_names, writer);
+        let reader = deref(&self.reader_names, reader);
+
+        if let Schema::Union(union) = writer {
+            let Value::Union(i, value) = value else {
+                return Err(format!("{}: expected a union value", location(path)));
+            };
+            let variant = union
+                .variants()
+                .get(i as usize)
+                .ok_or_else(|| format!("{}: invalid union index {}", location(path), i))?;
+            return self.resolve_value(*value, variant, reader, path);
+        }
+
+        if let Schema::Union(union) = reader {
+            let (i, variant) = self
+                .select_variant(writer, union.variants())
+                .ok_or_else(|| {
+                    format!(
+                        "{}: {} can't be read as any of the types of the reader's union",
+                        location(path),
+                        kind(writer)
+                    )
+                })?;
+            let value = self.resolve_value(value, writer, variant, path)?;
+            return Ok(Value::Union(i as u32, Box::new(value)));
+        }
+
+        match (writer, reader, value) {
+            (Schema::Record(w), Schema::Record(r), Value::Record(mut fields)) => {
+                let mut resolved = Vec::with_capacity(r.fields.len());
+                for field in &r.fields {
+                    let path = field_path(path, &field.name);
+                    let value = match writer_field(&w.fields, field) {
+                        Some((i, wf)) => {
+                            let value = fields
+                                .get_mut(i)
+                                .map(|(_, v)| std::mem::replace(v, Value::Null))
+                                .ok_or_else(|| format!("{}: missing value", location(&path)))?;
+                            self.resolve_value(value, &wf.schema, &field.schema, &path)?
+                        }
+                        None => self.default_value(field, &path)?,
+                    };
+                    resolved.push((field.name.clone(), value));
+                }
+                Ok(Value::Record(resolved))
+            }
+            (Schema::Enum(_), Schema::Enum(r), Value::Enum(_, symbol)) => {
+                let symbol = if r.symbols.contains(&symbol) {
+                    symbol
+                } else {
+                    r.default.clone().ok_or_else(|| {
+                        format!(
+                            "{}: symbol '{}' is missing from the reader's enum",
+                            location(path),
+                            symbol
+                        )
+                    })?
+                };
+
+                let i = r
+                    .symbols
+                    .iter()
+                    .position(|s| *s == symbol)
+                    .ok_or_else(|| format!("{}: invalid enum default", location(path)))?;
+                Ok(Value::Enum(i as u32, symbol))
+            }
+            (Schema::Array(w), Schema::Array(r), Value::Array(items)) => items
+                .into_iter()
+                .map(|v| self.resolve_value(v, w, r, path))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            (Schema::Map(w), Schema::Map(r), Value::Map(values)) => values
+                .into_iter()
+                .map(|(k, v)| Ok((k, self.resolve_value(v, w, r, path)?)))
+                .collect::<Result<_, String>>()
+                .map(Value::Map),
+            (Schema::Fixed(_), Schema::Fixed(_), value)
+            | (Schema::Decimal(_), Schema::Decimal(_), value) => Ok(value),
+            (_, reader, value) => {
+                // logical types are resolved by their underlying types
+                let value = match value {
+                    Value::Date(i) | Value::TimeMillis(i) => Value::Int(i),
+                    Value::TimeMicros(i)
+                    | Value::TimestampMillis(i)
+                    | Value::TimestampMicros(i)
+                    | Value::LocalTimestampMillis(i)
+                    | Value::LocalTimestampMicros(i) => Value::Long(i),
+                    Value::Uuid(u) => Value::String(u.to_string()),
+                    value => value,
+                };
+
+                value
+                    .resolve(reader)
+                    .map_err(|e| format!("{}: {}", location(path), e))
+            }
+        }
+    }
+
+    fn default_value(&self, field: &RecordField, path: &str) -> Result<Value, String> {
+        let default = field.default.as_ref().ok_or_else(|| {
+            format!(
+                "{}: missing from the writer's schema, and has no default",
+                location(path)
+            )
+        })?;
+
+        Value::from(default.clone())
+            .resolve(deref(&self.reader_names, &field.schema))
+            .map_err(|e| format!("{}: invalid default: {}", location(path), e))
+    }
+
+    /// Picks the variant of a reader union that a writer type is read as: the first variant of
+    /// the same type (with the same name, for named types), falling back to the first variant
+    /// that the writer type can be promoted to
+    fn select_variant<'a>(
+        &'a self,
+        writer: &Schema,
+        variants: &'a [Schema],
+    ) -> Option<(usize, &'a Schema)> {
+        let variants: Vec<_> = variants
+            .iter()
+            .map(|v| deref(&self.reader_names, v))
+            .enumerate()
+            .collect();
+
+        let same_kind = |v: &Schema| SchemaKind::from(v) == SchemaKind::from(writer);
+
+        variants
+            .iter()
+            .find(|(_, v)| same_kind(v) && name(v) == name(writer))
+            .or_else(|| variants.iter().find(|(_, v)| same_kind(v)))
+            .or_else(|| variants.iter().find(|(_, v)| promotable(writer, v)))
+            .copied()
+    }
+}
+
+/// Checks whether data written with the `writer` schema can be read with the `reader` schema,
+/// returning a description of the first incompatibility if not
+pub fn check_compatibility(writer: &Schema, reader: &Schema) -> Result<(), String> {
+    SchemaResolution::new(writer.clone(), reader.clone()).map(|_| ())
+}
+
+/// Finds the writer field that a reader field is read from, by name or by one of the reader
+/// field's aliases
+fn writer_field<'a>(
+    writer: &'a [RecordField],
+    field: &RecordField,
+) -> Option<(usize, &'a RecordField)> {
+    writer
+        .iter()
+        .enumerate()
+        .find(|(_, f)| f.name == field.name)
+        .or_else(|| {
+            writer
+                .iter()
+                .enumerate()
+                .find(|(_, f)| field.aliases.iter().flatten().any(|a| *a == f.name))
+        })
+}
+
+/// Collects the named types defined in a schema by their full names, so that references to them
+/// can be followed
+pub(crate) fn named_types(schema: &Schema) -> HashMap<String, Schema> {
+    fn collect(schema: &Schema, names: &mut HashMap<String, Schema>) {
+        match schema {
+            Schema::Record(record) => {
+                names.insert(record.name.fullname(None), schema.clone());
+                for field in &record.fields {
+                    collect(&field.schema, names);
+                }
+            }
+            Schema::Enum(e) => {
+                names.insert(e.name.fullname(None), schema.clone());
+            }
+            Schema::Fixed(f) => {
+                names.insert(f.name.fullname(None), schema.clone());
+            }
+            Schema::Array(items) | Schema::Map(items) => collect(items, names),
+            Schema::Union(union) => {
+                for v in union.variants() {
+                    collect(v, names);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut names = HashMap::new();
+    collect(schema, &mut names);
+    names
+}
+
+pub(crate) fn deref<'a>(names: &'a HashMap<String, Schema>, schema: &'a Schema) -> &'a Schema {
+    match schema {
+        Schema::Ref { name } => names.get(&name.fullname(None)).unwrap_or(schema),
+        schema => schema,
+    }
+}
+
+fn name(schema: &Schema) -> Option<&str> {
+    match schema {
+        Schema::Record(r) => Some(&r.name.name),
+        Schema::Enum(e) => Some(&e.name.name),
+        Schema::Fixed(f) => Some(&f.name.name),
+        _ => None,
+    }
+}
+
+/// The primitive type that a schema is encoded as, for primitives and the logical types built
+/// on them
+fn primitive(schema: &Schema) -> Option<SchemaKind> {
+    Some(match schema {
+        Schema::Null => SchemaKind::Null,
+        Schema::Boolean => SchemaKind::Boolean,
+        Schema::Int | Schema::Date | Schema::TimeMillis => SchemaKind::Int,
+        Schema::Long
+        | Schema::TimeMicros
+        | Schema::TimestampMillis
+        | Schema::TimestampMicros
+        | Schema::LocalTimestampMillis
+        | Schema::LocalTimestampMicros => SchemaKind::Long,
+        Schema::Float => SchemaKind::Float,
+        Schema::Double => SchemaKind::Double,
+        Schema::Bytes => SchemaKind::Bytes,
+        Schema::String | Schema::Uuid => SchemaKind::String,
+        _ => return None,
+    })
+}
+
+/// Whether a value written as `writer` can be read as `reader`, which must be primitives (or
+/// logical types) of the same type or of one of the promotions allowed by the spec
+fn promotable(writer: &Schema, reader: &Schema) -> bool {
+    use SchemaKind::*;
+
+    match (primitive(writer), primitive(reader)) {
+        (Some(w), Some(r)) => {
+            w == r
+                || matches!(
+                    (w, r),
+                    (Int, Long | Float | Double)
+                        | (Long, Float | Double)
+                        | (Float, Double)
+                        | (String, Bytes)
+                        | (Bytes, String)
+                )
+        }
+        _ => false,
+    }
+}
+
+fn kind(schema: &Schema) -> String {
+    format!("{:?}", SchemaKind::from(schema)).to_lowercase()
+}
+
+fn field_path(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", path, field)
+    }
+}
+
+fn location(path: &str) -> String {
+    if path.is_empty() {
+        "top-level schema".to_string()
+    } else {
+        format!("field '{}'", path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_compatibility, SchemaResolution};
+    use apache_avro::types::Value;
+    use apache_avro::Schema;
+
+    fn schema(fields: &str) -> Schema {
+        Schema::parse_str(&format!(
+            r#"{{"type": "record", "name": "User", "fields": [{}]}}"#,
+            fields
+        ))
+        .unwrap()
+    }
+
+    fn record(fields: Vec<(&str, Value)>) -> Value {
+        Value::Record(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_resolve_aliases_defaults_and_promotions() {
+        let writer = schema(
+            r#"{"name": "id", "type": "int"},
+               {"name": "user_name", "type": "string"},
+               {"name": "dropped", "type": "boolean"}"#,
+        );
+
+        let reader = schema(
+            r#"{"name": "id", "type": "long"},
+               {"name": "name", "type": "string", "aliases": ["user_name"]},
+               {"name": "country", "type": "string", "default": "US"},
+               {"name": "score", "type": ["null", "double"], "default": null}"#,
+        );
+
+        let resolution = SchemaResolution::new(writer, reader).unwrap();
+
+        let value = resolution
+            .resolve(record(vec![
+                ("id", Value::Int(5)),
+                ("user_name", Value::String("alice".to_string())),
+                ("dropped", Value::Boolean(true)),
+            ]))
+            .unwrap();
+
+        assert_eq!(
+            value,
+            record(vec![
+                ("id", Value::Long(5)),
+                ("name", Value::String("alice".to_string())),
+                ("country", Value::String("US".to_string())),
+                ("score", Value::Union(0, Box::new(Value::Null))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_resolve_into_union() {
+        let writer = schema(r#"{"name": "score", "type": "float"}"#);
+        let reader = schema(r#"{"name": "score", "type": ["null", "double"]}"#);
+
+        let value = SchemaResolution::new(writer, reader)
+            .unwrap()
+            .resolve(record(vec![("score", Value::Float(1.5))]))
+            .unwrap();
+
+        assert_eq!(
+            value,
+            record(vec![(
+                "score",
+                Value::Union(1, Box::new(Value::Double(1.5)))
+            )])
+        );
+    }
+
+    #[test]
+    fn test_incompatible_schemas() {
+        let reader = schema(
+            r#"{"name": "id", "type": "long"},
+               {"name": "country", "type": "string"}"#,
+        );
+
+        let err =
+            check_compatibility(&schema(r#"{"name": "id", "type": "long"}"#), &reader).unwrap_err();
+        assert!(err.contains("field 'country'"), "{}", err);
+        assert!(err.contains("no default"), "{}", err);
+
+        let err = check_compatibility(
+            &schema(
+                r#"{"name": "id", "type": "string"},
+                   {"name": "country", "type": "string"}"#,
+            ),
+            &reader,
+        )
+        .unwrap_err();
+        assert_eq!(err, "field 'id': string can't be read as long");
+
+        // nullable writer fields can't be read as non-nullable reader fields
+        let err = check_compatibility(
+            &schema(
+                r#"{"name": "id", "type": ["null", "long"]},
+                   {"name": "country", "type": "string"}"#,
+            ),
+            &reader,
+        )
+        .unwrap_err();
+        assert_eq!(err, "field 'id': null can't be read as long");
+
+        // but the reverse is fine
+        check_compatibility(
+            &reader,
+            &schema(
+                r#"{"name": "id", "type": ["null", "long"]},
+                   {"name": "country", "type": ["null", "string"]}"#,
+            ),
+        )
+        .unwrap();
+    }
+}
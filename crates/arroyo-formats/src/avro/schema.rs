@@ -1,9 +1,11 @@
+use crate::avro::resolution::named_types;
 use anyhow::{anyhow, bail};
 use apache_avro::Schema;
 use arrow_schema::{DataType, Field, Fields, TimeUnit};
 use arroyo_rpc::formats::AvroFormat;
 use arroyo_types::ArroyoExtensionType;
 use serde_json::json;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Computes an avro schema from an arrow schema
@@ -24,7 +26,7 @@ pub fn to_arrow(schema: &str) -> anyhow::Result<arrow_schema::Schema> {
     let schema =
         Schema::parse_str(schema).map_err(|e| anyhow!("avro schema is not valid: {:?}", e))?;
 
-    let (dt, _, _) = to_arrow_datatype(&schema);
+    let (dt, _, _) = to_arrow_datatype(&schema, &named_types(&schema), &mut vec![]);
     let fields = match dt {
         DataType::Struct(fields) => fields,
         _ => {
@@ -115,7 +117,14 @@ fn arrow_to_avro(name: &str, dt: &DataType) -> serde_json::value::Value {
     })
 }
 
-fn to_arrow_datatype(schema: &Schema) -> (DataType, bool, Option<ArroyoExtensionType>) {
+/// Converts an avro schema to an arrow type. References to named types are followed using `names`,
+/// except for recursive references (to a type in `expanding`), which can't be represented in
+/// arrow and are converted to JSON.
+fn to_arrow_datatype(
+    schema: &Schema,
+    names: &HashMap<String, Schema>,
+    expanding: &mut Vec<String>,
+) -> (DataType, bool, Option<ArroyoExtensionType>) {
     match schema {
         Schema::Null => (DataType::Null, false, None),
         Schema::Boolean => (DataType::Boolean, false, None),
@@ -146,27 +155,35 @@ fn to_arrow_datatype(schema: &Schema) -> (DataType, bool, Option<ArroyoExtension
                 .partition(|v| matches!(v, Schema::Null));
 
             if nulls.len() == 1 && not_nulls.len() == 1 {
-                let (dt, _, ext) = to_arrow_datatype(not_nulls[0]);
+                let (dt, _, ext) = to_arrow_datatype(not_nulls[0], names, expanding);
                 (dt, true, ext)
             } else {
                 (DataType::Utf8, false, Some(ArroyoExtensionType::JSON))
             }
         }
         Schema::Record(record) => {
+            expanding.push(record.name.fullname(None));
             let fields = record
                 .fields
                 .iter()
                 .map(|f| {
-                    let (dt, nullable, extension) = to_arrow_datatype(&f.schema);
+                    let (dt, nullable, extension) = to_arrow_datatype(&f.schema, names, expanding);
                     Arc::new(ArroyoExtensionType::add_metadata(
                         extension,
                         Field::new(&f.name, dt, nullable),
                     ))
                 })
                 .collect();
+            expanding.pop();
 
             (DataType::Struct(fields), false, None)
         }
+        Schema::Ref { name } if !expanding.contains(&name.fullname(None)) => {
+            match names.get(&name.fullname(None)) {
+                Some(schema) => to_arrow_datatype(schema, names, expanding),
+                None => (DataType::Utf8, false, Some(ArroyoExtensionType::JSON)),
+            }
+        }
         _ => (DataType::Utf8, false, Some(ArroyoExtensionType::JSON)),
     }
 }
@@ -0,0 +1,406 @@
+//! Columnar buffers used by the formats that are decoded directly into Arrow, without going
+//! through JSON. The format-specific code (see `avro::de` and `proto::de`) is responsible for
+//! converting its values into the representation of each column; this module handles the
+//! buffering, nulls, and building the final arrays.
+
+use anyhow::bail;
+use arrow::array::ArrayData;
+use arrow::buffer::{BooleanBuffer, Buffer, NullBuffer, OffsetBuffer};
+use arrow_array::{
+    make_array, Array, ArrayRef, BinaryArray, BooleanArray, Decimal128Array, Float32Array,
+    Float64Array, ListArray, MapArray, StringArray, StructArray, UInt64Array,
+};
+use arrow_schema::{DataType, FieldRef, Schema, TimeUnit};
+use arroyo_types::ArroyoExtensionType;
+use std::fmt::Display;
+use std::sync::Arc;
+
+/// Decodes records into Arrow columns for a target schema. Each format provides a method that
+/// appends a single record (like `decode_avro`), built on top of [`ColumnarDecoder::append_row`].
+pub(crate) struct ColumnarDecoder {
+    columns: Vec<Column>,
+    len: usize,
+}
+
+impl ColumnarDecoder {
+    pub fn new(schema: &Schema) -> anyhow::Result<Self> {
+        Ok(Self {
+            columns: schema
+                .fields()
+                .iter()
+                .map(|f| Column::new(f.clone()))
+                .collect::<anyhow::Result<_>>()?,
+            len: 0,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends a single row with `f`, which must append one value to each column. If it fails,
+    /// any values it appended are removed so that the columns stay aligned.
+    pub fn append_row(
+        &mut self,
+        f: impl FnOnce(&mut [Column]) -> Result<(), String>,
+    ) -> Result<(), String> {
+        if let Err(e) = f(&mut self.columns) {
+            for column in &mut self.columns {
+                column.truncate(self.len);
+            }
+            return Err(e);
+        }
+
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Returns the decoded columns and resets the decoder
+    pub fn flush(&mut self) -> Vec<ArrayRef> {
+        self.len = 0;
+        self.columns.iter_mut().map(|c| c.finish()).collect()
+    }
+}
+
+pub(crate) enum ColumnValues {
+    Boolean(Vec<bool>),
+    /// all integer-backed types, which are narrowed to the width of the target type on finish
+    Int(Vec<i64>),
+    /// uint64, whose values don't all fit in an i64
+    UInt64(Vec<u64>),
+    Float(Vec<f64>),
+    Decimal(Vec<i128>),
+    /// strings and binary, as offsets into the concatenated data
+    Bytes {
+        offsets: Vec<i32>,
+        data: Vec<u8>,
+    },
+    List {
+        offsets: Vec<i32>,
+        item: Box<Column>,
+    },
+    Struct(Vec<Column>),
+    Map {
+        offsets: Vec<i32>,
+        keys: Box<Column>,
+        values: Box<Column>,
+    },
+}
+
+pub(crate) struct Column {
+    pub field: FieldRef,
+    /// whether the field has the JSON extension type, in which case it receives the JSON encoding
+    /// of its values
+    pub json: bool,
+    pub validity: Vec<bool>,
+    pub values: ColumnValues,
+}
+
+impl Column {
+    fn new(field: FieldRef) -> anyhow::Result<Self> {
+        let values = match field.data_type() {
+            DataType::Boolean => ColumnValues::Boolean(vec![]),
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::Date32
+            | DataType::Date64
+            | DataType::Time32(_)
+            | DataType::Time64(_)
+            | DataType::Timestamp(_, _)
+            | DataType::Duration(_) => ColumnValues::Int(vec![]),
+            DataType::UInt64 => ColumnValues::UInt64(vec![]),
+            DataType::Float32 | DataType::Float64 => ColumnValues::Float(vec![]),
+            DataType::Decimal128(_, _) => ColumnValues::Decimal(vec![]),
+            DataType::Utf8 | DataType::LargeUtf8 | DataType::Binary | DataType::LargeBinary => {
+                ColumnValues::Bytes {
+                    offsets: vec![0],
+                    data: vec![],
+                }
+            }
+            DataType::List(item) | DataType::LargeList(item) => ColumnValues::List {
+                offsets: vec![0],
+                item: Box::new(Column::new(item.clone())?),
+            },
+            DataType::Struct(fields) => ColumnValues::Struct(
+                fields
+                    .iter()
+                    .map(|f| Column::new(f.clone()))
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            DataType::Map(entries, _) => {
+                let DataType::Struct(kv) = entries.data_type() else {
+                    bail!("map field '{}' has invalid entries type", field.name());
+                };
+                ColumnValues::Map {
+                    offsets: vec![0],
+                    keys: Box::new(Column::new(kv[0].clone())?),
+                    values: Box::new(Column::new(kv[1].clone())?),
+                }
+            }
+            dt => bail!(
+                "field '{}' has type {}, which is not supported for deserialization",
+                field.name(),
+                dt
+            ),
+        };
+
+        Ok(Self {
+            json: ArroyoExtensionType::from_map(field.metadata())
+                == Some(ArroyoExtensionType::JSON),
+            field,
+            validity: vec![],
+            values,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.validity.len()
+    }
+
+    /// Appends a null, failing if the field is not nullable
+    pub fn append_null(&mut self) -> Result<(), String> {
+        if !self.field.is_nullable() {
+            return Err(format!(
+                "null value for non-nullable field '{}'",
+                self.field.name()
+            ));
+        }
+        self.push_null();
+        Ok(())
+    }
+
+    /// Appends a null, regardless of whether the field is nullable; this is used directly for
+    /// the children of null structs, whose nulls are masked by the parent
+    pub fn push_null(&mut self) {
+        self.validity.push(false);
+        match &mut self.values {
+            ColumnValues::Boolean(values) => values.push(false),
+            ColumnValues::Int(values) => values.push(0),
+            ColumnValues::UInt64(values) => values.push(0),
+            ColumnValues::Float(values) => values.push(0.0),
+            ColumnValues::Decimal(values) => values.push(0),
+            ColumnValues::Bytes { offsets, .. }
+            | ColumnValues::List { offsets, .. }
+            | ColumnValues::Map { offsets, .. } => offsets.push(*offsets.last().unwrap()),
+            ColumnValues::Struct(columns) => columns.iter_mut().for_each(|c| c.push_null()),
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        if self.len() <= len {
+            return;
+        }
+
+        self.validity.truncate(len);
+        match &mut self.values {
+            ColumnValues::Boolean(values) => values.truncate(len),
+            ColumnValues::Int(values) => values.truncate(len),
+            ColumnValues::UInt64(values) => values.truncate(len),
+            ColumnValues::Float(values) => values.truncate(len),
+            ColumnValues::Decimal(values) => values.truncate(len),
+            ColumnValues::Bytes { offsets, data } => {
+                offsets.truncate(len + 1);
+                data.truncate(offsets[len] as usize);
+            }
+            ColumnValues::List { offsets, item } => {
+                offsets.truncate(len + 1);
+                item.truncate(offsets[len] as usize);
+            }
+            ColumnValues::Struct(columns) => columns.iter_mut().for_each(|c| c.truncate(len)),
+            ColumnValues::Map {
+                offsets,
+                keys,
+                values,
+            } => {
+                offsets.truncate(len + 1);
+                keys.truncate(offsets[len] as usize);
+                values.truncate(offsets[len] as usize);
+            }
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        let len = self.len();
+        let validity = std::mem::take(&mut self.validity);
+        let nulls = Some(NullBuffer::from(validity)).filter(|n| n.null_count() > 0);
+        let data_type = self.field.data_type();
+
+        match &mut self.values {
+            ColumnValues::Boolean(values) => Arc::new(BooleanArray::new(
+                BooleanBuffer::from(std::mem::take(values)),
+                nulls,
+            )),
+            ColumnValues::Int(values) => {
+                let values = std::mem::take(values);
+                let buffer = match data_type.primitive_width() {
+                    Some(1) => {
+                        Buffer::from_vec(values.iter().map(|v| *v as i8).collect::<Vec<_>>())
+                    }
+                    Some(2) => {
+                        Buffer::from_vec(values.iter().map(|v| *v as i16).collect::<Vec<_>>())
+                    }
+                    Some(4) => {
+                        Buffer::from_vec(values.iter().map(|v| *v as i32).collect::<Vec<_>>())
+                    }
+                    _ => Buffer::from_vec(values),
+                };
+
+                make_array(
+                    ArrayData::builder(data_type.clone())
+                        .len(len)
+                        .add_buffer(buffer)
+                        .nulls(nulls)
+                        .build()
+                        .unwrap(),
+                )
+            }
+            ColumnValues::UInt64(values) => {
+                Arc::new(UInt64Array::new(std::mem::take(values).into(), nulls))
+            }
+            ColumnValues::Float(values) => {
+                let values = std::mem::take(values);
+                if *data_type == DataType::Float32 {
+                    Arc::new(Float32Array::new(
+                        values.into_iter().map(|v| v as f32).collect(),
+                        nulls,
+                    ))
+                } else {
+                    Arc::new(Float64Array::new(values.into(), nulls))
+                }
+            }
+            ColumnValues::Decimal(values) => {
+                let DataType::Decimal128(precision, scale) = data_type else {
+                    unreachable!("decimal column with type {}", data_type);
+                };
+                Arc::new(
+                    Decimal128Array::new(std::mem::take(values).into(), nulls)
+                        .with_precision_and_scale(*precision, *scale)
+                        .unwrap(),
+                )
+            }
+            ColumnValues::Bytes { offsets, data } => {
+                let offsets = OffsetBuffer::new(std::mem::replace(offsets, vec![0]).into());
+                let data = Buffer::from_vec(std::mem::take(data));
+                let array: ArrayRef = match data_type {
+                    DataType::Utf8 | DataType::LargeUtf8 => {
+                        // data is only appended from strings, so it's always valid utf-8
+                        Arc::new(StringArray::new(offsets, data, nulls))
+                    }
+                    _ => Arc::new(BinaryArray::new(offsets, data, nulls)),
+                };
+                cast_if_needed(array, data_type)
+            }
+            ColumnValues::List { offsets, item } => {
+                let (DataType::List(item_field) | DataType::LargeList(item_field)) = data_type
+                else {
+                    unreachable!("list column with type {}", data_type);
+                };
+                let array = ListArray::new(
+                    item_field.clone(),
+                    OffsetBuffer::new(std::mem::replace(offsets, vec![0]).into()),
+                    item.finish(),
+                    nulls,
+                );
+                cast_if_needed(Arc::new(array), data_type)
+            }
+            ColumnValues::Struct(columns) => {
+                let DataType::Struct(fields) = data_type else {
+                    unreachable!("struct column with type {}", data_type);
+                };
+                Arc::new(StructArray::new(
+                    fields.clone(),
+                    columns.iter_mut().map(|c| c.finish()).collect(),
+                    nulls,
+                ))
+            }
+            ColumnValues::Map {
+                offsets,
+                keys,
+                values,
+            } => {
+                let DataType::Map(entries_field, sorted) = data_type else {
+                    unreachable!("map column with type {}", data_type);
+                };
+                let DataType::Struct(kv) = entries_field.data_type() else {
+                    unreachable!("map entries with type {}", entries_field.data_type());
+                };
+                let entries =
+                    StructArray::new(kv.clone(), vec![keys.finish(), values.finish()], None);
+                Arc::new(MapArray::new(
+                    entries_field.clone(),
+                    OffsetBuffer::new(std::mem::replace(offsets, vec![0]).into()),
+                    entries,
+                    nulls,
+                    *sorted,
+                ))
+            }
+        }
+    }
+}
+
+/// Records the end of a value that was appended to the data of a bytes column
+pub(crate) fn push_offset(
+    field: &FieldRef,
+    offsets: &mut Vec<i32>,
+    data: &[u8],
+) -> Result<(), String> {
+    let end = i32::try_from(data.len())
+        .map_err(|_| error(field, "batch contains more than 2GB of data"))?;
+    offsets.push(end);
+    Ok(())
+}
+
+pub(crate) fn error(field: &FieldRef, e: impl Display) -> String {
+    format!("invalid value for field '{}': {}", field.name(), e)
+}
+
+/// The "large" variants are decoded as their 32-bit offset equivalents and then cast
+fn cast_if_needed(array: ArrayRef, data_type: &DataType) -> ArrayRef {
+    if array.data_type() == data_type {
+        array
+    } else {
+        arrow::compute::cast(&array, data_type).unwrap()
+    }
+}
+
+pub(crate) fn convert_time_unit(value: i64, from: TimeUnit, to: &TimeUnit) -> Result<i64, String> {
+    let nanos = |unit: &TimeUnit| match unit {
+        TimeUnit::Second => 1_000_000_000,
+        TimeUnit::Millisecond => 1_000_000,
+        TimeUnit::Microsecond => 1_000,
+        TimeUnit::Nanosecond => 1,
+    };
+
+    let (from_nanos, to_nanos) = (nanos(&from), nanos(to));
+    if from_nanos >= to_nanos {
+        value
+            .checked_mul(from_nanos / to_nanos)
+            .ok_or_else(|| format!("{} is out of range for unit {:?}", value, to))
+    } else {
+        Ok(value.div_euclid(to_nanos / from_nanos))
+    }
+}
+
+/// Checks that a signed integer fits in the target integer type
+pub(crate) fn check_int_range(data_type: &DataType, v: i64) -> Result<i64, String> {
+    let in_range = match data_type {
+        DataType::Int8 => i8::try_from(v).is_ok(),
+        DataType::Int16 => i16::try_from(v).is_ok(),
+        DataType::Int32 | DataType::Date32 | DataType::Time32(_) => i32::try_from(v).is_ok(),
+        DataType::UInt8 => u8::try_from(v).is_ok(),
+        DataType::UInt16 => u16::try_from(v).is_ok(),
+        DataType::UInt32 => u32::try_from(v).is_ok(),
+        _ => true,
+    };
+
+    if in_range {
+        Ok(v)
+    } else {
+        Err(format!("{} is out of range for {}", v, data_type))
+    }
+}
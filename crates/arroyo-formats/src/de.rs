@@ -1,19 +1,26 @@
 use crate::avro::de;
+use crate::columnar::ColumnarDecoder;
+use crate::json::cdc;
+use crate::json::timestamps::{
+    epoch_unit, has_timestamps, with_timestamp_unit, TimestampConverter,
+};
 use crate::proto::schema::get_pool;
-use crate::{proto, should_flush};
+use crate::{ipc, proto, should_flush};
 use arrow::array::{Int32Builder, Int64Builder};
-use arrow::compute::kernels;
+use arrow::compute::{concat_batches, kernels};
 use arrow_array::builder::{
     ArrayBuilder, GenericByteBuilder, StringBuilder, TimestampNanosecondBuilder,
 };
 use arrow_array::types::GenericBinaryType;
-use arrow_array::RecordBatch;
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{Fields, Schema, SchemaRef};
 use arroyo_rpc::df::ArroyoSchema;
 use arroyo_rpc::formats::{
     AvroFormat, BadData, Format, Framing, FramingMethod, JsonFormat, ProtobufFormat,
+    TimestampFormat,
 };
 use arroyo_rpc::schema_resolver::{FailingSchemaResolver, FixedSchemaResolver, SchemaResolver};
-use arroyo_types::{to_nanos, SourceError};
+use arroyo_types::{to_nanos, BadRecord, SourceError};
 use prost_reflect::DescriptorPool;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -26,6 +33,7 @@ pub enum FieldValueType<'a> {
     Int64(i64),
     Int32(i32),
     String(&'a str),
+    NullableString(Option<&'a str>),
     // Extend with more types as needed
 }
 
@@ -70,6 +78,65 @@ impl<'a> Iterator for FramingIterator<'a> {
 
                         Some(&self.buf[prev..(prev + length)])
                     }
+                    FramingMethod::OctetCounting(octet_counting) => {
+                        let rest = &self.buf[self.offset..];
+
+                        // each frame is `<length> <message>`
+                        let Some((start, len)) = memchr::memchr(b' ', rest).and_then(|i| {
+                            let len = std::str::from_utf8(&rest[..i]).ok()?.parse().ok()?;
+                            Some((i + 1, len))
+                        }) else {
+                            // not a valid frame, so pass on the rest to be reported as bad data
+                            self.offset = self.buf.len();
+                            return Some(rest);
+                        };
+
+                        let end = start.saturating_add(len).min(rest.len());
+                        self.offset += end;
+
+                        // enforce max len if set
+                        let length = (end - start)
+                            .min(octet_counting.max_message_length.unwrap_or(u64::MAX) as usize);
+
+                        Some(&rest[start..(start + length)])
+                    }
+                    FramingMethod::LengthPrefixed(length_prefixed) => {
+                        let rest = &self.buf[self.offset..];
+                        let start = length_prefixed.prefix_bytes as usize;
+
+                        if rest.len() < start {
+                            // not a valid frame, so pass on the rest to be reported as bad data
+                            self.offset = self.buf.len();
+                            return Some(rest);
+                        }
+
+                        let len = length_prefixed.read_length(rest);
+                        let end = start
+                            .saturating_add(usize::try_from(len).unwrap_or(usize::MAX))
+                            .min(rest.len());
+                        self.offset += end;
+
+                        // enforce max len if set
+                        let length = (end - start)
+                            .min(length_prefixed.max_message_length.unwrap_or(u64::MAX) as usize);
+
+                        Some(&rest[start..(start + length)])
+                    }
+                    FramingMethod::Delimited(delimited) => {
+                        let delimiter = &delimited.delimiter;
+                        let end = memchr::memmem::find(&self.buf[self.offset..], delimiter)
+                            .map(|i| self.offset + i)
+                            .unwrap_or(self.buf.len());
+
+                        let prev = self.offset;
+                        self.offset = end + delimiter.len();
+
+                        // enforce max len if set
+                        let length = (end - prev)
+                            .min(delimited.max_message_length.unwrap_or(u64::MAX) as usize);
+
+                        Some(&self.buf[prev..(prev + length)])
+                    }
                 }
             }
             None => {
@@ -86,12 +153,22 @@ pub struct ArrowDeserializer {
     schema: ArroyoSchema,
     bad_data: BadData,
     json_decoder: Option<(arrow::json::reader::Decoder, TimestampNanosecondBuilder)>,
+    /// rewrites timestamps for JSON formats with a timestamp pattern, which the decoder can't
+    /// parse, along with the fields of the schema to look for them in
+    json_timestamps: Option<(TimestampConverter, Fields)>,
+    /// used for the formats that are decoded directly into arrow (avro and protobuf)
+    columnar_decoder: Option<(ColumnarDecoder, TimestampNanosecondBuilder)>,
+    /// batches decoded from Arrow IPC messages, already projected to the schema
+    ipc_batches: Option<(Vec<RecordBatch>, TimestampNanosecondBuilder)>,
     buffered_count: usize,
     buffered_since: Instant,
-    schema_registry: Arc<Mutex<HashMap<u32, apache_avro::schema::Schema>>>,
+    schema_registry: Arc<Mutex<de::WriterSchemas>>,
     proto_pool: DescriptorPool,
     schema_resolver: Arc<dyn SchemaResolver + Sync>,
     additional_fields_builder: Option<HashMap<String, Box<dyn ArrayBuilder>>>,
+    /// when dead-lettering, checks each JSON message against the schema before it's buffered, so
+    /// that messages which don't match fail with their input rather than being dropped on flush
+    json_validator: Option<JsonValidator>,
 }
 
 impl ArrowDeserializer {
@@ -132,33 +209,59 @@ impl ArrowDeserializer {
             DescriptorPool::global()
         };
 
-        Self {
-            json_decoder: matches!(
-                format,
-                Format::Json(..)
-                    | Format::Avro(AvroFormat {
-                        into_unstructured_json: false,
-                        ..
-                    })
-                    | Format::Protobuf(ProtobufFormat {
-                        into_unstructured_json: false,
-                        ..
-                    })
+        let columnar_decoder = decodes_columnar(&format).then(|| {
+            (
+                ColumnarDecoder::new(&schema.schema_without_timestamp())
+                    .expect("schema should have been checked when the table was created"),
+                TimestampNanosecondBuilder::new(),
             )
-            .then(|| {
-                // exclude the timestamp field
+        });
+
+        let json_timestamps = match &format {
+            Format::Json(json)
+                if matches!(json.timestamp_format, TimestampFormat::Pattern { .. }) =>
+            {
+                let fields = schema.schema_without_timestamp().fields;
+                has_timestamps(&fields)
+                    .then(|| TimestampConverter::new(&json.timestamp_format))
+                    .flatten()
+                    .map(|converter| (converter, fields))
+            }
+            _ => None,
+        };
+
+        // exclude the timestamp field; epoch timestamps are decoded in the unit of the format and
+        // cast to the unit of the schema when flushing
+        let mut json_schema = schema.schema_without_timestamp();
+        if let Format::Json(json) = &format {
+            if let Some(unit) = epoch_unit(&json.timestamp_format) {
+                json_schema = Schema::new(with_timestamp_unit(&json_schema.fields, &unit));
+            }
+        }
+
+        let json_schema = Arc::new(json_schema);
+        let json_validator = (matches!(format, Format::Json(..)) && bad_data.is_dead_letter())
+            .then(|| JsonValidator::new(json_schema.clone()));
+
+        Self {
+            json_timestamps,
+            json_decoder: matches!(format, Format::Json(..)).then(|| {
                 (
-                    arrow_json::reader::ReaderBuilder::new(Arc::new(
-                        schema.schema_without_timestamp(),
-                    ))
-                    .with_limit_to_batch_size(false)
-                    .with_strict_mode(false)
-                    .with_allow_bad_data(matches!(bad_data, BadData::Drop { .. }))
-                    .build_decoder()
-                    .unwrap(),
+                    arrow_json::reader::ReaderBuilder::new(json_schema)
+                        .with_limit_to_batch_size(false)
+                        .with_strict_mode(false)
+                        .with_allow_bad_data(matches!(
+                            bad_data,
+                            BadData::Drop { .. } | BadData::DeadLetter { .. }
+                        ))
+                        .build_decoder()
+                        .unwrap(),
                     TimestampNanosecondBuilder::new(),
                 )
             }),
+            columnar_decoder,
+            ipc_batches: matches!(format, Format::ArrowIpc(..))
+                .then(|| (vec![], TimestampNanosecondBuilder::new())),
             format: Arc::new(format),
             framing: framing.map(Arc::new),
             schema,
@@ -169,6 +272,7 @@ impl ArrowDeserializer {
             buffered_count: 0,
             buffered_since: Instant::now(),
             additional_fields_builder: None,
+            json_validator,
         }
     }
 
@@ -180,11 +284,39 @@ impl ArrowDeserializer {
         additional_fields: Option<&HashMap<&String, FieldValueType<'_>>>,
     ) -> Vec<SourceError> {
         match &*self.format {
-            Format::Avro(_) => self.deserialize_slice_avro(buffer, msg, timestamp).await,
-            _ => FramingIterator::new(self.framing.clone(), msg)
-                .map(|t| self.deserialize_single(buffer, t, timestamp, additional_fields))
-                .filter_map(|t| t.err())
+            Format::Avro(_) => self
+                .deserialize_slice_avro(buffer, msg, timestamp)
+                .await
+                .into_iter()
+                .map(|e| self.attach_record(e, msg, timestamp, additional_fields))
                 .collect(),
+            _ => {
+                let mut errors = vec![];
+                for frame in FramingIterator::new(self.framing.clone(), msg) {
+                    if let Err(e) =
+                        self.deserialize_single(buffer, frame, timestamp, additional_fields)
+                    {
+                        errors.push(self.attach_record(e, frame, timestamp, additional_fields));
+                    }
+                }
+                errors
+            }
+        }
+    }
+
+    /// When dead-lettering, attaches the input that caused a bad data error so that it can be
+    /// written to the dead-letter table
+    fn attach_record(
+        &self,
+        error: SourceError,
+        msg: &[u8],
+        timestamp: SystemTime,
+        additional_fields: Option<&HashMap<&String, FieldValueType>>,
+    ) -> SourceError {
+        if self.bad_data.is_dead_letter() {
+            error.with_record(|| bad_record(msg, timestamp, additional_fields))
+        } else {
+            error
         }
     }
 
@@ -193,6 +325,50 @@ impl ArrowDeserializer {
     }
 
     pub fn flush_buffer(&mut self) -> Option<Result<RecordBatch, SourceError>> {
+        if let Some((decoder, timestamp)) = &mut self.columnar_decoder {
+            if decoder.is_empty() {
+                return None;
+            }
+
+            self.buffered_since = Instant::now();
+            self.buffered_count = 0;
+            let mut columns = decoder.flush();
+            columns.insert(self.schema.timestamp_index, Arc::new(timestamp.finish()));
+            flush_additional_fields_builders(
+                &mut self.additional_fields_builder,
+                &self.schema,
+                &mut columns,
+            );
+            return Some(
+                RecordBatch::try_new(self.schema.schema.clone(), columns).map_err(|e| {
+                    SourceError::bad_data(format!("data does not match schema: {:?}", e))
+                }),
+            );
+        }
+
+        if let Some((batches, timestamp)) = &mut self.ipc_batches {
+            if batches.is_empty() {
+                return None;
+            }
+
+            self.buffered_since = Instant::now();
+            self.buffered_count = 0;
+            let batches = std::mem::take(batches);
+            let batch = concat_batches(&batches[0].schema(), &batches).unwrap();
+            let mut columns = batch.columns().to_vec();
+            columns.insert(self.schema.timestamp_index, Arc::new(timestamp.finish()));
+            flush_additional_fields_builders(
+                &mut self.additional_fields_builder,
+                &self.schema,
+                &mut columns,
+            );
+            return Some(
+                RecordBatch::try_new(self.schema.schema.clone(), columns).map_err(|e| {
+                    SourceError::bad_data(format!("data does not match schema: {:?}", e))
+                }),
+            );
+        }
+
         let (decoder, timestamp) = self.json_decoder.as_mut()?;
         self.buffered_since = Instant::now();
         self.buffered_count = 0;
@@ -205,7 +381,7 @@ impl ArrowDeserializer {
                     })
                     .transpose()?
                     .map(|batch| {
-                        let mut columns = batch.columns().to_vec();
+                        let mut columns = json_columns(&batch, &self.schema);
                         columns.insert(self.schema.timestamp_index, Arc::new(timestamp.finish()));
                         flush_additional_fields_builders(
                             &mut self.additional_fields_builder,
@@ -215,7 +391,7 @@ impl ArrowDeserializer {
                         RecordBatch::try_new(self.schema.schema.clone(), columns).unwrap()
                     }),
             ),
-            BadData::Drop { .. } => Some(
+            BadData::Drop { .. } | BadData::DeadLetter { .. } => Some(
                 decoder
                     .flush_with_bad_data()
                     .map_err(|e| {
@@ -226,7 +402,7 @@ impl ArrowDeserializer {
                     })
                     .transpose()?
                     .map(|(batch, mask, _)| {
-                        let mut columns = batch.columns().to_vec();
+                        let mut columns = json_columns(&batch, &self.schema);
                         let timestamp =
                             kernels::filter::filter(&timestamp.finish(), &mask).unwrap();
 
@@ -278,54 +454,82 @@ impl ArrowDeserializer {
                     msg
                 };
 
-                let Some((decoder, timestamp_builder)) = &mut self.json_decoder else {
-                    panic!("json decoder not initialized");
-                };
-
-                if self.additional_fields_builder.is_none() {
-                    if let Some(fields) = additional_fields.as_ref() {
-                        let mut builders = HashMap::new();
-                        for (key, value) in fields.iter() {
-                            let builder: Box<dyn ArrayBuilder> = match value {
-                                FieldValueType::Int32(_) => Box::new(Int32Builder::new()),
-                                FieldValueType::Int64(_) => Box::new(Int64Builder::new()),
-                                FieldValueType::String(_) => Box::new(StringBuilder::new()),
-                            };
-                            builders.insert(key, builder);
+                // the decoder expects Debezium records with RFC3339 or epoch timestamps, so other
+                // envelopes and timestamp patterns are rewritten first; CDC records may hold
+                // several rows
+                let (normalized, rows) = match json.cdc_envelope {
+                    Some(envelope) => {
+                        let mut records =
+                            cdc::to_debezium(envelope, msg).map_err(SourceError::bad_data)?;
+
+                        let mut buf = vec![];
+                        for record in &mut records {
+                            if let Some((converter, fields)) = &self.json_timestamps {
+                                converter
+                                    .normalize(fields, record)
+                                    .map_err(SourceError::bad_data)?;
+                            }
+                            serde_json::to_writer(&mut buf, record).unwrap();
+                            buf.push(b'\n');
                         }
-                        self.additional_fields_builder = Some(
-                            builders
-                                .into_iter()
-                                .map(|(k, v)| ((*k).clone(), v))
-                                .collect(),
-                        );
+
+                        (Some(buf), records.len())
                     }
+                    None => match &self.json_timestamps {
+                        Some((converter, fields)) => (
+                            Some(
+                                converter
+                                    .normalize_json(fields, msg)
+                                    .map_err(SourceError::bad_data)?,
+                            ),
+                            1,
+                        ),
+                        None => (None, 1),
+                    },
+                };
+
+                if rows == 0 {
+                    return Ok(());
+                }
+
+                let json = normalized.as_deref().unwrap_or(msg);
+                if let Some(validator) = &mut self.json_validator {
+                    validator.check(json)?;
                 }
 
+                self.init_additional_fields_builder(additional_fields);
+
+                let Some((decoder, timestamp_builder)) = &mut self.json_decoder else {
+                    panic!("json decoder not initialized");
+                };
+
                 decoder
-                    .decode(msg)
+                    .decode(json)
                     .map_err(|e| SourceError::bad_data(format!("invalid JSON: {:?}", e)))?;
-                timestamp_builder.append_value(to_nanos(timestamp) as i64);
 
-                add_additional_fields_using_builder(
-                    additional_fields,
-                    &mut self.additional_fields_builder,
-                );
-                self.buffered_count += 1;
+                for _ in 0..rows {
+                    timestamp_builder.append_value(to_nanos(timestamp) as i64);
+
+                    add_additional_fields_using_builder(
+                        additional_fields,
+                        &mut self.additional_fields_builder,
+                    );
+                }
+                self.buffered_count += rows;
             }
             Format::Protobuf(proto) => {
-                let json = proto::de::deserialize_proto(&mut self.proto_pool, proto, msg)?;
+                let message = proto::de::deserialize_proto(&mut self.proto_pool, proto, msg)?;
 
                 if proto.into_unstructured_json {
-                    self.decode_into_json(buffer, json, timestamp);
+                    self.decode_into_json(buffer, proto::de::proto_to_json(&message), timestamp);
                 } else {
-                    let Some((decoder, timestamp_builder)) = &mut self.json_decoder else {
-                        panic!("json decoder not initialized");
+                    self.init_additional_fields_builder(additional_fields);
+
+                    let Some((decoder, timestamp_builder)) = &mut self.columnar_decoder else {
+                        panic!("protobuf decoder not initialized");
                     };
 
-                    decoder
-                        .decode(json.to_string().as_bytes())
-                        .map_err(|e| SourceError::bad_data(format!("invalid JSON: {:?}", e)))?;
+                    decoder.decode_proto(&message)?;
                     timestamp_builder.append_value(to_nanos(timestamp) as i64);
 
                     add_additional_fields_using_builder(
@@ -336,13 +540,84 @@ impl ArrowDeserializer {
                     self.buffered_count += 1;
                 }
             }
+            Format::ArrowIpc(_) => {
+                // additional fields aren't in the message, and are filled in when flushing
+                let schema = Schema::new(
+                    self.schema
+                        .schema_without_timestamp()
+                        .fields
+                        .iter()
+                        .map(|f| {
+                            if additional_fields.is_some_and(|a| a.contains_key(f.name())) {
+                                Arc::new(f.as_ref().clone().with_nullable(true))
+                            } else {
+                                f.clone()
+                            }
+                        })
+                        .collect::<Vec<_>>(),
+                );
+                let batches = ipc::read_stream(msg)
+                    .and_then(|batches| {
+                        batches
+                            .iter()
+                            .map(|batch| ipc::project(batch, &schema))
+                            .collect::<anyhow::Result<Vec<_>>>()
+                    })
+                    .map_err(|e| {
+                        SourceError::bad_data(format!("invalid Arrow IPC message: {}", e))
+                    })?;
+
+                self.init_additional_fields_builder(additional_fields);
+
+                let Some((buffered, timestamp_builder)) = &mut self.ipc_batches else {
+                    panic!("arrow ipc decoder not initialized");
+                };
+
+                for batch in batches {
+                    let rows = batch.num_rows();
+                    for _ in 0..rows {
+                        timestamp_builder.append_value(to_nanos(timestamp) as i64);
+                        add_additional_fields_using_builder(
+                            additional_fields,
+                            &mut self.additional_fields_builder,
+                        );
+                    }
+                    self.buffered_count += rows;
+                    buffered.push(batch);
+                }
+            }
             Format::Avro(_) => unreachable!("this should not be called for avro"),
             Format::Parquet(_) => todo!("parquet is not supported as an input format"),
+            Format::Csv(_) => unreachable!("csv is rejected for sources at planning time"),
         }
 
         Ok(())
     }
 
+    fn init_additional_fields_builder(
+        &mut self,
+        additional_fields: Option<&HashMap<&String, FieldValueType>>,
+    ) {
+        if self.additional_fields_builder.is_some() {
+            return;
+        }
+
+        if let Some(fields) = additional_fields {
+            let mut builders = HashMap::new();
+            for (key, value) in fields.iter() {
+                let builder: Box<dyn ArrayBuilder> = match value {
+                    FieldValueType::Int32(_) => Box::new(Int32Builder::new()),
+                    FieldValueType::Int64(_) => Box::new(Int64Builder::new()),
+                    FieldValueType::String(_) | FieldValueType::NullableString(_) => {
+                        Box::new(StringBuilder::new())
+                    }
+                };
+                builders.insert((*key).clone(), builder);
+            }
+            self.additional_fields_builder = Some(builders);
+        }
+    }
+
     fn decode_into_json(
         &mut self,
         builders: &mut [Box<dyn ArrayBuilder>],
@@ -376,6 +651,7 @@ impl ArrowDeserializer {
 
         let messages = match de::avro_messages(
             format,
+            &self.schema.schema.fields,
             &self.schema_registry,
             &self.schema_resolver,
             msg,
@@ -393,24 +669,16 @@ impl ArrowDeserializer {
         messages
             .into_iter()
             .map(|record| {
-                let value = record.map_err(|e| {
-                    SourceError::bad_data(format!("failed to deserialize from avro: {:?}", e))
-                })?;
+                let value = record?;
 
                 if into_json {
                     self.decode_into_json(builders, de::avro_to_json(value), timestamp);
                 } else {
-                    // for now round-trip through json in order to handle unsupported avro features
-                    // as that allows us to rely on raw json deserialization
-                    let json = de::avro_to_json(value).to_string();
-
-                    let Some((decoder, timestamp_builder)) = &mut self.json_decoder else {
-                        panic!("json decoder not initialized");
+                    let Some((decoder, timestamp_builder)) = &mut self.columnar_decoder else {
+                        panic!("avro decoder not initialized");
                     };
 
-                    decoder
-                        .decode(json.as_bytes())
-                        .map_err(|e| SourceError::bad_data(format!("invalid JSON: {:?}", e)))?;
+                    decoder.decode_avro(value)?;
                     self.buffered_count += 1;
                     timestamp_builder.append_value(to_nanos(timestamp) as i64);
                 }
@@ -452,6 +720,116 @@ impl ArrowDeserializer {
     }
 }
 
+/// Decodes JSON messages one at a time to check that they match a schema
+struct JsonValidator {
+    schema: SchemaRef,
+    decoder: arrow::json::reader::Decoder,
+}
+
+impl JsonValidator {
+    fn new(schema: SchemaRef) -> Self {
+        Self {
+            decoder: Self::decoder(&schema),
+            schema,
+        }
+    }
+
+    fn decoder(schema: &SchemaRef) -> arrow::json::reader::Decoder {
+        arrow_json::reader::ReaderBuilder::new(schema.clone())
+            .with_limit_to_batch_size(false)
+            .with_strict_mode(false)
+            .build_decoder()
+            .unwrap()
+    }
+
+    fn check(&mut self, msg: &[u8]) -> Result<(), SourceError> {
+        let result =
+            match self.decoder.decode(msg) {
+                Ok(_) => self.decoder.flush().map(|_| ()).map_err(|e| {
+                    SourceError::bad_data(format!("JSON does not match schema: {:?}", e))
+                }),
+                Err(e) => Err(SourceError::bad_data(format!("invalid JSON: {:?}", e))),
+            };
+
+        if result.is_err() {
+            // a failed decode or flush can leave the message behind in the decoder
+            self.decoder = Self::decoder(&self.schema);
+        }
+        result
+    }
+}
+
+fn bad_record(
+    msg: &[u8],
+    timestamp: SystemTime,
+    additional_fields: Option<&HashMap<&String, FieldValueType>>,
+) -> BadRecord {
+    let metadata = additional_fields.map(|fields| {
+        Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| {
+                    let v = match v {
+                        FieldValueType::Int64(i) => Value::from(*i),
+                        FieldValueType::Int32(i) => Value::from(*i),
+                        FieldValueType::String(s) => Value::from(*s),
+                        FieldValueType::NullableString(s) => Value::from(*s),
+                    };
+                    ((*k).clone(), v)
+                })
+                .collect(),
+        )
+        .to_string()
+    });
+
+    BadRecord {
+        data: msg.to_vec(),
+        metadata,
+        timestamp,
+    }
+}
+
+/// The columns of a batch from the JSON decoder, with timestamps that were decoded in the unit of
+/// an epoch timestamp format cast to the types of the schema
+fn json_columns(batch: &RecordBatch, schema: &ArroyoSchema) -> Vec<ArrayRef> {
+    batch
+        .columns()
+        .iter()
+        .zip(schema.schema_without_timestamp().fields())
+        .map(|(column, field)| {
+            if column.data_type() == field.data_type() {
+                column.clone()
+            } else {
+                kernels::cast::cast(column, field.data_type())
+                    .expect("timestamps should be castable to the schema's unit")
+            }
+        })
+        .collect()
+}
+
+/// Whether the format is decoded directly into arrow, rather than through JSON
+fn decodes_columnar(format: &Format) -> bool {
+    matches!(
+        format,
+        Format::Avro(AvroFormat {
+            into_unstructured_json: false,
+            ..
+        }) | Format::Protobuf(ProtobufFormat {
+            into_unstructured_json: false,
+            ..
+        })
+    )
+}
+
+/// Checks that data in the format can be deserialized into the schema, so that unsupported
+/// fields are reported when a table is created rather than when its source starts
+pub fn check_schema(format: &Format, schema: &Schema) -> anyhow::Result<()> {
+    if decodes_columnar(format) {
+        ColumnarDecoder::new(schema)?;
+    }
+    Ok(())
+}
+
 pub(crate) fn add_timestamp(
     builder: &mut [Box<dyn ArrayBuilder>],
     idx: usize,
@@ -496,6 +874,13 @@ pub(crate) fn add_additional_fields(
                 .expect("additional field has incorrect type")
                 .append_value(s);
         }
+        FieldValueType::NullableString(s) => {
+            builder[idx]
+                .as_any_mut()
+                .downcast_mut::<StringBuilder>()
+                .expect("additional field has incorrect type")
+                .append_option(*s);
+        }
     }
 }
 
@@ -531,6 +916,13 @@ pub(crate) fn add_additional_fields_using_builder(
                             .expect("additional field has incorrect type")
                             .append_value(s);
                     }
+                    FieldValueType::NullableString(s) => {
+                        builder
+                            .as_any_mut()
+                            .downcast_mut::<StringBuilder>()
+                            .expect("additional field has incorrect type")
+                            .append_option(*s);
+                    }
                 }
             }
         }
@@ -568,16 +960,20 @@ mod tests {
     use arrow::datatypes::Int32Type;
     use arrow_array::builder::{make_builder, ArrayBuilder};
     use arrow_array::cast::AsArray;
-    use arrow_array::types::{GenericBinaryType, Int64Type, TimestampNanosecondType};
+    use arrow_array::types::{
+        GenericBinaryType, Int64Type, TimestampMicrosecondType, TimestampNanosecondType,
+    };
     use arrow_array::RecordBatch;
     use arrow_schema::{Schema, TimeUnit};
     use arroyo_rpc::df::ArroyoSchema;
     use arroyo_rpc::formats::{
-        BadData, Format, Framing, FramingMethod, JsonFormat, NewlineDelimitedFraming,
-        RawBytesFormat,
+        ArrowIpcFormat, BadData, CdcEnvelope, DelimitedFraming, Endianness, Format, Framing,
+        FramingMethod, JsonFormat, LengthPrefixedFraming, NewlineDelimitedFraming,
+        OctetCountingFraming, RawBytesFormat, TimestampFormat,
     };
     use arroyo_types::{to_nanos, SourceError};
     use serde_json::json;
+    use std::collections::HashMap;
     use std::sync::Arc;
     use std::time::SystemTime;
 
@@ -651,6 +1047,127 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_octet_counting_framing() {
+        let framing = Some(Arc::new(Framing {
+            method: FramingMethod::OctetCounting(OctetCountingFraming {
+                max_message_length: None,
+            }),
+        }));
+
+        let result: Vec<_> = FramingIterator::new(
+            framing.clone(),
+            "9 one block10 two\nblocks11 three block".as_bytes(),
+        )
+        .map(|t| String::from_utf8(t.to_vec()).unwrap())
+        .collect();
+
+        assert_eq!(
+            vec![
+                "one block".to_string(),
+                "two\nblocks".to_string(),
+                "three block".to_string(),
+            ],
+            result
+        );
+
+        let result: Vec<_> = FramingIterator::new(framing, "9 one blockinvalid".as_bytes())
+            .map(|t| String::from_utf8(t.to_vec()).unwrap())
+            .collect();
+
+        assert_eq!(vec!["one block".to_string(), "invalid".to_string()], result);
+
+        let framing = Some(Arc::new(Framing {
+            method: FramingMethod::OctetCounting(OctetCountingFraming {
+                max_message_length: Some(5),
+            }),
+        }));
+
+        let result: Vec<_> = FramingIterator::new(framing, "9 one block5 whole".as_bytes())
+            .map(|t| String::from_utf8(t.to_vec()).unwrap())
+            .collect();
+
+        assert_eq!(vec!["one b".to_string(), "whole".to_string()], result);
+    }
+
+    #[test]
+    fn test_length_prefixed_framing() {
+        let framing = Some(Arc::new(Framing {
+            method: FramingMethod::LengthPrefixed(LengthPrefixedFraming {
+                prefix_bytes: 4,
+                endianness: Endianness::Big,
+                max_message_length: None,
+            }),
+        }));
+
+        let result: Vec<_> = FramingIterator::new(
+            framing,
+            b"\x00\x00\x00\x09one block\x00\x00\x00\x0atwo\nblocks\x00\x00",
+        )
+        .map(|t| String::from_utf8(t.to_vec()).unwrap())
+        .collect();
+
+        assert_eq!(
+            vec![
+                "one block".to_string(),
+                "two\nblocks".to_string(),
+                "\0\0".to_string(),
+            ],
+            result
+        );
+
+        let framing = Some(Arc::new(Framing {
+            method: FramingMethod::LengthPrefixed(LengthPrefixedFraming {
+                prefix_bytes: 2,
+                endianness: Endianness::Little,
+                max_message_length: Some(5),
+            }),
+        }));
+
+        let result: Vec<_> = FramingIterator::new(framing, b"\x09\x00one block\x05\x00whole")
+            .map(|t| String::from_utf8(t.to_vec()).unwrap())
+            .collect();
+
+        assert_eq!(vec!["one b".to_string(), "whole".to_string()], result);
+    }
+
+    #[test]
+    fn test_delimited_framing() {
+        let framing = Some(Arc::new(Framing {
+            method: FramingMethod::Delimited(DelimitedFraming {
+                delimiter: vec![0x1e],
+                max_message_length: None,
+            }),
+        }));
+
+        let result: Vec<_> =
+            FramingIterator::new(framing, b"one block\x1etwo\nblocks\x1ethree block\x1e")
+                .map(|t| String::from_utf8(t.to_vec()).unwrap())
+                .collect();
+
+        assert_eq!(
+            vec![
+                "one block".to_string(),
+                "two\nblocks".to_string(),
+                "three block".to_string(),
+            ],
+            result
+        );
+
+        let framing = Some(Arc::new(Framing {
+            method: FramingMethod::Delimited(DelimitedFraming {
+                delimiter: b"||".to_vec(),
+                max_message_length: Some(5),
+            }),
+        }));
+
+        let result: Vec<_> = FramingIterator::new(framing, b"one block||whole||")
+            .map(|t| String::from_utf8(t.to_vec()).unwrap())
+            .collect();
+
+        assert_eq!(vec!["one b".to_string(), "whole".to_string()], result);
+    }
+
     fn setup_deserializer(bad_data: BadData) -> (Vec<Box<dyn ArrayBuilder>>, ArrowDeserializer) {
         let schema = Arc::new(Schema::new(vec![
             arrow_schema::Field::new("x", arrow_schema::DataType::Int64, true),
@@ -675,6 +1192,7 @@ mod tests {
                 schema_id: None,
                 include_schema: false,
                 debezium: false,
+                cdc_envelope: None,
                 unstructured: false,
                 timestamp_format: Default::default(),
             }),
@@ -758,6 +1276,273 @@ mod tests {
         assert!(matches!(err, SourceError::BadData { .. }));
     }
 
+    #[tokio::test]
+    async fn test_bad_data_dead_letter() {
+        let (mut arrays, mut deserializer) = setup_deserializer(BadData::DeadLetter {
+            table: "dlq".to_string(),
+        });
+
+        let now = SystemTime::now();
+        let partition = "partition".to_string();
+        let mut metadata = HashMap::new();
+        metadata.insert(&partition, FieldValueType::Int32(3));
+
+        let errors = deserializer
+            .deserialize_slice(&mut arrays[..], b"{ not json", now, Some(&metadata))
+            .await;
+        assert_eq!(errors.len(), 1);
+        let SourceError::BadData {
+            record: Some(record),
+            ..
+        } = &errors[0]
+        else {
+            panic!(
+                "expected a bad data error with a record, got {:?}",
+                errors[0]
+            );
+        };
+        assert_eq!(record.data, b"{ not json");
+        assert_eq!(record.metadata.as_deref(), Some(r#"{"partition":3}"#));
+        assert_eq!(record.timestamp, now);
+
+        // rows that are valid JSON but don't match the schema fail with their input too, and
+        // aren't buffered
+        let (mut arrays, mut deserializer) = setup_deserializer(BadData::DeadLetter {
+            table: "dlq".to_string(),
+        });
+
+        assert_eq!(
+            deserializer
+                .deserialize_slice(
+                    &mut arrays[..],
+                    json!({ "x": 5 }).to_string().as_bytes(),
+                    now,
+                    None
+                )
+                .await,
+            vec![]
+        );
+        let errors = deserializer
+            .deserialize_slice(
+                &mut arrays[..],
+                json!({ "x": "hello" }).to_string().as_bytes(),
+                now,
+                None,
+            )
+            .await;
+        assert_eq!(errors.len(), 1);
+        let SourceError::BadData {
+            record: Some(record),
+            ..
+        } = &errors[0]
+        else {
+            panic!(
+                "expected a bad data error with a record, got {:?}",
+                errors[0]
+            );
+        };
+        assert_eq!(record.data, json!({ "x": "hello" }).to_string().as_bytes());
+        assert_eq!(record.metadata, None);
+
+        let batch = deserializer.flush_buffer().unwrap().unwrap();
+        assert_eq!(batch.num_rows(), 1);
+        assert_eq!(batch.columns()[0].as_primitive::<Int64Type>().value(0), 5);
+    }
+
+    #[tokio::test]
+    async fn test_json_epoch_timestamps() {
+        let schema = Arc::new(Schema::new(vec![
+            arrow_schema::Field::new(
+                "t",
+                arrow_schema::DataType::Timestamp(TimeUnit::Nanosecond, None),
+                true,
+            ),
+            arrow_schema::Field::new(
+                "_timestamp",
+                arrow_schema::DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+        ]));
+
+        let mut arrays: Vec<_> = schema
+            .fields
+            .iter()
+            .map(|f| make_builder(f.data_type(), 16))
+            .collect();
+
+        let mut deserializer = ArrowDeserializer::new(
+            Format::Json(JsonFormat {
+                confluent_schema_registry: false,
+                schema_id: None,
+                include_schema: false,
+                debezium: false,
+                cdc_envelope: None,
+                unstructured: false,
+                timestamp_format: TimestampFormat::UnixSeconds,
+            }),
+            ArroyoSchema::from_schema_unkeyed(schema).unwrap(),
+            None,
+            BadData::Fail {},
+        );
+
+        let now = SystemTime::now();
+        for value in [json!({ "t": 1612274910 }), json!({ "t": null })] {
+            assert_eq!(
+                deserializer
+                    .deserialize_slice(&mut arrays[..], value.to_string().as_bytes(), now, None)
+                    .await,
+                vec![]
+            );
+        }
+
+        let batch = deserializer.flush_buffer().unwrap().unwrap();
+        let t = batch.columns()[0].as_primitive::<TimestampNanosecondType>();
+        assert_eq!(t.value(0), 1612274910000000000);
+        assert!(t.is_null(1));
+    }
+
+    #[tokio::test]
+    async fn test_json_epoch_timestamps_nested() {
+        let nested = arrow_schema::Fields::from(vec![arrow_schema::Field::new(
+            "t",
+            arrow_schema::DataType::Timestamp(TimeUnit::Microsecond, None),
+            true,
+        )]);
+        let schema = Arc::new(Schema::new(vec![
+            arrow_schema::Field::new("nested", arrow_schema::DataType::Struct(nested), true),
+            arrow_schema::Field::new(
+                "_timestamp",
+                arrow_schema::DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+        ]));
+
+        let mut arrays: Vec<_> = schema
+            .fields
+            .iter()
+            .map(|f| make_builder(f.data_type(), 16))
+            .collect();
+
+        let mut deserializer = ArrowDeserializer::new(
+            Format::Json(JsonFormat {
+                confluent_schema_registry: false,
+                schema_id: None,
+                include_schema: false,
+                debezium: false,
+                cdc_envelope: None,
+                unstructured: false,
+                timestamp_format: TimestampFormat::UnixMillis,
+            }),
+            ArroyoSchema::from_schema_unkeyed(schema).unwrap(),
+            None,
+            BadData::Fail {},
+        );
+
+        let value = json!({ "nested": { "t": 1612274910123i64 } });
+        assert_eq!(
+            deserializer
+                .deserialize_slice(
+                    &mut arrays[..],
+                    value.to_string().as_bytes(),
+                    SystemTime::now(),
+                    None
+                )
+                .await,
+            vec![]
+        );
+
+        // decoded in milliseconds, and cast back to the schema's microseconds
+        let batch = deserializer.flush_buffer().unwrap().unwrap();
+        let t = batch.columns()[0]
+            .as_struct()
+            .column(0)
+            .as_primitive::<TimestampMicrosecondType>()
+            .value(0);
+        assert_eq!(t, 1612274910123000);
+    }
+
+    #[tokio::test]
+    async fn test_canal_json() {
+        let row = arrow_schema::DataType::Struct(
+            vec![
+                arrow_schema::Field::new("id", arrow_schema::DataType::Int64, false),
+                arrow_schema::Field::new("status", arrow_schema::DataType::Utf8, true),
+            ]
+            .into(),
+        );
+        let schema = Arc::new(Schema::new(vec![
+            arrow_schema::Field::new("before", row.clone(), true),
+            arrow_schema::Field::new("after", row, true),
+            arrow_schema::Field::new("op", arrow_schema::DataType::Utf8, false),
+            arrow_schema::Field::new(
+                "_timestamp",
+                arrow_schema::DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+        ]));
+
+        let mut arrays: Vec<_> = schema
+            .fields
+            .iter()
+            .map(|f| make_builder(f.data_type(), 16))
+            .collect();
+
+        let mut deserializer = ArrowDeserializer::new(
+            Format::Json(JsonFormat {
+                confluent_schema_registry: false,
+                schema_id: None,
+                include_schema: false,
+                debezium: true,
+                cdc_envelope: Some(CdcEnvelope::Canal),
+                unstructured: false,
+                timestamp_format: Default::default(),
+            }),
+            ArroyoSchema::from_schema_unkeyed(schema).unwrap(),
+            None,
+            BadData::Fail {},
+        );
+
+        let now = SystemTime::now();
+        for value in [
+            json!({
+                "type": "INSERT",
+                "isDdl": false,
+                "data": [{"id": "1", "status": "new"}, {"id": "2", "status": "new"}],
+                "old": null
+            }),
+            json!({"type": "ALTER", "isDdl": true, "data": null}),
+            json!({
+                "type": "UPDATE",
+                "isDdl": false,
+                "data": [{"id": "1", "status": "shipped"}],
+                "old": [{"status": "new"}]
+            }),
+        ] {
+            assert_eq!(
+                deserializer
+                    .deserialize_slice(&mut arrays[..], value.to_string().as_bytes(), now, None)
+                    .await,
+                vec![]
+            );
+        }
+
+        let batch = deserializer.flush_buffer().unwrap().unwrap();
+        assert_eq!(batch.num_rows(), 3);
+
+        let op = batch.columns()[2].as_string::<i32>();
+        assert_eq!(
+            op.iter().collect::<Vec<_>>(),
+            [Some("c"), Some("c"), Some("u")]
+        );
+
+        let before = batch.columns()[0].as_struct();
+        assert!(before.is_null(0));
+        assert_eq!(before.column(1).as_string::<i32>().value(2), "new");
+        let after = batch.columns()[1].as_struct();
+        assert_eq!(after.column(0).as_primitive::<Int64Type>().value(1), 2);
+        assert_eq!(after.column(1).as_string::<i32>().value(2), "shipped");
+    }
+
     #[tokio::test]
     async fn test_raw_bytes() {
         let schema = Arc::new(Schema::new(vec![
@@ -808,6 +1593,64 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_arrow_ipc() {
+        let schema = Arc::new(Schema::new(vec![
+            arrow_schema::Field::new("id", arrow_schema::DataType::Int64, false),
+            arrow_schema::Field::new("name", arrow_schema::DataType::Utf8, true),
+            arrow_schema::Field::new(
+                "_timestamp",
+                arrow_schema::DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+        ]));
+
+        let mut deserializer = ArrowDeserializer::new(
+            Format::ArrowIpc(ArrowIpcFormat {}),
+            ArroyoSchema::from_schema_unkeyed(schema.clone()).unwrap(),
+            None,
+            BadData::Fail {},
+        );
+
+        // the message has an extra column, and is missing the nullable name column
+        let message = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                arrow_schema::Field::new("extra", arrow_schema::DataType::Boolean, false),
+                arrow_schema::Field::new("id", arrow_schema::DataType::Int64, false),
+            ])),
+            vec![
+                Arc::new(arrow_array::BooleanArray::from(vec![true, false, true])),
+                Arc::new(arrow_array::Int64Array::from(vec![1, 2, 3])),
+            ],
+        )
+        .unwrap();
+
+        let time = SystemTime::now();
+        let result = deserializer
+            .deserialize_slice(&mut [], &crate::ipc::write_stream(&message), time, None)
+            .await;
+        assert!(result.is_empty());
+
+        let batch = deserializer.flush_buffer().unwrap().unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(
+            batch.columns()[0].as_primitive::<Int64Type>().values(),
+            &[1, 2, 3]
+        );
+        assert_eq!(batch.columns()[1].null_count(), 3);
+        assert_eq!(
+            batch.columns()[2]
+                .as_primitive::<TimestampNanosecondType>()
+                .value(2),
+            to_nanos(time) as i64
+        );
+
+        let result = deserializer
+            .deserialize_slice(&mut [], b"not arrow", time, None)
+            .await;
+        assert_eq!(result.len(), 1);
+    }
+
     #[tokio::test]
     async fn test_additional_fields_deserialisation() {
         let schema = Arc::new(Schema::new(vec![
@@ -835,6 +1678,7 @@ mod tests {
                 schema_id: None,
                 include_schema: false,
                 debezium: false,
+                cdc_envelope: None,
                 unstructured: false,
                 timestamp_format: Default::default(),
             }),
@@ -0,0 +1,222 @@
+use anyhow::{anyhow, bail};
+use arrow::buffer::{Buffer, MutableBuffer};
+use arrow::compute::cast;
+use arrow::ipc::convert::fb_to_schema;
+use arrow::ipc::reader::read_record_batch;
+use arrow::ipc::writer::StreamWriter;
+use arrow::ipc::MessageHeader;
+use arrow_array::{new_null_array, RecordBatch};
+use arrow_schema::{Schema, SchemaRef};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Marks the start of a message in the IPC stream format; older writers omit it
+const CONTINUATION_MARKER: [u8; 4] = [0xff; 4];
+
+/// An encapsulated IPC message: flatbuffer-encoded metadata, followed by the message body
+pub struct IpcMessage {
+    metadata: Vec<u8>,
+    body: Buffer,
+}
+
+impl IpcMessage {
+    /// Reads a message from the front of `buf`, which starts with the little-endian length of
+    /// the metadata. Lengths are checked against the remaining data before anything is
+    /// allocated, so that corrupt messages fail rather than allocating arbitrary amounts.
+    pub fn read(buf: &mut &[u8]) -> anyhow::Result<Self> {
+        let mut size = [0; 4];
+        std::io::Read::read_exact(buf, &mut size)?;
+        let metadata_length = u32::from_le_bytes(size) as usize;
+        if metadata_length > buf.len() {
+            bail!(
+                "IPC message metadata length {} exceeds the {} remaining bytes",
+                metadata_length,
+                buf.len()
+            );
+        }
+        let mut metadata = vec![0; metadata_length];
+        std::io::Read::read_exact(buf, &mut metadata)?;
+
+        let body_length = arrow::ipc::root_as_message(&metadata)
+            .map_err(|e| anyhow!("Unable to read IPC message: {:?}", e))?
+            .bodyLength();
+        let body_length = usize::try_from(body_length)
+            .ok()
+            .filter(|body_length| *body_length <= buf.len())
+            .ok_or_else(|| {
+                anyhow!(
+                    "IPC message body length {} exceeds the {} remaining bytes",
+                    body_length,
+                    buf.len()
+                )
+            })?;
+
+        // read the block that makes up the body into an aligned buffer
+        let mut body = MutableBuffer::from_len_zeroed(body_length);
+        std::io::Read::read_exact(buf, &mut body)?;
+
+        Ok(Self {
+            metadata,
+            body: body.into(),
+        })
+    }
+
+    pub fn message(&self) -> arrow::ipc::Message<'_> {
+        // the metadata was already verified in `read`
+        arrow::ipc::root_as_message(&self.metadata).unwrap()
+    }
+
+    pub fn record_batch(&self, schema: SchemaRef) -> anyhow::Result<RecordBatch> {
+        let message = self.message();
+
+        let MessageHeader::RecordBatch = message.header_type() else {
+            bail!("unexpected message type: {:?}", message.header_type());
+        };
+
+        let Some(batch) = message.header_as_record_batch() else {
+            bail!("Unable to read IPC message as record batch")
+        };
+
+        Ok(read_record_batch(
+            &self.body,
+            batch,
+            schema,
+            &HashMap::new(),
+            None,
+            &message.version(),
+        )?)
+    }
+}
+
+/// Reads the record batches of a complete IPC stream (a schema message, followed by any number
+/// of record batches and an optional end-of-stream marker), or of several concatenated streams
+pub fn read_stream(data: &[u8]) -> anyhow::Result<Vec<RecordBatch>> {
+    let mut buf = data;
+    let mut schema = None;
+    let mut batches = vec![];
+
+    while !buf.is_empty() {
+        if buf.starts_with(&CONTINUATION_MARKER) {
+            buf = &buf[4..];
+        }
+
+        if buf.starts_with(&[0; 4]) {
+            // end of stream; another may follow it
+            buf = &buf[4..];
+            continue;
+        }
+
+        let message = IpcMessage::read(&mut buf)?;
+        match message.message().header_type() {
+            MessageHeader::Schema => {
+                let Some(s) = message.message().header_as_schema() else {
+                    bail!("Unable to read IPC message as schema");
+                };
+                schema = Some(Arc::new(fb_to_schema(s)));
+            }
+            MessageHeader::RecordBatch => {
+                let schema = schema
+                    .clone()
+                    .ok_or_else(|| anyhow!("IPC stream has a record batch before its schema"))?;
+                batches.push(message.record_batch(schema)?);
+            }
+            other => bail!("unsupported IPC message type: {:?}", other),
+        }
+    }
+
+    Ok(batches)
+}
+
+/// Writes a batch as a complete IPC stream
+pub fn write_stream(batch: &RecordBatch) -> Vec<u8> {
+    let mut writer = StreamWriter::try_new(vec![], &batch.schema()).unwrap();
+    writer.write(batch).unwrap();
+    writer.into_inner().unwrap()
+}
+
+/// Projects a batch onto the given schema by field name, casting columns that have different
+/// types and filling in nulls for nullable fields that the batch doesn't have
+pub fn project(batch: &RecordBatch, schema: &Schema) -> anyhow::Result<RecordBatch> {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match batch.column_by_name(field.name()) {
+            Some(column) if column.data_type() == field.data_type() => Ok(column.clone()),
+            Some(column) => cast(column, field.data_type()).map_err(|e| {
+                anyhow!(
+                    "field '{}' has type {} but expected {}: {}",
+                    field.name(),
+                    column.data_type(),
+                    field.data_type(),
+                    e
+                )
+            }),
+            None if field.is_nullable() => Ok(new_null_array(field.data_type(), batch.num_rows())),
+            None => bail!("missing required field '{}'", field.name()),
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(RecordBatch::try_new(Arc::new(schema.clone()), columns)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{project, read_stream, write_stream, IpcMessage};
+    use arrow_array::{Array, Int32Array, Int64Array, RecordBatch, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_roundtrip_and_project() {
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("extra", DataType::Utf8, false),
+                Field::new("id", DataType::Int32, false),
+            ])),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(Int32Array::from(vec![1, 2])),
+            ],
+        )
+        .unwrap();
+
+        let mut data = write_stream(&batch);
+        data.extend(write_stream(&batch));
+
+        // concatenated streams are read as one
+        let batches = read_stream(&data).unwrap();
+        assert_eq!(batches, vec![batch.clone(), batch]);
+
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]);
+        let projected = project(&batches[0], &schema).unwrap();
+        assert_eq!(
+            projected.column(0).as_ref(),
+            &Int64Array::from(vec![1, 2]) as &dyn Array
+        );
+        assert_eq!(projected.column(1).null_count(), 2);
+
+        let required = Schema::new(vec![Field::new("name", DataType::Utf8, false)]);
+        assert!(project(&batches[0], &required).is_err());
+    }
+
+    #[test]
+    fn test_read_rejects_oversized_lengths() {
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)])),
+            vec![Arc::new(Int32Array::from(vec![1, 2]))],
+        )
+        .unwrap();
+        let data = write_stream(&batch);
+
+        // a metadata length far beyond the data
+        let mut corrupt = &[0xff, 0xff, 0xff, 0x7f, 0, 0, 0, 0][..];
+        assert!(IpcMessage::read(&mut corrupt).is_err());
+
+        // a stream cut off in the middle of a record batch's body
+        let truncated = &data[..data.len() - 12];
+        assert!(read_stream(truncated).is_err());
+    }
+}
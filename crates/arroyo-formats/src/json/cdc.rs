@@ -0,0 +1,182 @@
+use arroyo_rpc::formats::CdcEnvelope;
+use serde_json::{json, Map, Value};
+
+/// Converts a Maxwell or Canal record into Debezium-style `{"before", "after", "op"}` records.
+/// Records that don't describe row changes, like DDL statements and Maxwell's bootstrap
+/// markers, produce no rows.
+pub fn to_debezium(envelope: CdcEnvelope, msg: &[u8]) -> Result<Vec<Value>, String> {
+    let Value::Object(record) =
+        serde_json::from_slice(msg).map_err(|e| format!("invalid JSON: {}", e))?
+    else {
+        return Err(format!(
+            "expected {:?} record to be a JSON object",
+            envelope
+        ));
+    };
+
+    match envelope {
+        CdcEnvelope::Maxwell => from_maxwell(record),
+        CdcEnvelope::Canal => from_canal(record),
+    }
+}
+
+fn from_maxwell(mut record: Map<String, Value>) -> Result<Vec<Value>, String> {
+    let op = match record.get("type").and_then(Value::as_str) {
+        Some("insert") => "c",
+        Some("bootstrap-insert") => "r",
+        Some("update") => "u",
+        Some("delete") => "d",
+        Some(_) => return Ok(vec![]),
+        None => return Err("Maxwell record is missing 'type'".to_string()),
+    };
+
+    let data = record
+        .remove("data")
+        .ok_or_else(|| "Maxwell record is missing 'data'".to_string())?;
+
+    Ok(vec![change(op, data, record.remove("old"))?])
+}
+
+fn from_canal(mut record: Map<String, Value>) -> Result<Vec<Value>, String> {
+    if record.get("isDdl").and_then(Value::as_bool) == Some(true) {
+        return Ok(vec![]);
+    }
+
+    let op = match record.get("type").and_then(Value::as_str) {
+        Some("INSERT") => "c",
+        Some("UPDATE") => "u",
+        Some("DELETE") => "d",
+        Some(_) => return Ok(vec![]),
+        None => return Err("Canal record is missing 'type'".to_string()),
+    };
+
+    let Some(Value::Array(data)) = record.remove("data") else {
+        return Err("Canal record is missing its 'data' rows".to_string());
+    };
+
+    // for updates, `old` has an entry for each row in `data`
+    let mut old = match record.remove("old") {
+        Some(Value::Array(old)) => old.into_iter(),
+        _ => vec![].into_iter(),
+    };
+
+    data.into_iter()
+        .map(|row| change(op, row, old.next()))
+        .collect()
+}
+
+fn change(op: &str, data: Value, old: Option<Value>) -> Result<Value, String> {
+    let Value::Object(data) = data else {
+        return Err(format!(
+            "expected row data to be a JSON object, not {}",
+            data
+        ));
+    };
+
+    let (before, after) = match op {
+        "c" | "r" => (Value::Null, Value::Object(data)),
+        "d" => (Value::Object(data), Value::Null),
+        _ => {
+            // old only has the previous values of the columns that changed
+            let mut before = data.clone();
+            if let Some(Value::Object(old)) = old {
+                before.extend(old);
+            }
+            (Value::Object(before), Value::Object(data))
+        }
+    };
+
+    Ok(json!({
+        "before": before,
+        "after": after,
+        "op": op,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_debezium;
+    use arroyo_rpc::formats::CdcEnvelope;
+    use serde_json::json;
+
+    fn convert(envelope: CdcEnvelope, record: serde_json::Value) -> Vec<serde_json::Value> {
+        to_debezium(envelope, record.to_string().as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_maxwell() {
+        assert_eq!(
+            convert(
+                CdcEnvelope::Maxwell,
+                json!({
+                    "database": "shop",
+                    "table": "orders",
+                    "type": "update",
+                    "ts": 1700000000,
+                    "data": {"id": 1, "status": "shipped", "total": 5},
+                    "old": {"status": "pending"}
+                })
+            ),
+            vec![json!({
+                "before": {"id": 1, "status": "pending", "total": 5},
+                "after": {"id": 1, "status": "shipped", "total": 5},
+                "op": "u"
+            })]
+        );
+
+        assert_eq!(
+            convert(
+                CdcEnvelope::Maxwell,
+                json!({"type": "delete", "data": {"id": 1}})
+            ),
+            vec![json!({"before": {"id": 1}, "after": null, "op": "d"})]
+        );
+
+        assert!(convert(
+            CdcEnvelope::Maxwell,
+            json!({"type": "table-create", "sql": "CREATE TABLE orders (id int)"})
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_canal() {
+        assert_eq!(
+            convert(
+                CdcEnvelope::Canal,
+                json!({
+                    "database": "shop",
+                    "table": "orders",
+                    "type": "UPDATE",
+                    "isDdl": false,
+                    "pkNames": ["id"],
+                    "data": [
+                        {"id": "1", "status": "shipped"},
+                        {"id": "2", "status": "shipped"}
+                    ],
+                    "old": [{"status": "pending"}, {"status": "packed"}]
+                })
+            ),
+            vec![
+                json!({
+                    "before": {"id": "1", "status": "pending"},
+                    "after": {"id": "1", "status": "shipped"},
+                    "op": "u"
+                }),
+                json!({
+                    "before": {"id": "2", "status": "packed"},
+                    "after": {"id": "2", "status": "shipped"},
+                    "op": "u"
+                })
+            ]
+        );
+
+        assert!(convert(
+            CdcEnvelope::Canal,
+            json!({"type": "ALTER", "isDdl": true, "data": null, "sql": "ALTER TABLE orders"})
+        )
+        .is_empty());
+
+        assert!(to_debezium(CdcEnvelope::Canal, br#"{"type": "INSERT"}"#).is_err());
+    }
+}
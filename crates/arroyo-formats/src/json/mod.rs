@@ -3,7 +3,9 @@ use arrow_schema::DataType;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 
+pub mod cdc;
 pub mod schema;
+pub mod timestamps;
 
 pub fn field_to_json_schema(field: &Field) -> Value {
     match field.data_type() {
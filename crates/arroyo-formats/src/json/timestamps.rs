@@ -0,0 +1,373 @@
+use arrow::array::timezone::Tz;
+use arrow::compute::cast;
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Int64Type, TimestampNanosecondType};
+use arrow_array::{ArrayRef, ListArray, RecordBatch, StringArray, StructArray};
+use arrow_schema::{DataType, Field, Fields, Schema, TimeUnit};
+use arroyo_rpc::formats::TimestampFormat;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc};
+use serde_json::Value;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Converts timestamps between a JSON `TimestampFormat` and the representations that the arrow
+/// JSON reader and writer support natively: on the way in, timestamps matching a pattern are
+/// rewritten as RFC3339 strings before decoding (epoch timestamps are decoded natively, see
+/// [`epoch_unit`]), and on the way out timestamp columns are converted to integers or formatted
+/// strings before encoding.
+pub struct TimestampConverter {
+    format: TimestampFormat,
+    timezone: Tz,
+}
+
+impl TimestampConverter {
+    /// Returns None for RFC3339, which doesn't need any conversion
+    pub fn new(format: &TimestampFormat) -> Option<Self> {
+        let timezone = match format {
+            TimestampFormat::RFC3339 => return None,
+            TimestampFormat::Pattern {
+                timezone: Some(tz), ..
+            } => Tz::from_str(tz)
+                .unwrap_or_else(|_| panic!("invalid timezone '{}' for timestamp format", tz)),
+            _ => Tz::from_str("+00:00").unwrap(),
+        };
+
+        Some(Self {
+            format: format.clone(),
+            timezone,
+        })
+    }
+
+    /// Rewrites the timestamps of the JSON records in `msg` (for the timestamp fields of
+    /// `fields`) as RFC3339 strings. Values that aren't in the configured format, like numbers
+    /// for a pattern format, are left for the decoder to handle.
+    pub fn normalize_json(&self, fields: &Fields, msg: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = vec![];
+        for value in serde_json::Deserializer::from_slice(msg).into_iter::<Value>() {
+            let mut value = value.map_err(|e| format!("invalid JSON: {}", e))?;
+            self.normalize(fields, &mut value)?;
+
+            if !out.is_empty() {
+                out.push(b'\n');
+            }
+            serde_json::to_writer(&mut out, &value).unwrap();
+        }
+
+        Ok(out)
+    }
+
+    /// Rewrites the timestamps of a single JSON record as RFC3339 strings
+    pub fn normalize(&self, fields: &Fields, value: &mut Value) -> Result<(), String> {
+        // other types are reported by the decoder
+        let Value::Object(object) = value else {
+            return Ok(());
+        };
+
+        for field in fields {
+            if let Some(v) = object.get_mut(field.name()) {
+                self.normalize_value(field.name(), field.data_type(), v)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn normalize_value(&self, name: &str, dt: &DataType, value: &mut Value) -> Result<(), String> {
+        match dt {
+            DataType::Timestamp(_, _) => {
+                let timestamp = self
+                    .parse(value)
+                    .map_err(|e| format!("invalid timestamp for field '{}': {}", name, e))?;
+
+                if let Some(timestamp) = timestamp {
+                    *value = Value::String(timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true));
+                }
+            }
+            DataType::Struct(fields) => self.normalize(fields, value)?,
+            DataType::List(item) | DataType::LargeList(item) => {
+                if let Value::Array(items) = value {
+                    for item_value in items {
+                        self.normalize_value(name, item.data_type(), item_value)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn parse(&self, value: &Value) -> Result<Option<DateTime<Utc>>, String> {
+        match (&self.format, value) {
+            (TimestampFormat::Pattern { pattern, .. }, Value::String(s)) => {
+                self.parse_pattern(s, pattern).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn parse_pattern(&self, s: &str, pattern: &str) -> Result<DateTime<Utc>, String> {
+        // patterns with an offset specify the time exactly
+        if let Ok(t) = DateTime::parse_from_str(s, pattern) {
+            return Ok(t.with_timezone(&Utc));
+        }
+
+        let naive = NaiveDateTime::parse_from_str(s, pattern)
+            .or_else(|e| {
+                // patterns with only a date are midnight
+                NaiveDate::parse_from_str(s, pattern)
+                    .map(|d| d.and_time(NaiveTime::default()))
+                    .map_err(|_| e)
+            })
+            .map_err(|e| format!("'{}' does not match pattern '{}': {}", s, pattern, e))?;
+
+        self.timezone
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+            .ok_or_else(|| format!("'{}' does not exist in the configured timezone", s))
+    }
+
+    /// Converts the timestamp columns of the batch (including those nested in structs and lists)
+    /// to their encoded form
+    pub fn encode_batch(&self, batch: &RecordBatch) -> RecordBatch {
+        let columns: Vec<_> = batch.columns().iter().map(|c| self.encode(c)).collect();
+        let fields: Vec<_> = batch
+            .schema()
+            .fields()
+            .iter()
+            .zip(&columns)
+            .map(|(f, c)| with_data_type(f, c))
+            .collect();
+
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).unwrap()
+    }
+
+    fn encode(&self, array: &ArrayRef) -> ArrayRef {
+        match array.data_type() {
+            DataType::Timestamp(_, tz) => {
+                let nanos = cast(
+                    array,
+                    &DataType::Timestamp(TimeUnit::Nanosecond, tz.clone()),
+                )
+                .expect("could not cast timestamp to nanoseconds");
+                let nanos = nanos.as_primitive::<TimestampNanosecondType>();
+
+                let nanos_per_unit = match &self.format {
+                    TimestampFormat::RFC3339 => return array.clone(),
+                    TimestampFormat::UnixSeconds => 1_000_000_000,
+                    TimestampFormat::UnixMillis => 1_000_000,
+                    TimestampFormat::UnixMicros => 1_000,
+                    TimestampFormat::UnixNanos => 1,
+                    TimestampFormat::Pattern { pattern, .. } => {
+                        let formatted: StringArray = nanos
+                            .iter()
+                            .map(|t| {
+                                t.map(|t| {
+                                    Utc.timestamp_nanos(t)
+                                        .with_timezone(&self.timezone)
+                                        .format(pattern)
+                                        .to_string()
+                                })
+                            })
+                            .collect();
+                        return Arc::new(formatted);
+                    }
+                };
+
+                Arc::new(nanos.unary::<_, Int64Type>(|t| t.div_euclid(nanos_per_unit)))
+            }
+            DataType::Struct(_) => {
+                let array = array.as_struct();
+                let columns: Vec<_> = array.columns().iter().map(|c| self.encode(c)).collect();
+                let fields: Fields = array
+                    .fields()
+                    .iter()
+                    .zip(&columns)
+                    .map(|(f, c)| with_data_type(f, c))
+                    .collect();
+                Arc::new(StructArray::new(fields, columns, array.nulls().cloned()))
+            }
+            DataType::List(item) => {
+                let array = array.as_list::<i32>();
+                let values = self.encode(array.values());
+                Arc::new(ListArray::new(
+                    with_data_type(item, &values),
+                    array.offsets().clone(),
+                    values,
+                    array.nulls().cloned(),
+                ))
+            }
+            _ => array.clone(),
+        }
+    }
+}
+
+/// Whether any of the fields, or the fields nested within them, are timestamps
+pub fn has_timestamps(fields: &Fields) -> bool {
+    fn is_timestamp(dt: &DataType) -> bool {
+        match dt {
+            DataType::Timestamp(_, _) => true,
+            DataType::Struct(fields) => has_timestamps(fields),
+            DataType::List(item) | DataType::LargeList(item) => is_timestamp(item.data_type()),
+            _ => false,
+        }
+    }
+
+    fields.iter().any(|f| is_timestamp(f.data_type()))
+}
+
+/// The unit of an epoch timestamp format. The arrow JSON reader decodes numbers as timestamps in
+/// the unit of the field, so fields read with this unit don't need to be rewritten.
+pub fn epoch_unit(format: &TimestampFormat) -> Option<TimeUnit> {
+    match format {
+        TimestampFormat::UnixSeconds => Some(TimeUnit::Second),
+        TimestampFormat::UnixMillis => Some(TimeUnit::Millisecond),
+        TimestampFormat::UnixMicros => Some(TimeUnit::Microsecond),
+        TimestampFormat::UnixNanos => Some(TimeUnit::Nanosecond),
+        TimestampFormat::RFC3339 | TimestampFormat::Pattern { .. } => None,
+    }
+}
+
+/// Changes the unit of the timestamp fields, including those nested in structs and lists
+pub fn with_timestamp_unit(fields: &Fields, unit: &TimeUnit) -> Fields {
+    fn convert(dt: &DataType, unit: &TimeUnit) -> DataType {
+        match dt {
+            DataType::Timestamp(_, tz) => DataType::Timestamp(*unit, tz.clone()),
+            DataType::Struct(fields) => DataType::Struct(with_timestamp_unit(fields, unit)),
+            DataType::List(item) => DataType::List(Arc::new(
+                item.as_ref()
+                    .clone()
+                    .with_data_type(convert(item.data_type(), unit)),
+            )),
+            DataType::LargeList(item) => DataType::LargeList(Arc::new(
+                item.as_ref()
+                    .clone()
+                    .with_data_type(convert(item.data_type(), unit)),
+            )),
+            dt => dt.clone(),
+        }
+    }
+
+    fields
+        .iter()
+        .map(|f| {
+            Arc::new(
+                f.as_ref()
+                    .clone()
+                    .with_data_type(convert(f.data_type(), unit)),
+            )
+        })
+        .collect()
+}
+
+fn with_data_type(field: &Arc<Field>, array: &ArrayRef) -> Arc<Field> {
+    if field.data_type() == array.data_type() {
+        field.clone()
+    } else {
+        Arc::new(
+            field
+                .as_ref()
+                .clone()
+                .with_data_type(array.data_type().clone()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimestampConverter;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int64Type;
+    use arrow_array::{RecordBatch, TimestampMillisecondArray};
+    use arrow_schema::{DataType, Field, Fields, Schema, TimeUnit};
+    use arroyo_rpc::formats::TimestampFormat;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn fields() -> Fields {
+        Fields::from(vec![
+            Field::new("t", DataType::Timestamp(TimeUnit::Nanosecond, None), false),
+            Field::new(
+                "nested",
+                DataType::Struct(Fields::from(vec![Field::new(
+                    "t",
+                    DataType::Timestamp(TimeUnit::Microsecond, None),
+                    true,
+                )])),
+                true,
+            ),
+        ])
+    }
+
+    fn normalize(format: TimestampFormat, msg: serde_json::Value) -> serde_json::Value {
+        let converted = TimestampConverter::new(&format)
+            .unwrap()
+            .normalize_json(&fields(), msg.to_string().as_bytes())
+            .unwrap();
+        serde_json::from_slice(&converted).unwrap()
+    }
+
+    #[test]
+    fn test_normalize_pattern() {
+        let format =
+            TimestampFormat::pattern("%Y-%m-%d %H:%M:%S%.f".to_string(), Some("+02:00".into()))
+                .unwrap();
+
+        assert_eq!(
+            normalize(format.clone(), json!({"t": "2023-11-15 00:13:20.25"})),
+            json!({"t": "2023-11-14T22:13:20.250Z"})
+        );
+
+        let err = TimestampConverter::new(&format)
+            .unwrap()
+            .normalize_json(&fields(), br#"{"t": "2023/11/15"}"#)
+            .unwrap_err();
+        assert!(
+            err.starts_with("invalid timestamp for field 't'"),
+            "{}",
+            err
+        );
+
+        let date_only = TimestampFormat::pattern("%Y-%m-%d".to_string(), None).unwrap();
+        assert_eq!(
+            normalize(date_only, json!({"t": "2023-11-15"})),
+            json!({"t": "2023-11-15T00:00:00Z"})
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new(
+                "t",
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+                true,
+            )])),
+            vec![Arc::new(TimestampMillisecondArray::from(vec![
+                Some(1700000000500),
+                None,
+            ]))],
+        )
+        .unwrap();
+
+        let encoded = TimestampConverter::new(&TimestampFormat::UnixSeconds)
+            .unwrap()
+            .encode_batch(&batch);
+        let column = encoded.column(0).as_primitive::<Int64Type>();
+        assert_eq!(column.value(0), 1700000000);
+        assert!(column.is_null(1));
+
+        let encoded = TimestampConverter::new(
+            &TimestampFormat::pattern("%Y-%m-%d %H:%M:%S%.3f".to_string(), Some("+02:00".into()))
+                .unwrap(),
+        )
+        .unwrap()
+        .encode_batch(&batch);
+        assert_eq!(encoded.schema().field(0).data_type(), &DataType::Utf8);
+        assert_eq!(
+            encoded.column(0).as_string::<i32>().value(0),
+            "2023-11-15 00:13:20.500"
+        );
+    }
+}
@@ -3,9 +3,11 @@ use serde_json::{json, Value};
 use std::time::Instant;
 
 pub mod avro;
+mod columnar;
 pub mod json;
 
 pub mod de;
+pub mod ipc;
 pub mod proto;
 pub mod ser;
 
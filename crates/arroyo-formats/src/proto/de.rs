@@ -1,18 +1,26 @@
+use crate::columnar::{
+    check_int_range, convert_time_unit, error, push_offset, Column, ColumnValues, ColumnarDecoder,
+};
 use crate::float_to_json;
+use crate::proto::schema::{is_wrapper, DURATION, TIMESTAMP};
 use anyhow::anyhow;
+use arrow::compute::kernels::cast_utils::string_to_timestamp_nanos;
+use arrow_schema::{DataType, FieldRef, TimeUnit};
 use arroyo_rpc::formats::ProtobufFormat;
 use arroyo_types::SourceError;
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
 use integer_encoding::VarInt;
-use prost_reflect::{DescriptorPool, DynamicMessage, FieldDescriptor, Kind, MapKey, Value};
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, FieldDescriptor, Kind, MapKey, OneofDescriptor, Value,
+};
 use serde_json::Value as JsonValue;
 
 pub(crate) fn deserialize_proto(
     pool: &mut DescriptorPool,
     proto: &ProtobufFormat,
     mut msg: &[u8],
-) -> Result<serde_json::Value, SourceError> {
+) -> Result<DynamicMessage, SourceError> {
     if proto.confluent_schema_registry {
         skip_confluent_header(&mut msg).map_err(|e| {
             SourceError::bad_data(format!("invalid confluent schema header: {:?}", e))
@@ -22,10 +30,272 @@ pub(crate) fn deserialize_proto(
     let message = proto.message_name.as_ref().expect("no message name");
     let descriptor = pool.get_message_by_name(message).expect("no descriptor");
 
-    let msg = DynamicMessage::decode(descriptor, msg)
-        .map_err(|e| SourceError::bad_data(format!("failed to deserialize protobuf: {:?}", e)))?;
+    DynamicMessage::decode(descriptor, msg)
+        .map_err(|e| SourceError::bad_data(format!("failed to deserialize protobuf: {:?}", e)))
+}
+
+impl ColumnarDecoder {
+    /// Decodes a protobuf message directly into the Arrow columns of the target schema, without
+    /// going through JSON. Fields are matched by name; unset fields that track presence (messages
+    /// and `optional` fields) are null, while other unset fields have their default value.
+    ///
+    /// Well-known types are converted to their natural representation (`Timestamp` to a
+    /// timestamp, `Duration` to a duration or a number of nanoseconds, and wrappers to their
+    /// value), enums are read as their names for string fields and as their numbers for integer
+    /// fields, and bytes are read as-is for binary fields and base64-encoded for string fields.
+    pub fn decode_proto(&mut self, message: &DynamicMessage) -> Result<(), SourceError> {
+        self.append_row(|columns| append_message_fields(columns, message))
+            .map_err(|e| SourceError::bad_data(format!("failed to deserialize protobuf: {}", e)))
+    }
+}
+
+/// Appends the fields of a message to the columns of a struct (or of the top-level schema)
+fn append_message_fields(columns: &mut [Column], message: &DynamicMessage) -> Result<(), String> {
+    let descriptor = message.descriptor();
+    for column in columns {
+        let column_field = column.field.clone();
+        let name = column_field.name();
+        if let Some(field) = descriptor.get_field_by_name(name) {
+            if field.supports_presence() && !message.has_field(&field) {
+                column.append_null()?;
+            } else {
+                append_proto(column, &field, &message.get_field(&field))?;
+            }
+        } else if let Some(oneof) = descriptor.oneofs().find(|o| o.name() == name) {
+            append_oneof(column, &oneof, message)?;
+        } else {
+            column.append_null()?;
+        }
+    }
+    Ok(())
+}
+
+/// Oneofs are decoded into a struct with a field for each member, of which only the one that's
+/// set is non-null; if no member is set, the struct is null
+fn append_oneof(
+    column: &mut Column,
+    oneof: &OneofDescriptor,
+    message: &DynamicMessage,
+) -> Result<(), String> {
+    let Some(set) = oneof.fields().find(|f| message.has_field(f)) else {
+        return column.append_null();
+    };
+
+    let ColumnValues::Struct(columns) = &mut column.values else {
+        return Err(error(
+            &column.field,
+            format!("oneof '{}' must be read into a struct", oneof.name()),
+        ));
+    };
+
+    for c in columns.iter_mut() {
+        if c.field.name() == set.name() {
+            append_proto(c, &set, &message.get_field(&set))?;
+        } else {
+            c.append_null()?;
+        }
+    }
+
+    column.validity.push(true);
+    Ok(())
+}
+
+fn append_proto(column: &mut Column, field: &FieldDescriptor, value: &Value) -> Result<(), String> {
+    if let Value::Message(m) = value {
+        if is_wrapper(&m.descriptor()) && !matches!(column.values, ColumnValues::Struct(_)) {
+            let inner = m.descriptor().get_field_by_name("value").unwrap();
+            return append_proto(column, &inner, &m.get_field(&inner));
+        }
+    }
+
+    let column_field = column.field.clone();
+    let f = &column_field;
+    let data_type = f.data_type();
+    match &mut column.values {
+        ColumnValues::Boolean(values) => match value {
+            Value::Bool(b) => values.push(*b),
+            v => return Err(invalid(f, v)),
+        },
+        ColumnValues::Int(values) => {
+            let v = to_int(data_type, value).map_err(|e| error(f, e))?;
+            values.push(v);
+        }
+        ColumnValues::UInt64(values) => {
+            let v = match value {
+                Value::U64(u) => Some(*u),
+                Value::U32(u) => Some(*u as u64),
+                Value::I32(i) => u64::try_from(*i).ok(),
+                Value::I64(i) => u64::try_from(*i).ok(),
+                v => return Err(invalid(f, v)),
+            };
+            values.push(v.ok_or_else(|| error(f, "negative value for UInt64"))?);
+        }
+        ColumnValues::Float(values) => values.push(match value {
+            Value::F32(v) => *v as f64,
+            Value::F64(v) => *v,
+            Value::I32(v) => *v as f64,
+            Value::I64(v) => *v as f64,
+            Value::U32(v) => *v as f64,
+            Value::U64(v) => *v as f64,
+            v => return Err(invalid(f, v)),
+        }),
+        ColumnValues::Decimal(_) => return Err(invalid(f, value)),
+        ColumnValues::Bytes { offsets, data } => {
+            match (data_type, value) {
+                (DataType::Utf8 | DataType::LargeUtf8, v) if column.json => {
+                    data.extend_from_slice(proto_value_to_json(field, v).to_string().as_bytes());
+                }
+                (DataType::Utf8 | DataType::LargeUtf8, Value::String(s)) => {
+                    data.extend_from_slice(s.as_bytes());
+                }
+                (DataType::Utf8 | DataType::LargeUtf8, Value::EnumNumber(i)) => {
+                    // unknown values are read as their number
+                    match enum_name(field, *i) {
+                        Some(name) => data.extend_from_slice(name.as_bytes()),
+                        None => data.extend_from_slice(i.to_string().as_bytes()),
+                    }
+                }
+                (DataType::Utf8 | DataType::LargeUtf8, v) => match proto_value_to_json(field, v) {
+                    JsonValue::String(s) => data.extend_from_slice(s.as_bytes()),
+                    v => data.extend_from_slice(v.to_string().as_bytes()),
+                },
+                (_, Value::Bytes(b)) => data.extend_from_slice(b),
+                (_, Value::String(s)) => data.extend_from_slice(s.as_bytes()),
+                (_, v) => return Err(invalid(f, v)),
+            }
+            push_offset(f, offsets, data)?;
+        }
+        ColumnValues::List { offsets, item } => {
+            let Value::List(items) = value else {
+                return Err(invalid(f, value));
+            };
+            for v in items {
+                append_proto(item, field, v)?;
+            }
+            offsets.push(item.len() as i32);
+        }
+        ColumnValues::Struct(columns) => {
+            let Value::Message(m) = value else {
+                return Err(invalid(f, value));
+            };
+            append_message_fields(columns, m)?;
+        }
+        ColumnValues::Map {
+            offsets,
+            keys,
+            values,
+        } => {
+            let (Value::Map(entries), Kind::Message(entry)) = (value, field.kind()) else {
+                return Err(invalid(f, value));
+            };
+            let key_field = entry.map_entry_key_field();
+            let value_field = entry.map_entry_value_field();
+            for (k, v) in entries {
+                append_proto(keys, &key_field, &map_key_to_value(k))?;
+                append_proto(values, &value_field, v)?;
+            }
+            offsets.push(keys.len() as i32);
+        }
+    }
+
+    column.validity.push(true);
+    Ok(())
+}
+
+fn value_kind(value: &Value) -> String {
+    match value {
+        Value::Bool(_) => "bool".to_string(),
+        Value::I32(_) => "int32".to_string(),
+        Value::I64(_) => "int64".to_string(),
+        Value::U32(_) => "uint32".to_string(),
+        Value::U64(_) => "uint64".to_string(),
+        Value::F32(_) => "float".to_string(),
+        Value::F64(_) => "double".to_string(),
+        Value::String(_) => "string".to_string(),
+        Value::Bytes(_) => "bytes".to_string(),
+        Value::EnumNumber(_) => "enum".to_string(),
+        Value::Message(m) => format!("message {}", m.descriptor().full_name()),
+        Value::List(_) => "repeated field".to_string(),
+        Value::Map(_) => "map".to_string(),
+    }
+}
+
+fn invalid(field: &FieldRef, value: &Value) -> String {
+    error(
+        field,
+        format!(
+            "expected {}, found {}",
+            field.data_type(),
+            value_kind(value)
+        ),
+    )
+}
+
+fn enum_name(field: &FieldDescriptor, number: i32) -> Option<String> {
+    match field.kind() {
+        Kind::Enum(desc) => desc.get_value(number).map(|v| v.name().to_string()),
+        _ => None,
+    }
+}
+
+fn map_key_to_value(key: &MapKey) -> Value {
+    match key {
+        MapKey::Bool(b) => Value::Bool(*b),
+        MapKey::I32(i) => Value::I32(*i),
+        MapKey::I64(i) => Value::I64(*i),
+        MapKey::U32(u) => Value::U32(*u),
+        MapKey::U64(u) => Value::U64(*u),
+        MapKey::String(s) => Value::String(s.clone()),
+    }
+}
+
+/// Converts a `Timestamp` or `Duration` message to nanoseconds
+fn message_nanos(message: &DynamicMessage) -> Result<i64, String> {
+    let seconds = message
+        .get_field_by_name("seconds")
+        .and_then(|v| v.as_i64())
+        .unwrap_or_default();
+    let nanos = message
+        .get_field_by_name("nanos")
+        .and_then(|v| v.as_i32())
+        .unwrap_or_default();
+
+    seconds
+        .checked_mul(1_000_000_000)
+        .and_then(|s| s.checked_add(nanos as i64))
+        .ok_or_else(|| format!("{} seconds is out of range", seconds))
+}
+
+/// Converts an integer-like protobuf value to the integer representation of the target type
+fn to_int(data_type: &DataType, value: &Value) -> Result<i64, String> {
+    let v = match (data_type, value) {
+        (DataType::Timestamp(unit, _), Value::Message(m))
+            if m.descriptor().full_name() == TIMESTAMP =>
+        {
+            convert_time_unit(message_nanos(m)?, TimeUnit::Nanosecond, unit)?
+        }
+        (DataType::Duration(unit), Value::Message(m)) if m.descriptor().full_name() == DURATION => {
+            convert_time_unit(message_nanos(m)?, TimeUnit::Nanosecond, unit)?
+        }
+        (DataType::Int64, Value::Message(m))
+            if matches!(m.descriptor().full_name(), TIMESTAMP | DURATION) =>
+        {
+            message_nanos(m)?
+        }
+        (DataType::Timestamp(unit, _), Value::String(s)) => {
+            let nanos = string_to_timestamp_nanos(s).map_err(|e| e.to_string())?;
+            convert_time_unit(nanos, TimeUnit::Nanosecond, unit)?
+        }
+        (_, Value::I32(i) | Value::EnumNumber(i)) => *i as i64,
+        (_, Value::I64(i)) => *i,
+        (_, Value::U32(u)) => *u as i64,
+        (_, Value::U64(u)) => {
+            i64::try_from(*u).map_err(|_| format!("{} is out of range for {}", u, data_type))?
+        }
+        (_, v) => return Err(format!("expected {}, found {}", data_type, value_kind(v))),
+    };
 
-    Ok(proto_to_json(&msg))
+    check_int_range(data_type, v)
 }
 
 pub(crate) fn proto_to_json(message: &DynamicMessage) -> JsonValue {
@@ -1,15 +1,35 @@
 use anyhow::{anyhow, bail, Context};
-use arrow_schema::{DataType, Field, Schema};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
 use arroyo_types::ArroyoExtensionType;
 use prost_reflect::{Cardinality, DescriptorPool, FieldDescriptor, Kind, MessageDescriptor};
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env::temp_dir;
 use std::path::Path;
 use std::sync::Arc;
 use tracing::warn;
 use uuid::Uuid;
 
+pub(crate) const TIMESTAMP: &str = "google.protobuf.Timestamp";
+pub(crate) const DURATION: &str = "google.protobuf.Duration";
+
+/// Whether the message is one of the well-known wrapper types (like `google.protobuf.Int32Value`),
+/// which are represented as a nullable value of the wrapped type
+pub(crate) fn is_wrapper(message: &MessageDescriptor) -> bool {
+    matches!(
+        message.full_name(),
+        "google.protobuf.BoolValue"
+            | "google.protobuf.Int32Value"
+            | "google.protobuf.Int64Value"
+            | "google.protobuf.UInt32Value"
+            | "google.protobuf.UInt64Value"
+            | "google.protobuf.FloatValue"
+            | "google.protobuf.DoubleValue"
+            | "google.protobuf.StringValue"
+            | "google.protobuf.BytesValue"
+    )
+}
+
 fn protobuf_to_arrow_datatype(
     field: &FieldDescriptor,
     in_list: bool,
@@ -38,6 +58,14 @@ fn protobuf_to_arrow_datatype(
                 if field.is_map() {
                     // we don't currently support maps so treat maps as raw json
                     return (DataType::Utf8, Some(ArroyoExtensionType::JSON));
+                } else if message.full_name() == TIMESTAMP {
+                    DataType::Timestamp(TimeUnit::Nanosecond, None)
+                } else if message.full_name() == DURATION {
+                    // durations are represented as a number of nanoseconds
+                    DataType::Int64
+                } else if is_wrapper(&message) {
+                    let value = message.get_field_by_name("value").unwrap();
+                    return protobuf_to_arrow_datatype(&value, true);
                 } else {
                     DataType::Struct(fields_for_message(&message).into())
                 }
@@ -48,17 +76,40 @@ fn protobuf_to_arrow_datatype(
     )
 }
 
+fn field_for(field: &FieldDescriptor) -> Arc<Field> {
+    let (t, ext) = protobuf_to_arrow_datatype(field, false);
+    Arc::new(ArroyoExtensionType::add_metadata(
+        ext,
+        Field::new(field.name(), t, is_nullable(field)),
+    ))
+}
+
+/// The fields of a message are mapped directly to Arrow fields, except for the members of a
+/// oneof, which are grouped into a nullable struct named after the oneof in which at most one
+/// field is non-null.
 fn fields_for_message(message: &MessageDescriptor) -> Vec<Arc<Field>> {
-    message
-        .fields()
-        .map(|f| {
-            let (t, ext) = protobuf_to_arrow_datatype(&f, false);
-            Arc::new(ArroyoExtensionType::add_metadata(
-                ext,
-                Field::new(f.name(), t, is_nullable(&f)),
-            ))
-        })
-        .collect()
+    let mut fields = vec![];
+    let mut oneofs = HashSet::new();
+
+    for f in message.fields() {
+        // proto3 optional fields are represented as synthetic oneofs, which we treat as normal
+        // nullable fields
+        match f.containing_oneof().filter(|o| !o.is_synthetic()) {
+            Some(oneof) => {
+                if oneofs.insert(oneof.name().to_string()) {
+                    let members: Vec<_> = oneof.fields().map(|f| field_for(&f)).collect();
+                    fields.push(Arc::new(Field::new(
+                        oneof.name(),
+                        DataType::Struct(members.into()),
+                        true,
+                    )));
+                }
+            }
+            None => fields.push(field_for(&f)),
+        }
+    }
+
+    fields
 }
 
 pub fn get_pool(encoded: &[u8]) -> anyhow::Result<DescriptorPool> {
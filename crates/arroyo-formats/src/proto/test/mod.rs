@@ -1,10 +1,16 @@
+use crate::columnar::ColumnarDecoder;
 use crate::proto::schema::{
     protobuf_to_arrow, schema_file_to_descriptor, schema_file_to_descriptor_with_resolver,
     ProtoSchemaResolver,
 };
-use arrow_schema::{DataType, Field, Schema};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    DurationMillisecondType, Int32Type, Int64Type, TimestampNanosecondType, UInt64Type,
+};
+use arrow_array::Array;
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
 use arroyo_types::ArroyoExtensionType;
-use prost_reflect::DescriptorPool;
+use prost_reflect::{DescriptorPool, DynamicMessage, MapKey, Value};
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -165,6 +171,208 @@ async fn test_enum_fields() {
     assert_field(&arrow_schema, "enum_field", DataType::Utf8, true);
 }
 
+/// Compiles decoding.proto, providing the subset of the well-known types that it uses
+async fn decoding_pool() -> DescriptorPool {
+    let dependencies = [
+        (
+            "google/protobuf/timestamp.proto",
+            "syntax = \"proto3\"; package google.protobuf; \
+            message Timestamp { int64 seconds = 1; int32 nanos = 2; }",
+        ),
+        (
+            "google/protobuf/duration.proto",
+            "syntax = \"proto3\"; package google.protobuf; \
+            message Duration { int64 seconds = 1; int32 nanos = 2; }",
+        ),
+        (
+            "google/protobuf/wrappers.proto",
+            "syntax = \"proto3\"; package google.protobuf; \
+            message Int32Value { int32 value = 1; }",
+        ),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    let bytes = schema_file_to_descriptor(include_str!("protos/decoding.proto"), &dependencies)
+        .await
+        .unwrap();
+
+    DescriptorPool::decode(bytes.as_ref()).unwrap()
+}
+
+#[tokio::test]
+async fn test_well_known_types_and_oneofs() {
+    let pool = decoding_pool().await;
+    let message = pool.get_message_by_name("TestDecoding").unwrap();
+    let arrow_schema = protobuf_to_arrow(&message).unwrap();
+
+    // the synthetic oneof for the optional field doesn't appear in the schema
+    assert_eq!(arrow_schema.fields().len(), 11);
+    assert_field(&arrow_schema, "name", DataType::Utf8, true);
+    assert_field(
+        &arrow_schema,
+        "created",
+        DataType::Timestamp(TimeUnit::Nanosecond, None),
+        true,
+    );
+    assert_field(&arrow_schema, "elapsed", DataType::Int64, true);
+    assert_field(&arrow_schema, "count", DataType::Int32, true);
+    assert_field(
+        &arrow_schema,
+        "target",
+        DataType::Struct(
+            vec![
+                Arc::new(Field::new("email", DataType::Utf8, true)),
+                Arc::new(Field::new("phone", DataType::Int32, true)),
+            ]
+            .into(),
+        ),
+        true,
+    );
+}
+
+fn test_message(pool: &DescriptorPool) -> DynamicMessage {
+    let mut message = DynamicMessage::new(pool.get_message_by_name("TestDecoding").unwrap());
+
+    let mut created = DynamicMessage::new(
+        pool.get_message_by_name("google.protobuf.Timestamp")
+            .unwrap(),
+    );
+    created.set_field_by_name("seconds", Value::I64(1));
+    created.set_field_by_name("nanos", Value::I32(5));
+
+    let mut elapsed = DynamicMessage::new(
+        pool.get_message_by_name("google.protobuf.Duration")
+            .unwrap(),
+    );
+    elapsed.set_field_by_name("seconds", Value::I64(2));
+
+    let mut count = DynamicMessage::new(
+        pool.get_message_by_name("google.protobuf.Int32Value")
+            .unwrap(),
+    );
+    count.set_field_by_name("value", Value::I32(7));
+
+    let mut inner = DynamicMessage::new(pool.get_message_by_name("TestDecoding.Inner").unwrap());
+    inner.set_field_by_name("data", Value::Bytes(b"hi".to_vec().into()));
+
+    message.set_field_by_name("id", Value::I64(1));
+    message.set_field_by_name("big", Value::U64(u64::MAX));
+    message.set_field_by_name("name", Value::String("a".to_string()));
+    message.set_field_by_name("status", Value::EnumNumber(1));
+    message.set_field_by_name("payload", Value::Bytes(vec![0, 255].into()));
+    message.set_field_by_name("created", Value::Message(created));
+    message.set_field_by_name("elapsed", Value::Message(elapsed));
+    message.set_field_by_name("count", Value::Message(count));
+    message.set_field_by_name("inners", Value::List(vec![Value::Message(inner)]));
+    message.set_field_by_name(
+        "counts",
+        Value::Map([(MapKey::String("x".to_string()), Value::I32(3))].into()),
+    );
+    message.set_field_by_name("phone", Value::I32(5));
+
+    message
+}
+
+#[tokio::test]
+async fn test_native_decoding() {
+    let pool = decoding_pool().await;
+    let descriptor = pool.get_message_by_name("TestDecoding").unwrap();
+    let mut decoder = ColumnarDecoder::new(&protobuf_to_arrow(&descriptor).unwrap()).unwrap();
+
+    decoder.decode_proto(&test_message(&pool)).unwrap();
+    // unset fields with presence are null, while other fields have their defaults
+    decoder
+        .decode_proto(&DynamicMessage::new(descriptor))
+        .unwrap();
+
+    let columns = decoder.flush();
+    assert!(decoder.is_empty());
+
+    let ids = columns[0].as_primitive::<Int64Type>();
+    assert_eq!(ids.values().to_vec(), vec![1, 0]);
+    assert_eq!(ids.null_count(), 0);
+
+    let big = columns[1].as_primitive::<UInt64Type>();
+    assert_eq!(big.values().to_vec(), vec![u64::MAX, 0]);
+
+    let names = columns[2].as_string::<i32>();
+    assert_eq!(names.value(0), "a");
+    assert!(names.is_null(1));
+
+    let statuses = columns[3].as_string::<i32>();
+    assert_eq!(statuses.value(0), "ACTIVE");
+    assert_eq!(statuses.value(1), "UNKNOWN");
+
+    let payloads = columns[4].as_string::<i32>();
+    assert_eq!(payloads.value(0), "AP8=");
+    assert_eq!(payloads.value(1), "");
+
+    let created = columns[5].as_primitive::<TimestampNanosecondType>();
+    assert_eq!(created.value(0), 1_000_000_005);
+    assert!(created.is_null(1));
+
+    let elapsed = columns[6].as_primitive::<Int64Type>();
+    assert_eq!(elapsed.value(0), 2_000_000_000);
+    assert!(elapsed.is_null(1));
+
+    let count = columns[7].as_primitive::<Int32Type>();
+    assert_eq!(count.value(0), 7);
+    assert!(count.is_null(1));
+
+    let inners = columns[8].as_list::<i32>();
+    let inner = inners.value(0);
+    assert_eq!(
+        inner.as_struct().column(0).as_string::<i32>().value(0),
+        "aGk="
+    );
+    assert!(inners.is_valid(1));
+    assert_eq!(inners.value(1).len(), 0);
+
+    let counts = columns[9].as_string::<i32>();
+    assert_eq!(counts.value(0), r#"{"x":3}"#);
+    assert_eq!(counts.value(1), "{}");
+
+    let target = columns[10].as_struct();
+    assert!(target.column(0).is_null(0));
+    assert_eq!(target.column(1).as_primitive::<Int32Type>().value(0), 5);
+    assert!(target.is_null(1));
+}
+
+#[tokio::test]
+async fn test_native_decoding_target_types() {
+    let pool = decoding_pool().await;
+
+    // the target schema may use other representations than the inferred schema
+    let schema = Schema::new(vec![
+        Field::new("payload", DataType::Binary, true),
+        Field::new("elapsed", DataType::Duration(TimeUnit::Millisecond), true),
+        Field::new("status", DataType::Int32, true),
+        Field::new("big", DataType::Int64, true),
+    ]);
+    let mut decoder = ColumnarDecoder::new(&schema).unwrap();
+
+    let mut message = test_message(&pool);
+    // out of range for an int64 column
+    assert!(decoder.decode_proto(&message).is_err());
+
+    message.set_field_by_name("big", Value::U64(10));
+    decoder.decode_proto(&message).unwrap();
+
+    let columns = decoder.flush();
+    assert!(columns.iter().all(|c| c.len() == 1));
+    assert_eq!(columns[0].as_binary::<i32>().value(0), &[0, 255]);
+    assert_eq!(
+        columns[1]
+            .as_primitive::<DurationMillisecondType>()
+            .value(0),
+        2_000
+    );
+    assert_eq!(columns[2].as_primitive::<Int32Type>().value(0), 1);
+    assert_eq!(columns[3].as_primitive::<Int64Type>().value(0), 10);
+}
+
 // Helper function to assert field properties
 fn assert_field(schema: &Schema, name: &str, data_type: DataType, nullable: bool) {
     let field = schema.field_with_name(name).unwrap();
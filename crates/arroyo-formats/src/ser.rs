@@ -1,12 +1,14 @@
 use crate::avro::schema;
-use crate::{avro, json};
+use crate::json::timestamps::TimestampConverter;
+use crate::{avro, ipc, json};
 use arrow_array::cast::AsArray;
 use arrow_array::types::GenericBinaryType;
 use arrow_array::RecordBatch;
 use arrow_json::writer::record_batch_to_vec;
 use arrow_schema::{DataType, Field};
 use arroyo_rpc::formats::{
-    AvroFormat, Format, JsonFormat, RawBytesFormat, RawStringFormat, TimestampFormat,
+    AvroFormat, Format, Framing, FramingMethod, JsonFormat, RawBytesFormat, RawStringFormat,
+    TimestampFormat,
 };
 use arroyo_rpc::TIMESTAMP_FIELD;
 use serde_json::Value;
@@ -16,6 +18,7 @@ pub struct ArrowSerializer {
     kafka_schema: Option<Value>,
     avro_schema: Option<Arc<apache_avro::schema::Schema>>,
     format: Format,
+    framing: Option<Arc<Framing>>,
     projection: Vec<usize>,
 }
 
@@ -25,10 +28,18 @@ impl ArrowSerializer {
             kafka_schema: None,
             avro_schema: None,
             format,
+            framing: None,
             projection: vec![],
         }
     }
 
+    /// Frames each serialized message with the given framing, for sinks that write messages
+    /// to a byte stream rather than as discrete records
+    pub fn with_framing(mut self, framing: Option<Framing>) -> Self {
+        self.framing = framing.map(Arc::new);
+        self
+    }
+
     fn projection(schema: &arrow_schema::Schema) -> Vec<usize> {
         schema
             .fields
@@ -76,16 +87,31 @@ impl ArrowSerializer {
             .project(&self.projection)
             .expect("batch has wrong number of columns");
 
-        match &self.format {
+        let values = match &self.format {
             Format::Json(json) => self.serialize_json(json, &batch),
             Format::Avro(avro) => self.serialize_avro(avro, &batch),
             Format::Parquet(_) => todo!("parquet"),
+            Format::Csv(_) => unreachable!("csv is only supported by the filesystem sink"),
+            Format::ArrowIpc(_) => self.serialize_arrow_ipc(&batch),
             Format::RawString(RawStringFormat {}) => self.serialize_raw_string(&batch),
             Format::RawBytes(RawBytesFormat {}) => self.serialize_raw_bytes(&batch),
             Format::Protobuf(_) => {
                 todo!("protobuf serializer!")
             }
+        };
+
+        match self.framing.clone() {
+            Some(framing) => Box::new(values.map(move |v| frame(&framing, v))),
+            None => values,
+        }
+    }
+
+    /// Arrow IPC sinks write each batch as a single message
+    fn serialize_arrow_ipc(&self, batch: &RecordBatch) -> Box<dyn Iterator<Item = Vec<u8>> + Send> {
+        if batch.num_rows() == 0 {
+            return Box::new(std::iter::empty());
         }
+        Box::new(std::iter::once(ipc::write_stream(batch)))
     }
 
     fn serialize_json(
@@ -104,15 +130,24 @@ impl ArrowSerializer {
             v
         });
 
-        let rows = record_batch_to_vec(
-            batch,
-            true,
-            match json.timestamp_format {
-                TimestampFormat::RFC3339 => arrow_json::writer::TimestampFormat::RFC3339,
-                TimestampFormat::UnixMillis => arrow_json::writer::TimestampFormat::UnixMillis,
-            },
-        )
-        .unwrap();
+        // the JSON writer handles RFC3339 and millis itself; other formats are converted first
+        let (batch, timestamp_format) = match &json.timestamp_format {
+            TimestampFormat::RFC3339 => {
+                (batch.clone(), arrow_json::writer::TimestampFormat::RFC3339)
+            }
+            TimestampFormat::UnixMillis => (
+                batch.clone(),
+                arrow_json::writer::TimestampFormat::UnixMillis,
+            ),
+            format => (
+                TimestampConverter::new(format)
+                    .expect("only RFC3339 has no converter")
+                    .encode_batch(batch),
+                arrow_json::writer::TimestampFormat::RFC3339,
+            ),
+        };
+
+        let rows = record_batch_to_vec(&batch, true, timestamp_format).unwrap();
 
         let include_schema = json.include_schema.then(|| self.kafka_schema.clone());
 
@@ -236,12 +271,46 @@ impl ArrowSerializer {
     }
 }
 
+fn frame(framing: &Framing, mut value: Vec<u8>) -> Vec<u8> {
+    match &framing.method {
+        FramingMethod::Newline(_) => {
+            value.push(b'\n');
+            value
+        }
+        FramingMethod::OctetCounting(_) => {
+            let mut framed = format!("{} ", value.len()).into_bytes();
+            framed.extend(value);
+            framed
+        }
+        FramingMethod::LengthPrefixed(lp) => {
+            let mut framed = lp.write_length(value.len() as u64).unwrap_or_else(|| {
+                panic!(
+                    "message of {} bytes is too long for a {}-byte length prefix",
+                    value.len(),
+                    lp.prefix_bytes
+                )
+            });
+            framed.extend(value);
+            framed
+        }
+        FramingMethod::Delimited(d) => {
+            value.extend_from_slice(&d.delimiter);
+            value
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ser::ArrowSerializer;
     use arrow_array::builder::TimestampNanosecondBuilder;
-    use arrow_schema::{Schema, TimeUnit};
-    use arroyo_rpc::formats::{Format, RawBytesFormat, RawStringFormat, TimestampFormat};
+    use arrow_array::{Array, Int64Array, StringArray, StructArray};
+    use arrow_schema::{Fields, Schema, TimeUnit};
+    use arroyo_rpc::formats::{
+        AvroFormat, DelimitedFraming, Endianness, Format, Framing, FramingMethod,
+        LengthPrefixedFraming, OctetCountingFraming, RawBytesFormat, RawStringFormat,
+        TimestampFormat,
+    };
     use arroyo_types::to_nanos;
     use std::sync::Arc;
     use std::time::{Duration, SystemTime};
@@ -324,6 +393,58 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn test_framing() {
+        let schema = Arc::new(Schema::new(vec![
+            arrow_schema::Field::new("value", arrow_schema::DataType::Utf8, false),
+            arrow_schema::Field::new(
+                "_timestamp",
+                arrow_schema::DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+        ]));
+
+        let batch = arrow_array::RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(arrow_array::StringArray::from(vec!["a", "hello"])),
+                Arc::new(arrow_array::TimestampNanosecondArray::from(vec![0, 1])),
+            ],
+        )
+        .unwrap();
+
+        let serialize = |method: FramingMethod| {
+            ArrowSerializer::new(Format::RawString(RawStringFormat {}))
+                .with_framing(Some(Framing { method }))
+                .serialize(&batch)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            serialize(FramingMethod::OctetCounting(OctetCountingFraming {
+                max_message_length: None
+            })),
+            vec![b"1 a".to_vec(), b"5 hello".to_vec()]
+        );
+
+        assert_eq!(
+            serialize(FramingMethod::LengthPrefixed(LengthPrefixedFraming {
+                prefix_bytes: 2,
+                endianness: Endianness::Little,
+                max_message_length: None,
+            })),
+            vec![b"\x01\x00a".to_vec(), b"\x05\x00hello".to_vec()]
+        );
+
+        assert_eq!(
+            serialize(FramingMethod::Delimited(DelimitedFraming {
+                delimiter: vec![0x1e],
+                max_message_length: None,
+            })),
+            vec![b"a\x1e".to_vec(), b"hello\x1e".to_vec()]
+        );
+    }
+
     #[test]
     fn test_json() {
         let mut serializer = ArrowSerializer::new(Format::Json(arroyo_rpc::formats::JsonFormat {
@@ -331,6 +452,7 @@ mod tests {
             schema_id: None,
             include_schema: false,
             debezium: false,
+            cdc_envelope: None,
             unstructured: false,
             timestamp_format: Default::default(),
         }));
@@ -383,6 +505,7 @@ mod tests {
             schema_id: None,
             include_schema: false,
             debezium: false,
+            cdc_envelope: None,
             unstructured: false,
             timestamp_format: TimestampFormat::UnixMillis,
         }));
@@ -426,4 +549,110 @@ mod tests {
         assert_eq!(iter.next().unwrap(), br#"{"value":null}"#);
         assert_eq!(iter.next().unwrap(), br#"{"value":1712274910045}"#);
     }
+
+    #[test]
+    fn test_json_pattern_ts() {
+        let mut serializer = ArrowSerializer::new(Format::Json(arroyo_rpc::formats::JsonFormat {
+            confluent_schema_registry: false,
+            schema_id: None,
+            include_schema: false,
+            debezium: false,
+            cdc_envelope: None,
+            unstructured: false,
+            timestamp_format: TimestampFormat::pattern(
+                "%d/%m/%Y %H:%M".to_string(),
+                Some("America/New_York".to_string()),
+            )
+            .unwrap(),
+        }));
+
+        let schema = Arc::new(Schema::new(vec![
+            arrow_schema::Field::new(
+                "value",
+                arrow_schema::DataType::Timestamp(TimeUnit::Nanosecond, None),
+                true,
+            ),
+            arrow_schema::Field::new(
+                "_timestamp",
+                arrow_schema::DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+        ]));
+
+        let batch = arrow_array::RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(arrow_array::TimestampNanosecondArray::from(vec![
+                    Some(1612274910045331968),
+                    None,
+                ])),
+                Arc::new(arrow_array::TimestampNanosecondArray::from(vec![0, 0])),
+            ],
+        )
+        .unwrap();
+
+        let mut iter = serializer.serialize(&batch);
+        assert_eq!(iter.next().unwrap(), br#"{"value":"02/02/2021 09:08"}"#);
+        assert_eq!(iter.next().unwrap(), br#"{"value":null}"#);
+    }
+
+    #[test]
+    fn test_debezium_avro() {
+        let mut format = AvroFormat::new(true, false, false);
+        format.debezium = true;
+        format.schema_id = Some(7);
+        let mut serializer = ArrowSerializer::new(Format::Avro(format));
+
+        let row_fields: Fields = vec![
+            arrow_schema::Field::new("id", arrow_schema::DataType::Int64, false),
+            arrow_schema::Field::new("status", arrow_schema::DataType::Utf8, true),
+        ]
+        .into();
+        let row_type = arrow_schema::DataType::Struct(row_fields.clone());
+
+        let schema = Arc::new(Schema::new(vec![
+            arrow_schema::Field::new("before", row_type.clone(), true),
+            arrow_schema::Field::new("after", row_type, true),
+            arrow_schema::Field::new("op", arrow_schema::DataType::Utf8, false),
+            arrow_schema::Field::new(
+                "_timestamp",
+                arrow_schema::DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+        ]));
+
+        let rows = StructArray::new(
+            row_fields,
+            vec![
+                Arc::new(Int64Array::from(vec![1])),
+                Arc::new(StringArray::from(vec!["shipped"])),
+            ],
+            None,
+        );
+
+        let batch = arrow_array::RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                arrow_array::new_null_array(rows.data_type(), 1),
+                Arc::new(rows),
+                Arc::new(StringArray::from(vec!["c"])),
+                Arc::new(arrow_array::TimestampNanosecondArray::from(vec![0])),
+            ],
+        )
+        .unwrap();
+
+        let record = serializer.serialize(&batch).next().unwrap();
+        assert_eq!(record[..5], [0, 0, 0, 0, 7]);
+
+        let avro_schema = ArrowSerializer::avro_schema(&schema);
+        let value = apache_avro::from_avro_datum(&avro_schema, &mut &record[5..], None).unwrap();
+        assert_eq!(
+            crate::avro::de::avro_to_json(value),
+            serde_json::json!({
+                "before": null,
+                "after": {"id": 1, "status": "shipped"},
+                "op": "c"
+            })
+        );
+    }
 }
@@ -2,8 +2,8 @@ use std::collections::HashMap;
 use std::sync::{Arc, OnceLock, RwLock};
 
 use arroyo_types::{
-    TaskInfo, BATCHES_RECV, BATCHES_SENT, BYTES_RECV, BYTES_SENT, DESERIALIZATION_ERRORS,
-    MESSAGES_RECV, MESSAGES_SENT,
+    TaskInfo, BATCHES_RECV, BATCHES_SENT, BYTES_RECV, BYTES_SENT, DEAD_LETTER_RECORDS,
+    DESERIALIZATION_ERRORS, LATE_RECORDS_DROPPED, MESSAGES_RECV, MESSAGES_SENT,
 };
 use lazy_static::lazy_static;
 use prometheus::{
@@ -85,6 +85,18 @@ lazy_static! {
         &TASK_METRIC_LABELS
     )
     .unwrap();
+    pub static ref DEAD_LETTER_RECORDS_COUNTER: IntCounterVec = register_int_counter_vec!(
+        DEAD_LETTER_RECORDS,
+        "Count of records that failed to deserialize and were sent to a dead-letter table",
+        &TASK_METRIC_LABELS
+    )
+    .unwrap();
+    pub static ref LATE_RECORDS_DROPPED_COUNTER: IntCounterVec = register_int_counter_vec!(
+        LATE_RECORDS_DROPPED,
+        "Count of records that arrived after their windows closed and were left out of the results",
+        &TASK_METRIC_LABELS
+    )
+    .unwrap();
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash)]
@@ -96,10 +108,12 @@ pub enum TaskCounters {
     BytesReceived,
     BytesSent,
     DeserializationErrors,
+    DeadLetterRecords,
+    LateRecordsDropped,
 }
 
 impl TaskCounters {
-    pub fn variants() -> [TaskCounters; 7] {
+    pub fn variants() -> [TaskCounters; 9] {
         use TaskCounters::*;
 
         [
@@ -110,6 +124,8 @@ impl TaskCounters {
             BytesReceived,
             BytesSent,
             DeserializationErrors,
+            DeadLetterRecords,
+            LateRecordsDropped,
         ]
     }
 }
@@ -125,6 +141,8 @@ impl TaskCounters {
             TaskCounters::BytesReceived => &BYTES_RECEIVED_COUNTER,
             TaskCounters::BytesSent => &BYTES_SENT_COUNTER,
             TaskCounters::DeserializationErrors => &DESERIALIZATION_ERRORS_COUNTER,
+            TaskCounters::DeadLetterRecords => &DEAD_LETTER_RECORDS_COUNTER,
+            TaskCounters::LateRecordsDropped => &LATE_RECORDS_DROPPED_COUNTER,
         }
     }
 
@@ -1,9 +1,10 @@
 use crate::operator::OperatorNode;
 use anyhow::{anyhow, bail};
-use arrow::datatypes::{DataType, Field};
+use arrow::datatypes::{DataType, Field, Fields};
 use arroyo_rpc::api_types::connections::{
     ConnectionProfile, ConnectionSchema, ConnectionType, TestSourceMessage,
 };
+use arroyo_rpc::formats::Format;
 use arroyo_rpc::OperatorConfig;
 use arroyo_types::DisplayAsSql;
 use serde::de::DeserializeOwned;
@@ -29,6 +30,17 @@ pub struct MetadataDef {
     pub data_type: DataType,
 }
 
+impl MetadataDef {
+    /// Returns whether this definition applies to the given metadata key. Names ending in `.*`
+    /// define a family of keys, like `headers.*` matching `headers.trace_id`.
+    pub fn matches(&self, key: &str) -> bool {
+        match self.name.strip_suffix('*') {
+            Some(prefix) => key.len() > prefix.len() && key.starts_with(prefix),
+            None => self.name == key,
+        }
+    }
+}
+
 #[allow(clippy::wrong_self_convention)]
 pub trait Connector: Send {
     type ProfileT: DeserializeOwned + Serialize;
@@ -57,6 +69,20 @@ pub trait Connector: Send {
 
     fn table_type(&self, config: Self::ProfileT, table: Self::TableT) -> ConnectionType;
 
+    /// Whether tables of the given type may use the csv format, which is write-only and only
+    /// supported by some sinks
+    #[allow(unused)]
+    fn supports_csv(&self, connection_type: &ConnectionType) -> bool {
+        false
+    }
+
+    /// Checks that a sink can write rows with the given fields. This is called when the table is
+    /// created and, for sinks whose fields are inferred from the query, when it's planned.
+    #[allow(unused)]
+    fn check_sink_fields(&self, table: &Self::TableT, fields: &Fields) -> anyhow::Result<()> {
+        Ok(())
+    }
+
     #[allow(unused)]
     fn get_schema(
         &self,
@@ -119,6 +145,44 @@ pub trait Connector: Send {
         config: OperatorConfig,
     ) -> anyhow::Result<OperatorNode>;
 }
+
+/// Checks that a table's format and schema are ones its connector can read or write
+fn check_connection<C: Connector>(
+    connector: &C,
+    connection: Connection,
+) -> anyhow::Result<Connection> {
+    if matches!(connection.schema.format, Some(Format::Csv(_)))
+        && !connector.supports_csv(&connection.connection_type)
+    {
+        bail!(
+            "csv format is not supported for {} connector '{}'",
+            connection.connector,
+            connection.name
+        );
+    }
+
+    if let (ConnectionType::Source, Some(format)) =
+        (&connection.connection_type, &connection.schema.format)
+    {
+        let schema = connection.schema.arroyo_schema().schema_without_timestamp();
+        arroyo_formats::de::check_schema(format, &schema).map_err(|e| {
+            anyhow!(
+                "invalid schema for {} connector '{}': {}",
+                connection.connector,
+                connection.name,
+                e
+            )
+        })?;
+    }
+
+    if connection.connection_type == ConnectionType::Sink && !connection.schema.fields.is_empty() {
+        let schema = connection.schema.arroyo_schema().schema_without_timestamp();
+        ErasedConnector::check_sink_fields(connector, &connection.config, &schema.fields)?;
+    }
+
+    Ok(connection)
+}
+
 #[allow(clippy::type_complexity)]
 #[allow(clippy::wrong_self_convention)]
 pub trait ErasedConnector: Send {
@@ -147,6 +211,9 @@ pub trait ErasedConnector: Send {
         schema: Option<&ConnectionSchema>,
     ) -> Result<Option<ConnectionSchema>, serde_json::Error>;
 
+    /// Checks the fields of a sink, given the operator config of its connection
+    fn check_sink_fields(&self, config: &str, fields: &Fields) -> anyhow::Result<()>;
+
     /// Returns a map of autocomplete values from key names (with paths separated by dots) to values that should
     /// be used to autocomplete them.
     #[allow(unused)]
@@ -233,6 +300,11 @@ impl<C: Connector> ErasedConnector for C {
         Ok(self.get_schema(self.parse_config(config)?, self.parse_table(table)?, schema))
     }
 
+    fn check_sink_fields(&self, config: &str, fields: &Fields) -> anyhow::Result<()> {
+        let config: OperatorConfig = serde_json::from_str(config)?;
+        self.check_sink_fields(&self.parse_table(&config.table)?, fields)
+    }
+
     fn get_autocomplete(
         &self,
         profile: &Value,
@@ -280,7 +352,7 @@ impl<C: Connector> ErasedConnector for C {
                     let field = self
                         .metadata_defs()
                         .iter()
-                        .find(|f| f.name == key)
+                        .find(|f| f.matches(key))
                         .ok_or_else(|| {
                             anyhow!(
                                 "unknown metadata field '{}' for {} connector '{}'",
@@ -300,7 +372,7 @@ impl<C: Connector> ErasedConnector for C {
             }
         }
 
-        self.from_options(name, options, schema, profile)
+        check_connection(self, self.from_options(name, options, schema, profile)?)
     }
 
     fn from_config(
@@ -311,13 +383,15 @@ impl<C: Connector> ErasedConnector for C {
         table: &serde_json::Value,
         schema: Option<&ConnectionSchema>,
     ) -> anyhow::Result<Connection> {
-        self.from_config(
+        let connection = self.from_config(
             id,
             name,
             self.parse_config(config)?,
             self.parse_table(table)?,
             schema,
-        )
+        )?;
+
+        check_connection(self, connection)
     }
 
     fn make_operator(&self, config: OperatorConfig) -> anyhow::Result<OperatorNode> {
@@ -1,5 +1,8 @@
 use crate::{server_for_hash_array, RateLimiter};
-use arrow::array::{make_builder, Array, ArrayBuilder, PrimitiveArray, RecordBatch};
+use arrow::array::{
+    make_builder, Array, ArrayBuilder, BinaryBuilder, PrimitiveArray, RecordBatch, StringBuilder,
+    TimestampNanosecondBuilder,
+};
 use arrow::compute::{partition, sort_to_indices, take};
 use arrow::datatypes::{SchemaRef, UInt64Type};
 use arroyo_formats::de::{ArrowDeserializer, FieldValueType};
@@ -14,7 +17,8 @@ use arroyo_rpc::{get_hasher, CompactionResult, ControlMessage, ControlResp};
 use arroyo_state::tables::table_manager::TableManager;
 use arroyo_state::{BackingStore, StateBackend};
 use arroyo_types::{
-    from_micros, ArrowMessage, CheckpointBarrier, SourceError, TaskInfo, UserError, Watermark,
+    from_micros, to_nanos, ArrowMessage, BadRecord, CheckpointBarrier, SourceError, TaskInfo,
+    UserError, Watermark,
 };
 use datafusion::common::hash_utils;
 use rand::Rng;
@@ -255,6 +259,8 @@ pub struct ArrowContext {
     buffered_error: Option<UserError>,
     error_rate_limiter: RateLimiter,
     deserializer: Option<ArrowDeserializer>,
+    /// records that failed to deserialize and are waiting to be sent to the dead-letter table
+    dead_letters: Vec<(String, Option<Box<BadRecord>>)>,
     pub table_manager: TableManager,
 }
 
@@ -284,6 +290,7 @@ pub struct ArrowCollector {
     out_schema: Option<ArroyoSchema>,
     projection: Option<Vec<usize>>,
     out_qs: Vec<Vec<BatchSender>>,
+    side_output_qs: Vec<Vec<BatchSender>>,
     tx_queue_rem_gauges: QueueGauges,
     tx_queue_size_gauges: QueueGauges,
     tx_queue_bytes_gauges: QueueGauges,
@@ -402,8 +409,21 @@ impl ArrowCollector {
         }
     }
 
+    /// Sends records to the operator's side-output sinks, like the dead-letter table for records
+    /// that failed to deserialize or the late-records table for records behind the watermark
+    pub async fn collect_side_output(&mut self, record: RecordBatch) {
+        for out_q in &self.side_output_qs {
+            for (partition, batch) in repartition(&record, &None, out_q.len()) {
+                out_q[partition]
+                    .send(ArrowMessage::Data(batch))
+                    .await
+                    .unwrap();
+            }
+        }
+    }
+
     pub async fn broadcast(&mut self, message: ArrowMessage) {
-        for out_node in &self.out_qs {
+        for out_node in self.out_qs.iter().chain(&self.side_output_qs) {
             for q in out_node {
                 q.send(message.clone()).await.unwrap_or_else(|e| {
                     panic!(
@@ -506,6 +526,7 @@ impl ArrowContext {
             collector: ArrowCollector {
                 task_info: task_info.clone(),
                 out_qs,
+                side_output_qs: vec![],
                 tx_queue_rem_gauges,
                 tx_queue_size_gauges,
                 tx_queue_bytes_gauges,
@@ -519,11 +540,41 @@ impl ArrowContext {
             buffer: None,
             error_rate_limiter: RateLimiter::new(),
             deserializer: None,
+            dead_letters: vec![],
             buffered_error: None,
             table_manager,
         }
     }
 
+    /// Sets the queues of the sinks for the operator's side output: records which fail to
+    /// deserialize for sources configured to dead-letter bad data, or late records for windows
+    pub fn set_side_output_qs(&mut self, side_output_qs: Vec<Vec<BatchSender>>) {
+        self.collector.side_output_qs = side_output_qs;
+    }
+
+    /// Counts records that arrived too late to be included in the operator's results, and sends
+    /// them to the late-records table if there is one
+    pub async fn drop_late_records(&mut self, batch: RecordBatch) {
+        if batch.num_rows() == 0 {
+            return;
+        }
+        TaskCounters::LateRecordsDropped
+            .for_task(&self.task_info, |c| c.inc_by(batch.num_rows() as u64));
+        if self.collector.side_output_qs.is_empty() {
+            return;
+        }
+        // late records are sent without the keys that were computed for the window
+        let indices: Vec<_> = match &self.in_schemas[0].key_indices {
+            Some(keys) => (0..batch.num_columns())
+                .filter(|i| !keys.contains(i))
+                .collect(),
+            None => (0..batch.num_columns()).collect(),
+        };
+        self.collector
+            .collect_side_output(batch.project(&indices).unwrap())
+            .await;
+    }
+
     pub fn watermark(&self) -> Option<Watermark> {
         self.watermarks.watermark()
     }
@@ -559,6 +610,11 @@ impl ArrowContext {
             }
         }
 
+        if !self.dead_letters.is_empty() {
+            let batch = dead_letter_batch(std::mem::take(&mut self.dead_letters));
+            self.collector.collect_side_output(batch).await;
+        }
+
         if let Some(error) = self.buffered_error.take() {
             return Err(error);
         }
@@ -672,6 +728,37 @@ impl ArrowContext {
         time: SystemTime,
         additional_fields: Option<&HashMap<&String, FieldValueType<'_>>>,
     ) -> Result<(), UserError> {
+        let errors = self.deserialize(msg, time, additional_fields).await;
+        self.collect_source_errors(errors).await
+    }
+
+    /// Deserializes a message read from a partition of a topic, like a Kafka record. Dead letters
+    /// for it always have the topic, partition and offset in their metadata, whether or not the
+    /// table declares metadata fields for them.
+    pub async fn deserialize_slice_from_partition(
+        &mut self,
+        msg: &[u8],
+        time: SystemTime,
+        additional_fields: Option<&HashMap<&String, FieldValueType<'_>>>,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+    ) -> Result<(), UserError> {
+        let errors = self
+            .deserialize(msg, time, additional_fields)
+            .await
+            .into_iter()
+            .map(|error| with_partition_metadata(error, topic, partition, offset))
+            .collect();
+        self.collect_source_errors(errors).await
+    }
+
+    async fn deserialize(
+        &mut self,
+        msg: &[u8],
+        time: SystemTime,
+        additional_fields: Option<&HashMap<&String, FieldValueType<'_>>>,
+    ) -> Vec<SourceError> {
         let deserializer = self
             .deserializer
             .as_mut()
@@ -684,22 +771,23 @@ impl ArrowContext {
                 .map(|t| ContextBuffer::new(t.schema.clone()));
         }
 
-        let errors = deserializer
+        deserializer
             .deserialize_slice(
                 &mut self.buffer.as_mut().expect("no out schema").buffer,
                 msg,
                 time,
                 additional_fields,
             )
-            .await;
-        self.collect_source_errors(errors).await?;
-
-        Ok(())
+            .await
     }
 
     /// Handling errors and rate limiting error reporting.
-    /// Considers the `bad_data` option to determine whether to drop or fail on bad data.
-    async fn collect_source_errors(&mut self, errors: Vec<SourceError>) -> Result<(), UserError> {
+    /// Considers the `bad_data` option to determine whether to drop, fail, or send bad data to
+    /// the dead-letter table.
+    pub async fn collect_source_errors(
+        &mut self,
+        errors: Vec<SourceError>,
+    ) -> Result<(), UserError> {
         let bad_data = self
             .deserializer
             .as_ref()
@@ -707,7 +795,7 @@ impl ArrowContext {
             .bad_data();
         for error in errors {
             match error {
-                SourceError::BadData { details } => match bad_data {
+                SourceError::BadData { details, record } => match bad_data {
                     BadData::Drop {} => {
                         self.error_rate_limiter
                             .rate_limit(|| async {
@@ -728,6 +816,15 @@ impl ArrowContext {
                     BadData::Fail {} => {
                         return Err(UserError::new("Deserialization error", details));
                     }
+                    BadData::DeadLetter { table } => {
+                        self.error_rate_limiter
+                            .rate_limit(|| async {
+                                warn!("Sending invalid data to '{}': {}", table, details);
+                            })
+                            .await;
+                        TaskCounters::DeadLetterRecords.for_task(&self.task_info, |c| c.inc());
+                        self.dead_letters.push((details, record));
+                    }
                 },
                 SourceError::Other { name, details } => {
                     return Err(UserError::new(name, details));
@@ -739,10 +836,75 @@ impl ArrowContext {
     }
 }
 
+/// Adds the topic, partition and offset a message was read from to the metadata of its dead letter
+fn with_partition_metadata(
+    error: SourceError,
+    topic: &str,
+    partition: i32,
+    offset: i64,
+) -> SourceError {
+    match error {
+        SourceError::BadData {
+            details,
+            record: Some(mut record),
+        } => {
+            let mut metadata = match record.metadata.as_deref().map(serde_json::from_str) {
+                Some(Ok(serde_json::Value::Object(fields))) => fields,
+                _ => serde_json::Map::new(),
+            };
+            metadata.insert("topic".to_string(), topic.into());
+            metadata.insert("partition".to_string(), partition.into());
+            metadata.insert("offset".to_string(), offset.into());
+            record.metadata = Some(serde_json::Value::Object(metadata).to_string());
+
+            SourceError::BadData {
+                details,
+                record: Some(record),
+            }
+        }
+        error => error,
+    }
+}
+
+fn dead_letter_batch(dead_letters: Vec<(String, Option<Box<BadRecord>>)>) -> RecordBatch {
+    let mut error = StringBuilder::new();
+    let mut value = BinaryBuilder::new();
+    let mut metadata = StringBuilder::new();
+    let mut timestamp = TimestampNanosecondBuilder::new();
+
+    for (details, record) in dead_letters {
+        error.append_value(details);
+        match record {
+            Some(record) => {
+                value.append_value(&record.data);
+                metadata.append_option(record.metadata);
+                timestamp.append_value(to_nanos(record.timestamp) as i64);
+            }
+            None => {
+                // the error applied to a whole batch, so there's no input to keep
+                value.append_null();
+                metadata.append_null();
+                timestamp.append_value(to_nanos(SystemTime::now()) as i64);
+            }
+        }
+    }
+
+    RecordBatch::try_new(
+        BadData::dead_letter_schema().schema,
+        vec![
+            Arc::new(error.finish()),
+            Arc::new(value.finish()),
+            Arc::new(metadata.finish()),
+            Arc::new(timestamp.finish()),
+        ],
+    )
+    .unwrap()
+}
+
 #[cfg(test)]
 mod tests {
-    use arrow::array::{ArrayRef, Int64Array, TimestampNanosecondArray, UInt64Array};
-    use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use arrow::array::{ArrayRef, AsArray, Int64Array, TimestampNanosecondArray, UInt64Array};
+    use arrow::datatypes::{DataType, Field, Schema, TimeUnit, TimestampNanosecondType};
     use arroyo_types::to_nanos;
     use std::time::Duration;
 
@@ -775,6 +937,70 @@ mod tests {
         assert_eq!(w.watermark(), Some(Watermark::Idle));
     }
 
+    #[test]
+    fn test_dead_letter_batch() {
+        let t = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        let batch = dead_letter_batch(vec![
+            (
+                "invalid JSON".to_string(),
+                Some(Box::new(BadRecord {
+                    data: b"{ not json".to_vec(),
+                    metadata: Some(r#"{"offset":5}"#.to_string()),
+                    timestamp: t,
+                })),
+            ),
+            ("data does not match schema".to_string(), None),
+        ]);
+
+        assert_eq!(batch.schema(), BadData::dead_letter_schema().schema);
+        assert_eq!(batch.num_rows(), 2);
+
+        let errors = batch.column(0).as_string::<i32>();
+        assert_eq!(errors.value(0), "invalid JSON");
+        assert_eq!(errors.value(1), "data does not match schema");
+
+        let values = batch.column(1).as_binary::<i32>();
+        assert_eq!(values.value(0), b"{ not json");
+        assert!(values.is_null(1));
+
+        let metadata = batch.column(2).as_string::<i32>();
+        assert_eq!(metadata.value(0), r#"{"offset":5}"#);
+        assert!(metadata.is_null(1));
+
+        let timestamps = batch.column(3).as_primitive::<TimestampNanosecondType>();
+        assert_eq!(timestamps.value(0), to_nanos(t) as i64);
+    }
+
+    #[test]
+    fn test_with_partition_metadata() {
+        let t = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        let error = SourceError::bad_data("invalid JSON").with_record(|| BadRecord {
+            data: b"{ not json".to_vec(),
+            metadata: Some(r#"{"p":3}"#.to_string()),
+            timestamp: t,
+        });
+
+        let SourceError::BadData {
+            record: Some(record),
+            ..
+        } = with_partition_metadata(error, "cars", 3, 5)
+        else {
+            panic!("expected a bad data error with a record");
+        };
+        let metadata: serde_json::Value =
+            serde_json::from_str(record.metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(
+            metadata,
+            serde_json::json!({ "p": 3, "topic": "cars", "partition": 3, "offset": 5 })
+        );
+
+        // errors without a record have nothing to add to
+        assert_eq!(
+            with_partition_metadata(SourceError::bad_data("bad batch"), "cars", 3, 5),
+            SourceError::bad_data("bad batch")
+        );
+    }
+
     #[tokio::test]
     async fn test_shuffles() {
         let timestamp = SystemTime::now();
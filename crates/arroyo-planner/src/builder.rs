@@ -3,10 +3,14 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
-use arrow::datatypes::IntervalMonthDayNanoType;
+use arrow::datatypes::{IntervalMonthDayNanoType, Schema};
 
-use arroyo_datastream::logical::{LogicalEdge, LogicalGraph, LogicalNode};
+use arroyo_datastream::logical::{
+    LogicalEdge, LogicalEdgeType, LogicalGraph, LogicalNode, OperatorName,
+};
+use arroyo_rpc::api_types::connections::ConnectionType;
 use arroyo_rpc::df::{ArroyoSchema, ArroyoSchemaRef};
+use arroyo_rpc::formats::BadData;
 
 use async_trait::async_trait;
 use datafusion::common::tree_node::{TreeNode, TreeNodeRecursion, TreeNodeVisitor};
@@ -22,6 +26,8 @@ use datafusion::physical_plan::ExecutionPlan;
 use datafusion::physical_planner::{DefaultPhysicalPlanner, ExtensionPlanner, PhysicalPlanner};
 use datafusion_proto::protobuf::{PhysicalExprNode, PhysicalPlanNode};
 use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::Direction;
+use prost::Message;
 use tokio::runtime::Builder;
 use tokio::sync::oneshot;
 
@@ -35,7 +41,8 @@ use crate::physical::{
     ToDebeziumExec,
 };
 use crate::schemas::add_timestamp_field_arrow;
-use crate::ArroyoSchemaProvider;
+use crate::tables::Table;
+use crate::{ArroyoSchemaProvider, SqlConfig};
 use datafusion_proto::physical_plan::to_proto::serialize_physical_expr;
 use datafusion_proto::physical_plan::DefaultPhysicalExtensionCodec;
 use datafusion_proto::{
@@ -54,13 +61,21 @@ pub(crate) struct PlanToGraphVisitor<'a> {
 }
 
 impl<'a> PlanToGraphVisitor<'a> {
-    pub fn new(schema_provider: &'a ArroyoSchemaProvider, session_state: &'a SessionState) -> Self {
+    pub fn new(
+        schema_provider: &'a ArroyoSchemaProvider,
+        session_state: &'a SessionState,
+        sql_config: &SqlConfig,
+    ) -> Self {
         Self {
             graph: Default::default(),
             output_schemas: Default::default(),
             named_nodes: Default::default(),
             traversal: vec![],
-            planner: Planner::new(schema_provider, session_state),
+            planner: Planner::new(
+                schema_provider,
+                session_state,
+                sql_config.default_parallelism,
+            ),
         }
     }
 }
@@ -69,12 +84,14 @@ pub(crate) struct Planner<'a> {
     schema_provider: &'a ArroyoSchemaProvider,
     planner: DefaultPhysicalPlanner,
     session_state: &'a SessionState,
+    default_parallelism: usize,
 }
 
 impl<'a> Planner<'a> {
     pub(crate) fn new(
         schema_provider: &'a ArroyoSchemaProvider,
         session_state: &'a SessionState,
+        default_parallelism: usize,
     ) -> Self {
         let planner = DefaultPhysicalPlanner::with_extension_planners(vec![Arc::new(
             ArroyoExtensionPlanner {},
@@ -83,9 +100,15 @@ impl<'a> Planner<'a> {
             schema_provider,
             planner,
             session_state,
+            default_parallelism,
         }
     }
 
+    /// The parallelism of operators that can be split across subtasks by key
+    pub(crate) fn default_parallelism(&self) -> usize {
+        self.default_parallelism
+    }
+
     pub(crate) fn sync_plan(&self, plan: &LogicalPlan) -> Result<Arc<dyn ExecutionPlan>> {
         let fut = self.planner.create_physical_plan(plan, self.session_state);
         let (tx, mut rx) = oneshot::channel();
@@ -276,6 +299,174 @@ impl<'a> PlanToGraphVisitor<'a> {
         Ok(())
     }
 
+    /// Connects each source that sends bad data to a dead-letter table to a sink for that table.
+    /// Sources that share a dead-letter table share a sink.
+    pub(crate) fn add_dead_letter_sinks(&mut self) -> Result<()> {
+        let schema_provider = self.planner.schema_provider;
+        let mut sources: Vec<_> = self
+            .named_nodes
+            .iter()
+            .filter_map(|(name, index)| match name {
+                NamedNode::Source(name) => Some((name.clone(), *index)),
+                _ => None,
+            })
+            .collect();
+        sources.sort_by_key(|(_, index)| *index);
+
+        let mut sinks: HashMap<String, NodeIndex> = HashMap::new();
+        for (name, source_index) in sources {
+            let Some(Table::ConnectorTable(source)) = schema_provider.get_table(name.table())
+            else {
+                continue;
+            };
+            let Some(BadData::DeadLetter { table }) = &source.bad_data else {
+                continue;
+            };
+
+            let sink_index = match sinks.get(table) {
+                Some(index) => *index,
+                None => {
+                    let index = self.add_side_output_sink(
+                        table,
+                        &format!("dead-letter table '{}' for source '{}'", table, name),
+                    )?;
+                    check_dead_letter_columns(schema_provider, table)?;
+                    sinks.insert(table.clone(), index);
+                    index
+                }
+            };
+
+            self.graph.add_edge(
+                source_index,
+                sink_index,
+                LogicalEdge::project_all(
+                    LogicalEdgeType::DeadLetter,
+                    BadData::dead_letter_schema(),
+                ),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Connects every window aggregate to a sink for the late-records table, if one is set, so
+    /// that records which arrive after their windows have closed are written there instead of
+    /// being dropped.
+    pub(crate) fn add_late_record_sinks(&mut self) -> Result<()> {
+        let Some(table) = self
+            .planner
+            .schema_provider
+            .planning_options
+            .late_records_table
+            .clone()
+        else {
+            return Ok(());
+        };
+
+        let windows: Vec<_> = self
+            .graph
+            .node_indices()
+            .filter(|index| {
+                matches!(
+                    self.graph[*index].operator_name,
+                    OperatorName::TumblingWindowAggregate
+                        | OperatorName::SlidingWindowAggregate
+                        | OperatorName::CumulativeWindowAggregate
+                        | OperatorName::SessionWindowAggregate
+                )
+            })
+            .collect();
+        if windows.is_empty() {
+            return plan_err!(
+                "late-records table '{}' is set, but the query has no windowed aggregates",
+                table
+            );
+        }
+
+        let mut late_schema: Option<ArroyoSchema> = None;
+        let mut edges = vec![];
+        for window_index in windows {
+            let Some(input_edge) = self
+                .graph
+                .edges_directed(window_index, Direction::Incoming)
+                .next()
+            else {
+                return plan_err!("window aggregate should have an input");
+            };
+            // late records are the window's input, without the keys it computed
+            let input_schema = &input_edge.weight().schema;
+            let key_indices = input_schema.key_indices.clone().unwrap_or_default();
+            let (indices, fields): (Vec<_>, Vec<_>) = input_schema
+                .schema
+                .fields()
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !key_indices.contains(i))
+                .map(|(i, field)| (i, field.clone()))
+                .unzip();
+            let Some(timestamp_index) = indices
+                .iter()
+                .position(|i| *i == input_schema.timestamp_index)
+            else {
+                return plan_err!("window aggregate input is missing its timestamp");
+            };
+            let schema = ArroyoSchema::new_unkeyed(
+                Arc::new(Schema::new_with_metadata(
+                    fields,
+                    input_schema.schema.metadata().clone(),
+                )),
+                timestamp_index,
+            );
+
+            match &late_schema {
+                Some(late_schema) if late_schema.schema.fields() != schema.schema.fields() => {
+                    return plan_err!(
+                        "all windowed aggregates sending late records to '{}' must have the same \
+                        input columns",
+                        table
+                    );
+                }
+                Some(_) => {}
+                None => late_schema = Some(schema.clone()),
+            }
+            edges.push((window_index, schema));
+        }
+
+        let sink_index =
+            self.add_side_output_sink(&table, &format!("late-records table '{}'", table))?;
+        for (window_index, schema) in edges {
+            self.graph.add_edge(
+                window_index,
+                sink_index,
+                LogicalEdge::project_all(LogicalEdgeType::LateRecords, schema),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Adds a sink for a table that receives a side output of the query, described in errors
+    /// by `description`
+    fn add_side_output_sink(&mut self, table: &str, description: &str) -> Result<NodeIndex> {
+        let schema_provider = self.planner.schema_provider;
+        let Some(sink @ Table::ConnectorTable(sink_table)) = schema_provider.get_table(table)
+        else {
+            return plan_err!("{} is not a connection table", description);
+        };
+        if sink_table.connection_type != ConnectionType::Sink {
+            return plan_err!("{} must be a sink", description);
+        }
+
+        let connector_op = sink.connector_op()?;
+        Ok(self.graph.add_node(LogicalNode {
+            operator_id: format!("sink_{}_{}", table, self.graph.node_count()),
+            description: connector_op.description.clone(),
+            operator_name: OperatorName::ConnectorSink,
+            parallelism: 1,
+            operator_config: connector_op.encode_to_vec(),
+        }))
+    }
+
     pub fn into_graph(self) -> LogicalGraph {
         self.graph
     }
@@ -327,6 +518,34 @@ impl<'a> PlanToGraphVisitor<'a> {
     }
 }
 
+/// Checks that a dead-letter table declares the columns of the records written to it, so that a
+/// mismatch fails when the query is planned rather than when the first bad record arrives
+fn check_dead_letter_columns(schema_provider: &ArroyoSchemaProvider, table: &str) -> Result<()> {
+    let Some(Table::ConnectorTable(sink_table)) = schema_provider.get_table(table) else {
+        return plan_err!("dead-letter table '{}' is not a connection table", table);
+    };
+
+    let columns = |schema: &Schema| {
+        schema
+            .fields()
+            .iter()
+            .map(|f| format!("{} {}", f.name(), f.data_type()))
+            .collect::<Vec<_>>()
+    };
+    let expected = columns(&BadData::dead_letter_schema().schema_without_timestamp());
+    let declared = columns(&sink_table.physical_schema());
+    if declared != expected {
+        return plan_err!(
+            "dead-letter table '{}' must have the columns ({}), but has ({})",
+            table,
+            expected.join(", "),
+            declared.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
 impl<'a> TreeNodeVisitor<'_> for PlanToGraphVisitor<'a> {
     type Node = LogicalPlan;
 
@@ -9,25 +9,32 @@ use arroyo_datastream::{
 use arroyo_rpc::{
     df::{ArroyoSchema, ArroyoSchemaRef},
     grpc::api::{
-        SessionWindowAggregateOperator, SlidingWindowAggregateOperator,
-        TumblingWindowAggregateOperator,
+        CumulativeWindowAggregateOperator, SessionWindowAggregateOperator,
+        SlidingWindowAggregateOperator, TumblingWindowAggregateOperator,
     },
-    TIMESTAMP_FIELD,
+    updating_meta_field, TIMESTAMP_FIELD, UPDATING_META_FIELD,
 };
+use arroyo_types::sliding_window_pane;
 use datafusion::common::{
     internal_err, plan_err, Column, DFSchema, DFSchemaRef, Result, ScalarValue,
 };
 use datafusion::error::DataFusionError;
+use datafusion::functions::datetime::date_bin;
 use datafusion::logical_expr;
 use datafusion::logical_expr::{
-    expr::ScalarFunction, Aggregate, BinaryExpr, Expr, Extension, LogicalPlan,
+    expr::ScalarFunction, lit, Aggregate, BinaryExpr, Expr, Extension, LogicalPlan,
     UserDefinedLogicalNodeCore,
 };
+use datafusion::prelude::named_struct;
 use datafusion_proto::physical_plan::to_proto::serialize_physical_expr;
 use datafusion_proto::physical_plan::DefaultPhysicalExtensionCodec;
-use datafusion_proto::{physical_plan::AsExecutionPlan, protobuf::PhysicalPlanNode};
+use datafusion_proto::{
+    physical_plan::AsExecutionPlan,
+    protobuf::{physical_plan_node::PhysicalPlanType, PhysicalPlanNode},
+};
 use prost::Message;
 
+use crate::functions::multi_hash;
 use crate::physical::window_scalar_function;
 use crate::{
     builder::{NamedNode, Planner, SplitPlanOutput},
@@ -40,12 +47,27 @@ use super::{ArroyoExtension, NodeWithIncomingEdges, TimestampAppendExtension};
 
 pub(crate) const AGGREGATE_EXTENSION_NAME: &str = "AggregateExtension";
 
+/// Firings of a tumbling or sliding window in addition to the one when the watermark passes its end.
+/// Windows that fire more than once produce updating output, retracting their earlier results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub(crate) struct WindowTrigger {
+    pub(crate) early_fire: Option<Duration>,
+    pub(crate) allowed_lateness: Duration,
+}
+
+impl WindowTrigger {
+    pub(crate) fn is_updating(&self) -> bool {
+        self.early_fire.is_some() || !self.allowed_lateness.is_zero()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct AggregateExtension {
     pub(crate) window_behavior: WindowBehavior,
     pub(crate) aggregate: LogicalPlan,
     pub(crate) schema: DFSchemaRef,
     pub(crate) key_fields: Vec<usize>,
+    pub(crate) trigger: WindowTrigger,
     pub(crate) final_calculation: LogicalPlan,
 }
 
@@ -54,15 +76,17 @@ impl AggregateExtension {
         window_behavior: WindowBehavior,
         aggregate: LogicalPlan,
         key_fields: Vec<usize>,
+        trigger: WindowTrigger,
     ) -> Self {
         let final_calculation =
-            Self::final_projection(&aggregate, window_behavior.clone()).unwrap();
+            Self::final_projection(&aggregate, window_behavior.clone(), trigger).unwrap();
 
         Self {
             window_behavior,
             aggregate,
             schema: final_calculation.schema().clone(),
             key_fields,
+            trigger,
             final_calculation,
         }
     }
@@ -102,6 +126,12 @@ impl AggregateExtension {
             partial_aggregation_plan: partial_aggregation_plan.encode_to_vec(),
             final_aggregation_plan: finish_plan.encode_to_vec(),
             final_projection: Some(final_physical_plan_node.encode_to_vec()),
+            early_fire_micros: self
+                .trigger
+                .early_fire
+                .map(|interval| interval.as_micros() as u64)
+                .unwrap_or_default(),
+            allowed_lateness_micros: self.trigger.allowed_lateness.as_micros() as u64,
         };
 
         Ok(LogicalNode {
@@ -121,7 +151,9 @@ impl AggregateExtension {
         width: Duration,
         slide: Duration,
     ) -> Result<LogicalNode> {
-        let binning_function_proto = planner.binning_function_proto(slide, input_schema.clone())?;
+        // rows are pre-aggregated into panes that evenly divide both the width and the slide
+        let binning_function_proto = planner
+            .binning_function_proto(sliding_window_pane(width, slide), input_schema.clone())?;
 
         let SplitPlanOutput {
             partial_aggregation_plan,
@@ -151,7 +183,12 @@ impl AggregateExtension {
             partial_aggregation_plan: partial_aggregation_plan.encode_to_vec(),
             final_aggregation_plan: finish_plan.encode_to_vec(),
             final_projection: final_physical_plan_node.encode_to_vec(),
-            // TODO add final aggregation.
+            early_fire_micros: self
+                .trigger
+                .early_fire
+                .map(|interval| interval.as_micros() as u64)
+                .unwrap_or_default(),
+            allowed_lateness_micros: self.trigger.allowed_lateness.as_micros() as u64,
         };
         Ok(LogicalNode {
             operator_id: format!("sliding_window_{}", index),
@@ -162,6 +199,65 @@ impl AggregateExtension {
         })
     }
 
+    pub fn cumulative_window_config(
+        &self,
+        planner: &Planner,
+        index: usize,
+        input_schema: DFSchemaRef,
+        step: Duration,
+        max_size: Duration,
+    ) -> Result<LogicalNode> {
+        let binning_function_proto = planner.binning_function_proto(step, input_schema.clone())?;
+
+        let SplitPlanOutput {
+            partial_aggregation_plan,
+            partial_schema,
+            finish_plan,
+        } = planner.split_physical_plan(self.key_fields.clone(), &self.aggregate, true)?;
+
+        let Some(PhysicalPlanType::Aggregate(aggregate)) = finish_plan.physical_plan_type.as_ref()
+        else {
+            return plan_err!("expect finish plan to be an aggregate");
+        };
+        let mut combine_aggregate = aggregate.as_ref().clone();
+        combine_aggregate.set_mode(datafusion_proto::protobuf::AggregateMode::CombinePartial);
+        let combine_plan = PhysicalPlanNode {
+            physical_plan_type: Some(PhysicalPlanType::Aggregate(Box::new(combine_aggregate))),
+        };
+
+        let final_physical_plan = planner.sync_plan(&self.final_calculation)?;
+        let final_physical_plan_node = PhysicalPlanNode::try_from_physical_plan(
+            final_physical_plan,
+            &ArroyoPhysicalExtensionCodec::default(),
+        )?;
+
+        let config = CumulativeWindowAggregateOperator {
+            name: format!("CumulativeWindow<{:?}>", max_size),
+            step_micros: step.as_micros() as u64,
+            max_size_micros: max_size.as_micros() as u64,
+            binning_function: binning_function_proto.encode_to_vec(),
+            input_schema: Some(
+                ArroyoSchema::from_schema_keys(
+                    Arc::new(input_schema.as_ref().into()),
+                    self.key_fields.clone(),
+                )?
+                .into(),
+            ),
+            partial_schema: Some(partial_schema.into()),
+            partial_aggregation_plan: partial_aggregation_plan.encode_to_vec(),
+            final_aggregation_plan: finish_plan.encode_to_vec(),
+            final_projection: final_physical_plan_node.encode_to_vec(),
+            combine_plan: combine_plan.encode_to_vec(),
+        };
+        Ok(LogicalNode {
+            operator_id: format!("cumulative_window_{}", index),
+            description: "cumulative window".to_string(),
+            operator_name: OperatorName::CumulativeWindowAggregate,
+            operator_config: config.encode_to_vec(),
+            parallelism: 1,
+        })
+    }
+
     pub fn session_window_config(
         &self,
         planner: &Planner,
@@ -270,6 +366,8 @@ impl AggregateExtension {
             partial_aggregation_plan: partial_aggregation_plan.encode_to_vec(),
             final_aggregation_plan: finish_plan.encode_to_vec(),
             final_projection,
+            early_fire_micros: 0,
+            allowed_lateness_micros: 0,
         };
 
         Ok(LogicalNode {
@@ -285,6 +383,7 @@ impl AggregateExtension {
     pub fn final_projection(
         aggregate_plan: &LogicalPlan,
         window_behavior: WindowBehavior,
+        trigger: WindowTrigger,
     ) -> Result<LogicalPlan> {
         let timestamp_field: DFField = aggregate_plan.inputs()[0]
             .schema()
@@ -301,6 +400,8 @@ impl AggregateExtension {
             .iter()
             .map(|field| Expr::Column(field.qualified_column()))
             .collect();
+        // cumulative windows all start at the beginning of their period
+        let mut period = None;
         let (window_field, window_index, width, is_nested) = match window_behavior {
             WindowBehavior::InData => return Ok(timestamp_append),
             WindowBehavior::FromOperator {
@@ -312,6 +413,11 @@ impl AggregateExtension {
                 WindowType::Tumbling { width, .. } | WindowType::Sliding { width, .. } => {
                     (window_field, window_index, width, is_nested)
                 }
+                // the operator sets _timestamp to the start of the last step of the window
+                WindowType::Cumulative { step, max_size } => {
+                    period = Some(max_size);
+                    (window_field, window_index, step, is_nested)
+                }
                 WindowType::Session { .. } => {
                     return Ok(LogicalPlan::Extension(Extension {
                         node: Arc::new(WindowAppendExtension::new(
@@ -330,6 +436,7 @@ impl AggregateExtension {
                 window_field,
                 window_index,
                 width,
+                period,
             );
         }
         let timestamp_column =
@@ -339,7 +446,10 @@ impl AggregateExtension {
             func: Arc::new(window_scalar_function()),
             args: vec![
                 // copy bin_start as first argument
-                Expr::Column(timestamp_column.clone()),
+                match period {
+                    Some(period) => Self::period_start(period, timestamp_column.clone()),
+                    None => Expr::Column(timestamp_column.clone()),
+                },
                 // add width interval to _timestamp for bin end
                 Expr::BinaryExpr(BinaryExpr {
                     left: Box::new(Expr::Column(timestamp_column.clone())),
@@ -364,6 +474,27 @@ impl AggregateExtension {
             )))),
         });
         aggregate_expressions.push(bin_end_calculation);
+        if trigger.is_updating() {
+            // each firing of a window retracts the previous results for the same key and window
+            let LogicalPlan::Aggregate(Aggregate { group_expr, .. }) = aggregate_plan else {
+                return internal_err!("expected an aggregate plan, found {}", aggregate_plan);
+            };
+            let mut id_args: Vec<_> = fields_with_qualifiers(aggregate_plan.schema())
+                .iter()
+                .take(group_expr.len())
+                .map(|field| Expr::Column(field.qualified_column()))
+                .collect();
+            id_args.push(Expr::Column(timestamp_column));
+            let id = Expr::ScalarFunction(ScalarFunction {
+                func: multi_hash(),
+                args: id_args,
+            });
+            aggregate_expressions.push(
+                named_struct(vec![lit("is_retract"), lit(false), lit("id"), id])
+                    .alias(UPDATING_META_FIELD),
+            );
+            aggregate_fields.push((None, updating_meta_field()).into());
+        }
         Ok(LogicalPlan::Projection(
             logical_expr::Projection::try_new_with_schema(
                 aggregate_expressions,
@@ -378,6 +509,7 @@ impl AggregateExtension {
         window_field: DFField,
         window_index: usize,
         width: Duration,
+        period: Option<Duration>,
     ) -> Result<LogicalPlan> {
         let timestamp_field: DFField = aggregate_plan
             .schema()
@@ -397,13 +529,16 @@ impl AggregateExtension {
             func: Arc::new(window_scalar_function()),
             args: vec![
                 // calculate the start of the bin
-                Expr::BinaryExpr(BinaryExpr {
-                    left: Box::new(Expr::Column(timestamp_column.clone())),
-                    op: logical_expr::Operator::Minus,
-                    right: Box::new(Expr::Literal(ScalarValue::IntervalMonthDayNano(Some(
-                        IntervalMonthDayNanoType::make_value(0, 0, width.as_nanos() as i64 - 1),
-                    )))),
-                }),
+                match period {
+                    Some(period) => Self::period_start(period, timestamp_column.clone()),
+                    None => Expr::BinaryExpr(BinaryExpr {
+                        left: Box::new(Expr::Column(timestamp_column.clone())),
+                        op: logical_expr::Operator::Minus,
+                        right: Box::new(Expr::Literal(ScalarValue::IntervalMonthDayNano(Some(
+                            IntervalMonthDayNanoType::make_value(0, 0, width.as_nanos() as i64 - 1),
+                        )))),
+                    }),
+                },
                 // add 1 nanosecond to the timestamp
                 Expr::BinaryExpr(BinaryExpr {
                     left: Box::new(Expr::Column(timestamp_column.clone())),
@@ -428,6 +563,16 @@ impl AggregateExtension {
             .unwrap(),
         ))
     }
+
+    // the start of the cumulative window period that the timestamp falls in
+    fn period_start(period: Duration, timestamp_column: Column) -> Expr {
+        date_bin().call(vec![
+            Expr::Literal(ScalarValue::IntervalMonthDayNano(Some(
+                IntervalMonthDayNanoType::make_value(0, 0, period.as_nanos() as i64),
+            ))),
+            Expr::Column(timestamp_column),
+        ])
+    }
 }
 
 impl UserDefinedLogicalNodeCore for AggregateExtension {
@@ -469,6 +614,7 @@ impl UserDefinedLogicalNodeCore for AggregateExtension {
             self.window_behavior.clone(),
             inputs[0].clone(),
             self.key_fields.clone(),
+            self.trigger,
         ))
     }
 }
@@ -511,6 +657,14 @@ impl ArroyoExtension for AggregateExtension {
                             *width,
                             *slide,
                         )?,
+                        WindowType::Cumulative { step, max_size } => self
+                            .cumulative_window_config(
+                                planner,
+                                index,
+                                input_df_schema,
+                                *step,
+                                *max_size,
+                            )?,
                         WindowType::Instant => {
                             return plan_err!(
                                 "instant window not supported in aggregate extension"
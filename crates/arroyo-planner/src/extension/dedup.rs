@@ -0,0 +1,142 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use arroyo_datastream::logical::{LogicalEdge, LogicalEdgeType, LogicalNode, OperatorName};
+use arroyo_rpc::{df::ArroyoSchema, grpc::api::DedupOperator, UPDATING_META_FIELD};
+use datafusion::common::{internal_err, plan_err, DFSchemaRef, Result};
+use datafusion::logical_expr::{Expr, LogicalPlan, UserDefinedLogicalNodeCore};
+use prost::Message;
+
+use crate::{fields_with_qualifiers, schema_from_df_fields_with_metadata, DFField};
+
+use super::{ArroyoExtension, NodeWithIncomingEdges};
+
+pub(crate) const DEDUP_EXTENSION_NAME: &str = "DedupExtension";
+
+/// Emits the first row to arrive for each key of an append-only input, dropping later rows with
+/// the same key until the key has not been seen for the TTL. The output has the `ROW_NUMBER()`
+/// column of the query this was planned from as its final field, which is always 1.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct DedupExtension {
+    input: LogicalPlan,
+    key_fields: Vec<usize>,
+    rank_field: DFField,
+    ttl: Duration,
+    schema: DFSchemaRef,
+}
+
+impl DedupExtension {
+    pub fn new(
+        input: LogicalPlan,
+        key_fields: Vec<usize>,
+        rank_field: DFField,
+        ttl: Duration,
+    ) -> Result<Self> {
+        let mut fields = fields_with_qualifiers(input.schema());
+        fields.push(rank_field.clone());
+        let schema = Arc::new(schema_from_df_fields_with_metadata(
+            &fields,
+            input.schema().metadata().clone(),
+        )?);
+
+        Ok(Self {
+            input,
+            key_fields,
+            rank_field,
+            ttl,
+            schema,
+        })
+    }
+}
+
+impl UserDefinedLogicalNodeCore for DedupExtension {
+    fn name(&self) -> &str {
+        DEDUP_EXTENSION_NAME
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Dedup: {}", self.schema())
+    }
+
+    fn with_exprs_and_inputs(&self, _exprs: Vec<Expr>, inputs: Vec<LogicalPlan>) -> Result<Self> {
+        if inputs.len() != 1 {
+            return internal_err!("input size inconsistent");
+        }
+
+        // the schema is recomputed as the input gains its timestamp during rewriting
+        Self::new(
+            inputs[0].clone(),
+            self.key_fields.clone(),
+            self.rank_field.clone(),
+            self.ttl,
+        )
+    }
+}
+
+impl ArroyoExtension for DedupExtension {
+    fn node_name(&self) -> Option<crate::builder::NamedNode> {
+        None
+    }
+
+    fn plan_node(
+        &self,
+        _planner: &crate::builder::Planner,
+        index: usize,
+        input_schemas: Vec<arroyo_rpc::df::ArroyoSchemaRef>,
+    ) -> Result<NodeWithIncomingEdges> {
+        if input_schemas.len() != 1 {
+            return plan_err!("DedupExtension requires exactly one input");
+        }
+        if input_schemas[0]
+            .schema
+            .column_with_name(UPDATING_META_FIELD)
+            .is_some()
+        {
+            return plan_err!(
+                "deduplication with ROW_NUMBER() or DISTINCT ON requires append-only input"
+            );
+        }
+        let input_schema = ArroyoSchema::from_schema_keys(
+            input_schemas[0].schema.clone(),
+            self.key_fields.clone(),
+        )?;
+
+        let config = DedupOperator {
+            name: "Dedup".to_string(),
+            input_schema: Some(input_schema.clone().into()),
+            output_schema: Some(self.output_schema().into()),
+            ttl_micros: self.ttl.as_micros() as u64,
+        };
+
+        let logical_node = LogicalNode {
+            operator_id: format!("dedup_{}", index),
+            description: "dedup".to_string(),
+            operator_name: OperatorName::Dedup,
+            operator_config: config.encode_to_vec(),
+            parallelism: 1,
+        };
+
+        let edge = LogicalEdge::project_all(LogicalEdgeType::Shuffle, input_schema);
+
+        Ok(NodeWithIncomingEdges {
+            node: logical_node,
+            edges: vec![edge],
+        })
+    }
+
+    fn output_schema(&self) -> ArroyoSchema {
+        ArroyoSchema::from_schema_unkeyed(Arc::new(self.schema().as_ref().clone().into())).unwrap()
+    }
+}
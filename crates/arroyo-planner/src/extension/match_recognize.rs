@@ -0,0 +1,245 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrow_schema::{DataType, TimeUnit};
+use arroyo_datastream::logical::{LogicalEdge, LogicalEdgeType, LogicalNode, OperatorName};
+use arroyo_rpc::grpc::api::{
+    MatchRecognizeMeasure, MatchRecognizeMeasureKind, MatchRecognizeOperator, MatchRecognizeState,
+    MatchRecognizeSymbol, MatchRecognizeTransition,
+};
+use arroyo_rpc::{df::ArroyoSchema, TIMESTAMP_FIELD, UPDATING_META_FIELD};
+use datafusion::common::{internal_err, plan_err, Column, DFSchemaRef, Result};
+use datafusion::logical_expr::{Expr, LogicalPlan, UserDefinedLogicalNodeCore};
+use datafusion_proto::physical_plan::to_proto::serialize_physical_expr;
+use datafusion_proto::physical_plan::DefaultPhysicalExtensionCodec;
+use prost::Message;
+
+use crate::{schema_from_df_fields, DFField};
+
+use super::{ArroyoExtension, NodeWithIncomingEdges};
+
+pub(crate) const MATCH_RECOGNIZE_EXTENSION_NAME: &str = "MatchRecognizeExtension";
+
+/// A pattern variable, along with its DEFINE condition over the current row
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct PatternSymbol {
+    pub name: String,
+    pub define: Option<Expr>,
+}
+
+/// A state of the NFA compiled from the pattern. Transitions consume a row that satisfies the
+/// symbol at the given index, while epsilon transitions move between states without one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub(crate) struct NfaState {
+    pub transitions: Vec<(usize, usize)>,
+    pub epsilon: Vec<usize>,
+    pub accepting: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct PatternMeasure {
+    pub name: String,
+    pub kind: MatchRecognizeMeasureKind,
+    pub symbol: Option<usize>,
+    pub column: Option<Column>,
+    pub data_type: DataType,
+}
+
+/// Finds matches of a row pattern over each partition of an append-only input, ordered by event
+/// time, emitting one row per match with the partition keys, the measures and the time the match
+/// completed. The input is keyed by the partition columns, which are its first fields.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct MatchRecognizeExtension {
+    input: LogicalPlan,
+    partition_fields: Vec<DFField>,
+    symbols: Vec<PatternSymbol>,
+    states: Vec<NfaState>,
+    measures: Vec<PatternMeasure>,
+    within: Duration,
+    schema: DFSchemaRef,
+}
+
+impl MatchRecognizeExtension {
+    pub fn new(
+        input: LogicalPlan,
+        partition_fields: Vec<DFField>,
+        symbols: Vec<PatternSymbol>,
+        states: Vec<NfaState>,
+        measures: Vec<PatternMeasure>,
+        within: Duration,
+    ) -> Result<Self> {
+        let mut fields = partition_fields.clone();
+        fields.extend(measures.iter().map(|measure| {
+            DFField::new_unqualified(
+                &measure.name,
+                measure.data_type.clone(),
+                measure.kind != MatchRecognizeMeasureKind::Count,
+            )
+        }));
+        fields.push(DFField::new_unqualified(
+            TIMESTAMP_FIELD,
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        ));
+        let schema = Arc::new(schema_from_df_fields(&fields)?);
+
+        Ok(Self {
+            input,
+            partition_fields,
+            symbols,
+            states,
+            measures,
+            within,
+            schema,
+        })
+    }
+}
+
+impl UserDefinedLogicalNodeCore for MatchRecognizeExtension {
+    fn name(&self) -> &str {
+        MATCH_RECOGNIZE_EXTENSION_NAME
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "MatchRecognize: {}", self.schema())
+    }
+
+    fn with_exprs_and_inputs(&self, _exprs: Vec<Expr>, inputs: Vec<LogicalPlan>) -> Result<Self> {
+        if inputs.len() != 1 {
+            return internal_err!("input size inconsistent");
+        }
+
+        Ok(Self {
+            input: inputs[0].clone(),
+            ..self.clone()
+        })
+    }
+}
+
+impl ArroyoExtension for MatchRecognizeExtension {
+    fn node_name(&self) -> Option<crate::builder::NamedNode> {
+        None
+    }
+
+    fn plan_node(
+        &self,
+        planner: &crate::builder::Planner,
+        index: usize,
+        input_schemas: Vec<arroyo_rpc::df::ArroyoSchemaRef>,
+    ) -> Result<NodeWithIncomingEdges> {
+        if input_schemas.len() != 1 {
+            return plan_err!("MatchRecognizeExtension requires exactly one input");
+        }
+        if input_schemas[0]
+            .schema
+            .column_with_name(UPDATING_META_FIELD)
+            .is_some()
+        {
+            return plan_err!("MATCH_RECOGNIZE requires append-only input");
+        }
+        let input_schema = ArroyoSchema::from_schema_keys(
+            input_schemas[0].schema.clone(),
+            (0..self.partition_fields.len()).collect(),
+        )?;
+
+        let symbols = self
+            .symbols
+            .iter()
+            .map(|symbol| {
+                let define = match &symbol.define {
+                    Some(expr) => {
+                        let physical_expr =
+                            planner.create_physical_expr(expr, self.input.schema())?;
+                        Some(
+                            serialize_physical_expr(
+                                physical_expr,
+                                &DefaultPhysicalExtensionCodec {},
+                            )?
+                            .encode_to_vec(),
+                        )
+                    }
+                    None => None,
+                };
+                Ok(MatchRecognizeSymbol {
+                    name: symbol.name.clone(),
+                    define,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let measures = self
+            .measures
+            .iter()
+            .map(|measure| {
+                let column = match &measure.column {
+                    Some(column) => Some(self.input.schema().index_of_column(column)? as u32),
+                    None => None,
+                };
+                Ok(MatchRecognizeMeasure {
+                    name: measure.name.clone(),
+                    kind: measure.kind as i32,
+                    symbol: measure.symbol.map(|symbol| symbol as u32),
+                    column,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let states = self
+            .states
+            .iter()
+            .map(|state| MatchRecognizeState {
+                transitions: state
+                    .transitions
+                    .iter()
+                    .map(|(symbol, target)| MatchRecognizeTransition {
+                        symbol: *symbol as u32,
+                        target: *target as u32,
+                    })
+                    .collect(),
+                epsilon: state.epsilon.iter().map(|target| *target as u32).collect(),
+                accepting: state.accepting,
+            })
+            .collect();
+
+        let config = MatchRecognizeOperator {
+            name: "MatchRecognize".to_string(),
+            input_schema: Some(input_schema.clone().into()),
+            output_schema: Some(self.output_schema().into()),
+            symbols,
+            states,
+            measures,
+            within_micros: self.within.as_micros() as u64,
+        };
+
+        let logical_node = LogicalNode {
+            operator_id: format!("match_recognize_{}", index),
+            description: "match_recognize".to_string(),
+            operator_name: OperatorName::MatchRecognize,
+            operator_config: config.encode_to_vec(),
+            parallelism: 1,
+        };
+
+        let edge = LogicalEdge::project_all(LogicalEdgeType::Shuffle, input_schema);
+
+        Ok(NodeWithIncomingEdges {
+            node: logical_node,
+            edges: vec![edge],
+        })
+    }
+
+    fn output_schema(&self) -> ArroyoSchema {
+        ArroyoSchema::from_schema_unkeyed(Arc::new(self.schema().as_ref().clone().into())).unwrap()
+    }
+}
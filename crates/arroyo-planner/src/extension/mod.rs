@@ -19,11 +19,14 @@ use prost::Message;
 use watermark_node::WatermarkNode;
 
 use self::debezium::{DebeziumUnrollingExtension, ToDebeziumExtension};
+use self::dedup::DedupExtension;
+use self::match_recognize::MatchRecognizeExtension;
+use self::temporal_join::{TemporalJoinExtension, TemporalTableExtension};
 use self::updating_aggregate::UpdatingAggregateExtension;
 use self::{
     aggregate::AggregateExtension, key_calculation::KeyCalculationExtension,
     remote_table::RemoteTableExtension, sink::SinkExtension, table_source::TableSourceExtension,
-    window_fn::WindowFunctionExtension,
+    top_n::TopNExtension, window_fn::WindowFunctionExtension,
 };
 use crate::builder::{NamedNode, Planner};
 use crate::schemas::{add_timestamp_field, has_timestamp_field};
@@ -32,11 +35,15 @@ use join::JoinExtension;
 
 pub(crate) mod aggregate;
 pub(crate) mod debezium;
+pub(crate) mod dedup;
 pub(crate) mod join;
 pub(crate) mod key_calculation;
+pub(crate) mod match_recognize;
 pub(crate) mod remote_table;
 pub(crate) mod sink;
 pub(crate) mod table_source;
+pub(crate) mod temporal_join;
+pub(crate) mod top_n;
 pub(crate) mod updating_aggregate;
 pub(crate) mod watermark_node;
 pub(crate) mod window_fn;
@@ -80,7 +87,12 @@ impl<'a> TryFrom<&'a dyn UserDefinedLogicalNode> for &'a dyn ArroyoExtension {
             .or_else(|_| try_from_t::<AggregateExtension>(node))
             .or_else(|_| try_from_t::<RemoteTableExtension>(node))
             .or_else(|_| try_from_t::<JoinExtension>(node))
+            .or_else(|_| try_from_t::<TemporalJoinExtension>(node))
+            .or_else(|_| try_from_t::<TemporalTableExtension>(node))
             .or_else(|_| try_from_t::<WindowFunctionExtension>(node))
+            .or_else(|_| try_from_t::<TopNExtension>(node))
+            .or_else(|_| try_from_t::<DedupExtension>(node))
+            .or_else(|_| try_from_t::<MatchRecognizeExtension>(node))
             .or_else(|_| try_from_t::<AsyncUDFExtension>(node))
             .or_else(|_| try_from_t::<ToDebeziumExtension>(node))
             .or_else(|_| try_from_t::<DebeziumUnrollingExtension>(node))
@@ -0,0 +1,226 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use arroyo_datastream::logical::{LogicalEdge, LogicalEdgeType, LogicalNode, OperatorName};
+use arroyo_rpc::df::{ArroyoSchema, ArroyoSchemaRef};
+use arroyo_rpc::grpc::api::TemporalJoinOperator;
+use arroyo_rpc::{TIMESTAMP_FIELD, UPDATING_META_FIELD};
+use datafusion::common::{internal_err, plan_err, Column, DFSchemaRef, Result};
+use datafusion::logical_expr::{Expr, LogicalPlan, UserDefinedLogicalNodeCore};
+use prost::Message;
+
+use crate::builder::{NamedNode, Planner};
+use crate::{fields_with_qualifiers, schema_from_df_fields};
+
+use super::{ArroyoExtension, NodeWithIncomingEdges};
+
+pub(crate) const TEMPORAL_TABLE_NAME: &str = "TemporalTableExtension";
+pub(crate) const TEMPORAL_JOIN_NAME: &str = "TemporalJoinExtension";
+
+/// Marks a table read `FOR SYSTEM_TIME AS OF` a column of the other side of its join, which the
+/// join rewriter turns into a [TemporalJoinExtension]. It has no effect on its own.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TemporalTableExtension {
+    pub(crate) input: LogicalPlan,
+    pub(crate) system_time: Column,
+    pub(crate) primary_keys: Vec<String>,
+}
+
+impl ArroyoExtension for TemporalTableExtension {
+    fn node_name(&self) -> Option<NamedNode> {
+        None
+    }
+
+    fn plan_node(
+        &self,
+        _planner: &Planner,
+        _index: usize,
+        _input_schemas: Vec<ArroyoSchemaRef>,
+    ) -> Result<NodeWithIncomingEdges> {
+        internal_err!("TemporalTableExtension should not be planned")
+    }
+
+    fn output_schema(&self) -> ArroyoSchema {
+        ArroyoSchema::from_schema_unkeyed(Arc::new(self.schema().as_ref().into())).unwrap()
+    }
+
+    fn transparent(&self) -> bool {
+        true
+    }
+}
+
+impl UserDefinedLogicalNodeCore for TemporalTableExtension {
+    fn name(&self) -> &str {
+        TEMPORAL_TABLE_NAME
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        self.input.schema()
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "TemporalTable: FOR SYSTEM_TIME AS OF {}",
+            self.system_time
+        )
+    }
+
+    fn with_exprs_and_inputs(&self, _exprs: Vec<Expr>, inputs: Vec<LogicalPlan>) -> Result<Self> {
+        if inputs.len() != 1 {
+            return internal_err!("input size inconsistent");
+        }
+
+        Ok(Self {
+            input: inputs[0].clone(),
+            ..self.clone()
+        })
+    }
+}
+
+/// Joins an append-only left input with the versions of an updating right input, both keyed by
+/// the join key, as of the left rows' timestamps. The output has the left fields, then the right
+/// fields without their timestamp and updating metadata, and is append-only.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TemporalJoinExtension {
+    left: LogicalPlan,
+    right: LogicalPlan,
+    left_outer: bool,
+    ttl: Duration,
+    schema: DFSchemaRef,
+}
+
+impl TemporalJoinExtension {
+    pub fn try_new(
+        left: LogicalPlan,
+        right: LogicalPlan,
+        left_outer: bool,
+        ttl: Duration,
+    ) -> Result<Self> {
+        let left_fields = fields_with_qualifiers(left.schema());
+        let Some(timestamp_field) = left_fields
+            .iter()
+            .find(|field| field.name() == TIMESTAMP_FIELD)
+            .cloned()
+        else {
+            return plan_err!("left side of temporal join must have a timestamp field");
+        };
+
+        let mut fields: Vec<_> = left_fields
+            .into_iter()
+            .filter(|field| field.name() != TIMESTAMP_FIELD)
+            .collect();
+        fields.extend(
+            fields_with_qualifiers(right.schema())
+                .into_iter()
+                .filter(|field| {
+                    field.name() != TIMESTAMP_FIELD && field.name() != UPDATING_META_FIELD
+                })
+                .map(|field| {
+                    let nullable = left_outer || field.is_nullable();
+                    field.with_nullable(nullable)
+                }),
+        );
+        fields.push(timestamp_field);
+        let schema = Arc::new(schema_from_df_fields(&fields)?);
+
+        Ok(Self {
+            left,
+            right,
+            left_outer,
+            ttl,
+            schema,
+        })
+    }
+}
+
+impl ArroyoExtension for TemporalJoinExtension {
+    fn node_name(&self) -> Option<NamedNode> {
+        None
+    }
+
+    fn plan_node(
+        &self,
+        _planner: &Planner,
+        index: usize,
+        input_schemas: Vec<ArroyoSchemaRef>,
+    ) -> Result<NodeWithIncomingEdges> {
+        if input_schemas.len() != 2 {
+            return plan_err!("temporal join should have exactly two inputs");
+        }
+        let left_schema = input_schemas[0].clone();
+        let right_schema = input_schemas[1].clone();
+
+        let config = TemporalJoinOperator {
+            name: format!("temporal_join_{}", index),
+            left_schema: Some(left_schema.as_ref().clone().into()),
+            right_schema: Some(right_schema.as_ref().clone().into()),
+            output_schema: Some(self.output_schema().into()),
+            left_outer: self.left_outer,
+            ttl_micros: self.ttl.as_micros() as u64,
+        };
+
+        let logical_node = LogicalNode {
+            operator_id: format!("temporal_join_{}", index),
+            description: "temporal_join".to_string(),
+            operator_name: OperatorName::TemporalJoin,
+            operator_config: config.encode_to_vec(),
+            parallelism: 1,
+        };
+
+        let left_edge =
+            LogicalEdge::project_all(LogicalEdgeType::LeftJoin, left_schema.as_ref().clone());
+        let right_edge =
+            LogicalEdge::project_all(LogicalEdgeType::RightJoin, right_schema.as_ref().clone());
+        Ok(NodeWithIncomingEdges {
+            node: logical_node,
+            edges: vec![left_edge, right_edge],
+        })
+    }
+
+    fn output_schema(&self) -> ArroyoSchema {
+        ArroyoSchema::from_schema_unkeyed(Arc::new(self.schema().as_ref().clone().into())).unwrap()
+    }
+}
+
+impl UserDefinedLogicalNodeCore for TemporalJoinExtension {
+    fn name(&self) -> &str {
+        TEMPORAL_JOIN_NAME
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.left, &self.right]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "TemporalJoinExtension: {}", self.schema())
+    }
+
+    fn with_exprs_and_inputs(&self, _exprs: Vec<Expr>, inputs: Vec<LogicalPlan>) -> Result<Self> {
+        if inputs.len() != 2 {
+            return internal_err!("input size inconsistent");
+        }
+
+        Ok(Self {
+            left: inputs[0].clone(),
+            right: inputs[1].clone(),
+            ..self.clone()
+        })
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::Arc;
+
+use arroyo_datastream::logical::{LogicalEdge, LogicalEdgeType, LogicalNode, OperatorName};
+use arroyo_rpc::{
+    df::ArroyoSchema,
+    grpc::api::{TopNOperator, TopNSortExpr},
+};
+use datafusion::common::{internal_err, plan_err, DFSchemaRef, Result};
+use datafusion::logical_expr::{expr::Sort, Expr, LogicalPlan, UserDefinedLogicalNodeCore};
+use datafusion_proto::physical_plan::to_proto::serialize_physical_expr;
+use datafusion_proto::physical_plan::DefaultPhysicalExtensionCodec;
+use prost::Message;
+
+use super::{ArroyoExtension, NodeWithIncomingEdges};
+
+pub(crate) const TOP_N_EXTENSION_NAME: &str = "TopNExtension";
+
+/// Keeps the first `limit` rows of each window (and key, if there are key fields) in the order
+/// given by `sort_exprs`. This is planned in place of a `ROW_NUMBER()` window function that is
+/// filtered on its rank, in which case the rank is emitted as the final column, and of a windowed
+/// `ORDER BY ... LIMIT`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TopNExtension {
+    input: LogicalPlan,
+    key_fields: Vec<usize>,
+    sort_exprs: Vec<Expr>,
+    limit: usize,
+    emit_row_number: bool,
+    schema: DFSchemaRef,
+}
+
+impl TopNExtension {
+    pub fn new(
+        input: LogicalPlan,
+        key_fields: Vec<usize>,
+        sort_exprs: Vec<Expr>,
+        limit: usize,
+        emit_row_number: bool,
+        schema: DFSchemaRef,
+    ) -> Self {
+        Self {
+            input,
+            key_fields,
+            sort_exprs,
+            limit,
+            emit_row_number,
+            schema,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl UserDefinedLogicalNodeCore for TopNExtension {
+    fn name(&self) -> &str {
+        TOP_N_EXTENSION_NAME
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        &self.schema
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "TopN: limit={} sort=[{}]",
+            self.limit,
+            self.sort_exprs
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    fn with_exprs_and_inputs(&self, _exprs: Vec<Expr>, inputs: Vec<LogicalPlan>) -> Result<Self> {
+        if inputs.len() != 1 {
+            return internal_err!("input size inconsistent");
+        }
+
+        Ok(Self::new(
+            inputs[0].clone(),
+            self.key_fields.clone(),
+            self.sort_exprs.clone(),
+            self.limit,
+            self.emit_row_number,
+            self.schema.clone(),
+        ))
+    }
+}
+
+impl ArroyoExtension for TopNExtension {
+    fn node_name(&self) -> Option<crate::builder::NamedNode> {
+        None
+    }
+
+    fn plan_node(
+        &self,
+        planner: &crate::builder::Planner,
+        index: usize,
+        input_schemas: Vec<arroyo_rpc::df::ArroyoSchemaRef>,
+    ) -> Result<NodeWithIncomingEdges> {
+        if input_schemas.len() != 1 {
+            return plan_err!("TopNExtension requires exactly one input");
+        }
+        let input_schema = ArroyoSchema::from_schema_keys(
+            input_schemas[0].schema.clone(),
+            self.key_fields.clone(),
+        )?;
+
+        let sort_exprs = self
+            .sort_exprs
+            .iter()
+            .map(|expr| {
+                let Expr::Sort(Sort {
+                    expr,
+                    asc,
+                    nulls_first,
+                }) = expr
+                else {
+                    return plan_err!("expected a sort expression, not {}", expr);
+                };
+                let physical_expr = planner.create_physical_expr(expr, self.input.schema())?;
+                Ok(TopNSortExpr {
+                    expr: serialize_physical_expr(
+                        physical_expr,
+                        &DefaultPhysicalExtensionCodec {},
+                    )?
+                    .encode_to_vec(),
+                    ascending: *asc,
+                    nulls_first: *nulls_first,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let config = TopNOperator {
+            name: "TopN".to_string(),
+            input_schema: Some(input_schema.clone().into()),
+            output_schema: Some(self.output_schema().into()),
+            sort_exprs,
+            limit: self.limit as u64,
+            emit_row_number: self.emit_row_number,
+        };
+
+        let logical_node = LogicalNode {
+            operator_id: format!("top_n_{}", index),
+            description: format!("top {}", self.limit),
+            operator_name: OperatorName::TopN,
+            operator_config: config.encode_to_vec(),
+            // without keys, every row of a window has to be ranked by the same subtask
+            parallelism: if self.key_fields.is_empty() {
+                1
+            } else {
+                planner.default_parallelism()
+            },
+        };
+
+        let edge = LogicalEdge::project_all(LogicalEdgeType::Shuffle, input_schema);
+
+        Ok(NodeWithIncomingEdges {
+            node: logical_node,
+            edges: vec![edge],
+        })
+    }
+
+    fn output_schema(&self) -> ArroyoSchema {
+        ArroyoSchema::from_schema_unkeyed(Arc::new(self.schema().as_ref().clone().into())).unwrap()
+    }
+}
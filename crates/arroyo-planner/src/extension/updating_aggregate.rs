@@ -10,6 +10,7 @@ use datafusion::logical_expr::expr::ScalarFunction;
 use datafusion::logical_expr::{
     col, lit, Expr, Extension, LogicalPlan, UserDefinedLogicalNodeCore,
 };
+use datafusion::physical_plan::aggregates::AggregateExec;
 use datafusion::prelude::named_struct;
 use datafusion::scalar::ScalarValue;
 use datafusion_proto::protobuf::{
@@ -56,6 +57,27 @@ impl UpdatingAggregateExtension {
             ttl,
         })
     }
+
+    /// Aggregates over updating inputs have to remove retracted rows from their accumulators,
+    /// which only some accumulators support.
+    fn check_retractable(planner: &Planner, aggregate: &LogicalPlan) -> Result<()> {
+        let physical_plan = planner.sync_plan(aggregate)?;
+        let Some(aggregate_exec) = physical_plan.as_any().downcast_ref::<AggregateExec>() else {
+            return plan_err!("expected an aggregate plan");
+        };
+
+        // the last aggregate is the max timestamp, which is never retracted
+        let aggregates = aggregate_exec.aggr_expr();
+        for expr in &aggregates[..aggregates.len() - 1] {
+            if !expr.create_accumulator()?.supports_retract_batch() {
+                return plan_err!(
+                    "{} is not supported over updating inputs, as it can't be retracted",
+                    expr.name()
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 impl UserDefinedLogicalNodeCore for UpdatingAggregateExtension {
@@ -112,6 +134,9 @@ impl ArroyoExtension for UpdatingAggregateExtension {
         }
 
         let input_schema = input_schemas[0].clone();
+        if input_schema.schema.index_of(UPDATING_META_FIELD).is_ok() {
+            Self::check_retractable(planner, &self.aggregate)?;
+        }
 
         let plan = if let LogicalPlan::Aggregate(aggregate) = &self.aggregate {
             let key_exprs: Vec<Expr> = self
@@ -17,8 +17,8 @@ pub(crate) const WINDOW_FUNCTION_EXTENSION_NAME: &str = "WindowFunctionExtension
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct WindowFunctionExtension {
-    window_plan: LogicalPlan,
-    key_fields: Vec<usize>,
+    pub(crate) window_plan: LogicalPlan,
+    pub(crate) key_fields: Vec<usize>,
 }
 
 impl WindowFunctionExtension {
@@ -30,8 +30,10 @@ use datafusion::physical_plan::functions::make_scalar_function;
 
 use datafusion::prelude::{create_udf, SessionConfig};
 
-use datafusion::sql::sqlparser::dialect::PostgreSqlDialect;
+use datafusion::sql::sqlparser::dialect::{GenericDialect, PostgreSqlDialect};
+use datafusion::sql::sqlparser::keywords::Keyword;
 use datafusion::sql::sqlparser::parser::{Parser, ParserError};
+use datafusion::sql::sqlparser::tokenizer::{Token, Tokenizer, Word};
 use datafusion::sql::{planner::ContextProvider, sqlparser, TableReference};
 
 use datafusion::logical_expr::expr::ScalarFunction;
@@ -48,6 +50,8 @@ use tables::{Insert, Table};
 
 use crate::builder::PlanToGraphVisitor;
 use crate::extension::sink::SinkExtension;
+use crate::plan::match_recognize::{move_within_clauses, plan_match_recognize};
+use crate::plan::temporal_join::{move_system_time_clauses, plan_temporal_joins};
 use crate::plan::ArroyoRewriter;
 use arroyo_datastream::logical::{DylibUdfConfig, ProgramConfig, PythonUdfConfig};
 use arroyo_rpc::api_types::connections::ConnectionProfile;
@@ -88,17 +92,28 @@ pub const ASYNC_RESULT_FIELD: &str = "__async_result";
 pub struct CompiledSql {
     pub program: LogicalProgram,
     pub connection_ids: Vec<i64>,
+    /// the rendered program, if the query was prefixed with `EXPLAIN [VERBOSE]`
+    pub explain: Option<String>,
 }
 
 #[derive(Clone)]
 pub struct PlanningOptions {
     ttl: Duration,
+    /// how often tumbling and sliding windows emit their current results before they close
+    early_fire: Option<Duration>,
+    /// how long after the watermark passes a window late records still update it
+    allowed_lateness: Duration,
+    /// the sink that records arriving too late for their windows are sent to
+    late_records_table: Option<String>,
 }
 
 impl Default for PlanningOptions {
     fn default() -> Self {
         Self {
             ttl: Duration::from_secs(24 * 60 * 60),
+            early_fire: None,
+            allowed_lateness: Duration::ZERO,
+            late_records_table: None,
         }
     }
 }
@@ -149,6 +164,20 @@ impl ArroyoSchemaProvider {
                 make_scalar_function(fn_impl),
             )),
         );
+        functions.insert(
+            "cumulate".to_string(),
+            Arc::new(create_udf(
+                "cumulate",
+                vec![
+                    DataType::Interval(datatypes::IntervalUnit::MonthDayNano),
+                    DataType::Interval(datatypes::IntervalUnit::MonthDayNano),
+                ],
+                window_return_type.clone(),
+                Volatility::Volatile,
+                #[allow(deprecated)]
+                make_scalar_function(fn_impl),
+            )),
+        );
         functions.insert(
             "tumble".to_string(),
             Arc::new(create_udf(
@@ -545,15 +574,33 @@ fn find_window(expression: &Expr) -> Result<Option<WindowType>> {
                 }
                 let slide = get_duration(&args[0])?;
                 let width = get_duration(&args[1])?;
-                if width.as_nanos() % slide.as_nanos() != 0 {
+                if slide.is_zero() || width.is_zero() {
                     return plan_err!(
-                        "hop() width {:?} currently must be a multiple of slide {:?}",
+                        "hop() width {:?} and slide {:?} must both be positive",
                         width,
                         slide
                     );
                 }
                 Ok(Some(WindowType::Sliding { width, slide }))
             }
+            "cumulate" => {
+                if args.len() != 2 {
+                    unreachable!("wrong number of arguments for cumulate(), expected two");
+                }
+                let step = get_duration(&args[0])?;
+                let max_size = get_duration(&args[1])?;
+                if step.is_zero()
+                    || max_size.is_zero()
+                    || max_size.as_nanos() % step.as_nanos() != 0
+                {
+                    return plan_err!(
+                        "cumulate() max size {:?} must be a positive multiple of step {:?}",
+                        max_size,
+                        step
+                    );
+                }
+                Ok(Some(WindowType::Cumulative { step, max_size }))
+            }
             "tumble" => {
                 if args.len() != 1 {
                     unreachable!("wrong number of arguments for tumble(), expect one");
@@ -614,35 +661,58 @@ fn try_handle_set_variable(
             return plan_err!("invalid syntax for `SET` call");
         };
 
-        if opt.to_string() != "updating_ttl" {
+        let opt = opt.to_string();
+        if !matches!(
+            opt.as_str(),
+            "updating_ttl"
+                | "window_early_fire"
+                | "window_allowed_lateness"
+                | "window_late_records_table"
+        ) {
             return plan_err!(
-                "invalid option '{}'; supported options are 'updating_ttl'",
+                "invalid option '{}'; supported options are 'updating_ttl', 'window_early_fire', \
+                'window_allowed_lateness' and 'window_late_records_table'",
                 opt
             );
         }
 
         if value.len() != 1 {
-            return plan_err!("invalid `SET updating_ttl` call; expected exactly one expression");
+            return plan_err!(
+                "invalid `SET {}` call; expected exactly one expression",
+                opt
+            );
         }
 
         let sqlparser::ast::Expr::Value(sqlparser::ast::Value::SingleQuotedString(s)) =
             value.first().unwrap()
         else {
             return plan_err!(
-                "invalid `SET updating_ttl`; expected a singly-quoted string argument"
+                "invalid `SET {}`; expected a singly-quoted string argument",
+                opt
             );
         };
 
+        if opt == "window_late_records_table" {
+            schema_provider.planning_options.late_records_table = Some(s.clone());
+            return Ok(true);
+        }
+
         let interval = parse_interval_day_time(s).map_err(|_| {
             DataFusionError::Plan(format!(
-                "could not parse '{}' as an interval in `SET updating_ttl` statement",
-                s
+                "could not parse '{}' as an interval in `SET {}` statement",
+                s, opt
             ))
         })?;
 
-        schema_provider.planning_options.ttl =
-            Duration::from_secs(interval.days as u64 * 24 * 60 * 60)
-                + Duration::from_millis(interval.milliseconds as u64);
+        let duration = Duration::from_secs(interval.days as u64 * 24 * 60 * 60)
+            + Duration::from_millis(interval.milliseconds as u64);
+
+        let options = &mut schema_provider.planning_options;
+        match opt.as_str() {
+            "updating_ttl" => options.ttl = duration,
+            "window_early_fire" => options.early_fire = Some(duration).filter(|d| !d.is_zero()),
+            _ => options.allowed_lateness = duration,
+        }
         return Ok(true);
     }
 
@@ -651,14 +721,35 @@ fn try_handle_set_variable(
 
 pub(crate) fn parse_sql(sql: &str) -> Result<Vec<Statement>, ParserError> {
     let dialect = PostgreSqlDialect {};
-    Parser::parse_sql(&dialect, sql)
+    let tokens = Tokenizer::new(&dialect, sql).tokenize()?;
+    let has_keyword = |keyword: Keyword| {
+        tokens
+            .iter()
+            .any(|token| matches!(token, Token::Word(Word { keyword: k, .. }) if *k == keyword))
+    };
+    let match_recognize = has_keyword(Keyword::MATCH_RECOGNIZE);
+    let system_time = has_keyword(Keyword::SYSTEM_TIME);
+    if !match_recognize && !system_time {
+        return Parser::parse_sql(&dialect, sql);
+    }
+
+    if match_recognize {
+        // sqlparser only supports MATCH_RECOGNIZE in the generic and snowflake dialects, and
+        // doesn't support its WITHIN clause
+        let dialect = GenericDialect {};
+        let tokens = move_within_clauses(Tokenizer::new(&dialect, sql).tokenize()?);
+        let tokens = move_system_time_clauses(tokens);
+        return Parser::new(&dialect).with_tokens(tokens).parse_statements();
+    }
+
+    let tokens = move_system_time_clauses(tokens);
+    Parser::new(&dialect).with_tokens(tokens).parse_statements()
 }
 
 pub async fn parse_and_get_arrow_program(
     query: String,
     mut schema_provider: ArroyoSchemaProvider,
-    // TODO: use config
-    _config: SqlConfig,
+    sql_config: SqlConfig,
 ) -> Result<CompiledSql> {
     let mut config = SessionConfig::new();
     config
@@ -672,11 +763,29 @@ pub async fn parse_and_get_arrow_program(
         .with_physical_optimizer_rules(vec![]);
 
     let mut inserts = vec![];
+    let mut explain = None;
     for statement in parse_sql(&query)? {
+        // EXPLAIN plans the whole program as usual, and then renders it as text
+        let mut statement = match statement {
+            Statement::Explain { analyze: true, .. } => {
+                return plan_err!("EXPLAIN ANALYZE is not supported");
+            }
+            Statement::Explain {
+                verbose, statement, ..
+            } => {
+                explain = Some(verbose);
+                *statement
+            }
+            statement => statement,
+        };
+
         if try_handle_set_variable(&statement, &mut schema_provider)? {
             continue;
         }
 
+        plan_temporal_joins(&mut statement, &mut schema_provider, &session_state)?;
+        plan_match_recognize(&mut statement, &mut schema_provider, &session_state)?;
+
         if let Some(table) =
             Table::try_from_statement(&statement, &schema_provider, &session_state)?
         {
@@ -767,10 +876,13 @@ pub async fn parse_and_get_arrow_program(
             node: Arc::new(sink?),
         }));
     }
-    let mut plan_to_graph_visitor = PlanToGraphVisitor::new(&schema_provider, &session_state);
+    let mut plan_to_graph_visitor =
+        PlanToGraphVisitor::new(&schema_provider, &session_state, &sql_config);
     for extension in extensions {
         plan_to_graph_visitor.add_plan(extension)?;
     }
+    plan_to_graph_visitor.add_dead_letter_sinks()?;
+    plan_to_graph_visitor.add_late_record_sinks()?;
     let graph = plan_to_graph_visitor.into_graph();
 
     let program = LogicalProgram::new(
@@ -782,6 +894,7 @@ pub async fn parse_and_get_arrow_program(
     );
 
     Ok(CompiledSql {
+        explain: explain.map(|verbose| program.explain(verbose)),
         program,
         connection_ids: used_connections.into_iter().collect(),
     })
@@ -1,4 +1,4 @@
-use crate::extension::aggregate::AggregateExtension;
+use crate::extension::aggregate::{AggregateExtension, WindowTrigger};
 use crate::extension::key_calculation::KeyCalculationExtension;
 use crate::extension::updating_aggregate::UpdatingAggregateExtension;
 use crate::plan::WindowDetectingVisitor;
@@ -6,12 +6,16 @@ use crate::{
     fields_with_qualifiers, find_window, schema_from_df_fields_with_metadata, ArroyoSchemaProvider,
     DFField, WindowBehavior,
 };
-use arroyo_rpc::{TIMESTAMP_FIELD, UPDATING_META_FIELD};
+use arrow_schema::DataType;
+use arroyo_datastream::WindowType;
+use arroyo_rpc::{TIMESTAMP_FIELD, UPDATING_META_FIELD, UPDATING_ROW_COUNT_FIELD};
 use datafusion::common::tree_node::{Transformed, TreeNodeRewriter};
 use datafusion::common::{not_impl_err, plan_err, DFSchema, DataFusionError, Result};
+use datafusion::execution::FunctionRegistry;
 use datafusion::logical_expr;
 use datafusion::logical_expr::expr::AggregateFunction;
 use datafusion::logical_expr::{aggregate_function, Aggregate, Expr, Extension, LogicalPlan};
+use datafusion::scalar::ScalarValue;
 use std::sync::Arc;
 use tracing::debug;
 
@@ -28,12 +32,9 @@ impl<'a> AggregateRewriter<'a> {
         schema: Arc<DFSchema>,
         schema_provider: &ArroyoSchemaProvider,
     ) -> Result<Transformed<LogicalPlan>> {
-        if input
+        let is_updating = input
             .schema()
-            .has_column_with_unqualified_name(UPDATING_META_FIELD)
-        {
-            return plan_err!("can't currently nest updating aggregates");
-        }
+            .has_column_with_unqualified_name(UPDATING_META_FIELD);
 
         let key_count = key_fields.len();
         key_fields.extend(fields_with_qualifiers(input.schema()));
@@ -81,6 +82,29 @@ impl<'a> AggregateRewriter<'a> {
         };
         let timestamp_field: DFField = timestamp_field.into();
         let column = timestamp_field.qualified_column();
+        let mut output_schema_fields = fields_with_qualifiers(&schema);
+
+        // over updating inputs, we also count the rows of each group so that the operator can
+        // remove the groups that all of the rows have been retracted from
+        if is_updating {
+            aggr_expr.push(
+                Expr::AggregateFunction(AggregateFunction::new_udf(
+                    schema_provider.udaf("count")?,
+                    vec![Expr::Literal(ScalarValue::Int64(Some(1)))],
+                    false,
+                    None,
+                    None,
+                    None,
+                ))
+                .alias(UPDATING_ROW_COUNT_FIELD),
+            );
+            output_schema_fields.push(DFField::new_unqualified(
+                UPDATING_ROW_COUNT_FIELD,
+                DataType::Int64,
+                false,
+            ));
+        }
+
         aggr_expr.push(Expr::AggregateFunction(AggregateFunction::new(
             aggregate_function::AggregateFunction::Max,
             vec![Expr::Column(column.clone())],
@@ -89,7 +113,6 @@ impl<'a> AggregateRewriter<'a> {
             None,
             None,
         )));
-        let mut output_schema_fields = fields_with_qualifiers(&schema);
         output_schema_fields.push(timestamp_field.clone());
         let output_schema = Arc::new(schema_from_df_fields_with_metadata(
             &output_schema_fields,
@@ -119,7 +142,20 @@ impl<'a> AggregateRewriter<'a> {
         let final_plan = LogicalPlan::Extension(Extension {
             node: Arc::new(updating_aggregate_extension),
         });
-        Ok(Transformed::yes(final_plan))
+
+        if !is_updating {
+            return Ok(Transformed::yes(final_plan));
+        }
+
+        // drop the row count, which is only used internally by the operator
+        let projection_expressions = fields_with_qualifiers(final_plan.schema())
+            .iter()
+            .filter(|field| field.name() != UPDATING_ROW_COUNT_FIELD)
+            .map(|field| Expr::Column(field.qualified_column()))
+            .collect();
+        Ok(Transformed::yes(LogicalPlan::Projection(
+            logical_expr::Projection::try_new(projection_expressions, Arc::new(final_plan))?,
+        )))
     }
 }
 
@@ -231,6 +267,30 @@ impl<'a> TreeNodeRewriter for AggregateRewriter<'a> {
             }
         };
 
+        let options = &self.schema_provider.planning_options;
+        let trigger = match &window_behavior {
+            WindowBehavior::FromOperator {
+                is_nested: false, ..
+            } => WindowTrigger {
+                early_fire: options.early_fire,
+                allowed_lateness: options.allowed_lateness,
+            },
+            _ => WindowTrigger::default(),
+        };
+        if trigger.is_updating()
+            && !matches!(
+                window_behavior,
+                WindowBehavior::FromOperator {
+                    window: WindowType::Tumbling { .. } | WindowType::Sliding { .. },
+                    ..
+                }
+            )
+        {
+            return plan_err!(
+                "early firing and allowed lateness are only supported for tumbling and sliding windows"
+            );
+        }
+
         let key_count = key_fields.len();
         key_fields.extend(fields_with_qualifiers(input.schema()));
 
@@ -285,6 +345,7 @@ impl<'a> TreeNodeRewriter for AggregateRewriter<'a> {
             window_behavior,
             LogicalPlan::Aggregate(rewritten_aggregate),
             (0..key_count).collect(),
+            trigger,
         );
         let final_plan = LogicalPlan::Extension(Extension {
             node: Arc::new(aggregate_extension),
@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use datafusion::common::tree_node::{Transformed, TreeNodeRewriter};
+use datafusion::common::{Column, Result as DFResult};
+use datafusion::logical_expr::utils::{conjunction, split_conjunction};
+use datafusion::logical_expr::{
+    expr::{Sort, WindowFunction},
+    Expr, Extension, Filter, LogicalPlan, Projection, Window,
+};
+
+use crate::extension::{dedup::DedupExtension, key_calculation::KeyCalculationExtension};
+use crate::tables::Table;
+use crate::{fields_with_qualifiers, ArroyoSchemaProvider};
+
+use super::top_n::{is_row_number, rank_bound, unaliased};
+use super::WindowDetectingVisitor;
+
+/// Plans `ROW_NUMBER() OVER (PARTITION BY key ORDER BY ts) = 1` over non-windowed input as a
+/// dedup, which emits the first row to arrive for each key and remembers the key for the TTL.
+/// `ts` must be the input's event time. Rows are emitted as they arrive rather than once the
+/// watermark passes them, so with out-of-order input the row kept is the first to arrive, which
+/// may not be the earliest by event time; a later row with an earlier timestamp is dropped as a
+/// duplicate.
+///
+/// Unlike the other rewrites this runs on the way down, before the input has been rewritten, as
+/// the window function would otherwise be rejected for not being windowed. The key calculation
+/// is planned here as well; its projection will pick up the timestamp as the rewrite continues.
+pub(crate) struct DedupRewriter<'a> {
+    pub(crate) schema_provider: &'a ArroyoSchemaProvider,
+}
+
+impl<'a> TreeNodeRewriter for DedupRewriter<'a> {
+    type Node = LogicalPlan;
+
+    fn f_down(&mut self, node: Self::Node) -> DFResult<Transformed<Self::Node>> {
+        let LogicalPlan::Filter(filter) = node else {
+            return Ok(Transformed::no(node));
+        };
+        let LogicalPlan::Window(Window {
+            input,
+            window_expr,
+            schema,
+            ..
+        }) = filter.input.as_ref()
+        else {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        };
+        if window_expr.len() != 1 {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        }
+        let Expr::WindowFunction(WindowFunction {
+            fun,
+            partition_by,
+            order_by,
+            ..
+        }) = unaliased(&window_expr[0])
+        else {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        };
+
+        // only ordering by ascending event time is close to keeping the first row to arrive;
+        // other orderings are left to the top-n rewrite over windows, or rejected
+        let by_event_time = matches!(
+            &order_by[..],
+            [Expr::Sort(Sort { expr, asc: true, .. })]
+                if is_event_time(expr, input, self.schema_provider)
+        );
+        if !is_row_number(fun) || partition_by.is_empty() || !by_event_time {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        }
+
+        // over windows this is a top-n with a limit of one
+        if !matches!(WindowDetectingVisitor::get_window(input), Ok(None)) {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        }
+
+        // the rank is the last column of the window's output
+        let rank_field = schema.qualified_field(schema.fields().len() - 1).into();
+        let rank_column = schema.field(schema.fields().len() - 1).name().clone();
+
+        let mut is_first = false;
+        let mut remaining = vec![];
+        for predicate in split_conjunction(&filter.predicate) {
+            match rank_bound(predicate, &rank_column) {
+                Some(1) => is_first = true,
+                _ => remaining.push(predicate.clone()),
+            }
+        }
+        if !is_first {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        }
+
+        let mut key_projection_expressions: Vec<_> = partition_by
+            .iter()
+            .enumerate()
+            .map(|(index, expression)| expression.clone().alias(format!("_key_{}", index)))
+            .collect();
+        key_projection_expressions.extend(
+            fields_with_qualifiers(input.schema())
+                .iter()
+                .map(|field| Expr::Column(field.qualified_column())),
+        );
+        let key_plan = LogicalPlan::Extension(Extension {
+            node: Arc::new(KeyCalculationExtension::new(
+                LogicalPlan::Projection(Projection::try_new(
+                    key_projection_expressions,
+                    input.clone(),
+                )?),
+                (0..partition_by.len()).collect(),
+            )),
+        });
+
+        let dedup = LogicalPlan::Extension(Extension {
+            node: Arc::new(DedupExtension::new(
+                key_plan,
+                (0..partition_by.len()).collect(),
+                rank_field,
+                self.schema_provider.planning_options.ttl,
+            )?),
+        });
+
+        Ok(Transformed::yes(match conjunction(remaining) {
+            Some(predicate) => LogicalPlan::Filter(Filter::try_new(predicate, Arc::new(dedup))?),
+            None => dedup,
+        }))
+    }
+}
+
+/// Whether `expr` over the output of `plan` is a column that carries the event time of the
+/// rows, i.e. the `event_time_field` of the source table it's projected from
+fn is_event_time(expr: &Expr, plan: &LogicalPlan, schema_provider: &ArroyoSchemaProvider) -> bool {
+    let Expr::Column(column) = unaliased(expr) else {
+        return false;
+    };
+    match plan {
+        LogicalPlan::Projection(projection) => projection
+            .schema
+            .index_of_column(column)
+            .map(|index| is_event_time(&projection.expr[index], &projection.input, schema_provider))
+            .unwrap_or(false),
+        LogicalPlan::TableScan(scan) => {
+            match schema_provider.get_table(scan.table_name.to_string()) {
+                Some(Table::ConnectorTable(table)) => {
+                    table.event_time_field.as_deref() == Some(column.name.as_str())
+                }
+                Some(Table::TableFromQuery { logical_plan, .. }) => is_event_time(
+                    &Expr::Column(Column::new_unqualified(&column.name)),
+                    logical_plan,
+                    schema_provider,
+                ),
+                _ => false,
+            }
+        }
+        // plans that pass their input's columns through unchanged
+        LogicalPlan::Filter(_)
+        | LogicalPlan::SubqueryAlias(_)
+        | LogicalPlan::Sort(_)
+        | LogicalPlan::Limit(_)
+        | LogicalPlan::Repartition(_) => {
+            let input = plan.inputs()[0];
+            let Ok(index) = plan.schema().index_of_column(column) else {
+                return false;
+            };
+            let (qualifier, field) = input.schema().qualified_field(index);
+            is_event_time(
+                &Expr::Column(Column::new(qualifier.cloned(), field.name())),
+                input,
+                schema_provider,
+            )
+        }
+        _ => false,
+    }
+}
@@ -1,10 +1,12 @@
 use crate::extension::join::JoinExtension;
 use crate::extension::key_calculation::KeyCalculationExtension;
+use crate::extension::temporal_join::{TemporalJoinExtension, TemporalTableExtension};
 use crate::plan::WindowDetectingVisitor;
 use crate::{fields_with_qualifiers, schema_from_df_fields_with_metadata, ArroyoSchemaProvider};
+use arrow_schema::{DataType, TimeUnit};
 use arroyo_datastream::WindowType;
-use arroyo_rpc::UPDATING_META_FIELD;
-use datafusion::common::tree_node::{Transformed, TreeNodeRewriter};
+use arroyo_rpc::{TIMESTAMP_FIELD, UPDATING_META_FIELD};
+use datafusion::common::tree_node::{Transformed, TreeNode, TreeNodeRecursion, TreeNodeRewriter};
 use datafusion::common::{
     not_impl_err, plan_err, Column, DataFusionError, JoinConstraint, JoinType, Result, ScalarValue,
     TableReference,
@@ -12,7 +14,8 @@ use datafusion::common::{
 use datafusion::logical_expr;
 use datafusion::logical_expr::expr::Alias;
 use datafusion::logical_expr::{
-    build_join_schema, BinaryExpr, Case, Expr, Extension, Join, LogicalPlan, Projection,
+    build_join_schema, BinaryExpr, Case, Expr, ExprSchemable, Extension, Join, LogicalPlan,
+    Projection,
 };
 use datafusion::prelude::coalesce;
 use std::sync::Arc;
@@ -82,6 +85,7 @@ impl<'a> JoinRewriter<'a> {
         input: Arc<LogicalPlan>,
         join_expressions: Vec<Expr>,
         name: &'static str,
+        timestamp: Option<Expr>,
     ) -> Result<LogicalPlan> {
         let key_count = join_expressions.len();
 
@@ -94,11 +98,14 @@ impl<'a> JoinRewriter<'a> {
                     format!("_key_{}", index),
                 )
             })
-            .chain(
-                fields_with_qualifiers(input.schema())
-                    .iter()
-                    .map(|field| Expr::Column(field.qualified_column())),
-            )
+            .chain(fields_with_qualifiers(input.schema()).iter().map(|field| {
+                match &timestamp {
+                    Some(timestamp) if field.name() == TIMESTAMP_FIELD => timestamp
+                        .clone()
+                        .alias_qualified(field.qualifier().cloned(), TIMESTAMP_FIELD),
+                    _ => Expr::Column(field.qualified_column()),
+                }
+            }))
             .collect();
 
         // Calculate initial projection with default names
@@ -113,6 +120,131 @@ impl<'a> JoinRewriter<'a> {
         }))
     }
 
+    fn find_temporal_table(plan: &LogicalPlan) -> Result<Option<TemporalTableExtension>> {
+        let mut temporal_table = None;
+        plan.apply(|node| {
+            if let LogicalPlan::Extension(Extension { node }) = node {
+                if let Some(table) = node.as_any().downcast_ref::<TemporalTableExtension>() {
+                    temporal_table = Some(table.clone());
+                    return Ok(TreeNodeRecursion::Stop);
+                }
+            }
+            Ok(TreeNodeRecursion::Continue)
+        })?;
+        Ok(temporal_table)
+    }
+
+    /// Plans a join against a table read `FOR SYSTEM_TIME AS OF` a column of the left side, which
+    /// looks up the version of the right row that was current as of that time.
+    fn rewrite_temporal_join(
+        &self,
+        join: Join,
+        temporal_table: TemporalTableExtension,
+    ) -> Result<LogicalPlan> {
+        let Join {
+            left,
+            right,
+            on,
+            filter,
+            join_type,
+            join_constraint: JoinConstraint::On,
+            schema: _,
+            null_equals_null: false,
+        } = join
+        else {
+            return not_impl_err!("can't handle join constraint other than ON");
+        };
+        let left_outer = match join_type {
+            JoinType::Inner => false,
+            JoinType::Left => true,
+            _ => return not_impl_err!("temporal joins must be inner or left joins"),
+        };
+        if filter.is_some() {
+            return not_impl_err!("temporal joins only support equality conditions");
+        }
+        if WindowDetectingVisitor::get_window(&left)?.is_some() {
+            return not_impl_err!("can't handle windowed left side of temporal join");
+        }
+        if left
+            .schema()
+            .has_column_with_unqualified_name(UPDATING_META_FIELD)
+        {
+            return plan_err!("can't handle updating left side of temporal join");
+        }
+
+        // the versioned table is keyed by its primary key, so that's what it must be joined on
+        let mut join_keys = on
+            .iter()
+            .map(|(_, right)| match right {
+                Expr::Column(column) => Some(column.name.clone()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default();
+        let mut primary_keys = temporal_table.primary_keys.clone();
+        join_keys.sort();
+        primary_keys.sort();
+        if join_keys != primary_keys {
+            return plan_err!(
+                "temporal joins must join on the primary key of the versioned table ({})",
+                temporal_table.primary_keys.join(", ")
+            );
+        }
+
+        let system_time = Expr::Column(temporal_table.system_time.clone());
+        if !matches!(
+            system_time.get_type(left.schema()),
+            Ok(DataType::Timestamp(_, _))
+        ) {
+            return plan_err!(
+                "FOR SYSTEM_TIME AS OF must refer to a timestamp column of the left side of the \
+                join, not {}",
+                temporal_table.system_time
+            );
+        }
+        let system_time = system_time.cast_to(
+            &DataType::Timestamp(TimeUnit::Nanosecond, None),
+            left.schema(),
+        )?;
+
+        let (left_expressions, right_expressions): (Vec<_>, Vec<_>) = on.into_iter().unzip();
+        // the keys are compared in their encoded form, so they need to have the same types
+        let left_expressions = left_expressions
+            .into_iter()
+            .zip(&right_expressions)
+            .map(|(left_expr, right_expr)| {
+                left_expr.cast_to(&right_expr.get_type(right.schema())?, left.schema())
+            })
+            .collect::<Result<_>>()?;
+
+        let right = right
+            .as_ref()
+            .clone()
+            .transform_up(|node| {
+                if let LogicalPlan::Extension(Extension { node }) = &node {
+                    if let Some(table) = node.as_any().downcast_ref::<TemporalTableExtension>() {
+                        return Ok(Transformed::yes(table.input.clone()));
+                    }
+                }
+                Ok(Transformed::no(node))
+            })?
+            .data;
+
+        let left_input =
+            self.create_join_key_plan(left, left_expressions, "left", Some(system_time))?;
+        let right_input =
+            self.create_join_key_plan(Arc::new(right), right_expressions, "right", None)?;
+
+        Ok(LogicalPlan::Extension(Extension {
+            node: Arc::new(TemporalJoinExtension::try_new(
+                left_input,
+                right_input,
+                left_outer,
+                self.schema_provider.planning_options.ttl,
+            )?),
+        }))
+    }
+
     fn post_join_timestamp_projection(&mut self, input: LogicalPlan) -> Result<LogicalPlan> {
         let schema = input.schema().clone();
         let mut schema_with_timestamp = fields_with_qualifiers(&schema);
@@ -196,6 +328,11 @@ impl<'a> TreeNodeRewriter for JoinRewriter<'a> {
         let LogicalPlan::Join(join) = node else {
             return Ok(Transformed::no(node));
         };
+        if let Some(temporal_table) = Self::find_temporal_table(&join.right)? {
+            return Ok(Transformed::yes(
+                self.rewrite_temporal_join(join, temporal_table)?,
+            ));
+        }
         let is_instant = Self::check_join_windowing(&join)?;
 
         let Join {
@@ -220,8 +357,8 @@ impl<'a> TreeNodeRewriter for JoinRewriter<'a> {
         let (left_expressions, right_expressions): (Vec<_>, Vec<_>) =
             on.clone().into_iter().unzip();
 
-        let left_input = self.create_join_key_plan(left, left_expressions, "left")?;
-        let right_input = self.create_join_key_plan(right, right_expressions, "right")?;
+        let left_input = self.create_join_key_plan(left, left_expressions, "left", None)?;
+        let right_input = self.create_join_key_plan(right, right_expressions, "right", None)?;
         let rewritten_join = LogicalPlan::Join(Join {
             schema: Arc::new(build_join_schema(
                 left_input.schema(),
@@ -0,0 +1,616 @@
+use std::sync::Arc;
+
+use arrow_schema::DataType;
+use arroyo_rpc::grpc::api::MatchRecognizeMeasureKind;
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::common::{
+    config::ConfigOptions, internal_err, plan_err, Column, DFSchema, DFSchemaRef, Result,
+    TableReference,
+};
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::{
+    EmptyRelation, Expr, ExprSchemable, Extension, LogicalPlan, Projection,
+};
+use datafusion::sql::planner::PlannerContext;
+use datafusion::sql::sqlparser::ast::{
+    self, AfterMatchSkip, FunctionArg, FunctionArgExpr, FunctionArguments, Ident,
+    MatchRecognizePattern, MatchRecognizeSymbol, ObjectName, Query, RepetitionQuantifier,
+    RowsPerMatch, SetExpr, Statement, TableFactor, TableWithJoins,
+};
+use datafusion::sql::sqlparser::keywords::Keyword;
+use datafusion::sql::sqlparser::tokenizer::{Token, Whitespace, Word};
+
+use crate::extension::key_calculation::KeyCalculationExtension;
+use crate::extension::match_recognize::{
+    MatchRecognizeExtension, NfaState, PatternMeasure, PatternSymbol,
+};
+use crate::extension::remote_table::RemoteTableExtension;
+use crate::tables::{produce_optimized_plan, sql_to_rel, Table};
+use crate::{fields_with_qualifiers, get_duration, parse_sql, rewrite_plan};
+use crate::{ArroyoSchemaProvider, DFField};
+
+/// sqlparser doesn't support `WITHIN`, so it's moved into `DEFINE` as this pseudo-variable
+const WITHIN_SYMBOL: &str = "__arroyo_within";
+
+// bounds the NFA that bounded quantifiers like {1,1000} expand into
+const MAX_NFA_STATES: usize = 10_000;
+
+pub(crate) fn is_keyword(token: &Token, keyword: Keyword) -> bool {
+    matches!(token, Token::Word(Word { keyword: k, quote_style: None, .. }) if *k == keyword)
+}
+
+/// Rewrites `PATTERN (...) WITHIN <interval> DEFINE ...` into
+/// `PATTERN (...) DEFINE __arroyo_within AS <interval>, ...`, which sqlparser can parse and from
+/// which the planner takes the interval back out.
+pub(crate) fn move_within_clauses(tokens: Vec<Token>) -> Vec<Token> {
+    let mut output = Vec::with_capacity(tokens.len() + 4);
+    let mut after_pattern = false;
+    let mut within: Option<Vec<Token>> = None;
+
+    for token in tokens {
+        if let Some(interval) = &mut within {
+            if !is_keyword(&token, Keyword::DEFINE) {
+                interval.push(token);
+                continue;
+            }
+            output.push(token);
+            output.push(Token::Whitespace(Whitespace::Space));
+            output.push(Token::make_word(WITHIN_SYMBOL, None));
+            output.push(Token::Whitespace(Whitespace::Space));
+            output.push(Token::make_keyword("AS"));
+            output.extend(within.take().unwrap());
+            output.push(Token::Comma);
+            continue;
+        }
+
+        if is_keyword(&token, Keyword::PATTERN) {
+            after_pattern = true;
+        } else if is_keyword(&token, Keyword::DEFINE) {
+            after_pattern = false;
+        } else if after_pattern && is_keyword(&token, Keyword::WITHIN) {
+            after_pattern = false;
+            within = Some(vec![]);
+            continue;
+        }
+        output.push(token);
+    }
+
+    // without a DEFINE the statement is invalid anyways, so leave it for the parser to reject
+    if let Some(interval) = within {
+        output.push(Token::make_keyword("WITHIN"));
+        output.extend(interval);
+    }
+    output
+}
+
+/// Plans each `MATCH_RECOGNIZE` in the statement, which DataFusion doesn't support, as a table
+/// that the statement then reads from in its place.
+pub(crate) fn plan_match_recognize(
+    statement: &mut Statement,
+    schema_provider: &mut ArroyoSchemaProvider,
+    session_state: &SessionState,
+) -> Result<()> {
+    let query = match statement {
+        Statement::Query(query)
+        | Statement::Insert(ast::Insert {
+            source: Some(query),
+            ..
+        })
+        | Statement::CreateView { query, .. }
+        | Statement::CreateTable {
+            query: Some(query), ..
+        } => query,
+        _ => return Ok(()),
+    };
+
+    MatchRecognizePlanner {
+        schema_provider,
+        session_state,
+    }
+    .rewrite_query(query)
+}
+
+pub(crate) fn ident_name(ident: &Ident) -> String {
+    match ident.quote_style {
+        Some(_) => ident.value.clone(),
+        None => ident.value.to_ascii_lowercase(),
+    }
+}
+
+struct MatchRecognizePlanner<'a> {
+    schema_provider: &'a mut ArroyoSchemaProvider,
+    session_state: &'a SessionState,
+}
+
+impl<'a> MatchRecognizePlanner<'a> {
+    fn rewrite_query(&mut self, query: &mut Query) -> Result<()> {
+        if let Some(with) = &mut query.with {
+            for cte in &mut with.cte_tables {
+                self.rewrite_query(&mut cte.query)?;
+            }
+        }
+        self.rewrite_set_expr(&mut query.body)
+    }
+
+    fn rewrite_set_expr(&mut self, set_expr: &mut SetExpr) -> Result<()> {
+        match set_expr {
+            SetExpr::Select(select) => {
+                for table in &mut select.from {
+                    self.rewrite_table_with_joins(table)?;
+                }
+                Ok(())
+            }
+            SetExpr::Query(query) => self.rewrite_query(query),
+            SetExpr::SetOperation { left, right, .. } => {
+                self.rewrite_set_expr(left)?;
+                self.rewrite_set_expr(right)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn rewrite_table_with_joins(&mut self, table: &mut TableWithJoins) -> Result<()> {
+        self.rewrite_table_factor(&mut table.relation)?;
+        for join in &mut table.joins {
+            self.rewrite_table_factor(&mut join.relation)?;
+        }
+        Ok(())
+    }
+
+    fn rewrite_table_factor(&mut self, factor: &mut TableFactor) -> Result<()> {
+        match factor {
+            TableFactor::Derived { subquery, .. } => return self.rewrite_query(subquery),
+            TableFactor::NestedJoin {
+                table_with_joins, ..
+            } => return self.rewrite_table_with_joins(table_with_joins),
+            TableFactor::MatchRecognize { table, .. } => self.rewrite_table_factor(table)?,
+            _ => return Ok(()),
+        }
+
+        let name = (0..)
+            .map(|i| format!("__match_recognize_{}", i))
+            .find(|name| self.schema_provider.get_table(name).is_none())
+            .unwrap();
+        let plan = self.plan(factor)?;
+        let schema = plan.schema().clone();
+        self.schema_provider.insert_table(Table::TableFromQuery {
+            name: name.clone(),
+            logical_plan: LogicalPlan::Extension(Extension {
+                node: Arc::new(RemoteTableExtension {
+                    input: plan,
+                    name: TableReference::bare(name.clone()),
+                    schema,
+                    materialize: true,
+                }),
+            }),
+        });
+
+        let TableFactor::MatchRecognize { alias, .. } = factor else {
+            unreachable!();
+        };
+        *factor = TableFactor::Table {
+            name: ObjectName(vec![Ident::new(name)]),
+            alias: alias.take(),
+            args: None,
+            with_hints: vec![],
+            version: None,
+            partitions: vec![],
+        };
+        Ok(())
+    }
+
+    fn plan(&self, factor: &TableFactor) -> Result<LogicalPlan> {
+        let TableFactor::MatchRecognize {
+            table,
+            partition_by,
+            order_by,
+            measures,
+            rows_per_match,
+            after_match_skip,
+            pattern,
+            symbols,
+            ..
+        } = factor
+        else {
+            return internal_err!("expected MATCH_RECOGNIZE, not {}", factor);
+        };
+
+        if !matches!(rows_per_match, None | Some(RowsPerMatch::OneRow)) {
+            return plan_err!("MATCH_RECOGNIZE only supports ONE ROW PER MATCH");
+        }
+        if !matches!(after_match_skip, None | Some(AfterMatchSkip::PastLastRow)) {
+            return plan_err!("MATCH_RECOGNIZE only supports AFTER MATCH SKIP PAST LAST ROW");
+        }
+
+        let schema_provider = &*self.schema_provider;
+        let input_statement = parse_sql(&format!("SELECT * FROM {}", table))?
+            .pop()
+            .unwrap();
+        let input = rewrite_plan(
+            produce_optimized_plan(&input_statement, schema_provider, self.session_state)?,
+            schema_provider,
+        )?;
+        let input_schema = input.schema().clone();
+
+        let sql_to_rel = sql_to_rel(schema_provider, self.session_state);
+        let mut planner_context = PlannerContext::new();
+
+        let Some(within) = symbols
+            .iter()
+            .find(|symbol| symbol.symbol.value == WITHIN_SYMBOL)
+        else {
+            return plan_err!(
+                "MATCH_RECOGNIZE requires a WITHIN clause to bound how long a match may take, \
+                e.g. `PATTERN (A B) WITHIN INTERVAL '5' MINUTE`"
+            );
+        };
+        let within = get_duration(&sql_to_rel.sql_to_expr(
+            within.definition.clone(),
+            &DFSchema::empty(),
+            &mut planner_context,
+        )?)?;
+        if within.is_zero() {
+            return plan_err!("MATCH_RECOGNIZE WITHIN must be a positive interval");
+        }
+
+        let mut partition_fields = vec![];
+        let mut key_projection = vec![];
+        for (index, expr) in partition_by.iter().enumerate() {
+            let Expr::Column(column) =
+                sql_to_rel.sql_to_expr(expr.clone(), &input_schema, &mut planner_context)?
+            else {
+                return plan_err!(
+                    "MATCH_RECOGNIZE can only PARTITION BY columns, not {}",
+                    expr
+                );
+            };
+            let field = input_schema.field_from_column(&column)?;
+            partition_fields.push(DFField::new_unqualified(
+                &column.name,
+                field.data_type().clone(),
+                field.is_nullable(),
+            ));
+            key_projection.push(Expr::Column(column).alias(format!("_key_{}", index)));
+        }
+        if partition_fields.is_empty() {
+            return plan_err!("MATCH_RECOGNIZE requires a PARTITION BY clause");
+        }
+
+        let [order_by] = order_by.as_slice() else {
+            return plan_err!("MATCH_RECOGNIZE must ORDER BY exactly one column, the event time");
+        };
+        let order_expr =
+            sql_to_rel.sql_to_expr(order_by.expr.clone(), &input_schema, &mut planner_context)?;
+        if order_by.asc == Some(false)
+            || !matches!(
+                order_expr.get_type(input_schema.as_ref())?,
+                DataType::Timestamp(..)
+            )
+        {
+            return plan_err!(
+                "MATCH_RECOGNIZE must ORDER BY the event time ascending, not {}",
+                order_by
+            );
+        }
+
+        let mut compiler = NfaCompiler::default();
+        let start = compiler.add_state()?;
+        let (entry, exit) = compiler.compile(pattern)?;
+        let accept = compiler.add_state()?;
+        compiler.states[start].epsilon.push(entry);
+        compiler.states[exit].epsilon.push(accept);
+        compiler.states[accept].accepting = true;
+
+        let mut defines = vec![None; compiler.symbols.len()];
+        for definition in symbols {
+            if definition.symbol.value == WITHIN_SYMBOL {
+                continue;
+            }
+            let name = ident_name(&definition.symbol);
+            let Some(index) = compiler.symbols.iter().position(|symbol| *symbol == name) else {
+                return plan_err!(
+                    "pattern variable {} is defined but does not appear in the PATTERN",
+                    definition.symbol
+                );
+            };
+            if defines[index].is_some() {
+                return plan_err!("pattern variable {} is defined twice", definition.symbol);
+            }
+
+            // the condition may refer to the current row's columns through the variable
+            let symbol_schema =
+                DFSchema::try_from_qualified_schema(name.as_str(), input_schema.as_arrow())?;
+            let expr = sql_to_rel
+                .sql_to_expr(
+                    definition.definition.clone(),
+                    &symbol_schema,
+                    &mut planner_context,
+                )?
+                .transform_up(&mut |e| {
+                    let Expr::Column(column) = &e else {
+                        return Ok(Transformed::no(e));
+                    };
+                    let (qualifier, field) =
+                        input_schema.qualified_field_with_unqualified_name(&column.name)?;
+                    Ok(Transformed::yes(Expr::Column(Column::new(
+                        qualifier.cloned(),
+                        field.name(),
+                    ))))
+                })?
+                .data;
+            let expr = self.coerce(expr, &input_schema)?;
+            if expr.get_type(input_schema.as_ref())? != DataType::Boolean {
+                return plan_err!(
+                    "DEFINE for pattern variable {} must be a boolean condition",
+                    definition.symbol
+                );
+            }
+            defines[index] = Some(expr);
+        }
+        let pattern_symbols = compiler
+            .symbols
+            .iter()
+            .zip(defines)
+            .map(|(name, define)| PatternSymbol {
+                name: name.clone(),
+                define,
+            })
+            .collect();
+
+        let measures = measures
+            .iter()
+            .map(|measure| plan_measure(measure, &compiler.symbols, &input_schema))
+            .collect::<Result<Vec<_>>>()?;
+
+        key_projection.extend(
+            fields_with_qualifiers(&input_schema)
+                .iter()
+                .map(|field| Expr::Column(field.qualified_column())),
+        );
+        let key_plan = LogicalPlan::Extension(Extension {
+            node: Arc::new(KeyCalculationExtension::new(
+                LogicalPlan::Projection(Projection::try_new(key_projection, Arc::new(input))?),
+                (0..partition_fields.len()).collect(),
+            )),
+        });
+
+        Ok(LogicalPlan::Extension(Extension {
+            node: Arc::new(MatchRecognizeExtension::new(
+                key_plan,
+                partition_fields,
+                pattern_symbols,
+                compiler.states,
+                measures,
+                within,
+            )?),
+        }))
+    }
+
+    /// Applies the analyzer's type coercion to an expression over the given schema
+    fn coerce(&self, expr: Expr, schema: &DFSchemaRef) -> Result<Expr> {
+        let plan = LogicalPlan::Projection(Projection::try_new(
+            vec![expr],
+            Arc::new(LogicalPlan::EmptyRelation(EmptyRelation {
+                produce_one_row: false,
+                schema: schema.clone(),
+            })),
+        )?);
+        let plan = self.schema_provider.analyzer.execute_and_check(
+            plan,
+            &ConfigOptions::default(),
+            |_plan, _rule| {},
+        )?;
+        let LogicalPlan::Projection(mut projection) = plan else {
+            return internal_err!("type coercion should preserve the projection");
+        };
+        Ok(projection.expr.remove(0))
+    }
+}
+
+/// Measures are FIRST, LAST, COUNT, MIN or MAX of a column over the rows of the match, optionally
+/// restricted to the rows mapped to one pattern variable. A bare column is its LAST value.
+fn plan_measure(
+    measure: &ast::Measure,
+    symbols: &[String],
+    input_schema: &DFSchema,
+) -> Result<PatternMeasure> {
+    let unsupported = || {
+        plan_err!(
+            "unsupported MATCH_RECOGNIZE measure {}; measures must be FIRST, LAST, COUNT, MIN or \
+            MAX of a column, optionally qualified by a pattern variable",
+            measure
+        )
+    };
+
+    let (kind, argument) = match &measure.expr {
+        ast::Expr::Function(function) => {
+            let kind = match function.name.to_string().to_ascii_lowercase().as_str() {
+                "first" => MatchRecognizeMeasureKind::First,
+                "last" => MatchRecognizeMeasureKind::Last,
+                "count" => MatchRecognizeMeasureKind::Count,
+                "min" => MatchRecognizeMeasureKind::Min,
+                "max" => MatchRecognizeMeasureKind::Max,
+                _ => return unsupported(),
+            };
+            let FunctionArguments::List(arguments) = &function.args else {
+                return unsupported();
+            };
+            let [FunctionArg::Unnamed(argument)] = arguments.args.as_slice() else {
+                return unsupported();
+            };
+            (kind, argument.clone())
+        }
+        expr => (
+            MatchRecognizeMeasureKind::Last,
+            FunctionArgExpr::Expr(expr.clone()),
+        ),
+    };
+
+    let symbol_index = |ident: &Ident| {
+        let name = ident_name(ident);
+        match symbols.iter().position(|symbol| *symbol == name) {
+            Some(index) => Ok(index),
+            None => plan_err!("{} in measure {} is not a pattern variable", ident, measure),
+        }
+    };
+
+    let (symbol, column) = match &argument {
+        FunctionArgExpr::Expr(ast::Expr::Identifier(column)) => (None, Some(column)),
+        FunctionArgExpr::Expr(ast::Expr::CompoundIdentifier(idents)) if idents.len() == 2 => {
+            (Some(symbol_index(&idents[0])?), Some(&idents[1]))
+        }
+        FunctionArgExpr::Wildcard if kind == MatchRecognizeMeasureKind::Count => (None, None),
+        FunctionArgExpr::QualifiedWildcard(ObjectName(idents))
+            if kind == MatchRecognizeMeasureKind::Count && idents.len() == 1 =>
+        {
+            (Some(symbol_index(&idents[0])?), None)
+        }
+        _ => return unsupported(),
+    };
+
+    let column = match column {
+        Some(column) => {
+            let (qualifier, field) =
+                input_schema.qualified_field_with_unqualified_name(&ident_name(column))?;
+            Some((
+                Column::new(qualifier.cloned(), field.name()),
+                field.data_type(),
+            ))
+        }
+        None => None,
+    };
+    let data_type = match (kind, &column) {
+        (MatchRecognizeMeasureKind::Count, _) => DataType::Int64,
+        (_, Some((_, data_type))) => (*data_type).clone(),
+        (_, None) => return unsupported(),
+    };
+
+    Ok(PatternMeasure {
+        name: ident_name(&measure.alias),
+        kind,
+        symbol,
+        column: column.map(|(column, _)| column),
+        data_type,
+    })
+}
+
+/// Compiles a pattern into an NFA with Thompson's construction
+#[derive(Default)]
+struct NfaCompiler {
+    states: Vec<NfaState>,
+    symbols: Vec<String>,
+}
+
+impl NfaCompiler {
+    fn add_state(&mut self) -> Result<usize> {
+        if self.states.len() >= MAX_NFA_STATES {
+            return plan_err!(
+                "MATCH_RECOGNIZE pattern is too large; patterns may compile to at most {} states",
+                MAX_NFA_STATES
+            );
+        }
+        self.states.push(NfaState::default());
+        Ok(self.states.len() - 1)
+    }
+
+    fn epsilon(&mut self, from: usize, to: usize) {
+        self.states[from].epsilon.push(to);
+    }
+
+    /// Compiles the pattern into a fragment of the NFA, returning its entry and exit states
+    fn compile(&mut self, pattern: &MatchRecognizePattern) -> Result<(usize, usize)> {
+        match pattern {
+            MatchRecognizePattern::Symbol(MatchRecognizeSymbol::Named(ident)) => {
+                let name = ident_name(ident);
+                let symbol = match self.symbols.iter().position(|symbol| *symbol == name) {
+                    Some(index) => index,
+                    None => {
+                        self.symbols.push(name);
+                        self.symbols.len() - 1
+                    }
+                };
+                let entry = self.add_state()?;
+                let exit = self.add_state()?;
+                self.states[entry].transitions.push((symbol, exit));
+                Ok((entry, exit))
+            }
+            MatchRecognizePattern::Group(pattern) => self.compile(pattern),
+            MatchRecognizePattern::Concat(patterns) => {
+                let fragments = patterns
+                    .iter()
+                    .map(|pattern| self.compile(pattern))
+                    .collect::<Result<Vec<_>>>()?;
+                for pair in fragments.windows(2) {
+                    self.epsilon(pair[0].1, pair[1].0);
+                }
+                Ok((fragments[0].0, fragments[fragments.len() - 1].1))
+            }
+            MatchRecognizePattern::Alternation(patterns) => {
+                let entry = self.add_state()?;
+                let exit = self.add_state()?;
+                for pattern in patterns {
+                    let (inner_entry, inner_exit) = self.compile(pattern)?;
+                    self.epsilon(entry, inner_entry);
+                    self.epsilon(inner_exit, exit);
+                }
+                Ok((entry, exit))
+            }
+            MatchRecognizePattern::Repetition(pattern, quantifier) => {
+                let (min, max) = match quantifier {
+                    RepetitionQuantifier::ZeroOrMore => (0, None),
+                    RepetitionQuantifier::OneOrMore => (1, None),
+                    RepetitionQuantifier::AtMostOne => (0, Some(1)),
+                    RepetitionQuantifier::Exactly(n) => (*n, Some(*n)),
+                    RepetitionQuantifier::AtLeast(n) => (*n, None),
+                    RepetitionQuantifier::AtMost(n) => (0, Some(*n)),
+                    RepetitionQuantifier::Range(n, m) => (*n, Some(*m)),
+                };
+                if max.is_some_and(|max| max == 0 || max < min) {
+                    return plan_err!(
+                        "invalid quantifier {} in MATCH_RECOGNIZE pattern",
+                        quantifier
+                    );
+                }
+
+                let entry = self.add_state()?;
+                let mut exit = entry;
+                for _ in 0..min {
+                    let (inner_entry, inner_exit) = self.compile(pattern)?;
+                    self.epsilon(exit, inner_entry);
+                    exit = inner_exit;
+                }
+                match max {
+                    None => {
+                        let (inner_entry, inner_exit) = self.compile(pattern)?;
+                        let repeat = self.add_state()?;
+                        self.epsilon(exit, repeat);
+                        self.epsilon(repeat, inner_entry);
+                        self.epsilon(inner_exit, repeat);
+                        exit = repeat;
+                    }
+                    Some(max) => {
+                        let optional_exit = self.add_state()?;
+                        for _ in min..max {
+                            let (inner_entry, inner_exit) = self.compile(pattern)?;
+                            self.epsilon(exit, inner_entry);
+                            self.epsilon(exit, optional_exit);
+                            exit = inner_exit;
+                        }
+                        self.epsilon(exit, optional_exit);
+                        exit = optional_exit;
+                    }
+                }
+                Ok((entry, exit))
+            }
+            MatchRecognizePattern::Symbol(_) => {
+                plan_err!("MATCH_RECOGNIZE patterns don't support the anchors ^ and $")
+            }
+            MatchRecognizePattern::Exclude(_) => {
+                plan_err!("MATCH_RECOGNIZE patterns don't support exclusions")
+            }
+            MatchRecognizePattern::Permute(_) => {
+                plan_err!("MATCH_RECOGNIZE patterns don't support PERMUTE")
+            }
+        }
+    }
+}
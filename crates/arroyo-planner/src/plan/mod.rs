@@ -14,6 +14,8 @@ use datafusion::logical_expr::{
 };
 use join::JoinRewriter;
 
+use self::dedup::DedupRewriter;
+use self::top_n::TopNRewriter;
 use self::window_fn::WindowFunctionRewriter;
 use crate::rewriters::TimeWindowNullCheckRemover;
 use crate::{
@@ -33,7 +35,11 @@ use crate::{
 };
 
 mod aggregate;
+mod dedup;
 mod join;
+pub(crate) mod match_recognize;
+pub(crate) mod temporal_join;
+mod top_n;
 mod window_fn;
 
 #[derive(Debug, Default)]
@@ -239,7 +245,7 @@ impl TreeNodeVisitor<'_> for WindowDetectingVisitor {
                             }
                             if self.fields.is_empty() {
                                 return Err(DataFusionError::Plan(
-                                    "must have window in aggregate. Make sure you are calling one of the windowing functions (hop, tumble, session) or using the window field of the input".to_string(),
+                                    "must have window in aggregate. Make sure you are calling one of the windowing functions (hop, tumble, session, cumulate) or using the window field of the input".to_string(),
                                 ));
                             }
                         }
@@ -261,6 +267,13 @@ pub struct ArroyoRewriter<'a> {
 impl<'a> TreeNodeRewriter for ArroyoRewriter<'a> {
     type Node = LogicalPlan;
 
+    fn f_down(&mut self, node: Self::Node) -> Result<Transformed<Self::Node>> {
+        DedupRewriter {
+            schema_provider: self.schema_provider,
+        }
+        .f_down(node)
+    }
+
     fn f_up(&mut self, mut node: Self::Node) -> Result<Transformed<Self::Node>> {
         match node {
             LogicalPlan::Projection(ref mut projection) => {
@@ -331,17 +344,21 @@ impl<'a> TreeNodeRewriter for ArroyoRewriter<'a> {
                     .predicate
                     .clone()
                     .rewrite(&mut TimeWindowNullCheckRemover {})?;
-                return Ok(if expr.transformed {
-                    Transformed::yes(LogicalPlan::Filter(Filter::try_new(expr.data, f.input)?))
+                let filter = if expr.transformed {
+                    Filter::try_new(expr.data, f.input)?
                 } else {
-                    Transformed::no(LogicalPlan::Filter(f))
-                });
+                    f
+                };
+                // a filter on the rank of a window function may turn it into a top-n
+                let mut rewritten = TopNRewriter {}.f_up(LogicalPlan::Filter(filter))?;
+                rewritten.transformed |= expr.transformed;
+                return Ok(rewritten);
             }
             LogicalPlan::Window(_) => {
                 return WindowFunctionRewriter {}.f_up(node);
             }
             LogicalPlan::Sort(_) => {
-                return plan_err!("ORDER BY is not currently supported ({})", node.display());
+                return TopNRewriter {}.f_up(node);
             }
             LogicalPlan::CrossJoin(_) => {
                 return plan_err!("CROSS JOIN is not currently supported ({})", node.display());
@@ -385,7 +402,7 @@ impl<'a> TreeNodeRewriter for ArroyoRewriter<'a> {
                 )));
             }
             LogicalPlan::Limit(_) => {
-                return plan_err!("LIMIT is not currently supported ({})", node.display());
+                return TopNRewriter {}.f_up(node);
             }
             LogicalPlan::Statement(s) => {
                 return plan_err!("Unsupported statement: {}", s.display());
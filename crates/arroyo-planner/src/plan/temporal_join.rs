@@ -0,0 +1,268 @@
+use std::sync::Arc;
+
+use datafusion::common::{plan_err, Column, Result, TableReference};
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::{Extension, LogicalPlan};
+use datafusion::sql::sqlparser::ast::{
+    self, FunctionArg, FunctionArgExpr, Ident, JoinOperator, ObjectName, Query, SetExpr, Statement,
+    TableAlias, TableFactor, TableVersion, TableWithJoins,
+};
+use datafusion::sql::sqlparser::keywords::Keyword;
+use datafusion::sql::sqlparser::tokenizer::Token;
+
+use crate::extension::temporal_join::TemporalTableExtension;
+use crate::plan::match_recognize::{ident_name, is_keyword};
+use crate::tables::{produce_optimized_plan, Table};
+use crate::{parse_sql, rewrite_plan, ArroyoSchemaProvider};
+
+/// sqlparser only supports `FOR SYSTEM_TIME AS OF` in some dialects, so it's passed through as
+/// this table argument instead
+const SYSTEM_TIME_ARG: &str = "__arroyo_system_time";
+
+fn skip_whitespace(tokens: &[Token], mut index: usize) -> usize {
+    while matches!(tokens.get(index), Some(Token::Whitespace(_))) {
+        index += 1;
+    }
+    index
+}
+
+// matches `FOR SYSTEM_TIME AS OF <column>` at `start`, returning the tokens of the column and the
+// index of the token following it
+fn system_time_clause(tokens: &[Token], start: usize) -> Option<(Vec<Token>, usize)> {
+    if !is_keyword(tokens.get(start)?, Keyword::FOR) {
+        return None;
+    }
+    let mut index = start + 1;
+    for keyword in [Keyword::SYSTEM_TIME, Keyword::AS, Keyword::OF] {
+        index = skip_whitespace(tokens, index);
+        if !is_keyword(tokens.get(index)?, keyword) {
+            return None;
+        }
+        index += 1;
+    }
+
+    let mut column = vec![];
+    loop {
+        index = skip_whitespace(tokens, index);
+        let word @ Token::Word(_) = tokens.get(index)? else {
+            return None;
+        };
+        column.push(word.clone());
+        index += 1;
+        if tokens.get(index) != Some(&Token::Period) {
+            return Some((column, index));
+        }
+        column.push(Token::Period);
+        index += 1;
+    }
+}
+
+/// Rewrites `rates FOR SYSTEM_TIME AS OF o.ts` into `rates(__arroyo_system_time => o.ts)`, which
+/// sqlparser can parse and from which the planner takes the column back out.
+pub(crate) fn move_system_time_clauses(tokens: Vec<Token>) -> Vec<Token> {
+    let mut output = Vec::with_capacity(tokens.len());
+    let mut index = 0;
+    while index < tokens.len() {
+        match system_time_clause(&tokens, index) {
+            Some((column, next)) => {
+                output.push(Token::LParen);
+                output.push(Token::make_word(SYSTEM_TIME_ARG, None));
+                output.push(Token::RArrow);
+                output.extend(column);
+                output.push(Token::RParen);
+                index = next;
+            }
+            None => {
+                output.push(tokens[index].clone());
+                index += 1;
+            }
+        }
+    }
+    output
+}
+
+/// Replaces each table that's joined `FOR SYSTEM_TIME AS OF` a column of the left side with a
+/// table that marks it as versioned, so that the join is planned as a temporal join.
+pub(crate) fn plan_temporal_joins(
+    statement: &mut Statement,
+    schema_provider: &mut ArroyoSchemaProvider,
+    session_state: &SessionState,
+) -> Result<()> {
+    let query = match statement {
+        Statement::Query(query)
+        | Statement::Insert(ast::Insert {
+            source: Some(query),
+            ..
+        })
+        | Statement::CreateView { query, .. }
+        | Statement::CreateTable {
+            query: Some(query), ..
+        } => query,
+        _ => return Ok(()),
+    };
+
+    TemporalJoinPlanner {
+        schema_provider,
+        session_state,
+    }
+    .rewrite_query(query)
+}
+
+struct TemporalJoinPlanner<'a> {
+    schema_provider: &'a mut ArroyoSchemaProvider,
+    session_state: &'a SessionState,
+}
+
+impl<'a> TemporalJoinPlanner<'a> {
+    fn rewrite_query(&mut self, query: &mut Query) -> Result<()> {
+        if let Some(with) = &mut query.with {
+            for cte in &mut with.cte_tables {
+                self.rewrite_query(&mut cte.query)?;
+            }
+        }
+        self.rewrite_set_expr(&mut query.body)
+    }
+
+    fn rewrite_set_expr(&mut self, set_expr: &mut SetExpr) -> Result<()> {
+        match set_expr {
+            SetExpr::Select(select) => {
+                for table in &mut select.from {
+                    self.rewrite_table_with_joins(table)?;
+                }
+                Ok(())
+            }
+            SetExpr::Query(query) => self.rewrite_query(query),
+            SetExpr::SetOperation { left, right, .. } => {
+                self.rewrite_set_expr(left)?;
+                self.rewrite_set_expr(right)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn rewrite_table_with_joins(&mut self, table: &mut TableWithJoins) -> Result<()> {
+        self.rewrite_table_factor(&mut table.relation, false)?;
+        for join in &mut table.joins {
+            let versioned = matches!(
+                join.join_operator,
+                JoinOperator::Inner(_) | JoinOperator::LeftOuter(_)
+            );
+            self.rewrite_table_factor(&mut join.relation, versioned)?;
+        }
+        Ok(())
+    }
+
+    fn rewrite_table_factor(&mut self, factor: &mut TableFactor, versioned: bool) -> Result<()> {
+        match factor {
+            TableFactor::Derived { subquery, .. } => self.rewrite_query(subquery),
+            TableFactor::NestedJoin {
+                table_with_joins, ..
+            } => self.rewrite_table_with_joins(table_with_joins),
+            TableFactor::MatchRecognize { table, .. } => self.rewrite_table_factor(table, false),
+            TableFactor::Table { .. } => self.rewrite_table(factor, versioned),
+            _ => Ok(()),
+        }
+    }
+
+    fn rewrite_table(&mut self, factor: &mut TableFactor, versioned: bool) -> Result<()> {
+        let TableFactor::Table {
+            name,
+            alias,
+            args,
+            version,
+            ..
+        } = factor
+        else {
+            return Ok(());
+        };
+
+        let system_time_arg = match args.as_deref() {
+            Some(
+                [FunctionArg::Named {
+                    name,
+                    arg: FunctionArgExpr::Expr(expr),
+                    ..
+                }],
+            ) if name.value == SYSTEM_TIME_ARG => Some(expr.clone()),
+            _ => None,
+        };
+        if system_time_arg.is_some() {
+            *args = None;
+        }
+        let system_time = match (version.take(), system_time_arg) {
+            (Some(TableVersion::ForSystemTimeAsOf(expr)), _) | (None, Some(expr)) => expr,
+            (None, None) => return Ok(()),
+        };
+
+        if !versioned {
+            return plan_err!(
+                "FOR SYSTEM_TIME AS OF is only supported for the right side of an inner or \
+                left join"
+            );
+        }
+        let system_time = match &system_time {
+            ast::Expr::Identifier(ident) => Column::new_unqualified(ident_name(ident)),
+            ast::Expr::CompoundIdentifier(idents) if idents.len() == 2 => Column::new(
+                Some(TableReference::bare(ident_name(&idents[0]))),
+                ident_name(&idents[1]),
+            ),
+            _ => {
+                return plan_err!(
+                    "FOR SYSTEM_TIME AS OF must refer to a column of the left side of the join, \
+                    not {}",
+                    system_time
+                )
+            }
+        };
+
+        let table_name = name.0.iter().map(ident_name).collect::<Vec<_>>().join(".");
+        let primary_keys = match self.schema_provider.get_table(&table_name) {
+            Some(Table::ConnectorTable(table))
+                if table.is_updating() && !table.primary_keys.is_empty() =>
+            {
+                table.primary_keys.as_ref().clone()
+            }
+            Some(_) => {
+                return plan_err!(
+                    "FOR SYSTEM_TIME AS OF requires an updating table with a primary key, like \
+                    a Debezium source, but {} is not",
+                    table_name
+                )
+            }
+            None => return plan_err!("Table {} not found", table_name),
+        };
+
+        let schema_provider = &*self.schema_provider;
+        let input_statement = parse_sql(&format!("SELECT * FROM {}", name))?
+            .pop()
+            .unwrap();
+        let input = rewrite_plan(
+            produce_optimized_plan(&input_statement, schema_provider, self.session_state)?,
+            schema_provider,
+        )?;
+
+        let versioned_name = (0..)
+            .map(|i| format!("__temporal_table_{}", i))
+            .find(|name| self.schema_provider.get_table(name).is_none())
+            .unwrap();
+        self.schema_provider.insert_table(Table::TableFromQuery {
+            name: versioned_name.clone(),
+            logical_plan: LogicalPlan::Extension(Extension {
+                node: Arc::new(TemporalTableExtension {
+                    input,
+                    system_time,
+                    primary_keys,
+                }),
+            }),
+        });
+
+        // keep the original name for the table, so that the query can still refer to it
+        let original_name = name.0.last().cloned().unwrap();
+        *alias = alias.take().or(Some(TableAlias {
+            name: original_name,
+            columns: vec![],
+        }));
+        *name = ObjectName(vec![Ident::new(versioned_name)]);
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use arrow_schema::DataType;
+use arroyo_datastream::WindowType;
+use datafusion::common::tree_node::{Transformed, TreeNodeRewriter};
+use datafusion::common::{plan_err, Result as DFResult, ScalarValue};
+use datafusion::logical_expr::utils::{conjunction, split_conjunction};
+use datafusion::logical_expr::{
+    expr::WindowFunction, BinaryExpr, Expr, Extension, Filter, Limit, LogicalPlan, Operator, Sort,
+    Window, WindowFunctionDefinition,
+};
+
+use crate::extension::{top_n::TopNExtension, window_fn::WindowFunctionExtension};
+
+use super::WindowDetectingVisitor;
+
+/// Plans top-n queries over windows without running a full window function over every row:
+///  * a filter bounding the rank from `ROW_NUMBER()` (`WHERE row_num <= N`) over an already
+///    planned window function becomes a top-n that also emits the rank
+///  * `ORDER BY ... LIMIT N` over windowed input keeps the first N rows of each window
+pub(crate) struct TopNRewriter {}
+
+/// The limit implied by a predicate that bounds the rank column, if it is one
+pub(super) fn rank_bound(predicate: &Expr, rank_column: &str) -> Option<usize> {
+    let Expr::BinaryExpr(BinaryExpr { left, op, right }) = predicate else {
+        return None;
+    };
+
+    let (op, value) = match (left.as_ref(), right.as_ref()) {
+        (Expr::Column(column), Expr::Literal(value)) if column.name == rank_column => (*op, value),
+        (Expr::Literal(value), Expr::Column(column)) if column.name == rank_column => {
+            (op.swap()?, value)
+        }
+        _ => return None,
+    };
+
+    let ScalarValue::Int64(Some(value)) = value.cast_to(&DataType::Int64).ok()? else {
+        return None;
+    };
+
+    let limit = match op {
+        Operator::LtEq => value,
+        Operator::Lt => value - 1,
+        Operator::Eq if value == 1 => 1,
+        _ => return None,
+    };
+
+    (limit > 0).then_some(limit as usize)
+}
+
+pub(super) fn unaliased(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Alias(alias) => unaliased(&alias.expr),
+        expr => expr,
+    }
+}
+
+pub(super) fn is_row_number(fun: &WindowFunctionDefinition) -> bool {
+    fun.to_string().eq_ignore_ascii_case("row_number")
+}
+
+impl TopNRewriter {
+    fn rewrite_filter(filter: Filter) -> DFResult<Transformed<LogicalPlan>> {
+        let LogicalPlan::Extension(Extension { node }) = filter.input.as_ref() else {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        };
+        let Some(window_function) = node.as_any().downcast_ref::<WindowFunctionExtension>() else {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        };
+        let LogicalPlan::Window(Window {
+            input,
+            window_expr,
+            schema,
+            ..
+        }) = &window_function.window_plan
+        else {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        };
+        let LogicalPlan::Sort(Sort {
+            input: key_plan, ..
+        }) = input.as_ref()
+        else {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        };
+        let Expr::WindowFunction(WindowFunction { fun, order_by, .. }) = unaliased(&window_expr[0])
+        else {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        };
+        if !is_row_number(fun) || order_by.is_empty() {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        }
+
+        // the rank is the last column of the window's output
+        let rank_column = schema.field(schema.fields().len() - 1).name().clone();
+
+        let mut limit: Option<usize> = None;
+        let mut remaining = vec![];
+        for predicate in split_conjunction(&filter.predicate) {
+            match rank_bound(predicate, &rank_column) {
+                Some(bound) => limit = Some(limit.map_or(bound, |limit| limit.min(bound))),
+                None => remaining.push(predicate.clone()),
+            }
+        }
+        let Some(limit) = limit else {
+            return Ok(Transformed::no(LogicalPlan::Filter(filter)));
+        };
+
+        let top_n = LogicalPlan::Extension(Extension {
+            node: Arc::new(TopNExtension::new(
+                key_plan.as_ref().clone(),
+                window_function.key_fields.clone(),
+                order_by.clone(),
+                limit,
+                true,
+                schema.clone(),
+            )),
+        });
+
+        Ok(Transformed::yes(match conjunction(remaining) {
+            Some(predicate) => LogicalPlan::Filter(Filter::try_new(predicate, Arc::new(top_n))?),
+            None => top_n,
+        }))
+    }
+
+    fn rewrite_sort(sort: Sort) -> DFResult<Transformed<LogicalPlan>> {
+        let Some(fetch) = sort.fetch else {
+            return plan_err!(
+                "ORDER BY is only supported together with LIMIT on windowed inputs ({})",
+                LogicalPlan::Sort(sort).display()
+            );
+        };
+
+        match WindowDetectingVisitor::get_window(&sort.input)? {
+            None => {
+                return plan_err!("ORDER BY ... LIMIT requires windowed input");
+            }
+            Some(WindowType::Session { .. }) => {
+                return plan_err!("ORDER BY ... LIMIT does not support session windows");
+            }
+            Some(_) => {}
+        }
+
+        let schema = sort.input.schema().clone();
+        Ok(Transformed::yes(LogicalPlan::Extension(Extension {
+            node: Arc::new(TopNExtension::new(
+                sort.input.as_ref().clone(),
+                vec![],
+                sort.expr,
+                fetch,
+                false,
+                schema,
+            )),
+        })))
+    }
+
+    fn rewrite_limit(limit: Limit) -> DFResult<Transformed<LogicalPlan>> {
+        // the limit is redundant if it was pushed down into a sort that became a top-n
+        if let LogicalPlan::Extension(Extension { node }) = limit.input.as_ref() {
+            if let Some(top_n) = node.as_any().downcast_ref::<TopNExtension>() {
+                if limit.skip == 0 && limit.fetch.is_some_and(|fetch| fetch >= top_n.limit()) {
+                    return Ok(Transformed::yes(limit.input.as_ref().clone()));
+                }
+            }
+        }
+
+        plan_err!(
+            "LIMIT is only supported together with ORDER BY on windowed inputs ({})",
+            LogicalPlan::Limit(limit).display()
+        )
+    }
+}
+
+impl TreeNodeRewriter for TopNRewriter {
+    type Node = LogicalPlan;
+
+    fn f_up(&mut self, node: Self::Node) -> DFResult<Transformed<Self::Node>> {
+        match node {
+            LogicalPlan::Filter(filter) => Self::rewrite_filter(filter),
+            LogicalPlan::Sort(sort) => Self::rewrite_sort(sort),
+            LogicalPlan::Limit(limit) => Self::rewrite_limit(limit),
+            node => Ok(Transformed::no(node)),
+        }
+    }
+}
@@ -26,8 +26,11 @@ use datafusion::common::{
 };
 use datafusion::logical_expr;
 use datafusion::logical_expr::expr::ScalarFunction;
+use datafusion::logical_expr::expr::WindowFunction;
 use datafusion::logical_expr::{
-    BinaryExpr, Expr, Extension, LogicalPlan, Projection, TableScan, Unnest,
+    col, lit, BinaryExpr, BuiltInWindowFunction, Distinct, DistinctOn, Expr, Extension,
+    LogicalPlan, LogicalPlanBuilder, Projection, TableScan, Unnest, WindowFrame,
+    WindowFunctionDefinition,
 };
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -626,7 +629,7 @@ pub struct TimeWindowUdfChecker {}
 pub fn is_time_window(expr: &Expr) -> Option<&str> {
     if let Expr::ScalarFunction(ScalarFunction { func, args: _ }) = expr {
         match func.name() {
-            "tumble" | "hop" | "session" => {
+            "tumble" | "hop" | "session" | "cumulate" => {
                 return Some(func.name());
             }
             _ => {}
@@ -679,3 +682,55 @@ impl TreeNodeRewriter for TimeWindowNullCheckRemover {
         Ok(Transformed::no(node))
     }
 }
+
+pub const DISTINCT_ON_RANK: &str = "__distinct_on_rank";
+
+/// Rewrites `SELECT DISTINCT ON (keys) ... ORDER BY ...` into a filter on the equivalent
+/// `ROW_NUMBER()`, so that it is planned as a dedup (or over windows, as a top-n) rather than
+/// the aggregate that DataFusion would replace it with
+pub struct DistinctOnRewriter {}
+
+impl TreeNodeRewriter for DistinctOnRewriter {
+    type Node = LogicalPlan;
+
+    fn f_up(&mut self, node: Self::Node) -> DFResult<Transformed<Self::Node>> {
+        let LogicalPlan::Distinct(Distinct::On(DistinctOn {
+            on_expr,
+            select_expr,
+            sort_expr,
+            input,
+            ..
+        })) = node
+        else {
+            return Ok(Transformed::no(node));
+        };
+
+        // DISTINCT ON requires the ORDER BY to start with the ON expressions, which don't order
+        // the rows within a partition
+        let order_by: Vec<_> = sort_expr
+            .unwrap_or_default()
+            .into_iter()
+            .filter(
+                |expr| !matches!(expr, Expr::Sort(sort) if on_expr.contains(sort.expr.as_ref())),
+            )
+            .collect();
+        let window_frame = WindowFrame::new((!order_by.is_empty()).then_some(false));
+        let row_number = Expr::WindowFunction(WindowFunction {
+            fun: WindowFunctionDefinition::BuiltInWindowFunction(BuiltInWindowFunction::RowNumber),
+            args: vec![],
+            partition_by: on_expr,
+            order_by,
+            window_frame,
+            null_treatment: None,
+        })
+        .alias(DISTINCT_ON_RANK);
+
+        let plan = LogicalPlanBuilder::from(input.as_ref().clone())
+            .window(vec![row_number])?
+            .filter(col(DISTINCT_ON_RANK).eq(lit(1u64)))?
+            .project(select_expr)?
+            .build()?;
+
+        Ok(Transformed::yes(plan))
+    }
+}
@@ -6,6 +6,7 @@ use arrow_schema::{DataType, Field, FieldRef, Schema};
 use arroyo_connectors::connector_for_type;
 
 use crate::extension::remote_table::RemoteTableExtension;
+use crate::rewriters::DistinctOnRewriter;
 use crate::types::convert_data_type;
 use crate::{
     external::{ProcessingMode, SqlSource},
@@ -75,6 +76,7 @@ pub struct ConnectorTable {
     pub watermark_field: Option<String>,
     pub idle_time: Option<Duration>,
     pub primary_keys: Arc<Vec<String>>,
+    pub bad_data: Option<BadData>,
 
     pub inferred_fields: Option<Vec<DFField>>,
 }
@@ -115,11 +117,10 @@ impl From<Field> for FieldSpec {
     }
 }
 
-fn produce_optimized_plan(
-    statement: &Statement,
-    schema_provider: &ArroyoSchemaProvider,
+pub(crate) fn sql_to_rel<'a>(
+    schema_provider: &'a ArroyoSchemaProvider,
     session_state: &SessionState,
-) -> Result<LogicalPlan> {
+) -> SqlToRel<'a, ArroyoSchemaProvider> {
     let mut sql_to_rel = SqlToRel::new(schema_provider);
 
     for planner in session_state.expr_planners() {
@@ -128,8 +129,16 @@ fn produce_optimized_plan(
     for planner in schema_provider.expr_planners() {
         sql_to_rel = sql_to_rel.with_user_defined_planner(planner);
     }
+    sql_to_rel
+}
 
-    let plan = sql_to_rel.sql_statement_to_plan(statement.clone())?;
+pub(crate) fn produce_optimized_plan(
+    statement: &Statement,
+    schema_provider: &ArroyoSchemaProvider,
+    session_state: &SessionState,
+) -> Result<LogicalPlan> {
+    let plan =
+        sql_to_rel(schema_provider, session_state).sql_statement_to_plan(statement.clone())?;
 
     let analyzed_plan = schema_provider.analyzer.execute_and_check(
         plan,
@@ -137,6 +146,11 @@ fn produce_optimized_plan(
         |_plan, _rule| {},
     )?;
 
+    // this has to happen before DISTINCT ON is replaced by an aggregate
+    let analyzed_plan = analyzed_plan
+        .rewrite_with_subqueries(&mut DistinctOnRewriter {})?
+        .data;
+
     let rules: Vec<Arc<dyn OptimizerRule + Send + Sync>> = vec![
         Arc::new(EliminateNestedUnion::new()),
         Arc::new(SimplifyExpressions::new()),
@@ -206,6 +220,7 @@ impl From<Connection> for ConnectorTable {
             watermark_field: None,
             idle_time: DEFAULT_IDLE_TIME,
             primary_keys: Arc::new(vec![]),
+            bad_data: value.schema.bad_data.clone(),
             inferred_fields: None,
         }
     }
@@ -250,7 +265,7 @@ impl ConnectorTable {
 
         let mut input_to_schema_fields = fields.clone();
 
-        if let Some(Format::Json(JsonFormat { debezium: true, .. })) = &format {
+        if format.as_ref().is_some_and(Format::is_updating) {
             // check that there are no virtual fields in fields
             if fields.iter().any(|f| f.is_virtual()) {
                 return plan_err!("can't use virtual fields with debezium format");
@@ -327,6 +342,18 @@ impl ConnectorTable {
             );
         }
 
+        if table.connection_type == ConnectionType::Sink
+            && matches!(
+                table.format,
+                Some(Format::Json(JsonFormat {
+                    cdc_envelope: Some(_),
+                    ..
+                }))
+            )
+        {
+            return plan_err!("maxwell_json and canal_json can only be used for sources");
+        }
+
         if table.connection_type == ConnectionType::Source
             && table.is_updating()
             && primary_keys.is_empty()
@@ -821,6 +848,15 @@ impl Table {
             }
         }
 
+        if t.connection_type == ConnectionType::Sink {
+            if let Some(connector) = connector_for_type(&t.connector) {
+                let sink_fields = fields.iter().map(|f| f.field().clone()).collect();
+                connector
+                    .check_sink_fields(&t.config, &sink_fields)
+                    .map_err(|e| DataFusionError::Plan(e.to_string()))?;
+            }
+        }
+
         t.inferred_fields.replace(fields);
 
         Ok(())
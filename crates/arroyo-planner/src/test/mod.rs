@@ -5,6 +5,7 @@ use arroyo_connectors::{
     nexmark::{NexmarkConnector, NexmarkTable},
     EmptyConfig,
 };
+use arroyo_datastream::logical::OperatorName;
 use arroyo_operator::connector::Connector;
 use arroyo_udf_host::parse::NullableType;
 use test_log::test;
@@ -58,3 +59,72 @@ async fn test_udf() {
         .await
         .unwrap();
 }
+
+#[test(tokio::test)]
+async fn test_explain() {
+    let sql = "EXPLAIN SELECT bid.auction, tumble(interval '1 second') as window, count(*)
+        FROM nexmark WHERE bid IS NOT NULL GROUP BY 1, 2";
+    let compiled = parse_and_get_program(sql, get_test_schema_provider(), SqlConfig::default())
+        .await
+        .unwrap();
+
+    let explain = compiled.explain.unwrap();
+    assert!(explain.contains("[ConnectorSource(nexmark)] parallelism=1"));
+    assert!(explain.contains("window: TumblingWindow(1s)"));
+    assert!(explain.contains("state: t (time-keyed, retention 1s)"));
+    assert!(explain.contains("(Shuffle) keys=("));
+    assert!(!explain.contains("schema: "));
+
+    let verbose = parse_and_get_program(
+        &sql.replace("EXPLAIN", "EXPLAIN VERBOSE"),
+        get_test_schema_provider(),
+        SqlConfig::default(),
+    )
+    .await
+    .unwrap()
+    .explain
+    .unwrap();
+    assert!(verbose.contains("schema: ("));
+
+    let plain = parse_and_get_program(
+        &sql.replace("EXPLAIN", ""),
+        get_test_schema_provider(),
+        SqlConfig::default(),
+    )
+    .await
+    .unwrap();
+    assert!(plain.explain.is_none());
+}
+
+#[test(tokio::test)]
+async fn test_top_n_parallelism() {
+    let top_n_parallelism = |sql: &'static str| async move {
+        let compiled = parse_and_get_program(
+            sql,
+            get_test_schema_provider(),
+            SqlConfig {
+                default_parallelism: 3,
+            },
+        )
+        .await
+        .unwrap();
+        compiled
+            .program
+            .graph
+            .node_weights()
+            .find(|node| node.operator_name == OperatorName::TopN)
+            .unwrap()
+            .parallelism
+    };
+
+    // ranking within each auction can be split by auction
+    assert_eq!(
+        top_n_parallelism(include_str!("queries/top_n_per_window.sql")).await,
+        3
+    );
+    // but ranking the whole window can't
+    assert_eq!(
+        top_n_parallelism(include_str!("queries/windowed_order_by_limit.sql")).await,
+        1
+    );
+}
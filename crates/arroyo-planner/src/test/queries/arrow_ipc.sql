@@ -0,0 +1,26 @@
+CREATE TABLE readings (
+    sensor_id BIGINT,
+    value DOUBLE
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'readings',
+    format = 'arrow_ipc'
+);
+
+CREATE TABLE scaled (
+    sensor_id BIGINT,
+    value DOUBLE
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'sink',
+    topic = 'scaled',
+    format = 'arrow_ipc'
+);
+
+INSERT INTO scaled
+SELECT sensor_id, value * 10
+FROM readings
+WHERE value IS NOT NULL;
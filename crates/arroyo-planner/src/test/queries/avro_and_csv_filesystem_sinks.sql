@@ -0,0 +1,33 @@
+CREATE TABLE impulse WITH (
+    connector = 'impulse',
+    event_rate = '10'
+);
+
+CREATE TABLE avro_sink (
+    counter BIGINT UNSIGNED,
+    subtask_index BIGINT UNSIGNED
+) WITH (
+    connector = 'filesystem',
+    type = 'sink',
+    path = '/tmp/arroyo/avro',
+    format = 'avro',
+    avro_codec = 'snappy',
+    rollover_seconds = '60'
+);
+
+CREATE TABLE csv_sink (
+    counter BIGINT UNSIGNED,
+    subtask_index BIGINT UNSIGNED
+) WITH (
+    connector = 'filesystem',
+    type = 'sink',
+    path = '/tmp/arroyo/csv',
+    format = 'csv',
+    csv_delimiter = '|',
+    csv_include_header = 'false',
+    rollover_seconds = '60'
+);
+
+INSERT INTO avro_sink SELECT counter, subtask_index FROM impulse;
+
+INSERT INTO csv_sink SELECT counter, subtask_index FROM impulse;
@@ -0,0 +1,16 @@
+CREATE TABLE Nexmark WITH (
+    connector = 'nexmark',
+    event_rate = '10'
+);
+
+SELECT
+    bid.auction as auction,
+    cumulate(interval '1 minute', interval '1 day') as window,
+    count(*) as count,
+    sum(bid.price) as total
+FROM
+    nexmark
+where
+    bid is not null
+GROUP BY
+1,2
@@ -0,0 +1,36 @@
+CREATE TABLE cars (
+  timestamp TIMESTAMP,
+  driver_id BIGINT,
+  event_type TEXT,
+  location TEXT
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/cars.json',
+  format = 'json',
+  type = 'source',
+  bad_data = 'dead_letter',
+  dead_letter_table = 'cars_dead_letters'
+);
+
+CREATE TABLE cars_dead_letters (
+  error TEXT,
+  value BYTEA,
+  metadata TEXT
+) WITH (
+  connector = 'single_file',
+  path = '$output_dir/dead_letters.json',
+  format = 'json',
+  type = 'sink'
+);
+
+CREATE TABLE cars_output (
+  driver_id BIGINT,
+  event_type TEXT
+) WITH (
+  connector = 'single_file',
+  path = '$output_path',
+  format = 'json',
+  type = 'sink'
+);
+
+INSERT INTO cars_output SELECT driver_id, event_type FROM cars;
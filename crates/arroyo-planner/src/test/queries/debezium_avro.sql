@@ -0,0 +1,49 @@
+CREATE TABLE orders (
+    id BIGINT PRIMARY KEY,
+    status TEXT
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'shop.orders',
+    format = 'debezium_avro'
+);
+
+CREATE TABLE order_events (
+    order_id BIGINT,
+    status TEXT
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'order_events',
+    format = 'json'
+);
+
+CREATE TABLE open_orders (
+    id BIGINT,
+    status TEXT
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'sink',
+    topic = 'open_orders',
+    format = 'debezium_avro'
+);
+
+CREATE TABLE status_counts (
+    status TEXT,
+    count BIGINT
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'sink',
+    topic = 'status_counts',
+    format = 'debezium_avro'
+);
+
+INSERT INTO open_orders
+SELECT id, status FROM orders WHERE status != 'shipped';
+
+INSERT INTO status_counts
+SELECT status, count(*) FROM order_events GROUP BY status;
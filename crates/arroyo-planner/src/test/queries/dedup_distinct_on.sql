@@ -0,0 +1,17 @@
+CREATE TABLE events (
+    event_id TEXT,
+    user_id TEXT,
+    event_time TIMESTAMP
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'events',
+    format = 'json',
+    event_time_field = 'event_time'
+);
+
+SELECT DISTINCT ON (event_id) event_id, user_id, event_time
+FROM (
+    SELECT * FROM events WHERE user_id IS NOT NULL)
+ORDER BY event_id, event_time
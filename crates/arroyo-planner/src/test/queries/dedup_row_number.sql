@@ -0,0 +1,18 @@
+CREATE TABLE events (
+    event_id TEXT,
+    user_id TEXT,
+    event_time TIMESTAMP
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'events',
+    format = 'json',
+    event_time_field = 'event_time'
+);
+
+SELECT * FROM (
+    SELECT *, ROW_NUMBER() OVER (
+        PARTITION BY event_id, user_id
+        ORDER BY event_time) AS row_num
+    FROM events) WHERE row_num = 1
@@ -0,0 +1,12 @@
+--fail=arrow_ipc is not supported by the FileSystem connector
+CREATE TABLE files (
+    id INT,
+    name TEXT
+) WITH (
+    connector = 'filesystem',
+    type = 'source',
+    path = '/tmp/arroyo/input',
+    format = 'arrow_ipc'
+);
+
+SELECT * FROM files;
@@ -0,0 +1,13 @@
+--fail=which is not supported for deserialization
+CREATE TABLE events (
+    id BIGINT,
+    gap INTERVAL
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'events',
+    format = 'avro'
+);
+
+SELECT id FROM events;
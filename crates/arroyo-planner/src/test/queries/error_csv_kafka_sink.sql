@@ -0,0 +1,14 @@
+--fail=csv format is not supported for kafka connector 'sink'
+CREATE TABLE sink (
+    id INT,
+    count BIGINT
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'sink',
+    topic = 'sink',
+    format = 'csv'
+);
+
+INSERT INTO sink
+SELECT 1, 2;
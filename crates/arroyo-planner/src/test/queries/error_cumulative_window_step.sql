@@ -0,0 +1,16 @@
+--fail=Error during planning: cumulate() max size 600s must be a positive multiple of step 180s
+CREATE TABLE Nexmark WITH (
+    connector = 'nexmark',
+    event_rate = '10'
+);
+
+SELECT
+    bid.auction as auction,
+    cumulate(interval '3 minute', interval '10 minute') as window,
+    count(*) as count
+FROM
+    nexmark
+where
+    bid is not null
+GROUP BY
+1,2
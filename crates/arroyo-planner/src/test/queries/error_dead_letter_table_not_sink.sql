@@ -0,0 +1,35 @@
+--fail=dead-letter table 'other_cars' for source 'cars' must be a sink
+CREATE TABLE cars (
+  timestamp TIMESTAMP,
+  driver_id BIGINT,
+  event_type TEXT,
+  location TEXT
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/cars.json',
+  format = 'json',
+  type = 'source',
+  bad_data = 'dead_letter',
+  dead_letter_table = 'other_cars'
+);
+
+CREATE TABLE other_cars (
+  driver_id BIGINT
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/other_cars.json',
+  format = 'json',
+  type = 'source'
+);
+
+CREATE TABLE cars_output (
+  driver_id BIGINT,
+  event_type TEXT
+) WITH (
+  connector = 'single_file',
+  path = '$output_path',
+  format = 'json',
+  type = 'sink'
+);
+
+INSERT INTO cars_output SELECT driver_id, event_type FROM cars;
@@ -0,0 +1,12 @@
+--fail=Window functions require already windowed input
+CREATE TABLE Nexmark WITH (
+    connector = 'nexmark',
+    event_rate = '10'
+);
+
+SELECT * FROM (
+    SELECT *, ROW_NUMBER() OVER (
+        PARTITION BY bid.auction, bid.bidder
+        ORDER BY bid.datetime) AS row_num
+    FROM nexmark
+    WHERE bid is not null) WHERE row_num = 1
@@ -0,0 +1,18 @@
+--fail=Error during planning: early firing and allowed lateness are only supported for tumbling and sliding windows
+CREATE TABLE Nexmark WITH (
+    connector = 'nexmark',
+    event_rate = '10'
+);
+
+SET window_early_fire = '10 seconds';
+
+SELECT
+    bid.auction as auction,
+    cumulate(interval '1 minute', interval '1 hour') as window,
+    count(*) as count
+FROM
+    nexmark
+where
+    bid is not null
+GROUP BY
+1,2
@@ -0,0 +1,12 @@
+--fail=Kafka sink configured with header field 'trace_id', but it has type Int64, not TEXT or BYTEA
+CREATE TABLE sink WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'sink',
+    topic = 'events',
+    format = 'json',
+    'sink.header_fields' = 'trace_id'
+);
+
+INSERT INTO sink
+SELECT 1 as trace_id, 1 as value;
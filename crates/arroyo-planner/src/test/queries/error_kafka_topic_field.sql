@@ -0,0 +1,13 @@
+--fail=Kafka sink configured with topic_field 'destination', but that does not appear in the schema
+CREATE TABLE sink WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'sink',
+    topic = 'fallback',
+    format = 'json',
+    'sink.topic_field' = 'destination',
+    'sink.allowed_topics' = 'events.*'
+);
+
+INSERT INTO sink
+SELECT 'events.a' as dest, 1 as value;
@@ -0,0 +1,29 @@
+--fail=late-records table 'cars' must be a sink
+CREATE TABLE cars (
+  timestamp TIMESTAMP,
+  driver_id BIGINT,
+  event_type TEXT,
+  location TEXT
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/cars.json',
+  format = 'json',
+  type = 'source',
+  event_time_field = 'timestamp'
+);
+
+CREATE TABLE driver_counts (
+  driver_id BIGINT,
+  count BIGINT
+) WITH (
+  connector = 'single_file',
+  path = '$output_path',
+  format = 'json',
+  type = 'sink'
+);
+
+SET window_late_records_table = 'cars';
+
+INSERT INTO driver_counts
+SELECT driver_id, count(*) FROM cars
+GROUP BY driver_id, tumble(interval '1 minute');
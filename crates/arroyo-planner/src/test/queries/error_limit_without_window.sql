@@ -0,0 +1,6 @@
+--fail=ORDER BY ... LIMIT requires windowed input
+SELECT bid.auction AS auction, bid.price AS price
+FROM nexmark
+WHERE bid is not null
+ORDER BY price DESC
+LIMIT 10
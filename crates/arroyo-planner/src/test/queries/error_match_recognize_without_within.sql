@@ -0,0 +1,24 @@
+--fail=MATCH_RECOGNIZE requires a WITHIN clause
+CREATE TABLE logins (
+  event_time TIMESTAMP,
+  user_id BIGINT,
+  success BOOLEAN
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/logins.json',
+  format = 'json',
+  type = 'source',
+  event_time_field = 'event_time'
+);
+
+SELECT m.user_id, m.failures
+FROM logins
+MATCH_RECOGNIZE (
+  PARTITION BY user_id
+  ORDER BY event_time
+  MEASURES COUNT(F.*) AS failures
+  PATTERN (F+ S)
+  DEFINE
+    F AS NOT success,
+    S AS success
+) AS m
@@ -0,0 +1,14 @@
+--fail=Error during planning: maxwell_json and canal_json can only be used for sources
+CREATE TABLE sink (
+    id INT,
+    count BIGINT
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'sink',
+    topic = 'sink',
+    format = 'maxwell_json'
+);
+
+INSERT INTO sink
+SELECT 1, 2;
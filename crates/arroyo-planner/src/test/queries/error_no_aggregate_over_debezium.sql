@@ -1,4 +1,4 @@
---fail=Error during planning: can't currently nest updating aggregates
+--fail=is not supported over updating inputs
 CREATE TABLE debezium_input (
     id int PRIMARY KEY,
     count int
@@ -10,4 +10,4 @@ CREATE TABLE debezium_input (
     format = 'debezium_json'
 );
 
-SELECT count(*) FROM debezium_input
\ No newline at end of file
+SELECT max(count) FROM debezium_input
\ No newline at end of file
@@ -1,4 +1,4 @@
---fail=Error during planning: can't currently nest updating aggregates
+--fail=is not supported over updating inputs
 CREATE TABLE impulse with (
     connector = 'impulse',
     event_rate = '10'
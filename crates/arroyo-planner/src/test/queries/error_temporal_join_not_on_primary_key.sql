@@ -0,0 +1,30 @@
+--fail=temporal joins must join on the primary key of the versioned table (currency)
+CREATE TABLE orders (
+    order_id BIGINT,
+    currency TEXT,
+    amount DOUBLE,
+    order_time TIMESTAMP
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'orders',
+    format = 'json',
+    event_time_field = 'order_time'
+);
+
+CREATE TABLE rates (
+    currency TEXT PRIMARY KEY,
+    rate DOUBLE
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'rates',
+    format = 'debezium_json'
+);
+
+SELECT o.order_id, r.currency
+FROM orders AS o
+JOIN rates FOR SYSTEM_TIME AS OF o.order_time AS r
+ON o.amount = r.rate
@@ -0,0 +1,30 @@
+CREATE TABLE logins (
+  event_time TIMESTAMP,
+  user_id BIGINT,
+  success BOOLEAN,
+  ip TEXT
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/logins.json',
+  format = 'json',
+  type = 'source',
+  event_time_field = 'event_time'
+);
+
+SELECT m.user_id, m.failures, m.first_failure, m.login_ip
+FROM logins
+MATCH_RECOGNIZE (
+  PARTITION BY user_id
+  ORDER BY event_time
+  MEASURES
+    COUNT(F.*) AS failures,
+    FIRST(F.event_time) AS first_failure,
+    S.ip AS login_ip
+  ONE ROW PER MATCH
+  AFTER MATCH SKIP PAST LAST ROW
+  PATTERN (F{3,} S)
+  WITHIN INTERVAL '5' MINUTE
+  DEFINE
+    F AS NOT success,
+    S AS success
+) AS m
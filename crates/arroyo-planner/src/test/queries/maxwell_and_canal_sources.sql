@@ -0,0 +1,51 @@
+CREATE TABLE orders (
+    id BIGINT PRIMARY KEY,
+    customer_id BIGINT,
+    status TEXT,
+    updated_at TIMESTAMP
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'maxwell',
+    format = 'maxwell_json'
+);
+
+CREATE TABLE customers (
+    id BIGINT PRIMARY KEY,
+    name TEXT
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'canal',
+    format = 'canal_json'
+);
+
+CREATE TABLE order_sink (
+    id BIGINT,
+    status TEXT
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'sink',
+    topic = 'orders',
+    format = 'debezium_json'
+);
+
+CREATE TABLE customer_sink (
+    id BIGINT,
+    name TEXT
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'sink',
+    topic = 'customers',
+    format = 'debezium_json'
+);
+
+INSERT INTO order_sink
+SELECT id, status FROM orders WHERE status != 'cancelled';
+
+INSERT INTO customer_sink
+SELECT id, upper(name) FROM customers;
@@ -0,0 +1,18 @@
+CREATE TABLE orders (
+    user_id BIGINT,
+    amount DOUBLE
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'orders',
+    format = 'json'
+);
+
+SELECT order_count, count(*) AS users, sum(total) AS total, avg(total) AS avg_total
+FROM (
+    SELECT user_id, count(*) AS order_count, sum(amount) AS total
+    FROM orders
+    GROUP BY user_id
+)
+GROUP BY order_count;
@@ -0,0 +1,18 @@
+CREATE TABLE Nexmark WITH (
+    connector = 'nexmark',
+    event_rate = '10'
+);
+
+SET window_early_fire = '10 seconds';
+SET window_allowed_lateness = '1 minute';
+
+SELECT
+    bid.auction as auction,
+    hop(interval '1 minute', interval '1 hour') as window,
+    count(*) as count
+FROM
+    nexmark
+where
+    bid is not null
+GROUP BY
+1,2
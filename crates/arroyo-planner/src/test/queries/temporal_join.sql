@@ -0,0 +1,29 @@
+CREATE TABLE orders (
+    order_id BIGINT,
+    currency TEXT,
+    amount DOUBLE,
+    order_time TIMESTAMP
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'orders',
+    format = 'json',
+    event_time_field = 'order_time'
+);
+
+CREATE TABLE rates (
+    currency TEXT PRIMARY KEY,
+    rate DOUBLE
+) WITH (
+    connector = 'kafka',
+    bootstrap_servers = 'localhost:9092',
+    type = 'source',
+    topic = 'rates',
+    format = 'debezium_json'
+);
+
+SELECT o.order_id, o.amount * r.rate AS converted, r.rate
+FROM orders AS o
+LEFT JOIN rates FOR SYSTEM_TIME AS OF o.order_time AS r
+ON o.currency = r.currency
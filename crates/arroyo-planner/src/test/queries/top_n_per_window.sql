@@ -0,0 +1,8 @@
+SELECT * FROM (
+    SELECT *, ROW_NUMBER() OVER (
+        PARTITION BY window, auction
+        ORDER BY count DESC) AS row_num
+    FROM (SELECT count(*) AS count, bid.auction AS auction, bid.bidder AS bidder,
+        tumble(interval '10 seconds') AS window
+            FROM nexmark WHERE bid is not null
+            GROUP BY 2, 3, window)) WHERE row_num <= 3 AND count > 1
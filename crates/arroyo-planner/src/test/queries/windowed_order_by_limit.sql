@@ -0,0 +1,6 @@
+SELECT count(*) AS count, bid.auction AS auction, tumble(interval '10 seconds') AS window
+FROM nexmark
+WHERE bid is not null
+GROUP BY 2, window
+ORDER BY count DESC
+LIMIT 5
@@ -15,6 +15,8 @@ pub struct ValidateQueryPost {
 pub struct QueryValidationResult {
     pub graph: Option<PipelineGraph>,
     pub errors: Vec<String>,
+    /// The text plan of the pipeline, for queries prefixed with `EXPLAIN [VERBOSE]`
+    pub explain: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
@@ -1,3 +1,8 @@
+use crate::df::ArroyoSchema;
+use arrow::datatypes::{DataType, Field};
+use arrow_array::timezone::Tz;
+use arroyo_types::ArroyoExtensionType;
+use chrono::format::{Item, StrftimeItems};
 use regex::Regex;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::cmp::Ordering;
@@ -15,7 +20,16 @@ pub enum TimestampFormat {
     #[default]
     #[serde(rename = "rfc3339")]
     RFC3339,
+    UnixSeconds,
     UnixMillis,
+    UnixMicros,
+    UnixNanos,
+    /// A strftime-style pattern, like `%Y-%m-%d %H:%M:%S%.f`. Timestamps that don't include an
+    /// offset are in `timezone`, which defaults to UTC.
+    Pattern {
+        pattern: String,
+        timezone: Option<String>,
+    },
 }
 
 impl TryFrom<&str> for TimestampFormat {
@@ -23,13 +37,44 @@ impl TryFrom<&str> for TimestampFormat {
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
-            "RFC3339" => Ok(TimestampFormat::RFC3339),
+            "RFC3339" | "rfc3339" => Ok(TimestampFormat::RFC3339),
+            "UnixSeconds" | "unix_seconds" => Ok(TimestampFormat::UnixSeconds),
             "UnixMillis" | "unix_millis" => Ok(TimestampFormat::UnixMillis),
+            "UnixMicros" | "unix_micros" => Ok(TimestampFormat::UnixMicros),
+            "UnixNanos" | "unix_nanos" => Ok(TimestampFormat::UnixNanos),
             _ => Err(()),
         }
     }
 }
 
+impl TimestampFormat {
+    /// Creates a pattern format, checking that the pattern and timezone are valid
+    pub fn pattern(pattern: String, timezone: Option<String>) -> Result<Self, String> {
+        if StrftimeItems::new(&pattern).any(|item| matches!(item, Item::Error)) {
+            return Err(format!("invalid timestamp pattern '{}'", pattern));
+        }
+
+        if let Some(tz) = &timezone {
+            Tz::from_str(tz).map_err(|_| format!("invalid timezone '{}'", tz))?;
+        }
+
+        Ok(TimestampFormat::Pattern { pattern, timezone })
+    }
+}
+
+/// Change-data-capture envelopes other than Debezium's. Records in these envelopes are converted
+/// to Debezium's `before`/`after`/`op` form when they're deserialized, so tables using them are
+/// planned the same way as Debezium tables.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CdcEnvelope {
+    /// Maxwell's `{"type": "insert", "data": {..}, "old": {..}}` records
+    Maxwell,
+    /// Canal's `{"type": "INSERT", "data": [..], "old": [..]}` records, which may contain
+    /// several rows
+    Canal,
+}
+
 #[derive(
     Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default, Hash, PartialOrd, ToSchema,
 )]
@@ -47,6 +92,10 @@ pub struct JsonFormat {
     #[serde(default)]
     pub debezium: bool,
 
+    /// set along with `debezium` for the CDC formats that use a different envelope
+    #[serde(default)]
+    pub cdc_envelope: Option<CdcEnvelope>,
+
     #[serde(default)]
     pub unstructured: bool,
 
@@ -55,7 +104,11 @@ pub struct JsonFormat {
 }
 
 impl JsonFormat {
-    fn from_opts(debezium: bool, opts: &mut HashMap<String, String>) -> Result<Self, String> {
+    fn from_opts(
+        debezium: bool,
+        cdc_envelope: Option<CdcEnvelope>,
+        opts: &mut HashMap<String, String>,
+    ) -> Result<Self, String> {
         let confluent_schema_registry = opts
             .remove("json.confluent_schema_registry")
             .filter(|t: &String| t == "true")
@@ -75,24 +128,39 @@ impl JsonFormat {
             .filter(|t| t == "true")
             .is_some();
 
-        let timestamp_format: TimestampFormat = opts
-            .remove("json.timestamp_format")
-            .map(|t| t.as_str().try_into())
-            .transpose()
-            .map_err(|_| "json.timestamp_format".to_string())?
-            .unwrap_or_else(|| {
-                if debezium {
-                    TimestampFormat::UnixMillis
-                } else {
-                    TimestampFormat::default()
+        let timezone = opts.remove("json.timezone");
+
+        let timestamp_format: TimestampFormat = match opts.remove("json.timestamp_format") {
+            // anything with a conversion specifier is a pattern
+            Some(t) if t.contains('%') => TimestampFormat::pattern(t, timezone)?,
+            t => {
+                if timezone.is_some() {
+                    return Err(
+                        "json.timezone can only be set when json.timestamp_format is a pattern"
+                            .to_string(),
+                    );
                 }
-            });
+
+                t.map(|t| t.as_str().try_into())
+                    .transpose()
+                    .map_err(|_| "json.timestamp_format".to_string())?
+                    .unwrap_or_else(|| {
+                        // maxwell and canal write timestamps as strings
+                        if debezium && cdc_envelope.is_none() {
+                            TimestampFormat::UnixMillis
+                        } else {
+                            TimestampFormat::default()
+                        }
+                    })
+            }
+        };
 
         Ok(Self {
             confluent_schema_registry,
             schema_id: None,
             include_schema,
             debezium,
+            cdc_envelope,
             unstructured,
             timestamp_format,
         })
@@ -183,6 +251,10 @@ pub struct AvroFormat {
     #[serde(default)]
     #[schema(read_only)]
     pub schema_id: Option<u32>,
+
+    /// whether records are Debezium change envelopes (`before`, `after` and `op`)
+    #[serde(default)]
+    pub debezium: bool,
 }
 
 impl AvroFormat {
@@ -197,6 +269,7 @@ impl AvroFormat {
             into_unstructured_json,
             reader_schema: None,
             schema_id: None,
+            debezium: false,
         }
     }
 
@@ -230,6 +303,15 @@ impl AvroFormat {
 #[serde(rename_all = "camelCase")]
 pub struct ParquetFormat {}
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvFormat {}
+
+/// Messages are Arrow IPC streams, each holding one or more record batches
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ArrowIpcFormat {}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProtobufFormat {
@@ -259,6 +341,8 @@ pub enum Format {
     Avro(AvroFormat),
     Protobuf(ProtobufFormat),
     Parquet(ParquetFormat),
+    Csv(CsvFormat),
+    ArrowIpc(ArrowIpcFormat),
     RawString(RawStringFormat),
     RawBytes(RawBytesFormat),
 }
@@ -270,23 +354,42 @@ impl Format {
         };
 
         Ok(Some(match name.as_str() {
-            "json" => Format::Json(JsonFormat::from_opts(false, opts)?),
-            "debezium_json" => Format::Json(JsonFormat::from_opts(true, opts)?),
+            "json" => Format::Json(JsonFormat::from_opts(false, None, opts)?),
+            "debezium_json" => Format::Json(JsonFormat::from_opts(true, None, opts)?),
+            "maxwell_json" => Format::Json(JsonFormat::from_opts(
+                true,
+                Some(CdcEnvelope::Maxwell),
+                opts,
+            )?),
+            "canal_json" => {
+                Format::Json(JsonFormat::from_opts(true, Some(CdcEnvelope::Canal), opts)?)
+            }
             "protobuf" => Format::Protobuf(ProtobufFormat::from_opts(opts)?),
             "avro" => Format::Avro(AvroFormat::from_opts(opts)?),
+            // debezium's avro envelopes are always written with the schema registry
+            "debezium_avro" => Format::Avro(AvroFormat {
+                confluent_schema_registry: true,
+                debezium: true,
+                ..AvroFormat::from_opts(opts)?
+            }),
             "raw_string" => Format::RawString(RawStringFormat {}),
             "raw_bytes" => Format::RawBytes(RawBytesFormat {}),
             "parquet" => Format::Parquet(ParquetFormat {}),
+            "csv" => Format::Csv(CsvFormat {}),
+            "arrow_ipc" => Format::ArrowIpc(ArrowIpcFormat {}),
             f => return Err(format!("Unknown format '{}'", f)),
         }))
     }
 
     pub fn is_updating(&self) -> bool {
         match self {
-            Format::Json(JsonFormat { debezium: true, .. }) => true,
+            Format::Json(JsonFormat { debezium: true, .. })
+            | Format::Avro(AvroFormat { debezium: true, .. }) => true,
             Format::Json(_)
             | Format::Avro(_)
             | Format::Parquet(_)
+            | Format::Csv(_)
+            | Format::ArrowIpc(_)
             | Format::RawString(_)
             | Format::Protobuf(_) => false,
             Format::RawBytes(_) => false,
@@ -299,6 +402,11 @@ impl Format {
 pub enum BadData {
     Fail {},
     Drop {},
+    /// Routes records that fail to deserialize, along with the error and source metadata, to the
+    /// named sink table
+    DeadLetter {
+        table: String,
+    },
 }
 
 impl Default for BadData {
@@ -316,11 +424,36 @@ impl BadData {
         let method = match method.as_str() {
             "drop" => BadData::Drop {},
             "fail" => BadData::Fail {},
+            "dead_letter" => BadData::DeadLetter {
+                table: opts.remove("dead_letter_table").ok_or_else(|| {
+                    "'dead_letter_table' must be set when bad_data is 'dead_letter'".to_string()
+                })?,
+            },
             f => return Err(format!("Unknown invalid data behavior '{}'", f)),
         };
 
         Ok(Some(method))
     }
+
+    /// Whether records that fail to deserialize should be kept so they can be sent to a
+    /// dead-letter table
+    pub fn is_dead_letter(&self) -> bool {
+        matches!(self, BadData::DeadLetter { .. })
+    }
+
+    /// The schema of records sent to a dead-letter table: the error, the raw input, and the
+    /// source's metadata fields as JSON, which for Kafka always include the topic, partition and
+    /// offset
+    pub fn dead_letter_schema() -> ArroyoSchema {
+        ArroyoSchema::from_fields(vec![
+            Field::new("error", DataType::Utf8, false),
+            Field::new("value", DataType::Binary, true),
+            ArroyoExtensionType::add_metadata(
+                Some(ArroyoExtensionType::JSON),
+                Field::new("metadata", DataType::Utf8, true),
+            ),
+        ])
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
@@ -337,6 +470,13 @@ impl Framing {
 
         let method = match method.as_str() {
             "newline" => FramingMethod::Newline(NewlineDelimitedFraming::from_opts(opts)?),
+            "octet_counting" => {
+                FramingMethod::OctetCounting(OctetCountingFraming::from_opts(opts)?)
+            }
+            "length_prefixed" => {
+                FramingMethod::LengthPrefixed(LengthPrefixedFraming::from_opts(opts)?)
+            }
+            "delimited" => FramingMethod::Delimited(DelimitedFraming::from_opts(opts)?),
             f => return Err(format!("Unknown framing method '{}'", f)),
         };
 
@@ -365,8 +505,197 @@ impl NewlineDelimitedFraming {
     }
 }
 
+/// Octet-counting framing as described in RFC 5425 and RFC 6587, where each message is
+/// prefixed by its length in bytes as ASCII digits followed by a space
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OctetCountingFraming {
+    pub max_message_length: Option<u64>,
+}
+
+impl OctetCountingFraming {
+    pub fn from_opts(opts: &mut HashMap<String, String>) -> Result<Self, String> {
+        let max_message_length = opts
+            .remove("framing.octet_counting.max_length")
+            .map(|t| u64::from_str(&t))
+            .transpose()
+            .map_err(|_| {
+                "invalid value for framing.octet_counting.max_length; must be an unsigned integer"
+                    .to_string()
+            })?;
+
+        Ok(OctetCountingFraming { max_message_length })
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Endianness {
+    #[default]
+    Big,
+    Little,
+}
+
+/// Each message is prefixed by its length in bytes, encoded as an unsigned integer of
+/// `prefix_bytes` bytes (1, 2, 4 or 8) with the configured byte order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LengthPrefixedFraming {
+    pub prefix_bytes: u8,
+    #[serde(default)]
+    pub endianness: Endianness,
+    pub max_message_length: Option<u64>,
+}
+
+impl LengthPrefixedFraming {
+    pub fn from_opts(opts: &mut HashMap<String, String>) -> Result<Self, String> {
+        let prefix_bytes = match opts.remove("framing.length_prefixed.size").as_deref() {
+            None | Some("4") => 4,
+            Some("1") => 1,
+            Some("2") => 2,
+            Some("8") => 8,
+            Some(s) => {
+                return Err(format!(
+                    "invalid value '{}' for framing.length_prefixed.size; must be one of 1, 2, 4 \
+                    or 8",
+                    s
+                ))
+            }
+        };
+
+        let endianness = match opts.remove("framing.length_prefixed.endianness").as_deref() {
+            None | Some("big") => Endianness::Big,
+            Some("little") => Endianness::Little,
+            Some(s) => {
+                return Err(format!(
+                    "invalid value '{}' for framing.length_prefixed.endianness; must be 'big' or \
+                    'little'",
+                    s
+                ))
+            }
+        };
+
+        let max_message_length = opts
+            .remove("framing.length_prefixed.max_length")
+            .map(|t| u64::from_str(&t))
+            .transpose()
+            .map_err(|_| {
+                "invalid value for framing.length_prefixed.max_length; must be an unsigned integer"
+                    .to_string()
+            })?;
+
+        Ok(LengthPrefixedFraming {
+            prefix_bytes,
+            endianness,
+            max_message_length,
+        })
+    }
+
+    /// Reads the length from the start of `buf`, which must contain at least `prefix_bytes` bytes
+    pub fn read_length(&self, buf: &[u8]) -> u64 {
+        let prefix = &buf[..self.prefix_bytes as usize];
+        let mut bytes = [0u8; 8];
+        match self.endianness {
+            Endianness::Big => {
+                bytes[8 - prefix.len()..].copy_from_slice(prefix);
+                u64::from_be_bytes(bytes)
+            }
+            Endianness::Little => {
+                bytes[..prefix.len()].copy_from_slice(prefix);
+                u64::from_le_bytes(bytes)
+            }
+        }
+    }
+
+    /// Encodes `len` as a length prefix, or returns None if it is too large for the prefix size
+    pub fn write_length(&self, len: u64) -> Option<Vec<u8>> {
+        let n = self.prefix_bytes as usize;
+        if n < 8 && len >= 1 << (n * 8) {
+            return None;
+        }
+
+        Some(match self.endianness {
+            Endianness::Big => len.to_be_bytes()[8 - n..].to_vec(),
+            Endianness::Little => len.to_le_bytes()[..n].to_vec(),
+        })
+    }
+}
+
+/// Messages are separated by an arbitrary sequence of bytes, like the ASCII record separator
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DelimitedFraming {
+    pub delimiter: Vec<u8>,
+    pub max_message_length: Option<u64>,
+}
+
+impl DelimitedFraming {
+    pub fn from_opts(opts: &mut HashMap<String, String>) -> Result<Self, String> {
+        let delimiter = opts.remove("framing.delimited.delimiter").ok_or_else(|| {
+            "framing.delimited.delimiter must be set for delimited framing".to_string()
+        })?;
+        let delimiter = unescape_bytes(&delimiter)
+            .map_err(|e| format!("invalid framing.delimited.delimiter: {}", e))?;
+        if delimiter.is_empty() {
+            return Err("framing.delimited.delimiter must not be empty".to_string());
+        }
+
+        let max_message_length = opts
+            .remove("framing.delimited.max_length")
+            .map(|t| u64::from_str(&t))
+            .transpose()
+            .map_err(|_| {
+                "invalid value for framing.delimited.max_length; must be an unsigned integer"
+                    .to_string()
+            })?;
+
+        Ok(DelimitedFraming {
+            delimiter,
+            max_message_length,
+        })
+    }
+}
+
+/// Converts a string with C-style escapes (like `\x1e`, `\t` or `\0`) into bytes
+fn unescape_bytes(s: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![];
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('r') => bytes.push(b'\r'),
+            Some('t') => bytes.push(b'\t'),
+            Some('0') => bytes.push(0),
+            Some('\\') => bytes.push(b'\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let b = (hex.len() == 2)
+                    .then(|| u8::from_str_radix(&hex, 16).ok())
+                    .flatten()
+                    .ok_or_else(|| format!("invalid escape '\\x{}'", hex))?;
+                bytes.push(b);
+            }
+            Some(c) => return Err(format!("unknown escape '\\{}'", c)),
+            None => return Err("ends with an incomplete escape".to_string()),
+        }
+    }
+
+    Ok(bytes)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum FramingMethod {
     Newline(NewlineDelimitedFraming),
+    OctetCounting(OctetCountingFraming),
+    LengthPrefixed(LengthPrefixedFraming),
+    Delimited(DelimitedFraming),
 }
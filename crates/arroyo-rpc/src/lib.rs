@@ -224,6 +224,9 @@ pub fn error_chain(e: anyhow::Error) -> String {
 
 pub const TIMESTAMP_FIELD: &str = "_timestamp";
 pub const UPDATING_META_FIELD: &str = "_updating_meta";
+/// The hidden count of the rows in each group of an aggregate over an updating input, which
+/// is used to remove the groups whose rows have all been retracted
+pub const UPDATING_ROW_COUNT_FIELD: &str = "_updating_row_count";
 
 pub fn updating_meta_fields() -> Fields {
     static UPDATING_META_FIELDS: OnceLock<Fields> = OnceLock::new();
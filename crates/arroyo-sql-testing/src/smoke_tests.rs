@@ -258,7 +258,12 @@ fn set_internal_parallelism(graph: &mut Graph<LogicalNode, LogicalEdge>, paralle
         let mut edges_to_make_shuffle = vec![];
         for node in graph.externals(Direction::Outgoing) {
             for edge in graph.edges_directed(node, Direction::Incoming) {
-                edges_to_make_shuffle.push(edge.id());
+                match edge.weight().edge_type {
+                    // side outputs are already partitioned like shuffles, and keep their type so
+                    // that the engine still routes dead letters and late records to them
+                    LogicalEdgeType::DeadLetter | LogicalEdgeType::LateRecords => {}
+                    _ => edges_to_make_shuffle.push(edge.id()),
+                }
             }
         }
         for node in graph.node_indices() {
@@ -331,31 +336,23 @@ async fn finish_from_checkpoint(job_id: &str, program: Program) {
     run_until_finished(&running_engine, &mut control_rx).await;
 }
 
+/// Runs the pipeline, checking each of `outputs`, given as (output, golden output) locations,
+/// after the initial run and after resuming from a checkpoint
 async fn run_pipeline_and_assert_outputs(
     job_id: &str,
     mut graph: LogicalGraph,
     checkpoint_interval: i32,
-    output_location: String,
-    golden_output_location: String,
+    outputs: &[(String, String)],
     udfs: &[LocalUdf],
     primary_keys: Option<&[&str]>,
 ) {
-    // remove output_location before running the pipeline
-    if std::path::Path::new(&output_location).exists() {
-        std::fs::remove_file(&output_location).unwrap();
-    }
+    // remove the outputs before running the pipeline
+    remove_outputs(outputs);
 
     let get_program =
         |graph: &LogicalGraph| Program::local_from_logical(job_id.to_string(), graph, udfs);
 
-    run_completely(
-        job_id,
-        get_program(&graph),
-        output_location.clone(),
-        golden_output_location.clone(),
-        primary_keys,
-    )
-    .await;
+    run_completely(job_id, get_program(&graph), outputs, primary_keys).await;
 
     // debezium sources can't be arbitrarily split because they are effectively stateful
     // and ordering matters
@@ -376,20 +373,21 @@ async fn run_pipeline_and_assert_outputs(
 
     finish_from_checkpoint(job_id, get_program(&graph)).await;
 
-    check_output_files(
-        "resuming from checkpointing",
-        output_location,
-        golden_output_location,
-        primary_keys,
-    )
-    .await;
+    for (output_location, golden_output_location) in outputs {
+        check_output_files(
+            "resuming from checkpointing",
+            output_location.clone(),
+            golden_output_location.clone(),
+            primary_keys,
+        )
+        .await;
+    }
 }
 
 async fn run_completely(
     job_id: &str,
     program: Program,
-    output_location: String,
-    golden_output_location: String,
+    outputs: &[(String, String)],
     primary_keys: Option<&[&str]>,
 ) {
     let engine = Engine::for_local(program, job_id.to_string());
@@ -401,15 +399,23 @@ async fn run_completely(
 
     run_until_finished(&running_engine, &mut control_rx).await;
 
-    check_output_files(
-        "initial run",
-        output_location.clone(),
-        golden_output_location,
-        primary_keys,
-    )
-    .await;
-    if std::path::Path::new(&output_location).exists() {
-        std::fs::remove_file(&output_location).unwrap();
+    for (output_location, golden_output_location) in outputs {
+        check_output_files(
+            "initial run",
+            output_location.clone(),
+            golden_output_location.clone(),
+            primary_keys,
+        )
+        .await;
+    }
+    remove_outputs(outputs);
+}
+
+fn remove_outputs(outputs: &[(String, String)]) {
+    for (output_location, _) in outputs {
+        if std::path::Path::new(output_location).exists() {
+            std::fs::remove_file(output_location).unwrap();
+        }
     }
 }
 
@@ -619,6 +625,30 @@ pub async fn correctness_run_codegen(
         parent_directory, test_name
     );
 
+    // replace $output_dir with outputs/query_name/, which holds the outputs of side-output sinks,
+    // like late-records tables; each is checked against the file of the same name in
+    // golden_outputs/query_name/
+    let physical_output_dir = format!(
+        "{}/arroyo-sql-testing/outputs/{}",
+        parent_directory, test_name
+    );
+    let query_string = query_string.replace("$output_dir", &physical_output_dir);
+    let golden_output_dir = format!(
+        "{}/arroyo-sql-testing/golden_outputs/{}",
+        parent_directory, test_name
+    );
+
+    let mut outputs = vec![(physical_output, golden_output_location)];
+    if let Ok(entries) = std::fs::read_dir(&golden_output_dir) {
+        for entry in entries {
+            let file_name = entry.unwrap().file_name().to_string_lossy().to_string();
+            outputs.push((
+                format!("{}/{}", physical_output_dir, file_name),
+                format!("{}/{}", golden_output_dir, file_name),
+            ));
+        }
+    }
+
     let udfs = get_udfs();
 
     let logical_program = get_graph(query_string.clone(), &udfs).await?;
@@ -626,8 +656,7 @@ pub async fn correctness_run_codegen(
         &test_name,
         logical_program.graph,
         checkpoint_interval,
-        physical_output,
-        golden_output_location,
+        &outputs,
         &udfs,
         primary_keys,
     )
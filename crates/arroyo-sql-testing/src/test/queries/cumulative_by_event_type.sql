@@ -0,0 +1,29 @@
+CREATE TABLE cars(
+  timestamp TIMESTAMP,
+  driver_id BIGINT,
+  event_type TEXT,
+  location TEXT
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/cars.json',
+  format = 'json',
+  type = 'source',
+  event_time_field = 'timestamp'
+);
+CREATE TABLE cumulative_counts (
+  event_type TEXT,
+  start TIMESTAMP,
+  end TIMESTAMP,
+  count BIGINT
+) WITH (
+  connector = 'single_file',
+  path = '$output_path',
+  format = 'json',
+  type = 'sink'
+);
+INSERT INTO cumulative_counts
+SELECT event_type, window.start, window.end, count
+FROM (
+SELECT event_type, CUMULATE(INTERVAL '20' MINUTE, INTERVAL '1' HOUR) as window, COUNT(*) as count
+FROM cars
+GROUP BY 1,2);
@@ -0,0 +1,29 @@
+CREATE TABLE cars(
+  timestamp TIMESTAMP,
+  driver_id BIGINT,
+  event_type TEXT,
+  location TEXT
+) WITH (
+  connector = 'single_file',
+  path = '$input_dir/sorted_cars.json',
+  format = 'json',
+  type = 'source',
+  event_time_field = 'timestamp',
+  watermark_field = 'timestamp'
+);
+CREATE TABLE drivers_by_pickups (
+  pickups BIGINT,
+  drivers BIGINT
+) WITH (
+  connector = 'single_file',
+  path = '$output_path',
+  format = 'debezium_json',
+  type = 'sink'
+);
+INSERT INTO drivers_by_pickups
+SELECT pickups, count(*) drivers FROM
+(
+SELECT driver_id, sum(case when event_type = 'pickup' then 1 else 0 END) as pickups
+FROM cars
+GROUP BY 1
+) GROUP BY pickups
@@ -7,20 +7,23 @@ use std::{
 
 use anyhow::{anyhow, bail, Ok, Result};
 use arrow::compute::{concat_batches, filter_record_batch, kernels::aggregate, take};
-use arrow::row::OwnedRow;
+use arrow::row::{OwnedRow, SortField};
 use arrow_array::{
     cast::AsArray,
+    new_null_array,
     types::{TimestampNanosecondType, UInt64Type},
-    BooleanArray, PrimitiveArray, RecordBatch, TimestampNanosecondArray, UInt64Array,
+    ArrayRef, BooleanArray, PrimitiveArray, RecordBatch, StructArray, TimestampNanosecondArray,
+    UInt64Array,
 };
 use arrow_ord::{partition::partition, sort::sort_to_indices};
+use arrow_schema::{DataType, SchemaRef};
 use arroyo_rpc::{
     df::server_for_hash_array,
     grpc::rpc::{
         ExpiringKeyedTimeSubtaskCheckpointMetadata, ExpiringKeyedTimeTableCheckpointMetadata,
         ExpiringKeyedTimeTableConfig, OperatorMetadata, ParquetTimeFile, TableEnum,
     },
-    Converter,
+    updating_meta_fields, Converter, UPDATING_META_FIELD,
 };
 use arroyo_storage::StorageProviderRef;
 use arroyo_types::{
@@ -242,6 +245,39 @@ impl ExpiringTimeKeyTable {
         }
         Ok(view)
     }
+
+    pub(crate) async fn get_versioned_key_view(
+        &self,
+        state_tx: Sender<StateMessage>,
+        watermark: Option<SystemTime>,
+    ) -> Result<VersionedKeyView> {
+        let cutoff = self.get_cutoff(watermark);
+        let files = self.get_files_with_filtering(cutoff);
+        let mut view = VersionedKeyView::new(self.clone(), state_tx)?;
+        let batches = self
+            .call_on_filtered_batches(files, |batch| {
+                let timestamp_array: &PrimitiveArray<TimestampNanosecondType> = batch
+                    .column(self.schema.timestamp_index())
+                    .as_primitive_opt()
+                    .ok_or_else(|| anyhow!("failed to find timestamp column"))?;
+                let max_timestamp = from_nanos(
+                    aggregate::max(timestamp_array)
+                        .ok_or_else(|| anyhow!("should have max timestamp"))?
+                        as u128,
+                );
+                if max_timestamp < cutoff {
+                    Ok(vec![])
+                } else {
+                    Ok(vec![batch])
+                }
+            })
+            .await?;
+        for batch in batches {
+            view.insert_internal(&batch)?;
+        }
+        view.expire(watermark).await?;
+        Ok(view)
+    }
 }
 
 #[async_trait::async_trait]
@@ -1001,12 +1037,16 @@ pub struct LastKeyValueView {
     expirations: BTreeMap<SystemTime, HashSet<Vec<u8>>>,
     state_tx: Sender<StateMessage>,
     key_indices: Vec<usize>,
+    updating_meta_index: Option<usize>,
 }
 #[derive(Debug)]
 struct Value {
     value_row_bytes: Vec<u8>,
     timestamp: SystemTime,
     generation: u64,
+    // set for rows written as retractions, which delete the key. These are kept until they expire
+    // so that older generations restored after them don't bring the key back.
+    is_retract: bool,
 }
 
 impl LastKeyValueView {
@@ -1033,8 +1073,11 @@ impl LastKeyValueView {
             parent,
             state_tx,
             key_indices: schema.key_indices.as_ref().unwrap().clone(),
+            updating_meta_index: schema.schema.index_of(UPDATING_META_FIELD).ok(),
         })
     }
+    /// Inserts the rows of `batch` as the current values of their keys. If the table has updating
+    /// metadata, rows whose `is_retract` is set instead delete their keys.
     pub async fn insert_batch(&mut self, batch: RecordBatch) -> Result<()> {
         self.insert_batch_internal(batch, false).await
     }
@@ -1042,27 +1085,39 @@ impl LastKeyValueView {
     pub fn get_current_matching_values(
         &self,
         batch: &RecordBatch,
+    ) -> Result<Option<(RecordBatch, BooleanArray)>> {
+        self.get_current_values_for_keys(batch.schema(), &batch.project(&self.key_indices)?)
+    }
+
+    /// Like [Self::get_current_matching_values], but looks up the rows of a batch of just the key
+    /// columns, returning the matches in `schema`.
+    pub fn get_current_values_for_keys(
+        &self,
+        schema: SchemaRef,
+        key_batch: &RecordBatch,
     ) -> Result<Option<(RecordBatch, BooleanArray)>> {
         if self.backing_map.is_empty() {
             return Ok(None);
         }
-        let key_batch: RecordBatch = batch.project(&self.key_indices)?;
         let key_rows = self
             .key_converter
             .convert_all_columns(key_batch.columns(), key_batch.num_rows())?;
-        let capacity = batch.num_rows().min(self.backing_map.len());
+        let capacity = key_batch.num_rows().min(self.backing_map.len());
         let mut prior_values = Vec::with_capacity(capacity);
         let mut prior_timestamp_builder = TimestampNanosecondArray::builder(capacity);
         let mut prior_row_filter = BooleanArray::builder(capacity);
-        for i in 0..batch.num_rows() {
+        for i in 0..key_batch.num_rows() {
             match self.backing_map.get(key_rows.row(i).as_ref()) {
-                None => {
+                None
+                | Some(Value {
+                    is_retract: true, ..
+                }) => {
                     prior_row_filter.append_value(false);
                 }
                 Some(Value {
                     value_row_bytes,
                     timestamp,
-                    generation: _,
+                    ..
                 }) => {
                     prior_row_filter.append_value(true);
                     prior_timestamp_builder.append_value(to_nanos(*timestamp) as i64);
@@ -1071,7 +1126,7 @@ impl LastKeyValueView {
             }
         }
         let filter = prior_row_filter.finish();
-        let filtered_key_batch: RecordBatch = filter_record_batch(&key_batch, &filter)?;
+        let filtered_key_batch: RecordBatch = filter_record_batch(key_batch, &filter)?;
         if filtered_key_batch.num_rows() == 0 {
             return Ok(None);
         }
@@ -1080,10 +1135,7 @@ impl LastKeyValueView {
         columns.extend(value_columns);
         columns.push(Arc::new(prior_timestamp_builder.finish()));
 
-        Ok(Some((
-            RecordBatch::try_new(batch.schema(), columns)?,
-            filter,
-        )))
+        Ok(Some((RecordBatch::try_new(schema, columns)?, filter)))
     }
 
     async fn insert_batch_internal(&mut self, batch: RecordBatch, is_backfill: bool) -> Result<()> {
@@ -1120,12 +1172,29 @@ impl LastKeyValueView {
                     .ok_or_else(|| anyhow!("should have generation array"))
             })
             .transpose()?;
+        let retracts = self
+            .updating_meta_index
+            .map(|index| {
+                batch
+                    .column(index)
+                    .as_struct_opt()
+                    .and_then(|meta| meta.column_by_name("is_retract"))
+                    .and_then(|is_retract| is_retract.as_boolean_opt())
+                    .cloned()
+                    .ok_or_else(|| anyhow!("failed to find is_retract column"))
+            })
+            .transpose()?;
         for i in 0..batch.num_rows() {
+            let is_retract = retracts
+                .as_ref()
+                .map(|retracts| retracts.is_valid(i) && retracts.value(i))
+                .unwrap_or(false);
             let (max_timestamp, generation) = self.insert_entry(
                 key_rows.row(i).as_ref(),
                 value_rows.row(i).as_ref(),
                 from_nanos(timestamp_columns.value(i) as u128),
                 generation_array.map(|generation_array| generation_array.value(i)),
+                is_retract,
             )?;
             if !is_backfill {
                 new_generation_values.push(generation);
@@ -1156,6 +1225,7 @@ impl LastKeyValueView {
         value_row: &[u8],
         timestamp: SystemTime,
         generation: Option<u64>,
+        is_retract: bool,
     ) -> Result<(SystemTime, u64)> {
         match self.backing_map.get_mut(key_row) {
             None => {
@@ -1166,6 +1236,7 @@ impl LastKeyValueView {
                         value_row_bytes: value_row.to_vec(),
                         timestamp,
                         generation,
+                        is_retract,
                     },
                 );
                 self.expirations
@@ -1178,6 +1249,7 @@ impl LastKeyValueView {
                 value_row_bytes: old_value,
                 timestamp: existing_timestamp,
                 generation: current_generation,
+                is_retract: existing_is_retract,
             }) => {
                 match generation {
                     Some(generation) => {
@@ -1207,6 +1279,7 @@ impl LastKeyValueView {
                     *existing_timestamp = timestamp;
                 }
                 *old_value = value_row.to_vec();
+                *existing_is_retract = is_retract;
                 Ok((*existing_timestamp, *current_generation))
             }
         }
@@ -1238,3 +1311,478 @@ impl LastKeyValueView {
         Ok(())
     }
 }
+
+/// Keeps each version of a key's value, as of the time it was written, so that the value the key
+/// had at a given time can be looked up. Rows that are retractions delete the key as of their
+/// timestamp.
+#[derive(Debug)]
+pub struct VersionedKeyView {
+    parent: ExpiringTimeKeyTable,
+    key_converter: Converter,
+    value_converter: Converter,
+    key_indices: Vec<usize>,
+    // indices of schema that aren't keys, the timestamp or the updating metadata
+    value_indices: Vec<usize>,
+    timestamp_index: usize,
+    updating_meta_index: Option<usize>,
+    // the versions of each key, None if the key was deleted at that time
+    versions: HashMap<Vec<u8>, BTreeMap<SystemTime, Option<Vec<u8>>>>,
+    state_tx: Sender<StateMessage>,
+}
+
+impl VersionedKeyView {
+    fn new(parent: ExpiringTimeKeyTable, state_tx: Sender<StateMessage>) -> Result<Self> {
+        let schema = parent.schema.memory_schema();
+        let Some(key_indices) = schema.key_indices.clone() else {
+            bail!("versioned table {} must be keyed", parent.table_name);
+        };
+        let key_converter = schema.converter(false)?;
+        let updating_meta_index = schema.schema.index_of(UPDATING_META_FIELD).ok();
+        let value_indices: Vec<_> = schema
+            .value_indices(false)
+            .into_iter()
+            .filter(|index| Some(*index) != updating_meta_index)
+            .collect();
+        let value_converter = Converter::new(
+            value_indices
+                .iter()
+                .map(|index| SortField::new(schema.schema.field(*index).data_type().clone()))
+                .collect(),
+        )?;
+        Ok(Self {
+            key_converter,
+            value_converter,
+            key_indices,
+            value_indices,
+            timestamp_index: schema.timestamp_index,
+            updating_meta_index,
+            versions: HashMap::new(),
+            parent,
+            state_tx,
+        })
+    }
+
+    pub async fn insert_batch(&mut self, batch: RecordBatch) -> Result<()> {
+        if batch.num_rows() == 0 {
+            return Ok(());
+        }
+        self.state_tx
+            .send(StateMessage::TableData {
+                table: self.parent.table_name.to_string(),
+                data: TableData::RecordBatch(batch.clone()),
+            })
+            .await?;
+        self.insert_internal(&batch)
+    }
+
+    fn insert_internal(&mut self, batch: &RecordBatch) -> Result<()> {
+        let key_batch = batch.project(&self.key_indices)?;
+        let key_rows = self
+            .key_converter
+            .convert_all_columns(key_batch.columns(), batch.num_rows())?;
+        let value_batch = batch.project(&self.value_indices)?;
+        let value_rows = self
+            .value_converter
+            .convert_all_columns(value_batch.columns(), batch.num_rows())?;
+        let timestamps: &PrimitiveArray<TimestampNanosecondType> = batch
+            .column(self.timestamp_index)
+            .as_primitive_opt()
+            .ok_or_else(|| anyhow!("failed to find timestamp column"))?;
+        let retracts = self
+            .updating_meta_index
+            .map(|index| {
+                batch
+                    .column(index)
+                    .as_struct_opt()
+                    .and_then(|meta| meta.column_by_name("is_retract"))
+                    .and_then(|is_retract| is_retract.as_boolean_opt())
+                    .cloned()
+                    .ok_or_else(|| anyhow!("failed to find is_retract column"))
+            })
+            .transpose()?;
+
+        for i in 0..batch.num_rows() {
+            let is_retract = retracts
+                .as_ref()
+                .map(|retracts| retracts.is_valid(i) && retracts.value(i))
+                .unwrap_or(false);
+            self.versions
+                .entry(key_rows.row(i).as_ref().to_vec())
+                .or_default()
+                .insert(
+                    from_nanos(timestamps.value(i) as u128),
+                    (!is_retract).then(|| value_rows.row(i).as_ref().to_vec()),
+                );
+        }
+        Ok(())
+    }
+
+    /// Looks up the value each key had at the corresponding time, returning the value columns
+    /// for the rows whose key had one along with the filter that selects those rows.
+    pub fn get_values_at(
+        &self,
+        key_columns: &[ArrayRef],
+        times: &TimestampNanosecondArray,
+    ) -> Result<(Vec<ArrayRef>, BooleanArray)> {
+        let key_rows = self
+            .key_converter
+            .convert_all_columns(key_columns, times.len())?;
+        let mut values = vec![];
+        let mut filter = BooleanArray::builder(times.len());
+        for i in 0..times.len() {
+            let value = self
+                .versions
+                .get(key_rows.row(i).as_ref())
+                .and_then(|versions| {
+                    versions
+                        .range(..=from_nanos(times.value(i) as u128))
+                        .next_back()
+                })
+                .and_then(|(_, value)| value.as_deref());
+            filter.append_value(value.is_some());
+            values.extend(value);
+        }
+        Ok((
+            self.value_converter.convert_raw_rows(values)?,
+            filter.finish(),
+        ))
+    }
+
+    /// Drops the versions that can no longer be looked up, as lookups are never for times at or
+    /// before the watermark: those replaced by a later version at or before the watermark, and
+    /// deletions. A key's current version is kept however long the key goes unchanged; once it is
+    /// older than the table's retention it's written again as of the watermark, so that it isn't
+    /// lost with the checkpoint files that expire.
+    pub async fn expire(&mut self, watermark: Option<SystemTime>) -> Result<()> {
+        let Some(watermark) = watermark else {
+            return Ok(());
+        };
+        let cutoff = watermark - self.parent.retention;
+        let mut refreshed = vec![];
+        self.versions.retain(|key, versions| {
+            if let Some(current) = versions.range(..=watermark).next_back().map(|(t, _)| *t) {
+                *versions = versions.split_off(&current);
+            }
+            if matches!(versions.first_key_value(), Some((time, None)) if *time <= watermark) {
+                versions.pop_first();
+            }
+            if matches!(versions.first_key_value(), Some((time, Some(_))) if *time < cutoff) {
+                let (_, value) = versions.pop_first().expect("just checked");
+                let value = value.expect("just checked");
+                versions.insert(watermark, Some(value.clone()));
+                refreshed.push((key.clone(), value));
+            }
+            !versions.is_empty()
+        });
+        if refreshed.is_empty() {
+            return Ok(());
+        }
+        let batch = self.rows_at(watermark, &refreshed)?;
+        self.state_tx
+            .send(StateMessage::TableData {
+                table: self.parent.table_name.to_string(),
+                data: TableData::RecordBatch(batch),
+            })
+            .await?;
+        Ok(())
+    }
+
+    // builds a batch of the table's schema with the given keys and values as of time
+    fn rows_at(&self, time: SystemTime, rows: &[(Vec<u8>, Vec<u8>)]) -> Result<RecordBatch> {
+        let schema = self.parent.schema.memory_schema();
+        let key_columns = self
+            .key_converter
+            .convert_raw_rows(rows.iter().map(|(key, _)| key.as_slice()).collect())?;
+        let value_columns = self
+            .value_converter
+            .convert_raw_rows(rows.iter().map(|(_, value)| value.as_slice()).collect())?;
+        let columns = (0..schema.schema.fields().len())
+            .map(|i| {
+                if let Some(k) = self.key_indices.iter().position(|index| *index == i) {
+                    Ok(key_columns[k].clone())
+                } else if let Some(v) = self.value_indices.iter().position(|index| *index == i) {
+                    Ok(value_columns[v].clone())
+                } else if i == self.timestamp_index {
+                    Ok(Arc::new(TimestampNanosecondArray::from(vec![
+                        to_nanos(time) as i64;
+                        rows.len()
+                    ])) as ArrayRef)
+                } else if Some(i) == self.updating_meta_index {
+                    Ok(Arc::new(StructArray::new(
+                        updating_meta_fields(),
+                        vec![
+                            Arc::new(BooleanArray::from(vec![false; rows.len()])),
+                            new_null_array(&DataType::FixedSizeBinary(16), rows.len()),
+                        ],
+                        None,
+                    )) as ArrayRef)
+                } else {
+                    bail!(
+                        "unexpected column {} in versioned table {}",
+                        i,
+                        self.parent.table_name
+                    )
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(RecordBatch::try_new(schema.schema.clone(), columns)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Float64Array, StringArray};
+    use arrow_schema::{Field, Schema, TimeUnit};
+    use arroyo_rpc::updating_meta_field;
+    use arroyo_storage::StorageProvider;
+    use arroyo_types::get_test_task_info;
+    use tokio::sync::mpsc::{channel, Receiver};
+
+    fn rates_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("currency", DataType::Utf8, false),
+            Field::new("rate", DataType::Float64, false),
+            Field::new(
+                "_timestamp",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+        ]))
+    }
+
+    async fn versioned_view(retention: Duration) -> (VersionedKeyView, Receiver<StateMessage>) {
+        let schema = ArroyoSchema::new_keyed(rates_schema(), 2, vec![0]);
+        let table = ExpiringTimeKeyTable {
+            table_name: "right".to_string(),
+            task_info: Arc::new(get_test_task_info()),
+            schema: SchemaWithHashAndOperation::new(Arc::new(schema), false),
+            retention,
+            storage_provider: Arc::new(
+                StorageProvider::for_url("file:///tmp/arroyo-testing/versioned-key-view")
+                    .await
+                    .unwrap(),
+            ),
+            checkpoint_files: vec![],
+        };
+        let (tx, rx) = channel(16);
+        (VersionedKeyView::new(table, tx).unwrap(), rx)
+    }
+
+    fn rates(currency: &str, rate: f64, time: SystemTime) -> RecordBatch {
+        RecordBatch::try_new(
+            rates_schema(),
+            vec![
+                Arc::new(StringArray::from(vec![currency])),
+                Arc::new(Float64Array::from(vec![rate])),
+                Arc::new(TimestampNanosecondArray::from(vec![to_nanos(time) as i64])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn rate_at(view: &VersionedKeyView, currency: &str, time: SystemTime) -> Option<f64> {
+        let (values, filter) = view
+            .get_values_at(
+                &[Arc::new(StringArray::from(vec![currency]))],
+                &TimestampNanosecondArray::from(vec![to_nanos(time) as i64]),
+            )
+            .unwrap();
+        filter.value(0).then(|| {
+            values[0]
+                .as_primitive::<arrow_array::types::Float64Type>()
+                .value(0)
+        })
+    }
+
+    fn updating_rates_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("currency", DataType::Utf8, false),
+            Field::new("rate", DataType::Float64, false),
+            updating_meta_field().as_ref().clone(),
+            Field::new(
+                "_timestamp",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+        ]))
+    }
+
+    async fn last_key_value_table() -> ExpiringTimeKeyTable {
+        let schema = ArroyoSchema::new_keyed(updating_rates_schema(), 3, vec![0]);
+        ExpiringTimeKeyTable {
+            table_name: "f".to_string(),
+            task_info: Arc::new(get_test_task_info()),
+            schema: SchemaWithHashAndOperation::new(Arc::new(schema), true),
+            retention: Duration::from_secs(60),
+            storage_provider: Arc::new(
+                StorageProvider::for_url("file:///tmp/arroyo-testing/last-key-value-view")
+                    .await
+                    .unwrap(),
+            ),
+            checkpoint_files: vec![],
+        }
+    }
+
+    fn updating_rates(
+        currency: &str,
+        rate: f64,
+        is_retract: bool,
+        time: SystemTime,
+    ) -> RecordBatch {
+        let meta = StructArray::new(
+            updating_meta_fields(),
+            vec![
+                Arc::new(BooleanArray::from(vec![is_retract])),
+                new_null_array(&DataType::FixedSizeBinary(16), 1),
+            ],
+            None,
+        );
+        RecordBatch::try_new(
+            updating_rates_schema(),
+            vec![
+                Arc::new(StringArray::from(vec![currency])),
+                Arc::new(Float64Array::from(vec![rate])),
+                Arc::new(meta),
+                Arc::new(TimestampNanosecondArray::from(vec![to_nanos(time) as i64])),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn current_rate(view: &LastKeyValueView, currency: &str) -> Option<f64> {
+        let probe = updating_rates(currency, 0.0, false, SystemTime::UNIX_EPOCH);
+        view.get_current_matching_values(&probe)
+            .unwrap()
+            .map(|(values, _)| {
+                values
+                    .column(1)
+                    .as_primitive::<arrow_array::types::Float64Type>()
+                    .value(0)
+            })
+    }
+
+    #[tokio::test]
+    async fn test_retracted_key_stays_deleted_on_restore() {
+        let (tx, mut rx) = channel(16);
+        let mut view = LastKeyValueView::new(last_key_value_table().await, tx.clone()).unwrap();
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+
+        view.insert_batch(updating_rates("EUR", 1.1, false, start))
+            .await
+            .unwrap();
+        assert_eq!(current_rate(&view, "EUR"), Some(1.1));
+
+        view.insert_batch(updating_rates(
+            "EUR",
+            1.1,
+            true,
+            start + Duration::from_secs(1),
+        ))
+        .await
+        .unwrap();
+        assert_eq!(current_rate(&view, "EUR"), None);
+
+        let mut written = vec![];
+        for _ in 0..2 {
+            let Some(StateMessage::TableData {
+                data: TableData::RecordBatch(batch),
+                ..
+            }) = rx.recv().await
+            else {
+                panic!("expected the rows to be written to state");
+            };
+            written.push(batch);
+        }
+
+        // restoring the insert after the retraction that superseded it doesn't bring the key back
+        let mut restored = LastKeyValueView::new(last_key_value_table().await, tx.clone()).unwrap();
+        for batch in written.iter().rev() {
+            restored
+                .insert_batch_internal(batch.clone(), true)
+                .await
+                .unwrap();
+        }
+        assert_eq!(current_rate(&restored, "EUR"), None);
+
+        view.insert_batch(updating_rates(
+            "EUR",
+            1.2,
+            false,
+            start + Duration::from_secs(2),
+        ))
+        .await
+        .unwrap();
+        assert_eq!(current_rate(&view, "EUR"), Some(1.2));
+        let Some(StateMessage::TableData {
+            data: TableData::RecordBatch(batch),
+            ..
+        }) = rx.recv().await
+        else {
+            panic!("expected the rows to be written to state");
+        };
+        written.push(batch);
+
+        // a key inserted again after it was retracted is restored, regardless of order
+        for batches in [written.clone(), written.iter().rev().cloned().collect()] {
+            let mut restored =
+                LastKeyValueView::new(last_key_value_table().await, tx.clone()).unwrap();
+            for batch in batches {
+                restored.insert_batch_internal(batch, true).await.unwrap();
+            }
+            assert_eq!(current_rate(&restored, "EUR"), Some(1.2));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_key_outlives_retention() {
+        let (mut view, mut rx) = versioned_view(Duration::from_secs(10)).await;
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        view.insert_batch(rates("EUR", 1.1, start)).await.unwrap();
+        view.insert_batch(rates("USD", 1.0, start)).await.unwrap();
+        view.insert_batch(rates("USD", 0.9, start + Duration::from_secs(5)))
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            rx.recv().await.unwrap();
+        }
+
+        // EUR hasn't changed for longer than the retention, but is still current
+        let watermark = start + Duration::from_secs(100);
+        view.expire(Some(watermark)).await.unwrap();
+        assert_eq!(
+            rate_at(&view, "EUR", watermark + Duration::from_secs(1)),
+            Some(1.1)
+        );
+        assert_eq!(
+            rate_at(&view, "USD", watermark + Duration::from_secs(1)),
+            Some(0.9)
+        );
+        // superseded versions are dropped
+        assert_eq!(view.versions.values().map(BTreeMap::len).sum::<usize>(), 2);
+
+        // the current versions are written again as of the watermark, so they are restored
+        let Some(StateMessage::TableData {
+            data: TableData::RecordBatch(refreshed),
+            ..
+        }) = rx.recv().await
+        else {
+            panic!("expected the current versions to be written to state");
+        };
+        assert_eq!(refreshed.num_rows(), 2);
+        let timestamps: &TimestampNanosecondArray = refreshed.column(2).as_primitive();
+        assert!(timestamps
+            .iter()
+            .all(|timestamp| timestamp == Some(to_nanos(watermark) as i64)));
+
+        // and aren't written again until they are older than the retention once more
+        view.expire(Some(watermark + Duration::from_secs(5)))
+            .await
+            .unwrap();
+        assert!(rx.try_recv().is_err());
+        assert_eq!(
+            rate_at(&view, "EUR", watermark + Duration::from_secs(6)),
+            Some(1.1)
+        );
+    }
+}
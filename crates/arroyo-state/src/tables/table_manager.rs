@@ -24,7 +24,7 @@ use crate::{get_storage_provider, tables::global_keyed_map::GlobalKeyedTable, St
 use crate::{CheckpointMessage, TableData};
 
 use super::expiring_time_key_map::{
-    ExpiringTimeKeyTable, ExpiringTimeKeyView, KeyTimeView, LastKeyValueView,
+    ExpiringTimeKeyTable, ExpiringTimeKeyView, KeyTimeView, LastKeyValueView, VersionedKeyView,
 };
 use super::global_keyed_map::GlobalKeyedView;
 use super::{ErasedCheckpointer, ErasedTable};
@@ -473,4 +473,33 @@ impl TableManager {
             .ok_or_else(|| anyhow!("Failed to downcast table {}", table_name))?;
         Ok(cache)
     }
+
+    pub async fn get_versioned_key_table(
+        &mut self,
+        table_name: &str,
+        watermark: Option<SystemTime>,
+    ) -> Result<&mut VersionedKeyView> {
+        if let std::collections::hash_map::Entry::Vacant(e) =
+            self.caches.entry(table_name.to_string())
+        {
+            let table_implementation = self
+                .tables
+                .get(table_name)
+                .ok_or_else(|| anyhow!("no registered table {}", table_name))?;
+            let expiring_time_key_table = table_implementation
+                .as_any()
+                .downcast_ref::<ExpiringTimeKeyTable>()
+                .ok_or_else(|| anyhow!("wrong table type for table {}", table_name))?;
+            let saved_data = expiring_time_key_table
+                .get_versioned_key_view(self.writer.sender.clone(), watermark)
+                .await?;
+            let cache: Box<dyn Any + Send> = Box::new(saved_data);
+            e.insert(cache);
+        }
+        let cache = self.caches.get_mut(table_name).unwrap();
+        let cache: &mut VersionedKeyView = cache
+            .downcast_mut()
+            .ok_or_else(|| anyhow!("Failed to downcast table {}", table_name))?;
+        Ok(cache)
+    }
 }
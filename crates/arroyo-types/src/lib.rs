@@ -120,6 +120,16 @@ pub fn from_nanos(ts: u128) -> SystemTime {
         + Duration::from_nanos((ts % 1_000_000_000) as u64)
 }
 
+/// The width of the panes that a sliding window with the given width and slide is computed over:
+/// the largest duration that evenly divides both, so every window is made up of whole panes.
+pub fn sliding_window_pane(width: Duration, slide: Duration) -> Duration {
+    let (mut a, mut b) = (width.as_nanos(), slide.as_nanos());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    Duration::new((a / 1_000_000_000) as u64, (a % 1_000_000_000) as u32)
+}
+
 pub fn print_time(time: SystemTime) -> String {
     chrono::DateTime::<chrono::Utc>::from(time)
         .format("%Y-%m-%d %H:%M:%S%.3f")
@@ -206,14 +216,46 @@ impl UserError {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SourceError {
-    BadData { details: String },
-    Other { name: String, details: String },
+    BadData {
+        details: String,
+        /// the input that failed to deserialize, if it's being routed to a dead-letter table
+        record: Option<Box<BadRecord>>,
+    },
+    Other {
+        name: String,
+        details: String,
+    },
+}
+
+/// A record that could not be deserialized, along with the context needed to write it to a
+/// dead-letter table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BadRecord {
+    pub data: Vec<u8>,
+    /// JSON-encoded metadata provided by the source, like the Kafka topic, partition and offset
+    pub metadata: Option<String>,
+    pub timestamp: SystemTime,
 }
 
 impl SourceError {
     pub fn bad_data(details: impl Into<String>) -> SourceError {
         SourceError::BadData {
             details: details.into(),
+            record: None,
+        }
+    }
+
+    /// Attaches the input that caused a bad data error, if it doesn't already have one
+    pub fn with_record(self, f: impl FnOnce() -> BadRecord) -> SourceError {
+        match self {
+            SourceError::BadData {
+                details,
+                record: None,
+            } => SourceError::BadData {
+                details,
+                record: Some(Box::new(f())),
+            },
+            e => e,
         }
     }
     pub fn other(name: impl Into<String>, details: impl Into<String>) -> SourceError {
@@ -225,7 +267,7 @@ impl SourceError {
 
     pub fn details(&self) -> &String {
         match self {
-            SourceError::BadData { details } | SourceError::Other { details, .. } => details,
+            SourceError::BadData { details, .. } | SourceError::Other { details, .. } => details,
         }
     }
 }
@@ -447,6 +489,8 @@ pub static BATCHES_SENT: &str = "arroyo_worker_batches_sent";
 pub static TX_QUEUE_SIZE: &str = "arroyo_worker_tx_queue_size";
 pub static TX_QUEUE_REM: &str = "arroyo_worker_tx_queue_rem";
 pub static DESERIALIZATION_ERRORS: &str = "arroyo_worker_deserialization_errors";
+pub static DEAD_LETTER_RECORDS: &str = "arroyo_worker_dead_letter_records";
+pub static LATE_RECORDS_DROPPED: &str = "arroyo_worker_late_records_dropped";
 
 #[derive(Debug, Copy, Clone, Encode, Decode, PartialEq, Eq)]
 pub struct CheckpointBarrier {
@@ -616,6 +660,22 @@ pub fn range_for_server(i: usize, n: usize) -> RangeInclusive<u64> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sliding_window_pane() {
+        assert_eq!(
+            sliding_window_pane(Duration::from_secs(600), Duration::from_secs(180)),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            sliding_window_pane(Duration::from_secs(60), Duration::from_secs(2)),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            sliding_window_pane(Duration::from_millis(1500), Duration::from_secs(1)),
+            Duration::from_millis(500)
+        );
+    }
+
     #[test]
     fn test_range_for_server() {
         let n = 6;
@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Result};
+use arrow::compute::{filter_record_batch, max};
+use arrow::row::OwnedRow;
+use arrow_array::{ArrayRef, BooleanArray, RecordBatch, TimestampNanosecondArray, UInt64Array};
+use arrow_schema::SchemaRef;
+use arroyo_operator::context::ArrowContext;
+use arroyo_operator::operator::{ArrowOperator, OperatorConstructor, OperatorNode, Registry};
+use arroyo_rpc::df::{ArroyoSchema, ArroyoSchemaRef};
+use arroyo_rpc::grpc::{api, rpc::TableConfig};
+use arroyo_rpc::Converter;
+use arroyo_state::timestamp_table_config;
+use arroyo_types::{from_nanos, CheckpointBarrier, Watermark};
+use tracing::warn;
+
+/// Emits the first row to arrive for each key, whatever its timestamp, and drops every later row
+/// with that key until the watermark has passed the first row's timestamp by the TTL.
+pub struct DedupOperator {
+    input_schema: ArroyoSchemaRef,
+    output_schema: SchemaRef,
+    // the keys and timestamps of emitted rows, which is all that we need to restore
+    state_schema: ArroyoSchemaRef,
+    state_indices: Vec<usize>,
+    key_converter: Converter,
+    ttl: Duration,
+    seen: HashMap<OwnedRow, SystemTime>,
+    expirations: BTreeMap<SystemTime, Vec<OwnedRow>>,
+}
+
+impl DedupOperator {
+    /// Records the keys that haven't been seen before, returning which rows were first-seen
+    fn observe(
+        &mut self,
+        key_columns: &[ArrayRef],
+        timestamps: &TimestampNanosecondArray,
+    ) -> Result<Vec<bool>> {
+        let keys = self
+            .key_converter
+            .convert_all_columns(key_columns, timestamps.len())?;
+
+        let mut first_seen = vec![false; timestamps.len()];
+        for (index, is_first) in first_seen.iter_mut().enumerate() {
+            let key = keys.row(index).owned();
+            if self.seen.contains_key(&key) {
+                continue;
+            }
+            let timestamp = from_nanos(timestamps.value(index) as u128);
+            self.seen.insert(key.clone(), timestamp);
+            self.expirations.entry(timestamp).or_default().push(key);
+            *is_first = true;
+        }
+        Ok(first_seen)
+    }
+}
+
+#[async_trait::async_trait]
+impl ArrowOperator for DedupOperator {
+    fn name(&self) -> String {
+        "Dedup".to_string()
+    }
+
+    async fn on_start(&mut self, ctx: &mut ArrowContext) {
+        let watermark = ctx.last_present_watermark();
+        let table = ctx
+            .table_manager
+            .get_expiring_time_key_table("s", watermark)
+            .await
+            .unwrap();
+        let batches: Vec<_> = table
+            .all_batches_for_watermark(watermark)
+            .flat_map(|(_, batches)| batches.iter().cloned())
+            .collect();
+        let state_schema = self.state_schema.clone();
+        let key_count = state_schema.key_indices.as_ref().unwrap().len();
+        for batch in batches {
+            self.observe(
+                &batch.columns()[..key_count],
+                state_schema.timestamp_column(&batch),
+            )
+            .expect("should be able to restore dedup state");
+        }
+    }
+
+    async fn process_batch(&mut self, batch: RecordBatch, ctx: &mut ArrowContext) {
+        if batch.num_rows() == 0 {
+            warn!("empty batch received");
+            return;
+        }
+        let key_columns: Vec<_> = self
+            .input_schema
+            .key_indices
+            .iter()
+            .flatten()
+            .map(|index| batch.column(*index).clone())
+            .collect();
+        let input_schema = self.input_schema.clone();
+        let first_seen = self
+            .observe(&key_columns, input_schema.timestamp_column(&batch))
+            .expect("should be able to compute keys");
+        if !first_seen.contains(&true) {
+            return;
+        }
+
+        let emitted = filter_record_batch(&batch, &BooleanArray::from(first_seen)).unwrap();
+
+        let max_timestamp =
+            from_nanos(max(input_schema.timestamp_column(&emitted)).unwrap() as u128);
+        let watermark = ctx.last_present_watermark();
+        ctx.table_manager
+            .get_expiring_time_key_table("s", watermark)
+            .await
+            .expect("should have dedup table")
+            .insert(max_timestamp, emitted.project(&self.state_indices).unwrap());
+
+        let mut columns = emitted.columns().to_vec();
+        columns.push(Arc::new(UInt64Array::from(vec![1; emitted.num_rows()])));
+        ctx.collect(RecordBatch::try_new(self.output_schema.clone(), columns).unwrap())
+            .await;
+    }
+
+    async fn handle_watermark(
+        &mut self,
+        watermark: Watermark,
+        ctx: &mut ArrowContext,
+    ) -> Option<Watermark> {
+        let Some(current) = ctx.last_present_watermark() else {
+            return Some(watermark);
+        };
+        let cutoff = current - self.ttl;
+        while let Some(entry) = self.expirations.first_entry() {
+            if *entry.key() >= cutoff {
+                break;
+            }
+            let (timestamp, keys) = entry.remove_entry();
+            for key in keys {
+                if self.seen.get(&key) == Some(&timestamp) {
+                    self.seen.remove(&key);
+                }
+            }
+        }
+        Some(watermark)
+    }
+
+    async fn handle_checkpoint(&mut self, _cb: CheckpointBarrier, ctx: &mut ArrowContext) {
+        let watermark = ctx.last_present_watermark();
+        ctx.table_manager
+            .get_expiring_time_key_table("s", watermark)
+            .await
+            .expect("should have dedup table")
+            .flush(watermark)
+            .await
+            .expect("should flush");
+    }
+
+    fn tables(&self) -> HashMap<String, TableConfig> {
+        let mut tables = HashMap::new();
+        tables.insert(
+            "s".to_string(),
+            timestamp_table_config(
+                "s",
+                "dedup keys",
+                self.ttl,
+                false,
+                self.state_schema.as_ref().clone(),
+            ),
+        );
+        tables
+    }
+}
+
+pub struct DedupConstructor;
+impl OperatorConstructor for DedupConstructor {
+    type ConfigT = api::DedupOperator;
+    fn with_config(
+        &self,
+        config: Self::ConfigT,
+        _registry: Arc<Registry>,
+    ) -> anyhow::Result<OperatorNode> {
+        let input_schema = Arc::new(ArroyoSchema::try_from(
+            config
+                .input_schema
+                .ok_or_else(|| anyhow!("missing input schema"))?,
+        )?);
+        let output_schema = ArroyoSchema::try_from(
+            config
+                .output_schema
+                .ok_or_else(|| anyhow!("missing output schema"))?,
+        )?
+        .schema;
+
+        let key_indices = input_schema
+            .key_indices
+            .clone()
+            .ok_or_else(|| anyhow!("dedup input must be keyed"))?;
+        let mut state_indices = key_indices.clone();
+        state_indices.push(input_schema.timestamp_index);
+        let state_schema = Arc::new(ArroyoSchema::from_schema_keys(
+            Arc::new(input_schema.schema.project(&state_indices)?),
+            (0..key_indices.len()).collect(),
+        )?);
+
+        Ok(OperatorNode::from_operator(Box::new(DedupOperator {
+            key_converter: input_schema.converter(false)?,
+            input_schema,
+            output_schema,
+            state_schema,
+            state_indices,
+            ttl: Duration::from_micros(config.ttl_micros),
+            seen: HashMap::new(),
+            expirations: BTreeMap::new(),
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arrow::test_utils::{nanos, OperatorHarness};
+    use arroyo_datastream::logical::OperatorName;
+    use serde_json::{json, Value};
+
+    const QUERY: &str = "
+        CREATE TABLE events (
+            event_id TEXT,
+            user_id TEXT,
+            event_time TIMESTAMP
+        ) WITH (
+            connector = 'kafka',
+            bootstrap_servers = 'localhost:9092',
+            type = 'source',
+            topic = 'events',
+            format = 'json',
+            event_time_field = 'event_time'
+        );
+
+        SELECT * FROM (
+            SELECT *, ROW_NUMBER() OVER (
+                PARTITION BY event_id
+                ORDER BY event_time) AS row_num
+            FROM events) WHERE row_num = 1";
+
+    fn event(event_id: &str, user_id: &str, secs: u64) -> Value {
+        json!({
+            "_key_0": event_id,
+            "event_id": event_id,
+            "user_id": user_id,
+            "event_time": nanos(secs),
+            "_timestamp": nanos(secs),
+        })
+    }
+
+    fn emitted(rows: Vec<Value>) -> Vec<(String, String)> {
+        rows.iter()
+            .map(|row| {
+                assert_eq!(row["row_num"], json!(1));
+                (
+                    row["event_id"].as_str().unwrap().to_string(),
+                    row["user_id"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_keeps_first_to_arrive() {
+        let mut harness = OperatorHarness::from_sql(QUERY, OperatorName::Dedup).await;
+
+        harness
+            .process(0, &[event("e1", "first", 10), event("e2", "first", 12)])
+            .await;
+        assert_eq!(
+            emitted(harness.output()),
+            vec![
+                ("e1".to_string(), "first".to_string()),
+                ("e2".to_string(), "first".to_string())
+            ]
+        );
+
+        // these are earlier by event time, but the rows for their keys have already been emitted
+        harness
+            .process(0, &[event("e1", "earlier", 5), event("e2", "earlier", 11)])
+            .await;
+        harness.watermark(15).await;
+        assert_eq!(emitted(harness.output()), vec![]);
+
+        // other keys are deduplicated independently
+        harness.process(0, &[event("e3", "first", 16)]).await;
+        harness
+            .process(0, &[event("e4", "first", 20), event("e3", "later", 21)])
+            .await;
+        assert_eq!(
+            emitted(harness.output()),
+            vec![
+                ("e3".to_string(), "first".to_string()),
+                ("e4".to_string(), "first".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,746 @@
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, bail, Result};
+use arrow::compute::kernels::cmp::gt;
+use arrow::compute::{concat_batches, filter_record_batch, lexsort_to_indices, max, not, take};
+use arrow::row::OwnedRow;
+use arrow_array::{Array, ArrayRef, BooleanArray, RecordBatch, TimestampNanosecondArray};
+use arrow_schema::SchemaRef;
+use arroyo_operator::context::ArrowContext;
+use arroyo_operator::operator::{ArrowOperator, OperatorConstructor, OperatorNode, Registry};
+use arroyo_rpc::df::{ArroyoSchema, ArroyoSchemaRef};
+use arroyo_rpc::grpc::{api, rpc::TableConfig};
+use arroyo_rpc::Converter;
+use arroyo_state::timestamp_table_config;
+use arroyo_types::{from_nanos, to_nanos, CheckpointBarrier, Watermark};
+use datafusion::common::ScalarValue;
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion_proto::physical_plan::from_proto::parse_physical_expr;
+use datafusion_proto::physical_plan::DefaultPhysicalExtensionCodec;
+use datafusion_proto::protobuf::PhysicalExprNode;
+use prost::Message;
+
+struct Measure {
+    kind: api::MatchRecognizeMeasureKind,
+    symbol: Option<usize>,
+    column: Option<usize>,
+    // the value of the measure for a match without any rows to aggregate
+    empty: ScalarValue,
+}
+
+/// The NFA compiled from the pattern, which starts in state 0. States are identified with their
+/// epsilon closures, so a partial match is in a single state between rows.
+struct Nfa {
+    transitions: Vec<Vec<(usize, usize)>>,
+    // the states reachable from each state through epsilon transitions, including itself
+    closures: Vec<Vec<usize>>,
+    accepting: Vec<bool>,
+    // whether further rows can be consumed from each state
+    extendable: Vec<bool>,
+    measures: Vec<Measure>,
+    within: Duration,
+}
+
+#[derive(Clone)]
+struct PartialMatch {
+    state: usize,
+    start: SystemTime,
+    end: SystemTime,
+    measures: Vec<Option<ScalarValue>>,
+}
+
+struct CompletedMatch {
+    key: Vec<ScalarValue>,
+    measures: Vec<Option<ScalarValue>>,
+    completed_at: SystemTime,
+}
+
+struct Partition {
+    key: Vec<ScalarValue>,
+    partial: Vec<PartialMatch>,
+    // the longest complete match among those that started earliest, which is emitted once no
+    // partial match that started at or before it can be extended
+    complete: Option<PartialMatch>,
+}
+
+impl Nfa {
+    fn new(
+        states: Vec<api::MatchRecognizeState>,
+        measures: Vec<Measure>,
+        within: Duration,
+    ) -> Self {
+        let closures: Vec<Vec<usize>> = (0..states.len())
+            .map(|state| {
+                let mut closure = vec![state];
+                let mut seen: HashSet<_> = closure.iter().copied().collect();
+                let mut next = 0;
+                while next < closure.len() {
+                    for target in &states[closure[next]].epsilon {
+                        if seen.insert(*target as usize) {
+                            closure.push(*target as usize);
+                        }
+                    }
+                    next += 1;
+                }
+                closure
+            })
+            .collect();
+
+        Self {
+            accepting: closures
+                .iter()
+                .map(|closure| closure.iter().any(|state| states[*state].accepting))
+                .collect(),
+            extendable: closures
+                .iter()
+                .map(|closure| {
+                    closure
+                        .iter()
+                        .any(|state| !states[*state].transitions.is_empty())
+                })
+                .collect(),
+            transitions: states
+                .iter()
+                .map(|state| {
+                    state
+                        .transitions
+                        .iter()
+                        .map(|t| (t.symbol as usize, t.target as usize))
+                        .collect()
+                })
+                .collect(),
+            closures,
+            measures,
+            within,
+        }
+    }
+
+    fn update_measures(
+        &self,
+        values: &mut [Option<ScalarValue>],
+        symbol: usize,
+        batch: &RecordBatch,
+        row: usize,
+    ) -> Result<()> {
+        for (measure, value) in self.measures.iter().zip(values.iter_mut()) {
+            if measure.symbol.is_some_and(|s| s != symbol) {
+                continue;
+            }
+            let current = match measure.column {
+                Some(column) => ScalarValue::try_from_array(batch.column(column), row)?,
+                None => ScalarValue::Null,
+            };
+            match measure.kind {
+                api::MatchRecognizeMeasureKind::First => {
+                    if value.is_none() {
+                        *value = Some(current);
+                    }
+                }
+                api::MatchRecognizeMeasureKind::Last => *value = Some(current),
+                api::MatchRecognizeMeasureKind::Count => {
+                    if measure.column.is_none() || !current.is_null() {
+                        let count = match value {
+                            Some(ScalarValue::Int64(Some(count))) => *count,
+                            _ => 0,
+                        };
+                        *value = Some(ScalarValue::Int64(Some(count + 1)));
+                    }
+                }
+                api::MatchRecognizeMeasureKind::Min | api::MatchRecognizeMeasureKind::Max => {
+                    if current.is_null() {
+                        continue;
+                    }
+                    let replace = match value {
+                        None => true,
+                        Some(existing) => {
+                            if measure.kind == api::MatchRecognizeMeasureKind::Min {
+                                current < *existing
+                            } else {
+                                current > *existing
+                            }
+                        }
+                    };
+                    if replace {
+                        *value = Some(current);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Feeds a row to the partition's partial matches, which each branch into every transition
+    /// the row satisfies, and to a new partial match starting at the row. Returns the match that
+    /// was completed by this row, if any.
+    fn advance(
+        &self,
+        partition: &mut Partition,
+        batch: &RecordBatch,
+        row: usize,
+        timestamp: SystemTime,
+        matched_symbols: &[bool],
+    ) -> Result<Option<PartialMatch>> {
+        let within = self.within;
+        let mut partial = mem::take(&mut partition.partial);
+        partial.retain(|m| timestamp.duration_since(m.start).unwrap_or_default() <= within);
+        partial.push(PartialMatch {
+            state: 0,
+            start: timestamp,
+            end: timestamp,
+            measures: vec![None; self.measures.len()],
+        });
+
+        let mut seen = HashSet::new();
+        let mut next = vec![];
+        for m in partial {
+            for state in &self.closures[m.state] {
+                for (symbol, target) in &self.transitions[*state] {
+                    if !matched_symbols[*symbol] || !seen.insert((*target, m.start)) {
+                        continue;
+                    }
+                    let mut measures = m.measures.clone();
+                    self.update_measures(&mut measures, *symbol, batch, row)?;
+                    next.push(PartialMatch {
+                        state: *target,
+                        start: m.start,
+                        end: timestamp,
+                        measures,
+                    });
+                }
+            }
+        }
+
+        for m in next.iter().filter(|m| self.accepting[m.state]) {
+            let preferred = match &partition.complete {
+                None => true,
+                Some(complete) => {
+                    m.start < complete.start || (m.start == complete.start && m.end > complete.end)
+                }
+            };
+            if preferred {
+                partition.complete = Some(m.clone());
+            }
+        }
+        partition.partial = next
+            .into_iter()
+            .filter(|m| self.extendable[m.state])
+            .collect();
+
+        Ok(Self::take_complete(partition))
+    }
+
+    /// Drops partial matches that no row after the watermark could extend within the bound
+    fn expire(&self, partition: &mut Partition, watermark: SystemTime) -> Option<PartialMatch> {
+        partition
+            .partial
+            .retain(|m| m.start + self.within > watermark);
+        Self::take_complete(partition)
+    }
+
+    fn take_complete(partition: &mut Partition) -> Option<PartialMatch> {
+        let complete = partition.complete.as_ref()?;
+        if partition.partial.iter().any(|m| m.start <= complete.start) {
+            return None;
+        }
+        let complete = partition.complete.take().unwrap();
+        // matching resumes after the last row of the match
+        partition.partial.retain(|m| m.start > complete.end);
+        Some(complete)
+    }
+}
+
+/// Matches a row pattern over each key of its input in event-time order, emitting a row for
+/// each match. Rows are buffered until the watermark passes them, and kept in state for the
+/// pattern's WITHIN bound so that partial matches can be rebuilt on restore.
+pub struct MatchRecognizeOperator {
+    input_schema: ArroyoSchemaRef,
+    output_schema: SchemaRef,
+    key_converter: Converter,
+    // the DEFINE condition of each pattern variable; variables without one match every row
+    symbols: Vec<Option<Arc<dyn PhysicalExpr>>>,
+    nfa: Nfa,
+    buffered: Vec<RecordBatch>,
+    partitions: HashMap<OwnedRow, Partition>,
+}
+
+impl MatchRecognizeOperator {
+    /// Runs the buffered rows up to the watermark through the NFA, in order for each key
+    fn advance_to(&mut self, watermark: SystemTime) -> Result<Vec<CompletedMatch>> {
+        let mut completed = vec![];
+        let batches = mem::take(&mut self.buffered);
+        let batch = concat_batches(&self.input_schema.schema, batches.iter())?;
+
+        let watermark_scalar = TimestampNanosecondArray::new_scalar(to_nanos(watermark) as i64);
+        let pending = gt(
+            self.input_schema.timestamp_column(&batch),
+            &watermark_scalar,
+        )?;
+        let ready = filter_record_batch(&batch, &not(&pending)?)?;
+        let pending = filter_record_batch(&batch, &pending)?;
+        if pending.num_rows() > 0 {
+            self.buffered.push(pending);
+        }
+
+        if ready.num_rows() > 0 {
+            let indices = lexsort_to_indices(&self.input_schema.sort_columns(&ready, true), None)?;
+            let columns = ready
+                .columns()
+                .iter()
+                .map(|column| take(column, &indices, None))
+                .collect::<Result<Vec<_>, _>>()?;
+            let batch = RecordBatch::try_new(ready.schema(), columns)?;
+
+            let conditions = self
+                .symbols
+                .iter()
+                .map(|symbol| match symbol {
+                    Some(expr) => {
+                        let array = expr.evaluate(&batch)?.into_array(batch.num_rows())?;
+                        array
+                            .as_any()
+                            .downcast_ref::<BooleanArray>()
+                            .cloned()
+                            .map(Some)
+                            .ok_or_else(|| anyhow!("DEFINE condition must be boolean"))
+                    }
+                    None => Ok(None),
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let key_indices = self.input_schema.key_indices.as_ref().unwrap();
+            let key_columns: Vec<ArrayRef> = key_indices
+                .iter()
+                .map(|index| batch.column(*index).clone())
+                .collect();
+            let keys = self
+                .key_converter
+                .convert_all_columns(&key_columns, batch.num_rows())?;
+            let timestamps = self.input_schema.timestamp_column(&batch);
+
+            for row in 0..batch.num_rows() {
+                let partition = match self.partitions.entry(keys.row(row).owned()) {
+                    std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                    std::collections::hash_map::Entry::Vacant(entry) => entry.insert(Partition {
+                        key: key_columns
+                            .iter()
+                            .map(|column| ScalarValue::try_from_array(column, row))
+                            .collect::<Result<_, _>>()?,
+                        partial: vec![],
+                        complete: None,
+                    }),
+                };
+                let matched_symbols: Vec<_> = conditions
+                    .iter()
+                    .map(|condition| {
+                        condition
+                            .as_ref()
+                            .map_or(true, |c| c.is_valid(row) && c.value(row))
+                    })
+                    .collect();
+                let timestamp = from_nanos(timestamps.value(row) as u128);
+                if let Some(m) =
+                    self.nfa
+                        .advance(partition, &batch, row, timestamp, &matched_symbols)?
+                {
+                    completed.push(CompletedMatch {
+                        key: partition.key.clone(),
+                        measures: m.measures,
+                        completed_at: timestamp,
+                    });
+                }
+            }
+        }
+
+        let nfa = &self.nfa;
+        self.partitions.retain(|_, partition| {
+            if let Some(m) = nfa.expire(partition, watermark) {
+                completed.push(CompletedMatch {
+                    key: partition.key.clone(),
+                    measures: m.measures,
+                    completed_at: watermark,
+                });
+            }
+            !partition.partial.is_empty() || partition.complete.is_some()
+        });
+
+        Ok(completed)
+    }
+
+    fn to_record_batch(&self, completed: Vec<CompletedMatch>) -> Result<RecordBatch> {
+        let key_count = completed[0].key.len();
+        let mut columns = (0..key_count)
+            .map(|index| ScalarValue::iter_to_array(completed.iter().map(|m| m.key[index].clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (index, measure) in self.nfa.measures.iter().enumerate() {
+            columns.push(ScalarValue::iter_to_array(completed.iter().map(|m| {
+                m.measures[index]
+                    .clone()
+                    .unwrap_or_else(|| measure.empty.clone())
+            }))?);
+        }
+        columns.push(Arc::new(TimestampNanosecondArray::from(
+            completed
+                .iter()
+                .map(|m| to_nanos(m.completed_at) as i64)
+                .collect::<Vec<_>>(),
+        )));
+        Ok(RecordBatch::try_new(self.output_schema.clone(), columns)?)
+    }
+}
+
+#[async_trait::async_trait]
+impl ArrowOperator for MatchRecognizeOperator {
+    fn name(&self) -> String {
+        "MatchRecognize".to_string()
+    }
+
+    async fn on_start(&mut self, ctx: &mut ArrowContext) {
+        let watermark = ctx.last_present_watermark();
+        let table = ctx
+            .table_manager
+            .get_expiring_time_key_table("i", watermark)
+            .await
+            .unwrap();
+        self.buffered = table
+            .all_batches_for_watermark(watermark)
+            .flat_map(|(_, batches)| batches.iter().cloned())
+            .collect();
+
+        // the rows the watermark has passed were matched before the checkpoint, so they're only
+        // replayed to rebuild the partial matches
+        if let Some(watermark) = watermark {
+            self.advance_to(watermark)
+                .expect("should be able to restore partial matches");
+        }
+    }
+
+    async fn process_batch(&mut self, batch: RecordBatch, ctx: &mut ArrowContext) {
+        let watermark = ctx.last_present_watermark();
+        let batch = if let Some(watermark) = watermark {
+            let watermark_scalar = TimestampNanosecondArray::new_scalar(to_nanos(watermark) as i64);
+            let on_time = gt(
+                self.input_schema.timestamp_column(&batch),
+                &watermark_scalar,
+            )
+            .unwrap();
+            ctx.drop_late_records(filter_record_batch(&batch, &not(&on_time).unwrap()).unwrap())
+                .await;
+            filter_record_batch(&batch, &on_time).unwrap()
+        } else {
+            batch
+        };
+        if batch.num_rows() == 0 {
+            return;
+        }
+
+        let max_timestamp =
+            from_nanos(max(self.input_schema.timestamp_column(&batch)).unwrap() as u128);
+        ctx.table_manager
+            .get_expiring_time_key_table("i", watermark)
+            .await
+            .expect("should have input table")
+            .insert(max_timestamp, batch.clone());
+        self.buffered.push(batch);
+    }
+
+    async fn handle_watermark(
+        &mut self,
+        watermark: Watermark,
+        ctx: &mut ArrowContext,
+    ) -> Option<Watermark> {
+        let Some(current) = ctx.last_present_watermark() else {
+            return Some(watermark);
+        };
+        let completed = self
+            .advance_to(current)
+            .expect("should be able to match rows");
+        if !completed.is_empty() {
+            let batch = self
+                .to_record_batch(completed)
+                .expect("should be able to build matches");
+            ctx.collect(batch).await;
+        }
+        Some(watermark)
+    }
+
+    async fn handle_checkpoint(&mut self, _cb: CheckpointBarrier, ctx: &mut ArrowContext) {
+        let watermark = ctx.last_present_watermark();
+        ctx.table_manager
+            .get_expiring_time_key_table("i", watermark)
+            .await
+            .expect("should have input table")
+            .flush(watermark)
+            .await
+            .expect("should flush");
+    }
+
+    fn tables(&self) -> HashMap<String, TableConfig> {
+        let mut tables = HashMap::new();
+        tables.insert(
+            "i".to_string(),
+            timestamp_table_config(
+                "i",
+                "match_recognize input",
+                self.nfa.within,
+                false,
+                self.input_schema.as_ref().clone(),
+            ),
+        );
+        tables
+    }
+}
+
+pub struct MatchRecognizeConstructor;
+impl OperatorConstructor for MatchRecognizeConstructor {
+    type ConfigT = api::MatchRecognizeOperator;
+    fn with_config(
+        &self,
+        config: Self::ConfigT,
+        registry: Arc<Registry>,
+    ) -> anyhow::Result<OperatorNode> {
+        let input_schema = Arc::new(ArroyoSchema::try_from(
+            config
+                .input_schema
+                .ok_or_else(|| anyhow!("missing input schema"))?,
+        )?);
+        let output_schema = ArroyoSchema::try_from(
+            config
+                .output_schema
+                .ok_or_else(|| anyhow!("missing output schema"))?,
+        )?
+        .schema;
+        if input_schema.key_indices.is_none() {
+            bail!("match_recognize input must be keyed");
+        }
+        if config.states.is_empty() {
+            bail!("match_recognize requires a compiled pattern");
+        }
+
+        let symbols = config
+            .symbols
+            .iter()
+            .map(|symbol| {
+                symbol
+                    .define
+                    .as_ref()
+                    .map(|define| {
+                        parse_physical_expr(
+                            &PhysicalExprNode::decode(&mut define.as_slice())?,
+                            registry.as_ref(),
+                            &input_schema.schema,
+                            &DefaultPhysicalExtensionCodec {},
+                        )
+                        .map_err(|e| anyhow!(e))
+                    })
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let key_count = input_schema.key_indices.as_ref().unwrap().len();
+        let measures = config
+            .measures
+            .iter()
+            .enumerate()
+            .map(|(index, measure)| {
+                let kind = api::MatchRecognizeMeasureKind::try_from(measure.kind)
+                    .map_err(|_| anyhow!("invalid measure kind {}", measure.kind))?;
+                let empty = match kind {
+                    api::MatchRecognizeMeasureKind::Count => ScalarValue::Int64(Some(0)),
+                    _ => ScalarValue::try_from(output_schema.field(key_count + index).data_type())?,
+                };
+                Ok(Measure {
+                    kind,
+                    symbol: measure.symbol.map(|symbol| symbol as usize),
+                    column: measure.column.map(|column| column as usize),
+                    empty,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(OperatorNode::from_operator(Box::new(
+            MatchRecognizeOperator {
+                key_converter: input_schema.converter(false)?,
+                nfa: Nfa::new(
+                    config.states,
+                    measures,
+                    Duration::from_micros(config.within_micros),
+                ),
+                input_schema,
+                output_schema,
+                symbols,
+                buffered: vec![],
+                partitions: HashMap::new(),
+            },
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arrow::test_utils::{nanos, OperatorHarness};
+    use arroyo_datastream::logical::OperatorName;
+    use serde_json::{json, Value};
+
+    const QUERY: &str = "
+        CREATE TABLE logins (
+            event_time TIMESTAMP,
+            user_id BIGINT,
+            success BOOLEAN,
+            ip TEXT
+        ) WITH (
+            connector = 'kafka',
+            bootstrap_servers = 'localhost:9092',
+            type = 'source',
+            topic = 'logins',
+            format = 'json',
+            event_time_field = 'event_time'
+        );
+
+        SELECT m.user_id, m.failures, m.login_ip
+        FROM logins
+        MATCH_RECOGNIZE (
+            PARTITION BY user_id
+            ORDER BY event_time
+            MEASURES
+                COUNT(F.*) AS failures,
+                S.ip AS login_ip
+            ONE ROW PER MATCH
+            AFTER MATCH SKIP PAST LAST ROW
+            PATTERN (F{3,} S)
+            WITHIN INTERVAL '5' MINUTE
+            DEFINE
+                F AS NOT success,
+                S AS success
+        ) AS m";
+
+    fn login(secs: u64, user_id: i64, success: bool) -> Value {
+        json!({
+            "_key_0": user_id,
+            "event_time": nanos(secs),
+            "user_id": user_id,
+            "success": success,
+            "ip": format!("10.0.0.{}", secs),
+            "_timestamp": nanos(secs),
+        })
+    }
+
+    /// Logins for a user at consecutive seconds starting at `start`, where `F` fails and `S`
+    /// succeeds
+    fn logins(start: u64, user_id: i64, pattern: &str) -> Vec<Value> {
+        pattern
+            .chars()
+            .enumerate()
+            .map(|(i, c)| login(start + i as u64, user_id, c == 'S'))
+            .collect()
+    }
+
+    fn matches(rows: Vec<Value>) -> Vec<(i64, i64, String)> {
+        rows.iter()
+            .map(|row| {
+                (
+                    row["user_id"].as_i64().unwrap(),
+                    row["failures"].as_i64().unwrap(),
+                    row["login_ip"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    async fn harness() -> OperatorHarness {
+        OperatorHarness::from_sql(QUERY, OperatorName::MatchRecognize).await
+    }
+
+    #[tokio::test]
+    async fn test_match_requires_at_least_three_failures() {
+        let mut harness = harness().await;
+
+        harness.process(0, &logins(1, 1, "FFS")).await;
+        harness.watermark(10).await;
+        assert_eq!(matches(harness.output()), vec![]);
+
+        harness.process(0, &logins(11, 1, "FFFFS")).await;
+        harness.watermark(20).await;
+        assert_eq!(
+            matches(harness.output()),
+            vec![(1, 4, "10.0.0.15".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_matching_resumes_past_the_last_row() {
+        let mut harness = harness().await;
+
+        // the later failures also precede the success, but they're part of the first match, so
+        // the only other match is the one after it
+        harness.process(0, &logins(1, 1, "FFFFSFFFS")).await;
+        harness.watermark(20).await;
+        assert_eq!(
+            matches(harness.output()),
+            vec![
+                (1, 4, "10.0.0.5".to_string()),
+                (1, 3, "10.0.0.9".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_match_must_complete_within_bound() {
+        let mut harness = harness().await;
+
+        // the success is more than five minutes after the first failure, and it can't start
+        // from the later ones as there aren't three of them
+        harness.process(0, &logins(1, 1, "FFF")).await;
+        harness.process(0, &[login(302, 1, true)]).await;
+        harness.watermark(400).await;
+        assert_eq!(matches(harness.output()), vec![]);
+
+        // a match that starts after the partial one expired isn't affected by it
+        harness.process(0, &logins(500, 1, "FFFS")).await;
+        harness.watermark(600).await;
+        assert_eq!(
+            matches(harness.output()),
+            vec![(1, 3, "10.0.0.503".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_partitions_are_matched_separately() {
+        let mut harness = harness().await;
+
+        let mut rows = logins(1, 1, "FF");
+        rows.extend(logins(3, 2, "F"));
+        rows.extend(logins(4, 1, "FS"));
+        rows.extend(logins(6, 2, "FS"));
+        harness.process(0, &rows).await;
+        harness.watermark(10).await;
+
+        // user 2 has only two failures, even though there are three failures in a row overall
+        assert_eq!(
+            matches(harness.output()),
+            vec![(1, 3, "10.0.0.5".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_partial_match_survives_restore() {
+        let mut harness = harness().await;
+
+        harness.process(0, &logins(1, 1, "FF")).await;
+        harness.watermark(5).await;
+        // the next failure is still buffered behind the watermark when the checkpoint is taken
+        harness.process(0, &logins(6, 1, "F")).await;
+        harness.checkpoint().await;
+        assert_eq!(matches(harness.output()), vec![]);
+
+        let mut harness = harness.restore().await;
+        harness.process(0, &[login(7, 1, true)]).await;
+        harness.watermark(10).await;
+        assert_eq!(
+            matches(harness.output()),
+            vec![(1, 3, "10.0.0.7".to_string())]
+        );
+    }
+}
@@ -25,11 +25,17 @@ use std::sync::Arc;
 use std::sync::RwLock;
 
 pub mod async_udf;
+pub mod dedup;
 pub mod instant_join;
 pub mod join_with_expiration;
+pub mod match_recognize;
 pub mod session_aggregating_window;
 pub mod sliding_aggregating_window;
 pub(crate) mod sync;
+pub mod temporal_join;
+#[cfg(test)]
+pub(crate) mod test_utils;
+pub mod top_n;
 pub mod tumbling_aggregating_window;
 pub mod updating_aggregator;
 pub mod watermark_generator;
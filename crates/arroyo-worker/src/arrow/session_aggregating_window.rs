@@ -7,7 +7,7 @@ use std::{
 use anyhow::{anyhow, bail, Context, Result};
 use arrow::{
     compute::{
-        concat_batches, filter_record_batch, kernels::cmp::gt_eq, lexsort_to_indices, max,
+        concat_batches, filter_record_batch, kernels::cmp::gt_eq, lexsort_to_indices, max, not,
         partition, take, SortColumn,
     },
     row::{OwnedRow, RowConverter, SortField},
@@ -821,6 +821,8 @@ impl ArrowOperator for SessionAggregatingWindowFunc {
                 .unwrap();
             let watermark_scalar = TimestampNanosecondArray::new_scalar(to_nanos(watermark) as i64);
             let on_time = gt_eq(timestamp_column, &watermark_scalar).unwrap();
+            ctx.drop_late_records(filter_record_batch(&batch, &not(&on_time).unwrap()).unwrap())
+                .await;
             filter_record_batch(&batch, &on_time).unwrap()
         } else {
             batch
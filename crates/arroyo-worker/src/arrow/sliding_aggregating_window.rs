@@ -8,12 +8,14 @@ use arroyo_operator::{
 };
 use arroyo_rpc::grpc::{api, rpc::TableConfig};
 use arroyo_state::timestamp_table_config;
-use arroyo_types::{from_nanos, print_time, to_nanos, CheckpointBarrier, Watermark};
+use arroyo_types::{
+    from_nanos, print_time, sliding_window_pane, to_nanos, CheckpointBarrier, Watermark,
+};
 use datafusion::common::ScalarValue;
 use datafusion::{execution::context::SessionContext, physical_plan::ExecutionPlan};
 use std::borrow::Cow;
 use std::{
-    collections::{BTreeMap, HashMap, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
     fmt::{Display, Formatter},
     sync::{Arc, RwLock},
     time::SystemTime,
@@ -41,14 +43,26 @@ use tokio_stream::StreamExt;
 use tracing::info;
 
 use super::sync::streams::KeyedCloneableStreamFuture;
+use super::tumbling_aggregating_window::{emit_firing, fired_schema};
 
 pub struct SlidingAggregatingWindowFunc<K: Copy> {
     slide: Duration,
     width: Duration,
+    // partial aggregates are computed for panes of this width, which evenly divides both the
+    // slide and the width, so each window is the combination of width / pane panes
+    pane: Duration,
+    // cumulative windows all start at a multiple of the width and grow by the slide until they
+    // reach it, instead of sliding over the data
+    cumulative: bool,
     binning_function: Arc<dyn PhysicalExpr>,
     partial_aggregation_plan: Arc<dyn ExecutionPlan>,
     partial_schema: ArroyoSchema,
     finish_execution_plan: Arc<dyn ExecutionPlan>,
+    // for cumulative windows, merges partial aggregates, so that each window is computed from the
+    // running partial of the one before it and its newest pane
+    combine_plan: Option<Arc<dyn ExecutionPlan>>,
+    // the start of the current cumulative window and its partial aggregates up to the last pane
+    running_partial: Option<(SystemTime, Vec<RecordBatch>)>,
     // the partial aggregation plan shares a reference to it,
     // which is only used on the exec()
     receiver: Arc<RwLock<Option<UnboundedReceiver<RecordBatch>>>>,
@@ -59,6 +73,14 @@ pub struct SlidingAggregatingWindowFunc<K: Copy> {
     projection_input_schema: SchemaRef,
     final_projection: Arc<dyn ExecutionPlan>,
     state: SlidingWindowState,
+    early_fire: Option<Duration>,
+    allowed_lateness: Duration,
+    // when windows fire more than once, the last results emitted for each key and window,
+    // keyed by the id of their _updating_meta. The panes of such windows stay in execs until
+    // no open window needs them, instead of moving through the tiered_record_batches.
+    fired_schema: Option<ArroyoSchema>,
+    // the ends of the windows that have changed since they last fired
+    changed_windows: BTreeSet<SystemTime>,
 }
 
 #[allow(clippy::enum_variant_names)]
@@ -69,8 +91,8 @@ enum SlidingWindowState {
     // We've received data, but don't have any data in the memory_view.
     OnlyBufferedData { earliest_bin_time: SystemTime },
     // There is data in memory_view waiting to be emitted.
-    // will trigger on a watermark after next_window_start + self.slide
-    InMemoryData { next_window_start: SystemTime },
+    // will trigger on a watermark after next_pane_start + self.pane
+    InMemoryData { next_pane_start: SystemTime },
 }
 
 impl Display for SlidingWindowState {
@@ -80,8 +102,8 @@ impl Display for SlidingWindowState {
             SlidingWindowState::OnlyBufferedData { earliest_bin_time } => {
                 write!(f, "OnlyBufferedData({})", print_time(*earliest_bin_time))
             }
-            SlidingWindowState::InMemoryData { next_window_start } => {
-                write!(f, "InMemoryData({})", print_time(*next_window_start))
+            SlidingWindowState::InMemoryData { next_pane_start } => {
+                write!(f, "InMemoryData({})", print_time(*next_pane_start))
             }
         }
     }
@@ -89,14 +111,53 @@ impl Display for SlidingWindowState {
 
 impl<K: Copy> SlidingAggregatingWindowFunc<K> {
     fn bin_start(&self, timestamp: SystemTime) -> SystemTime {
-        if self.slide == Duration::ZERO {
+        if self.pane == Duration::ZERO {
             return timestamp;
         }
         let mut nanos = to_nanos(timestamp);
-        nanos -= nanos % self.slide.as_nanos();
+        nanos -= nanos % self.pane.as_nanos();
 
         from_nanos(nanos)
     }
+
+    // windows start at multiples of the slide, so their ends are offset from them by the
+    // remainder of the width. Returns the end of the first window ending at or after timestamp.
+    // Cumulative widths are a multiple of the slide, so their windows end at each slide.
+    fn window_end(&self, timestamp: SystemTime) -> SystemTime {
+        let slide = self.slide.as_nanos();
+        let offset = self.width.as_nanos() % slide;
+        let remainder = (to_nanos(timestamp) % slide + slide - offset) % slide;
+        if remainder == 0 {
+            return timestamp;
+        }
+        timestamp + Duration::from_nanos((slide - remainder) as u64)
+    }
+
+    fn window_start(&self, window_end: SystemTime) -> SystemTime {
+        if !self.cumulative {
+            return window_end - self.width;
+        }
+        match to_nanos(window_end) % self.width.as_nanos() {
+            0 => window_end - self.width,
+            into_period => window_end - Duration::from_nanos(into_period as u64),
+        }
+    }
+
+    // panes before this can no longer receive data
+    fn first_open_pane(&self, watermark: SystemTime) -> SystemTime {
+        self.bin_start(watermark - self.allowed_lateness)
+    }
+
+    // the ends of the windows that contain the pane starting at pane_start
+    fn windows_for_pane(&self, pane_start: SystemTime) -> Vec<SystemTime> {
+        let mut window_end = self.window_end(pane_start + self.pane);
+        let mut windows = vec![];
+        while self.window_start(window_end) <= pane_start {
+            windows.push(window_end);
+            window_end += self.slide;
+        }
+        windows
+    }
 }
 
 impl SlidingAggregatingWindowFunc<SystemTime> {
@@ -105,10 +166,10 @@ impl SlidingAggregatingWindowFunc<SystemTime> {
         match self.state {
             SlidingWindowState::NoData => false,
             SlidingWindowState::OnlyBufferedData { earliest_bin_time } => {
-                earliest_bin_time + self.slide <= watermark_bin
+                earliest_bin_time + self.pane <= watermark_bin
             }
-            SlidingWindowState::InMemoryData { next_window_start } => {
-                next_window_start + self.slide <= watermark_bin
+            SlidingWindowState::InMemoryData { next_pane_start } => {
+                next_pane_start + self.pane <= watermark_bin
             }
         }
     }
@@ -117,14 +178,14 @@ impl SlidingAggregatingWindowFunc<SystemTime> {
         let bin_start = match self.state {
             SlidingWindowState::NoData => unreachable!(),
             SlidingWindowState::OnlyBufferedData { earliest_bin_time } => earliest_bin_time,
-            SlidingWindowState::InMemoryData { next_window_start } => next_window_start,
+            SlidingWindowState::InMemoryData { next_pane_start } => next_pane_start,
         };
         let partial_table = ctx
             .table_manager
             .get_expiring_time_key_table("t", ctx.last_present_watermark())
             .await?;
 
-        let bin_end = bin_start + self.slide;
+        let bin_end = bin_start + self.pane;
         partial_table.flush(Some(bin_end)).await?;
 
         if let Some(mut bin_exec) = self.execs.remove(&bin_start) {
@@ -155,22 +216,30 @@ impl SlidingAggregatingWindowFunc<SystemTime> {
             }
         }
         partial_table.flush_timestamp(bin_end).await?;
-        partial_table.expire_timestamp(bin_end - self.width + self.slide);
-        let interval_start = bin_end - self.width;
+
+        // a window is emitted once its final pane is complete
+        let emit_window = self.window_end(bin_end) == bin_end;
+        let next_window_end = if emit_window {
+            bin_end + self.slide
+        } else {
+            self.window_end(bin_end)
+        };
+        // panes before the start of the next window to be emitted are no longer needed
+        let cutoff = self.window_start(next_window_end);
+        partial_table.expire_timestamp(cutoff);
+        let interval_start = self.window_start(bin_end);
         let interval_end = bin_end;
-        {
-            let mut batches = self.final_batches_passer.write().unwrap();
-            *batches = self
-                .tiered_record_batches
-                .batches_for_interval(interval_start, interval_end)?;
-        }
-        self.finish_execution_plan.reset()?;
-        let mut final_exec = self
-            .finish_execution_plan
-            .execute(0, SessionContext::new().task_ctx())
-            .unwrap();
-        self.tiered_record_batches
-            .delete_before(bin_end + self.slide - self.width)?;
+        let window_batches = if !emit_window {
+            None
+        } else if self.cumulative {
+            Some(self.cumulate(interval_start, bin_start).await?)
+        } else {
+            Some(
+                self.tiered_record_batches
+                    .batches_for_interval(interval_start, interval_end)?,
+            )
+        };
+        self.tiered_record_batches.delete_before(cutoff)?;
 
         self.state = if self.tiered_record_batches.is_empty() {
             match partial_table.get_min_time() {
@@ -181,18 +250,101 @@ impl SlidingAggregatingWindowFunc<SystemTime> {
             }
         } else {
             SlidingWindowState::InMemoryData {
-                next_window_start: bin_end,
+                next_pane_start: bin_end,
             }
         };
+        let Some(window_batches) = window_batches else {
+            return Ok(());
+        };
+        for batch in self
+            .finish_window(interval_start, interval_end, window_batches)
+            .await?
+        {
+            ctx.collector.collect(batch).await;
+        }
+
+        Ok(())
+    }
+
+    // adds the pane starting at pane_start to the running partial of the cumulative window starting
+    // at window_start, returning the window's partial aggregates
+    async fn cumulate(
+        &mut self,
+        window_start: SystemTime,
+        pane_start: SystemTime,
+    ) -> Result<Vec<RecordBatch>> {
+        let mut partials = match self.running_partial.take() {
+            Some((start, partials)) if start == window_start => partials,
+            // the window's first pane, or the first since a restore, which has to start from the
+            // window's earlier panes
+            _ => self
+                .tiered_record_batches
+                .batches_for_interval(window_start, pane_start)?,
+        };
+        partials.extend(
+            self.tiered_record_batches
+                .batches_for_interval(pane_start, pane_start + self.pane)?,
+        );
+
+        let partials = self.combine(partials).await?;
+        self.running_partial = Some((window_start, partials.clone()));
+        Ok(partials)
+    }
+
+    // merges partial aggregates into one per key
+    async fn combine(&mut self, partials: Vec<RecordBatch>) -> Result<Vec<RecordBatch>> {
+        let Some(combine_plan) = self.combine_plan.clone() else {
+            bail!("only cumulative windows combine partial aggregates");
+        };
+        if partials.is_empty() {
+            return Ok(partials);
+        }
+        {
+            let mut batches = self.final_batches_passer.write().unwrap();
+            *batches = partials;
+        }
+        combine_plan.reset()?;
+        let mut combine_exec = combine_plan.execute(0, SessionContext::new().task_ctx())?;
+        let partial_schema = self.partial_aggregation_plan.schema();
+        let mut combined = vec![];
+        while let Some(batch) = combine_exec.next().await {
+            combined.push(RecordBatch::try_new(
+                partial_schema.clone(),
+                batch?.columns().to_vec(),
+            )?);
+        }
+        Ok(combined)
+    }
+
+    // computes the results of a window from the partial aggregates of its panes
+    async fn finish_window(
+        &mut self,
+        window_start: SystemTime,
+        window_end: SystemTime,
+        partial_batches: Vec<RecordBatch>,
+    ) -> Result<Vec<RecordBatch>> {
+        {
+            let mut batches = self.final_batches_passer.write().unwrap();
+            *batches = partial_batches;
+        }
+        self.finish_execution_plan.reset()?;
+        let mut final_exec = self
+            .finish_execution_plan
+            .execute(0, SessionContext::new().task_ctx())?;
+        // the final projection of cumulative windows computes their start from the start of the
+        // newest pane, as the windows of a period don't all have the same width
+        let window_timestamp = if self.cumulative {
+            window_end - self.slide
+        } else {
+            window_start
+        };
         let mut aggregate_results = Vec::new();
         while let Some(batch) = final_exec.next().await {
-            let batch = batch.expect("should be able to compute batch");
-            let with_timestamp = Self::add_bin_start_as_timestamp(
-                &batch,
-                interval_start,
+            aggregate_results.push(Self::add_bin_start_as_timestamp(
+                &batch?,
+                window_timestamp,
                 self.projection_input_schema.clone(),
-            )?;
-            aggregate_results.push(with_timestamp);
+            )?);
         }
         {
             let mut batches = self.final_batches_passer.write().unwrap();
@@ -202,13 +354,54 @@ impl SlidingAggregatingWindowFunc<SystemTime> {
         let mut final_projection_exec = self
             .final_projection
             .execute(0, SessionContext::new().task_ctx())?;
+        let mut results = vec![];
         while let Some(batch) = final_projection_exec.next().await {
-            let batch = batch.expect("should be able to compute batch");
-            ctx.collector.collect(batch).await;
+            results.push(batch?);
         }
+        Ok(results)
+    }
 
-        Ok(())
+    // emits the current results of the window ending at window_end, retracting whatever it
+    // emitted before
+    async fn fire_window(&mut self, window_end: SystemTime, ctx: &mut ArrowContext) -> Result<()> {
+        let Some(fired_schema) = self.fired_schema.clone() else {
+            bail!("only windows with early firing or allowed lateness fire more than once");
+        };
+        self.changed_windows.remove(&window_end);
+        let window_start = self.window_start(window_end);
+
+        let watermark = ctx.last_present_watermark();
+        let table = ctx
+            .table_manager
+            .get_expiring_time_key_table("t", watermark)
+            .await?;
+        let mut partial_batches = vec![];
+        for (pane_start, exec) in self.execs.range_mut(window_start..window_end) {
+            // the drained partials won't be seen by the next checkpoint, so write them now
+            let new_partials = exec.drain_active_exec().await;
+            for batch in &new_partials {
+                table.insert(
+                    *pane_start,
+                    Self::add_bin_start_as_timestamp(
+                        batch,
+                        *pane_start,
+                        self.partial_schema.schema.clone(),
+                    )?,
+                );
+            }
+            exec.finished_batches.extend(new_partials);
+            partial_batches.extend(exec.finished_batches.iter().cloned());
+        }
+        if partial_batches.is_empty() {
+            return Ok(());
+        }
+
+        let results = self
+            .finish_window(window_start, window_end, partial_batches)
+            .await?;
+        emit_firing(&fired_schema, results, ctx).await
     }
+
     // TODO: don't repeat this
     fn add_bin_start_as_timestamp(
         batch: &RecordBatch,
@@ -443,23 +636,39 @@ impl<K: Copy> Default for BinComputingHolder<K> {
     }
 }
 
+impl<K: Copy> BinComputingHolder<K> {
+    // closes the active partial aggregation, returning the batches it still had to produce
+    async fn drain_active_exec(&mut self) -> Vec<RecordBatch> {
+        self.sender.take();
+        let mut batches = vec![];
+        if let Some(mut active_exec) = self.active_exec.take() {
+            while let (_bin, Some((batch, next_exec))) = active_exec.await {
+                active_exec = next_exec;
+                batches.push(batch.expect("should be able to compute batch"));
+            }
+        }
+        batches
+    }
+}
+
 type NextBatchFuture<K> = KeyedCloneableStreamFuture<K, SendableRecordBatchStream>;
 
 pub struct SlidingAggregatingWindowConstructor;
 
-impl OperatorConstructor for SlidingAggregatingWindowConstructor {
-    type ConfigT = api::SlidingWindowAggregateOperator;
-    fn with_config(
-        &self,
-        config: Self::ConfigT,
+impl SlidingAggregatingWindowConstructor {
+    // cumulative windows are built with the plan that combines their partial aggregates
+    fn build(
+        config: api::SlidingWindowAggregateOperator,
         registry: Arc<Registry>,
-    ) -> anyhow::Result<OperatorNode> {
+        combine_plan: Option<Vec<u8>>,
+    ) -> anyhow::Result<SlidingAggregatingWindowFunc<SystemTime>> {
         let width = Duration::from_micros(config.width_micros);
         let input_schema: ArroyoSchema = config
             .input_schema
             .ok_or_else(|| anyhow!("missing input schema"))?
             .try_into()?;
         let slide = Duration::from_micros(config.slide_micros);
+        let pane = sliding_window_pane(width, slide);
         let binning_function = PhysicalExprNode::decode(&mut config.binning_function.as_slice())?;
         let binning_function = parse_physical_expr(
             &binning_function,
@@ -505,25 +714,104 @@ impl OperatorConstructor for SlidingAggregatingWindowConstructor {
             &final_codec,
         )?;
 
+        let cumulative = combine_plan.is_some();
+        let combine_plan =
+            combine_plan
+                .map(|combine_plan| -> Result<_> {
+                    Ok(PhysicalPlanNode::decode(&mut combine_plan.as_slice())?
+                        .try_into_physical_plan(
+                            registry.as_ref(),
+                            &RuntimeEnv::new(RuntimeConfig::new()).unwrap(),
+                            &final_codec,
+                        )?)
+                })
+                .transpose()?;
+
+        let early_fire = Some(Duration::from_micros(config.early_fire_micros))
+            .filter(|interval| !interval.is_zero());
+        let allowed_lateness = Duration::from_micros(config.allowed_lateness_micros);
+        let fired_schema = if early_fire.is_some() || !allowed_lateness.is_zero() {
+            Some(fired_schema(&final_projection.schema())?)
+        } else {
+            None
+        };
+
+        Ok(SlidingAggregatingWindowFunc {
+            slide,
+            width,
+            pane,
+            cumulative,
+            binning_function,
+            partial_aggregation_plan,
+            partial_schema,
+            finish_execution_plan,
+            combine_plan,
+            running_partial: None,
+            receiver,
+            final_batches_passer,
+            futures: FuturesUnordered::new(),
+            execs: BTreeMap::new(),
+            tiered_record_batches: TieredRecordBatchHolder::new(vec![pane])?,
+            projection_input_schema: final_projection.children()[0].schema().clone(),
+            final_projection,
+            state: SlidingWindowState::NoData,
+            early_fire,
+            allowed_lateness,
+            fired_schema,
+            changed_windows: BTreeSet::new(),
+        })
+    }
+}
+
+impl OperatorConstructor for SlidingAggregatingWindowConstructor {
+    type ConfigT = api::SlidingWindowAggregateOperator;
+    fn with_config(
+        &self,
+        config: Self::ConfigT,
+        registry: Arc<Registry>,
+    ) -> anyhow::Result<OperatorNode> {
+        Ok(OperatorNode::from_operator(Box::new(Self::build(
+            config, registry, None,
+        )?)))
+    }
+}
+
+pub struct CumulativeAggregatingWindowConstructor;
+
+impl OperatorConstructor for CumulativeAggregatingWindowConstructor {
+    type ConfigT = api::CumulativeWindowAggregateOperator;
+    fn with_config(
+        &self,
+        config: Self::ConfigT,
+        registry: Arc<Registry>,
+    ) -> anyhow::Result<OperatorNode> {
+        if config.step_micros == 0 || config.max_size_micros % config.step_micros != 0 {
+            bail!(
+                "cumulative window max size {}us must be a multiple of its step {}us",
+                config.max_size_micros,
+                config.step_micros
+            );
+        }
+        // a cumulative window is computed like a sliding window over panes of the step, only
+        // combining the panes since the start of the period rather than the last width's worth.
+        // Each window's partial aggregates are kept and merged with the next pane's, rather than
+        // recombining every pane of the period at each step.
+        let combine_plan = config.combine_plan;
+        let config = api::SlidingWindowAggregateOperator {
+            name: config.name,
+            width_micros: config.max_size_micros,
+            slide_micros: config.step_micros,
+            binning_function: config.binning_function,
+            input_schema: config.input_schema,
+            partial_schema: config.partial_schema,
+            partial_aggregation_plan: config.partial_aggregation_plan,
+            final_aggregation_plan: config.final_aggregation_plan,
+            final_projection: config.final_projection,
+            early_fire_micros: 0,
+            allowed_lateness_micros: 0,
+        };
         Ok(OperatorNode::from_operator(Box::new(
-            SlidingAggregatingWindowFunc {
-                slide,
-                width,
-                binning_function,
-                partial_aggregation_plan,
-                partial_schema,
-                finish_execution_plan,
-                receiver,
-                final_batches_passer,
-                futures: FuturesUnordered::new(),
-                execs: BTreeMap::new(),
-                tiered_record_batches: TieredRecordBatchHolder::new(vec![Duration::from_micros(
-                    config.slide_micros,
-                )])?,
-                projection_input_schema: final_projection.children()[0].schema().clone(),
-                final_projection,
-                state: SlidingWindowState::NoData,
-            },
+            SlidingAggregatingWindowConstructor::build(config, registry, Some(combine_plan))?,
         )))
     }
 }
@@ -532,15 +820,29 @@ impl OperatorConstructor for SlidingAggregatingWindowConstructor {
 
 impl ArrowOperator for SlidingAggregatingWindowFunc<SystemTime> {
     fn name(&self) -> String {
-        "sliding_window".to_string()
+        if self.cumulative {
+            "cumulative_window".to_string()
+        } else {
+            "sliding_window".to_string()
+        }
     }
 
     fn display(&self) -> DisplayableOperator {
         DisplayableOperator {
-            name: Cow::Borrowed("SlidingAggregatingWindowFunc"),
+            name: Cow::Borrowed(if self.cumulative {
+                "CumulativeAggregatingWindowFunc"
+            } else {
+                "SlidingAggregatingWindowFunc"
+            }),
             fields: vec![
                 ("slide", AsDisplayable::Debug(&self.slide)),
                 ("width", AsDisplayable::Debug(&self.width)),
+                ("pane", AsDisplayable::Debug(&self.pane)),
+                ("early_fire", AsDisplayable::Debug(&self.early_fire)),
+                (
+                    "allowed_lateness",
+                    AsDisplayable::Debug(&self.allowed_lateness),
+                ),
                 (
                     "partial_aggregation_plan",
                     self.partial_aggregation_plan.as_ref().into(),
@@ -561,6 +863,29 @@ impl ArrowOperator for SlidingAggregatingWindowFunc<SystemTime> {
             .get_expiring_time_key_table("t", watermark)
             .await
             .expect("should be able to load table");
+        if self.fired_schema.is_some() {
+            let first_open_pane = watermark.map(|watermark| self.first_open_pane(watermark));
+            for (timestamp, batches) in table.all_batches_for_watermark(watermark) {
+                let pane = self.bin_start(*timestamp);
+                // we don't know whether open windows fired before the restore, so fire them again
+                for window_end in self.windows_for_pane(pane) {
+                    if first_open_pane.map_or(true, |first_open_pane| window_end > first_open_pane)
+                    {
+                        self.changed_windows.insert(window_end);
+                    }
+                }
+                self.execs
+                    .entry(pane)
+                    .or_default()
+                    .finished_batches
+                    .extend(batches.iter().cloned());
+            }
+            ctx.table_manager
+                .get_last_key_value_table("f", watermark)
+                .await
+                .expect("should be able to load table");
+            return;
+        }
         // bins before the watermark should be put into the TieredRecordBatchHolder, those after in the exec.
         let watermark_bin = self.bin_start(watermark.unwrap_or(SystemTime::UNIX_EPOCH));
         for (timestamp, batches) in table.all_batches_for_watermark(watermark) {
@@ -590,12 +915,11 @@ impl ArrowOperator for SlidingAggregatingWindowFunc<SystemTime> {
             }
         } else {
             self.state = SlidingWindowState::InMemoryData {
-                next_window_start: watermark_bin,
+                next_pane_start: watermark_bin,
             };
         }
     }
 
-    // TODO: filter out late data
     async fn process_batch(&mut self, batch: RecordBatch, ctx: &mut ArrowContext) {
         let bin = self
             .binning_function
@@ -624,10 +948,17 @@ impl ArrowOperator for SlidingAggregatingWindowFunc<SystemTime> {
 
             let watermark = ctx.last_present_watermark();
 
-            if watermark.is_some() && bin_start < self.bin_start(watermark.unwrap()) {
-                return;
+            if watermark.is_some() && bin_start < self.first_open_pane(watermark.unwrap()) {
+                ctx.drop_late_records(sorted.slice(range.start, range.end - range.start))
+                    .await;
+                continue;
             }
 
+            if self.fired_schema.is_some() {
+                for window_end in self.windows_for_pane(bin_start) {
+                    self.changed_windows.insert(window_end);
+                }
+            }
             self.state = match self.state {
                 SlidingWindowState::NoData => SlidingWindowState::OnlyBufferedData {
                     earliest_bin_time: bin_start,
@@ -637,8 +968,8 @@ impl ArrowOperator for SlidingAggregatingWindowFunc<SystemTime> {
                         earliest_bin_time: earliest_bin_time.min(bin_start),
                     }
                 }
-                SlidingWindowState::InMemoryData { next_window_start } => {
-                    SlidingWindowState::InMemoryData { next_window_start }
+                SlidingWindowState::InMemoryData { next_pane_start } => {
+                    SlidingWindowState::InMemoryData { next_pane_start }
                 }
             };
             let bin_batch = sorted.slice(range.start, range.end - range.start);
@@ -675,6 +1006,30 @@ impl ArrowOperator for SlidingAggregatingWindowFunc<SystemTime> {
     ) -> Option<Watermark> {
         let last_watermark = ctx.last_present_watermark()?;
 
+        if self.fired_schema.is_some() {
+            // fire the closed windows that changed, then drop the panes no open window needs
+            let closed_windows: Vec<_> = self
+                .changed_windows
+                .range(..=self.bin_start(last_watermark))
+                .copied()
+                .collect();
+            for window_end in closed_windows {
+                self.fire_window(window_end, ctx)
+                    .await
+                    .expect("should be able to fire window");
+            }
+            if let Some(cutoff) = self.first_open_pane(last_watermark).checked_sub(self.width) {
+                self.execs = self.execs.split_off(&cutoff);
+            }
+            ctx.table_manager
+                .get_last_key_value_table("f", Some(last_watermark))
+                .await
+                .expect("should have fired table")
+                .expire(Some(last_watermark))
+                .expect("should expire fired table");
+            return Some(watermark);
+        }
+
         while self.should_advance(last_watermark) {
             self.advance(ctx).await.unwrap();
         }
@@ -724,17 +1079,37 @@ impl ArrowOperator for SlidingAggregatingWindowFunc<SystemTime> {
     }
 
     fn tables(&self) -> HashMap<String, TableConfig> {
-        vec![(
+        let retention = self.width + self.allowed_lateness;
+        let mut tables = HashMap::new();
+        tables.insert(
             "t".to_string(),
             timestamp_table_config(
                 "t",
                 "Sliding_intermediate",
-                self.width,
+                retention,
                 false,
                 self.partial_schema.clone(),
             ),
-        )]
-        .into_iter()
-        .collect()
+        );
+        if let Some(fired_schema) = &self.fired_schema {
+            tables.insert(
+                "f".to_string(),
+                timestamp_table_config("f", "sliding_fired", retention, true, fired_schema.clone()),
+            );
+        }
+        tables
+    }
+
+    fn tick_interval(&self) -> Option<Duration> {
+        self.early_fire
+    }
+
+    async fn handle_tick(&mut self, _tick: u64, ctx: &mut ArrowContext) {
+        let changed_windows: Vec<_> = self.changed_windows.iter().copied().collect();
+        for window_end in changed_windows {
+            self.fire_window(window_end, ctx)
+                .await
+                .expect("should be able to fire window");
+        }
     }
 }
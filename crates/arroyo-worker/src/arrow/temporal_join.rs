@@ -0,0 +1,453 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, bail, Result};
+use arrow::compute::kernels::cmp::gt;
+use arrow::compute::{filter_record_batch, max, not, take};
+use arrow_array::{ArrayRef, RecordBatch, TimestampNanosecondArray, UInt32Array};
+use arrow_schema::SchemaRef;
+use arroyo_operator::context::ArrowContext;
+use arroyo_operator::operator::{
+    ArrowOperator, AsDisplayable, DisplayableOperator, OperatorConstructor, OperatorNode, Registry,
+};
+use arroyo_rpc::df::{ArroyoSchema, ArroyoSchemaRef};
+use arroyo_rpc::grpc::{api, rpc::TableConfig};
+use arroyo_state::tables::expiring_time_key_map::VersionedKeyView;
+use arroyo_state::timestamp_table_config;
+use arroyo_types::{from_nanos, to_nanos, CheckpointBarrier, Watermark};
+
+/// Joins each left row with the version of the right row with the same key that was current as
+/// of the left row's timestamp. Left rows are held until the watermark passes their timestamp, as
+/// only then are all of the right side's versions up to that time known.
+pub struct TemporalJoin {
+    left_input_schema: ArroyoSchemaRef,
+    left_schema: ArroyoSchema,
+    right_input_schema: ArroyoSchemaRef,
+    output_schema: SchemaRef,
+    left_outer: bool,
+    ttl: Duration,
+    buffered: Vec<RecordBatch>,
+}
+
+impl TemporalJoin {
+    fn split_at(&self, batch: &RecordBatch, watermark: SystemTime) -> Result<[RecordBatch; 2]> {
+        let watermark_scalar = TimestampNanosecondArray::new_scalar(to_nanos(watermark) as i64);
+        let pending = gt(
+            self.left_input_schema.timestamp_column(batch),
+            &watermark_scalar,
+        )?;
+        Ok([
+            filter_record_batch(batch, &not(&pending)?)?,
+            filter_record_batch(batch, &pending)?,
+        ])
+    }
+
+    fn join(&self, left: &RecordBatch, right: &VersionedKeyView) -> Result<RecordBatch> {
+        let key_columns: Vec<_> = self
+            .left_input_schema
+            .key_indices
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|index| left.column(*index).clone())
+            .collect();
+        let (right_columns, found) =
+            right.get_values_at(&key_columns, self.left_input_schema.timestamp_column(left))?;
+
+        let mut left = self.left_input_schema.unkeyed_batch(left)?;
+        let right_columns: Vec<ArrayRef> = if self.left_outer {
+            // rows without a version take a null index, which makes their right columns null
+            let mut next = 0;
+            let indices: UInt32Array = found
+                .iter()
+                .map(|found| {
+                    found.unwrap_or(false).then(|| {
+                        next += 1;
+                        next - 1
+                    })
+                })
+                .collect();
+            right_columns
+                .iter()
+                .map(|column| take(column, &indices, None))
+                .collect::<Result<_, _>>()?
+        } else {
+            left = filter_record_batch(&left, &found)?;
+            right_columns
+        };
+
+        let timestamp = left.column(self.left_schema.timestamp_index).clone();
+        self.left_schema.remove_timestamp_column(&mut left);
+        let mut columns = left.columns().to_vec();
+        columns.extend(right_columns);
+        columns.push(timestamp);
+        Ok(RecordBatch::try_new(self.output_schema.clone(), columns)?)
+    }
+}
+
+#[async_trait::async_trait]
+impl ArrowOperator for TemporalJoin {
+    fn name(&self) -> String {
+        "TemporalJoin".to_string()
+    }
+
+    fn display(&self) -> DisplayableOperator {
+        DisplayableOperator {
+            name: Cow::Borrowed("TemporalJoin"),
+            fields: vec![
+                ("left_outer", AsDisplayable::Debug(&self.left_outer)),
+                ("ttl", AsDisplayable::Debug(&self.ttl)),
+            ],
+        }
+    }
+
+    async fn on_start(&mut self, ctx: &mut ArrowContext) {
+        let watermark = ctx.last_present_watermark();
+        let table = ctx
+            .table_manager
+            .get_expiring_time_key_table("left", watermark)
+            .await
+            .expect("should have left table");
+        let batches: Vec<_> = table
+            .all_batches_for_watermark(watermark)
+            .flat_map(|(_, batches)| batches.iter().cloned())
+            .collect();
+
+        // rows the watermark had passed were joined before the checkpoint
+        self.buffered = match watermark {
+            Some(watermark) => batches
+                .iter()
+                .map(|batch| {
+                    let [_, pending] = self.split_at(batch, watermark)?;
+                    Ok(pending)
+                })
+                .collect::<Result<_>>()
+                .expect("should be able to restore left rows"),
+            None => batches,
+        };
+    }
+
+    async fn process_batch(&mut self, _record_batch: RecordBatch, _ctx: &mut ArrowContext) {
+        unreachable!();
+    }
+
+    async fn process_batch_index(
+        &mut self,
+        index: usize,
+        total_inputs: usize,
+        batch: RecordBatch,
+        ctx: &mut ArrowContext,
+    ) {
+        let watermark = ctx.last_present_watermark();
+        if index / (total_inputs / 2) == 1 {
+            ctx.table_manager
+                .get_versioned_key_table("right", watermark)
+                .await
+                .expect("should have right table")
+                .insert_batch(batch)
+                .await
+                .expect("should insert into right table");
+            return;
+        }
+
+        let batch = if let Some(watermark) = watermark {
+            let [late, on_time] = self.split_at(&batch, watermark).unwrap();
+            ctx.drop_late_records(late).await;
+            on_time
+        } else {
+            batch
+        };
+        if batch.num_rows() == 0 {
+            return;
+        }
+
+        let max_timestamp =
+            from_nanos(max(self.left_input_schema.timestamp_column(&batch)).unwrap() as u128);
+        ctx.table_manager
+            .get_expiring_time_key_table("left", watermark)
+            .await
+            .expect("should have left table")
+            .insert(max_timestamp, batch.clone());
+        self.buffered.push(batch);
+    }
+
+    async fn handle_watermark(
+        &mut self,
+        watermark: Watermark,
+        ctx: &mut ArrowContext,
+    ) -> Option<Watermark> {
+        let Some(current) = ctx.last_present_watermark() else {
+            return Some(watermark);
+        };
+
+        let mut ready = vec![];
+        for batch in mem::take(&mut self.buffered) {
+            let [passed, pending] = self
+                .split_at(&batch, current)
+                .expect("should be able to split left rows");
+            if passed.num_rows() > 0 {
+                ready.push(passed);
+            }
+            if pending.num_rows() > 0 {
+                self.buffered.push(pending);
+            }
+        }
+
+        let right = ctx
+            .table_manager
+            .get_versioned_key_table("right", Some(current))
+            .await
+            .expect("should have right table");
+        let joined: Vec<_> = ready
+            .iter()
+            .map(|batch| self.join(batch, right))
+            .collect::<Result<_>>()
+            .expect("should be able to join left rows");
+        right
+            .expire(Some(current))
+            .await
+            .expect("should be able to expire right table");
+
+        for batch in joined {
+            if batch.num_rows() > 0 {
+                ctx.collect(batch).await;
+            }
+        }
+        Some(watermark)
+    }
+
+    async fn handle_checkpoint(&mut self, _cb: CheckpointBarrier, ctx: &mut ArrowContext) {
+        let watermark = ctx.last_present_watermark();
+        ctx.table_manager
+            .get_expiring_time_key_table("left", watermark)
+            .await
+            .expect("should have left table")
+            .flush(watermark)
+            .await
+            .expect("should flush");
+    }
+
+    fn tables(&self) -> HashMap<String, TableConfig> {
+        let mut tables = HashMap::new();
+        tables.insert(
+            "left".to_string(),
+            timestamp_table_config(
+                "left",
+                "temporal join left rows",
+                Duration::ZERO,
+                false,
+                self.left_input_schema.as_ref().clone(),
+            ),
+        );
+        tables.insert(
+            "right".to_string(),
+            timestamp_table_config(
+                "right",
+                "temporal join versioned table",
+                self.ttl,
+                false,
+                self.right_input_schema.as_ref().clone(),
+            ),
+        );
+        tables
+    }
+}
+
+pub struct TemporalJoinConstructor;
+impl OperatorConstructor for TemporalJoinConstructor {
+    type ConfigT = api::TemporalJoinOperator;
+    fn with_config(
+        &self,
+        config: Self::ConfigT,
+        _registry: Arc<Registry>,
+    ) -> anyhow::Result<OperatorNode> {
+        let left_input_schema: ArroyoSchema = config
+            .left_schema
+            .ok_or_else(|| anyhow!("missing left schema"))?
+            .try_into()?;
+        let right_input_schema: ArroyoSchema = config
+            .right_schema
+            .ok_or_else(|| anyhow!("missing right schema"))?
+            .try_into()?;
+        let output_schema: ArroyoSchema = config
+            .output_schema
+            .ok_or_else(|| anyhow!("missing output schema"))?
+            .try_into()?;
+        if left_input_schema.key_indices.is_none() || right_input_schema.key_indices.is_none() {
+            bail!("temporal join inputs must be keyed");
+        }
+
+        Ok(OperatorNode::from_operator(Box::new(TemporalJoin {
+            left_schema: left_input_schema.schema_without_keys()?,
+            left_input_schema: Arc::new(left_input_schema),
+            right_input_schema: Arc::new(right_input_schema),
+            output_schema: output_schema.schema,
+            left_outer: config.left_outer,
+            ttl: Duration::from_micros(config.ttl_micros),
+            buffered: vec![],
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arrow::test_utils::{nanos, OperatorHarness};
+    use arroyo_datastream::logical::OperatorName;
+    use serde_json::{json, Value};
+
+    const QUERY: &str = "
+        CREATE TABLE orders (
+            order_id BIGINT,
+            currency TEXT,
+            amount DOUBLE,
+            order_time TIMESTAMP
+        ) WITH (
+            connector = 'kafka',
+            bootstrap_servers = 'localhost:9092',
+            type = 'source',
+            topic = 'orders',
+            format = 'json',
+            event_time_field = 'order_time'
+        );
+
+        CREATE TABLE rates (
+            currency TEXT PRIMARY KEY,
+            rate DOUBLE
+        ) WITH (
+            connector = 'kafka',
+            bootstrap_servers = 'localhost:9092',
+            type = 'source',
+            topic = 'rates',
+            format = 'debezium_json'
+        );
+
+        SELECT o.order_id, o.amount * r.rate AS converted, r.rate
+        FROM orders AS o
+        LEFT JOIN rates FOR SYSTEM_TIME AS OF o.order_time AS r
+        ON o.currency = r.currency";
+
+    const ORDERS: usize = 0;
+    const RATES: usize = 1;
+
+    fn order(order_id: i64, currency: &str, secs: u64) -> Value {
+        json!({
+            "_key_0": currency,
+            "order_id": order_id,
+            "currency": currency,
+            "amount": 10.0,
+            "order_time": nanos(secs),
+            "_timestamp": nanos(secs),
+        })
+    }
+
+    fn rate(currency: &str, rate: f64, secs: u64, is_retract: bool) -> Value {
+        json!({
+            "_key_0": currency,
+            "currency": currency,
+            "rate": rate,
+            "_timestamp": nanos(secs),
+            "is_retract": is_retract,
+        })
+    }
+
+    /// The rate each order was joined with, by order id
+    fn rates(rows: Vec<Value>) -> Vec<(i64, Option<f64>)> {
+        let mut rates: Vec<_> = rows
+            .iter()
+            .map(|row| (row["order_id"].as_i64().unwrap(), row["rate"].as_f64()))
+            .collect();
+        rates.sort_by_key(|(order_id, _)| *order_id);
+        rates
+    }
+
+    async fn harness() -> OperatorHarness {
+        OperatorHarness::from_sql(QUERY, OperatorName::TemporalJoin).await
+    }
+
+    #[tokio::test]
+    async fn test_order_before_first_version_has_no_rate() {
+        let mut harness = harness().await;
+
+        harness.process(RATES, &[rate("EUR", 1.1, 10, false)]).await;
+        harness
+            .process(ORDERS, &[order(1, "EUR", 5), order(2, "EUR", 15)])
+            .await;
+        harness.watermark(20).await;
+
+        assert_eq!(rates(harness.output()), vec![(1, None), (2, Some(1.1))]);
+    }
+
+    #[tokio::test]
+    async fn test_orders_join_the_version_current_at_their_time() {
+        let mut harness = harness().await;
+
+        harness.process(RATES, &[rate("EUR", 1.1, 10, false)]).await;
+        harness.process(ORDERS, &[order(1, "EUR", 15)]).await;
+        harness.process(RATES, &[rate("EUR", 1.2, 20, false)]).await;
+        // this order arrives after the update, but is from before it
+        harness
+            .process(ORDERS, &[order(2, "EUR", 25), order(3, "EUR", 18)])
+            .await;
+        harness.watermark(30).await;
+
+        assert_eq!(
+            rates(harness.output()),
+            vec![(1, Some(1.1)), (2, Some(1.2)), (3, Some(1.1))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_left_join_without_match_emits_nulls() {
+        let mut harness = harness().await;
+
+        harness.process(RATES, &[rate("EUR", 1.1, 10, false)]).await;
+        harness.process(ORDERS, &[order(1, "USD", 15)]).await;
+        harness.watermark(20).await;
+
+        let output = harness.output();
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0]["order_id"], json!(1));
+        assert_eq!(output[0]["rate"], Value::Null);
+        assert_eq!(output[0]["converted"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_versions_survive_restore() {
+        let mut harness = harness().await;
+
+        harness
+            .process(
+                RATES,
+                &[rate("EUR", 1.1, 10, false), rate("GBP", 0.9, 10, false)],
+            )
+            .await;
+        harness.process(RATES, &[rate("GBP", 0.9, 20, true)]).await;
+        // this order is still waiting for the watermark when the checkpoint is taken
+        harness.process(ORDERS, &[order(1, "EUR", 15)]).await;
+        harness.watermark(12).await;
+        harness.checkpoint().await;
+        assert_eq!(rates(harness.output()), vec![]);
+
+        let mut harness = harness.restore().await;
+        harness
+            .process(
+                ORDERS,
+                &[
+                    order(2, "EUR", 25),
+                    order(3, "GBP", 15),
+                    order(4, "GBP", 25),
+                ],
+            )
+            .await;
+        harness.watermark(30).await;
+
+        // the deletion is kept too, so GBP only has a rate between the two versions
+        assert_eq!(
+            rates(harness.output()),
+            vec![(1, Some(1.1)), (2, Some(1.1)), (3, Some(0.9)), (4, None)]
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! Runs a single operator outside of a pipeline, so that tests can feed it batches, watermarks,
+//! ticks and checkpoints directly, and restore it from the checkpoints it wrote.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use arrow::datatypes::{DataType, Schema};
+use arrow_array::{new_null_array, BooleanArray, RecordBatch, StructArray};
+use arrow_json::writer::JsonArray;
+use arroyo_datastream::logical::OperatorName;
+use arroyo_df::physical::new_registry;
+use arroyo_df::{parse_and_get_arrow_program, ArroyoSchemaProvider, SqlConfig};
+use arroyo_metrics::TaskCounters;
+use arroyo_operator::context::{batch_bounded, ArrowContext, BatchReceiver};
+use arroyo_operator::operator::{ArrowOperator, OperatorNode};
+use arroyo_rpc::df::ArroyoSchema;
+use arroyo_rpc::grpc::rpc::{CheckpointMetadata, TaskCheckpointCompletedReq};
+use arroyo_rpc::{updating_meta_fields, ControlResp, UPDATING_META_FIELD};
+use arroyo_state::checkpoint_state::CheckpointState;
+use arroyo_types::{ArrowMessage, CheckpointBarrier, TaskInfo, Watermark};
+use futures::FutureExt;
+use petgraph::Direction;
+use serde_json::Value;
+use tokio::sync::mpsc::{channel, Receiver};
+
+use crate::engine::construct_operator;
+
+/// The time `secs` seconds after the epoch
+pub(crate) fn time(secs: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// [time] as nanos, which is how timestamps are given in input rows
+pub(crate) fn nanos(secs: u64) -> i64 {
+    Duration::from_secs(secs).as_nanos() as i64
+}
+
+pub(crate) struct OperatorHarness {
+    job_id: String,
+    operator_id: String,
+    operator_name: OperatorName,
+    config: Vec<u8>,
+    in_schemas: Vec<ArroyoSchema>,
+    out_schema: Option<ArroyoSchema>,
+    has_side_output: bool,
+    epoch: u32,
+    operator: Box<dyn ArrowOperator + Send>,
+    pub ctx: ArrowContext,
+    control_rx: Receiver<ControlResp>,
+    output: BatchReceiver,
+    side_output: Option<BatchReceiver>,
+}
+
+impl OperatorHarness {
+    /// Plans `query` and runs the first operator of type `operator_name` in the plan, with the
+    /// schemas of its edges. The inputs of joins are ordered left, then right.
+    pub(crate) async fn from_sql(query: &str, operator_name: OperatorName) -> Self {
+        let program = parse_and_get_arrow_program(
+            query.to_string(),
+            ArroyoSchemaProvider::new(),
+            SqlConfig::default(),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("failed to plan query: {:?}", e))
+        .program;
+        let graph = &program.graph;
+
+        let index = graph
+            .node_indices()
+            .find(|index| graph[*index].operator_name == operator_name)
+            .unwrap_or_else(|| panic!("no {:?} operator in the plan", operator_name));
+
+        let mut in_edges: Vec<_> = graph
+            .edges_directed(index, Direction::Incoming)
+            .map(|edge| edge.weight())
+            .collect();
+        in_edges.sort_by_key(|edge| edge.edge_type);
+
+        let out_schema = graph
+            .edges_directed(index, Direction::Outgoing)
+            .find(|edge| !edge.weight().edge_type.is_side_output())
+            .map(|edge| edge.weight().schema.clone());
+        let has_side_output = graph
+            .edges_directed(index, Direction::Outgoing)
+            .any(|edge| edge.weight().edge_type.is_side_output());
+
+        // task metrics are labelled by operator rather than by job, so each harness gets an
+        // operator id of its own to keep the counters of tests running in parallel apart
+        let test_id = uuid::Uuid::new_v4();
+        let node = &graph[index];
+        Self::start(
+            format!("operator-test-{}", test_id),
+            format!("{}-{}", node.operator_id, test_id),
+            operator_name,
+            node.operator_config.clone(),
+            in_edges.iter().map(|edge| edge.schema.clone()).collect(),
+            out_schema,
+            has_side_output,
+            0,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn start(
+        job_id: String,
+        operator_id: String,
+        operator_name: OperatorName,
+        config: Vec<u8>,
+        in_schemas: Vec<ArroyoSchema>,
+        out_schema: Option<ArroyoSchema>,
+        has_side_output: bool,
+        epoch: u32,
+    ) -> Self {
+        let OperatorNode::Operator(mut operator) =
+            construct_operator(operator_name, config.clone(), Arc::new(new_registry()))
+        else {
+            panic!("{:?} is a source", operator_name);
+        };
+
+        let (control_tx, control_rx) = channel(128);
+        let (_, ctx_control_rx) = channel(128);
+        let (output_tx, output) = batch_bounded(u32::MAX);
+        let restore_from = (epoch > 0).then(|| CheckpointMetadata {
+            job_id: job_id.clone(),
+            epoch,
+            ..Default::default()
+        });
+
+        let mut ctx = ArrowContext::new(
+            TaskInfo::for_test(&job_id, &operator_id),
+            restore_from,
+            ctx_control_rx,
+            control_tx,
+            in_schemas.len(),
+            in_schemas.clone(),
+            out_schema.clone(),
+            None,
+            vec![vec![output_tx]],
+            operator.tables(),
+        )
+        .await;
+
+        let side_output = has_side_output.then(|| {
+            let (side_output_tx, side_output) = batch_bounded(u32::MAX);
+            ctx.set_side_output_qs(vec![vec![side_output_tx]]);
+            side_output
+        });
+
+        operator.on_start(&mut ctx).await;
+
+        Self {
+            job_id,
+            operator_id,
+            operator_name,
+            config,
+            in_schemas,
+            out_schema,
+            has_side_output,
+            epoch,
+            operator,
+            ctx,
+            control_rx,
+            output,
+            side_output,
+        }
+    }
+
+    /// Builds a batch for input `input` from JSON rows, which give timestamps as nanos. If the
+    /// input is updating, rows with `"is_retract": true` are retractions.
+    pub(crate) fn batch(&self, input: usize, rows: &[Value]) -> RecordBatch {
+        let schema = self.in_schemas[input].schema.clone();
+        let meta_index = schema.index_of(UPDATING_META_FIELD).ok();
+        let decode_schema = Schema::new(
+            schema
+                .fields()
+                .iter()
+                .filter(|field| field.name() != UPDATING_META_FIELD)
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+
+        let mut decoder = arrow_json::ReaderBuilder::new(Arc::new(decode_schema))
+            .build_decoder()
+            .unwrap();
+        decoder.serialize(rows).unwrap();
+        let mut columns = decoder
+            .flush()
+            .unwrap()
+            .expect("input should have rows")
+            .columns()
+            .to_vec();
+
+        if let Some(index) = meta_index {
+            let is_retract: BooleanArray = rows
+                .iter()
+                .map(|row| Some(row.get("is_retract") == Some(&Value::Bool(true))))
+                .collect();
+            columns.insert(
+                index,
+                Arc::new(StructArray::new(
+                    updating_meta_fields(),
+                    vec![
+                        Arc::new(is_retract),
+                        new_null_array(&DataType::FixedSizeBinary(16), rows.len()),
+                    ],
+                    None,
+                )),
+            );
+        }
+
+        RecordBatch::try_new(schema, columns).unwrap()
+    }
+
+    /// The operator's config
+    pub(crate) fn config<T: prost::Message + Default>(&self) -> T {
+        T::decode(self.config.as_slice()).unwrap()
+    }
+
+    /// Processes `rows` as a batch from input `input`
+    pub(crate) async fn process(&mut self, input: usize, rows: &[Value]) {
+        let batch = self.batch(input, rows);
+        self.operator
+            .process_batch_index(input, self.in_schemas.len(), batch, &mut self.ctx)
+            .await;
+    }
+
+    /// Advances the watermark of every input to `secs` seconds after the epoch
+    pub(crate) async fn watermark(&mut self, secs: u64) {
+        let watermark = Watermark::EventTime(time(secs));
+        for input in 0..self.in_schemas.len() {
+            self.ctx.watermarks.set(input, watermark);
+        }
+        self.operator
+            .handle_watermark_int(watermark, &mut self.ctx)
+            .await;
+    }
+
+    pub(crate) async fn tick(&mut self) {
+        self.operator.handle_tick(0, &mut self.ctx).await;
+    }
+
+    /// Checkpoints the operator and its tables, as the engine does when a barrier arrives, and
+    /// records the checkpoint so that it can be restored from
+    pub(crate) async fn checkpoint(&mut self) {
+        self.epoch += 1;
+        let barrier = CheckpointBarrier {
+            epoch: self.epoch,
+            min_epoch: 0,
+            timestamp: SystemTime::now(),
+            then_stop: false,
+        };
+        self.operator
+            .handle_checkpoint(barrier, &mut self.ctx)
+            .await;
+        let watermark = self.ctx.last_present_watermark();
+        self.ctx.table_manager.checkpoint(barrier, watermark).await;
+
+        let mut checkpoint_state = CheckpointState::new(
+            Arc::new(self.job_id.clone()),
+            self.epoch.to_string(),
+            self.epoch,
+            0,
+            HashMap::from([(self.operator_id.clone(), 1)]),
+        );
+        loop {
+            let resp = self
+                .control_rx
+                .recv()
+                .await
+                .expect("operator should report its checkpoint");
+            if let ControlResp::CheckpointCompleted(c) = resp {
+                checkpoint_state
+                    .checkpoint_finished(TaskCheckpointCompletedReq {
+                        worker_id: 1,
+                        time: c.subtask_metadata.finish_time,
+                        job_id: self.job_id.clone(),
+                        operator_id: c.operator_id,
+                        epoch: c.checkpoint_epoch,
+                        needs_commit: false,
+                        metadata: Some(c.subtask_metadata),
+                    })
+                    .await
+                    .unwrap();
+                break;
+            }
+        }
+    }
+
+    /// Replaces the operator with a new one restored from the last checkpoint, dropping anything
+    /// that happened since
+    pub(crate) async fn restore(self) -> Self {
+        assert!(self.epoch > 0, "there's no checkpoint to restore from");
+        Self::start(
+            self.job_id,
+            self.operator_id,
+            self.operator_name,
+            self.config,
+            self.in_schemas,
+            self.out_schema,
+            self.has_side_output,
+            self.epoch,
+        )
+        .await
+    }
+
+    /// The rows the operator has emitted since this was last called
+    pub(crate) fn output(&mut self) -> Vec<Value> {
+        drain_rows(&mut self.output)
+    }
+
+    /// The rows the operator has sent to its side output since this was last called
+    pub(crate) fn side_output(&mut self) -> Vec<Value> {
+        drain_rows(
+            self.side_output
+                .as_mut()
+                .expect("operator should have a side output"),
+        )
+    }
+
+    /// The value of one of the operator's task counters
+    pub(crate) fn counter(&self, counter: TaskCounters) -> u64 {
+        let value = Cell::new(0);
+        counter.for_task(&self.ctx.task_info, |c| value.set(c.get()));
+        value.get()
+    }
+
+    /// Errors the operator has reported since this was last called
+    pub(crate) fn errors(&mut self) -> Vec<String> {
+        let mut errors = vec![];
+        while let Ok(resp) = self.control_rx.try_recv() {
+            if let ControlResp::Error { message, .. } = resp {
+                errors.push(message);
+            }
+        }
+        errors
+    }
+}
+
+/// Converts the batches in a queue to JSON rows, in order. The updating metadata is replaced by
+/// an `is_retract` field.
+fn drain_rows(rx: &mut BatchReceiver) -> Vec<Value> {
+    let mut rows = vec![];
+    while let Some(Some(message)) = rx.recv().now_or_never() {
+        let ArrowMessage::Data(mut batch) = message else {
+            continue;
+        };
+        let is_retract = batch
+            .schema()
+            .index_of(UPDATING_META_FIELD)
+            .ok()
+            .map(|index| {
+                batch
+                    .remove_column(index)
+                    .as_any()
+                    .downcast_ref::<StructArray>()
+                    .expect("updating metadata should be a struct")
+                    .column_by_name("is_retract")
+                    .expect("updating metadata should have is_retract")
+                    .as_any()
+                    .downcast_ref::<BooleanArray>()
+                    .expect("is_retract should be a boolean")
+                    .clone()
+            });
+
+        let mut writer = arrow_json::WriterBuilder::new()
+            .with_explicit_nulls(true)
+            .build::<_, JsonArray>(vec![]);
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        let Value::Array(batch_rows) = serde_json::from_slice(&writer.into_inner()).unwrap() else {
+            panic!("JSON writer should write an array");
+        };
+
+        for (i, mut row) in batch_rows.into_iter().enumerate() {
+            if let (Some(is_retract), Value::Object(fields)) = (&is_retract, &mut row) {
+                fields.insert("is_retract".to_string(), is_retract.value(i).into());
+            }
+            rows.push(row);
+        }
+    }
+    rows
+}
@@ -0,0 +1,507 @@
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Result};
+use arrow::compute::{max, min};
+use arrow::row::{OwnedRow, RowConverter, SortField};
+use arrow_array::{ArrayRef, RecordBatch, UInt64Array};
+use arrow_schema::{SchemaRef, SortOptions};
+use arroyo_operator::context::ArrowContext;
+use arroyo_operator::operator::{ArrowOperator, OperatorConstructor, OperatorNode, Registry};
+use arroyo_rpc::df::ArroyoSchema;
+use arroyo_rpc::grpc::rpc::TableConfig;
+use arroyo_rpc::Converter;
+use arroyo_rpc::{df::ArroyoSchemaRef, grpc::api};
+use arroyo_state::timestamp_table_config;
+use arroyo_types::{from_nanos, CheckpointBarrier, Watermark};
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion_proto::physical_plan::from_proto::parse_physical_expr;
+use datafusion_proto::physical_plan::DefaultPhysicalExtensionCodec;
+use datafusion_proto::protobuf::PhysicalExprNode;
+use prost::Message;
+use tracing::warn;
+
+/// A row retained for a partition. Rows order by their sort key, with ties going to the row that
+/// arrived first, so the greatest row in a partition's heap is the next one to be evicted.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct RankedRow {
+    sort_key: OwnedRow,
+    sequence: u64,
+    row: OwnedRow,
+}
+
+/// Keeps the first `limit` rows (by the sort expressions) for each key within each window, and
+/// emits them once the watermark passes the window.
+pub struct TopNOperator {
+    input_schema: ArroyoSchemaRef,
+    // this is for time bucketing
+    input_schema_unkeyed: ArroyoSchemaRef,
+    output_schema: SchemaRef,
+    sort_exprs: Vec<Arc<dyn PhysicalExpr>>,
+    sort_converter: RowConverter,
+    key_converter: Converter,
+    row_converter: RowConverter,
+    limit: usize,
+    emit_row_number: bool,
+    sequence: u64,
+    // rows with a sequence below this were written to state by an earlier checkpoint
+    checkpointed_sequence: u64,
+    partitions: BTreeMap<SystemTime, HashMap<OwnedRow, BinaryHeap<RankedRow>>>,
+}
+
+impl TopNOperator {
+    fn split_batches(
+        &self,
+        batch: RecordBatch,
+        watermark: Option<SystemTime>,
+    ) -> Result<Vec<(RecordBatch, SystemTime)>> {
+        if batch.num_rows() == 0 {
+            warn!("empty batch received");
+            return Ok(vec![]);
+        }
+        let timestamp_column = self.input_schema.timestamp_column(&batch);
+        let min_timestamp = from_nanos(min(timestamp_column).unwrap() as u128);
+        let max_timestamp = from_nanos(max(timestamp_column).unwrap() as u128);
+
+        // early exit if all rows should be filtered.
+        if let Some(watermark) = watermark {
+            if max_timestamp < watermark {
+                return Ok(vec![]);
+            }
+        }
+
+        if min_timestamp == max_timestamp {
+            return Ok(vec![(batch, max_timestamp)]);
+        }
+        let sorted_batch = self.input_schema_unkeyed.sort(batch, true)?;
+        let filtered_batch = self
+            .input_schema_unkeyed
+            .filter_by_time(sorted_batch, watermark)?;
+        let filtered_timestamps = self.input_schema.timestamp_column(&filtered_batch);
+        Ok(self
+            .input_schema_unkeyed
+            .partition(&filtered_batch, true)?
+            .into_iter()
+            .map(|range| {
+                (
+                    filtered_batch.slice(range.start, range.end - range.start),
+                    from_nanos(filtered_timestamps.value(range.start) as u128),
+                )
+            })
+            .collect())
+    }
+
+    /// Adds the rows of a batch that all belong to the same window
+    fn insert(&mut self, batch: &RecordBatch, timestamp: SystemTime) -> Result<()> {
+        let num_rows = batch.num_rows();
+        let sort_columns = self
+            .sort_exprs
+            .iter()
+            .map(|expr| expr.evaluate(batch)?.into_array(num_rows))
+            .collect::<datafusion::common::Result<Vec<_>>>()?;
+        let sort_keys = self.sort_converter.convert_columns(&sort_columns)?;
+
+        let key_columns: Vec<_> = self
+            .input_schema
+            .key_indices
+            .iter()
+            .flatten()
+            .map(|index| batch.column(*index).clone())
+            .collect();
+        let keys = self
+            .key_converter
+            .convert_all_columns(&key_columns, num_rows)?;
+        let rows = self.row_converter.convert_columns(batch.columns())?;
+
+        let first_sequence = self.sequence;
+        let partitions = self.partitions.entry(timestamp).or_default();
+
+        for index in 0..num_rows {
+            let heap = partitions.entry(keys.row(index).owned()).or_default();
+            if heap.len() >= self.limit {
+                let worst = heap.peek().expect("limit is non-zero");
+                // a tie loses to the row that is already retained
+                if sort_keys.row(index) >= worst.sort_key.row() {
+                    continue;
+                }
+                heap.pop();
+            }
+            heap.push(RankedRow {
+                sort_key: sort_keys.row(index).owned(),
+                sequence: first_sequence + index as u64,
+                row: rows.row(index).owned(),
+            });
+        }
+        self.sequence += num_rows as u64;
+
+        Ok(())
+    }
+
+    /// The rows of each window that are currently retained and haven't yet been written to state.
+    /// Rows written by earlier checkpoints and since evicted are evicted again on restore, as the
+    /// rows that displaced them are restored as well.
+    fn unwritten_rows(&self) -> Result<Vec<(SystemTime, RecordBatch)>> {
+        let mut result = vec![];
+        for (timestamp, partitions) in &self.partitions {
+            let mut rows: Vec<_> = partitions
+                .values()
+                .flat_map(|heap| heap.iter())
+                .filter(|ranked| ranked.sequence >= self.checkpointed_sequence)
+                .collect();
+            if rows.is_empty() {
+                continue;
+            }
+            // keep arrival order so that ties are broken the same way on restore
+            rows.sort_by_key(|ranked| ranked.sequence);
+            let columns = self
+                .row_converter
+                .convert_rows(rows.iter().map(|ranked| ranked.row.row()))?;
+            result.push((
+                *timestamp,
+                RecordBatch::try_new(self.input_schema.schema.clone(), columns)?,
+            ));
+        }
+        Ok(result)
+    }
+
+    fn finish(&self, partitions: HashMap<OwnedRow, BinaryHeap<RankedRow>>) -> Result<RecordBatch> {
+        let mut rows = vec![];
+        let mut row_numbers = vec![];
+        for heap in partitions.into_values() {
+            for (rank, ranked) in heap.into_sorted_vec().into_iter().enumerate() {
+                rows.push(ranked.row);
+                row_numbers.push(rank as u64 + 1);
+            }
+        }
+
+        let mut columns: Vec<ArrayRef> = self
+            .row_converter
+            .convert_rows(rows.iter().map(|row| row.row()))?;
+        if self.emit_row_number {
+            columns.push(Arc::new(UInt64Array::from(row_numbers)));
+        }
+        Ok(RecordBatch::try_new(self.output_schema.clone(), columns)?)
+    }
+}
+
+#[async_trait::async_trait]
+impl ArrowOperator for TopNOperator {
+    fn name(&self) -> String {
+        "TopN".to_string()
+    }
+
+    async fn on_start(&mut self, ctx: &mut ArrowContext) {
+        let watermark = ctx.last_present_watermark();
+        let table = ctx
+            .table_manager
+            .get_expiring_time_key_table("input", watermark)
+            .await
+            .unwrap();
+        let batches: Vec<_> = table
+            .all_batches_for_watermark(watermark)
+            .flat_map(|(timestamp, batches)| {
+                batches.iter().map(|batch| (*timestamp, batch.clone()))
+            })
+            .collect();
+        for (timestamp, batch) in batches {
+            self.insert(&batch, timestamp)
+                .expect("should be able to restore top-n state");
+        }
+        self.checkpointed_sequence = self.sequence;
+    }
+
+    async fn process_batch(&mut self, batch: RecordBatch, ctx: &mut ArrowContext) {
+        let current_watermark = ctx.last_present_watermark();
+        for (batch, timestamp) in self.split_batches(batch, current_watermark).unwrap() {
+            self.insert(&batch, timestamp)
+                .expect("should be able to rank batch");
+        }
+    }
+
+    async fn handle_watermark(
+        &mut self,
+        watermark_message: Watermark,
+        ctx: &mut ArrowContext,
+    ) -> Option<Watermark> {
+        let Some(watermark) = ctx.last_present_watermark() else {
+            return Some(watermark_message);
+        };
+        loop {
+            match self.partitions.first_key_value() {
+                Some((timestamp, _)) if *timestamp < watermark => {}
+                _ => break,
+            }
+            let (_, partitions) = self.partitions.pop_first().unwrap();
+            let batch = self
+                .finish(partitions)
+                .expect("should be able to emit top-n rows");
+            if batch.num_rows() > 0 {
+                ctx.collect(batch).await;
+            }
+        }
+        Some(watermark_message)
+    }
+
+    async fn handle_checkpoint(&mut self, _cb: CheckpointBarrier, ctx: &mut ArrowContext) {
+        let watermark = ctx.last_present_watermark();
+        let table = ctx
+            .table_manager
+            .get_expiring_time_key_table("input", watermark)
+            .await
+            .expect("should have input table");
+        for (timestamp, batch) in self
+            .unwritten_rows()
+            .expect("should be able to snapshot top-n rows")
+        {
+            table.insert(timestamp, batch);
+        }
+        table.flush(watermark).await.expect("should flush");
+        self.checkpointed_sequence = self.sequence;
+    }
+
+    fn tables(&self) -> HashMap<String, TableConfig> {
+        let mut tables = HashMap::new();
+        tables.insert(
+            "input".to_string(),
+            timestamp_table_config(
+                "input",
+                "top-n retained rows",
+                Duration::ZERO,
+                false,
+                self.input_schema.as_ref().clone(),
+            ),
+        );
+        tables
+    }
+}
+
+pub struct TopNConstructor;
+impl OperatorConstructor for TopNConstructor {
+    type ConfigT = api::TopNOperator;
+    fn with_config(
+        &self,
+        config: Self::ConfigT,
+        registry: Arc<Registry>,
+    ) -> anyhow::Result<OperatorNode> {
+        let input_schema = Arc::new(ArroyoSchema::try_from(
+            config
+                .input_schema
+                .ok_or_else(|| anyhow!("missing input schema"))?,
+        )?);
+        let output_schema = ArroyoSchema::try_from(
+            config
+                .output_schema
+                .ok_or_else(|| anyhow!("missing output schema"))?,
+        )?
+        .schema;
+
+        if config.limit == 0 {
+            return Err(anyhow!("top-n limit must be positive"));
+        }
+        if config.sort_exprs.is_empty() {
+            return Err(anyhow!("top-n requires at least one sort expression"));
+        }
+
+        let mut sort_exprs = vec![];
+        let mut sort_fields = vec![];
+        for sort_expr in config.sort_exprs {
+            let expr = parse_physical_expr(
+                &PhysicalExprNode::decode(&mut sort_expr.expr.as_slice())?,
+                registry.as_ref(),
+                &input_schema.schema,
+                &DefaultPhysicalExtensionCodec {},
+            )?;
+            sort_fields.push(SortField::new_with_options(
+                expr.data_type(&input_schema.schema)?,
+                SortOptions {
+                    descending: !sort_expr.ascending,
+                    nulls_first: sort_expr.nulls_first,
+                },
+            ));
+            sort_exprs.push(expr);
+        }
+
+        let row_converter = RowConverter::new(
+            input_schema
+                .schema
+                .fields()
+                .iter()
+                .map(|field| SortField::new(field.data_type().clone()))
+                .collect(),
+        )?;
+        let input_schema_unkeyed = Arc::new(ArroyoSchema::from_schema_unkeyed(
+            input_schema.schema.clone(),
+        )?);
+
+        Ok(OperatorNode::from_operator(Box::new(TopNOperator {
+            key_converter: input_schema.converter(false)?,
+            input_schema,
+            input_schema_unkeyed,
+            output_schema,
+            sort_exprs,
+            sort_converter: RowConverter::new(sort_fields)?,
+            row_converter,
+            limit: config.limit as usize,
+            emit_row_number: config.emit_row_number,
+            sequence: 0,
+            checkpointed_sequence: 0,
+            partitions: BTreeMap::new(),
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arrow::test_utils::{nanos, OperatorHarness};
+    use arroyo_datastream::logical::OperatorName;
+    use serde_json::{json, Value};
+
+    const SOURCE: &str = "
+        CREATE TABLE bids (
+            auction BIGINT,
+            bidder BIGINT,
+            bid_time TIMESTAMP
+        ) WITH (
+            connector = 'kafka',
+            bootstrap_servers = 'localhost:9092',
+            type = 'source',
+            topic = 'bids',
+            format = 'json',
+            event_time_field = 'bid_time'
+        );";
+
+    /// The top two bidders of each auction by bid count in each window
+    fn per_auction_query() -> String {
+        format!(
+            "{SOURCE}
+            SELECT * FROM (
+                SELECT *, ROW_NUMBER() OVER (
+                    PARTITION BY window, auction
+                    ORDER BY bids DESC) AS row_num
+                FROM (SELECT auction, bidder, count(*) AS bids,
+                    tumble(interval '10 seconds') AS window
+                    FROM bids
+                    GROUP BY auction, bidder, window)) WHERE row_num <= 2"
+        )
+    }
+
+    /// The counts of bids by bidder in the first window, which ends at 10 seconds
+    fn bids(auction: i64, bidder: i64, bids: i64) -> Value {
+        json!({
+            "_key_0": auction,
+            "auction": auction,
+            "bidder": bidder,
+            "bids": bids,
+            "window": {"start": nanos(0), "end": nanos(10)},
+            "_timestamp": nanos(10) - 1,
+        })
+    }
+
+    /// The bidders emitted for each auction, with their row numbers
+    fn ranks(rows: Vec<Value>) -> Vec<(i64, i64, i64)> {
+        let mut ranks: Vec<_> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row["auction"].as_i64().unwrap(),
+                    row["row_num"].as_i64().unwrap(),
+                    row["bidder"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        ranks.sort();
+        ranks
+    }
+
+    async fn harness() -> OperatorHarness {
+        OperatorHarness::from_sql(&per_auction_query(), OperatorName::TopN).await
+    }
+
+    #[tokio::test]
+    async fn test_ties_go_to_the_first_row_to_arrive() {
+        let mut harness = harness().await;
+
+        harness
+            .process(0, &[bids(1, 1, 5), bids(1, 2, 3), bids(1, 3, 3)])
+            .await;
+        harness.process(0, &[bids(1, 4, 3)]).await;
+        harness.watermark(10).await;
+
+        assert_eq!(ranks(harness.output()), vec![(1, 1, 1), (1, 2, 2)]);
+    }
+
+    #[tokio::test]
+    async fn test_better_rows_evict_the_worst_per_auction() {
+        let mut harness = harness().await;
+
+        harness
+            .process(0, &[bids(1, 1, 3), bids(1, 2, 2), bids(2, 3, 1)])
+            .await;
+        // nothing is emitted until the watermark passes the window
+        harness.watermark(5).await;
+        assert_eq!(ranks(harness.output()), vec![]);
+
+        harness.process(0, &[bids(1, 4, 4), bids(2, 5, 2)]).await;
+        harness.watermark(10).await;
+
+        assert_eq!(
+            ranks(harness.output()),
+            vec![(1, 1, 4), (1, 2, 1), (2, 1, 5), (2, 2, 3)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_order_by_limit_has_no_row_number() {
+        let query = format!(
+            "{SOURCE}
+            SELECT count(*) AS bids, auction, tumble(interval '10 seconds') AS window
+            FROM bids
+            GROUP BY auction, window
+            ORDER BY bids DESC
+            LIMIT 2"
+        );
+        let mut harness = OperatorHarness::from_sql(&query, OperatorName::TopN).await;
+
+        let rows: Vec<_> = [(1, 2), (2, 5), (3, 4)]
+            .into_iter()
+            .map(|(auction, bids)| {
+                json!({
+                    "bids": bids,
+                    "auction": auction,
+                    "window": {"start": nanos(0), "end": nanos(10)},
+                    "_timestamp": nanos(10) - 1,
+                })
+            })
+            .collect();
+        harness.process(0, &rows).await;
+        harness.watermark(10).await;
+
+        let output = harness.output();
+        let mut auctions: Vec<_> = output
+            .iter()
+            .map(|row| row["auction"].as_i64().unwrap())
+            .collect();
+        auctions.sort();
+        assert_eq!(auctions, vec![2, 3]);
+        assert!(output.iter().all(|row| row.get("row_num").is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_rows_evicted_since_last_checkpoint_stay_evicted_on_restore() {
+        let mut harness = harness().await;
+
+        harness.process(0, &[bids(1, 1, 3), bids(1, 2, 2)]).await;
+        harness.checkpoint().await;
+        // this evicts bidder 2, which was written to state by the first checkpoint
+        harness.process(0, &[bids(1, 3, 4)]).await;
+        harness.checkpoint().await;
+
+        let mut harness = harness.restore().await;
+        // a tie with a restored row still loses to it
+        harness.process(0, &[bids(1, 4, 3)]).await;
+        harness.watermark(10).await;
+
+        assert_eq!(ranks(harness.output()), vec![(1, 1, 3), (1, 2, 1)]);
+    }
+}
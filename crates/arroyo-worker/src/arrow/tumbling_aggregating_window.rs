@@ -1,13 +1,17 @@
-use anyhow::{anyhow, Result};
-use arrow::compute::{partition, sort_to_indices, take};
-use arrow_array::{types::TimestampNanosecondType, Array, PrimitiveArray, RecordBatch};
-use arrow_schema::SchemaRef;
+use anyhow::{anyhow, bail, Result};
+use arrow::compute::{concat_batches, partition, sort_to_indices, take};
+use arrow_array::cast::AsArray;
+use arrow_array::{
+    types::TimestampNanosecondType, Array, BooleanArray, PrimitiveArray, RecordBatch, StructArray,
+};
+use arrow_schema::{Schema, SchemaRef};
 use arroyo_df::schemas::add_timestamp_field_arrow;
 use arroyo_operator::context::ArrowContext;
 use arroyo_operator::operator::{
     ArrowOperator, AsDisplayable, DisplayableOperator, OperatorConstructor, OperatorNode, Registry,
 };
 use arroyo_rpc::grpc::{api, rpc::TableConfig};
+use arroyo_rpc::{updating_meta_fields, UPDATING_META_FIELD};
 use arroyo_state::timestamp_table_config;
 use arroyo_types::{from_nanos, print_time, to_nanos, CheckpointBarrier, Watermark};
 use datafusion::common::ScalarValue;
@@ -53,6 +57,11 @@ pub struct TumblingAggregatingWindowFunc<K: Copy> {
     finish_execution_plan: Arc<dyn ExecutionPlan>,
     aggregate_with_timestamp_schema: SchemaRef,
     final_projection: Option<Arc<dyn ExecutionPlan>>,
+    early_fire: Option<Duration>,
+    allowed_lateness: Duration,
+    // when windows fire more than once, the last results emitted for each key and window,
+    // keyed by the id of their _updating_meta
+    fired_schema: Option<ArroyoSchema>,
     // the partial aggregation plan shares a reference to it,
     // which is only used on the exec()
     receiver: Arc<RwLock<Option<UnboundedReceiver<RecordBatch>>>>,
@@ -71,12 +80,19 @@ impl<K: Copy> TumblingAggregatingWindowFunc<K> {
 
         from_nanos(nanos)
     }
+
+    // bins before this can no longer receive data
+    fn first_open_bin(&self, watermark: SystemTime) -> SystemTime {
+        self.bin_start(watermark - self.allowed_lateness)
+    }
 }
 
 struct BinComputingHolder<K: Copy> {
     active_exec: Option<NextBatchFuture<K>>,
     finished_batches: Vec<RecordBatch>,
     sender: Option<UnboundedSender<RecordBatch>>,
+    // whether the bin has data that hasn't been fired yet
+    changed: bool,
 }
 
 impl<K: Copy> Default for BinComputingHolder<K> {
@@ -85,7 +101,23 @@ impl<K: Copy> Default for BinComputingHolder<K> {
             active_exec: None,
             finished_batches: Vec::new(),
             sender: None,
+            changed: false,
+        }
+    }
+}
+
+impl<K: Copy> BinComputingHolder<K> {
+    // closes the active partial aggregation, returning the batches it still had to produce
+    async fn drain_active_exec(&mut self) -> Vec<RecordBatch> {
+        self.sender.take();
+        let mut batches = vec![];
+        if let Some(mut active_exec) = self.active_exec.take() {
+            while let (_bin, Some((batch, next_exec))) = active_exec.await {
+                active_exec = next_exec;
+                batches.push(batch.expect("should be able to compute batch"));
+            }
         }
+        batches
     }
 }
 
@@ -104,6 +136,147 @@ impl TumblingAggregatingWindowFunc<SystemTime> {
         RecordBatch::try_new(schema.clone(), columns)
             .map_err(|err| anyhow::anyhow!("schema: {:?}\nbatch:{:?}\nerr:{}", schema, batch, err))
     }
+
+    // computes the results of a bin from its partial aggregates
+    async fn finish_bin(
+        &self,
+        bin: SystemTime,
+        partial_batches: Vec<RecordBatch>,
+    ) -> Result<Vec<RecordBatch>> {
+        {
+            let mut batches = self.final_batches_passer.write().unwrap();
+            *batches = partial_batches;
+        }
+        self.finish_execution_plan.reset()?;
+        let mut final_exec = self
+            .finish_execution_plan
+            .execute(0, SessionContext::new().task_ctx())?;
+        let mut aggregate_results = vec![];
+        while let Some(batch) = final_exec.next().await {
+            aggregate_results.push(Self::add_bin_start_as_timestamp(
+                &batch?,
+                bin,
+                self.aggregate_with_timestamp_schema.clone(),
+            )?);
+        }
+        let Some(final_projection) = self.final_projection.as_ref() else {
+            return Ok(aggregate_results);
+        };
+        {
+            let mut batches = self.final_batches_passer.write().unwrap();
+            *batches = aggregate_results;
+        }
+        final_projection.reset()?;
+        let mut final_projection_exec =
+            final_projection.execute(0, SessionContext::new().task_ctx())?;
+        let mut results = vec![];
+        while let Some(batch) = final_projection_exec.next().await {
+            results.push(batch?);
+        }
+        Ok(results)
+    }
+
+    // emits the current results of a bin, retracting whatever it emitted before
+    async fn fire_bin(&mut self, bin: SystemTime, ctx: &mut ArrowContext) -> Result<()> {
+        let Some(fired_schema) = self.fired_schema.clone() else {
+            bail!("only windows with early firing or allowed lateness fire more than once");
+        };
+        let Some(exec) = self.execs.get_mut(&bin) else {
+            return Ok(());
+        };
+        exec.changed = false;
+        let new_partials = exec.drain_active_exec().await;
+        exec.finished_batches.extend(new_partials.iter().cloned());
+        let partial_batches = exec.finished_batches.clone();
+
+        // the drained partials won't be seen by the next checkpoint, so write them now
+        let watermark = ctx.last_present_watermark();
+        let table = ctx
+            .table_manager
+            .get_expiring_time_key_table("t", watermark)
+            .await?;
+        for batch in new_partials {
+            let state_batch =
+                Self::add_bin_start_as_timestamp(&batch, bin, self.partial_schema.schema.clone())?;
+            table.insert(bin, state_batch);
+        }
+
+        let results = self.finish_bin(bin, partial_batches).await?;
+        emit_firing(&fired_schema, results, ctx).await
+    }
+}
+
+/// The schema of the table of the results last emitted by windows that fire more than once:
+/// [id, output fields..., _timestamp], where the output's _updating_meta follows its _timestamp
+pub(crate) fn fired_schema(output_schema: &Schema) -> Result<ArroyoSchema> {
+    let meta_index = output_schema.index_of(UPDATING_META_FIELD)?;
+    let mut fields = vec![updating_meta_fields()[1].clone()];
+    fields.extend(
+        output_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != meta_index)
+            .map(|(_, field)| field.clone()),
+    );
+    let timestamp_index = fields.len() - 1;
+    Ok(ArroyoSchema::new_keyed(
+        Arc::new(Schema::new(fields)),
+        timestamp_index,
+        vec![0],
+    ))
+}
+
+/// Emits the results of a window firing, retracting the results previously emitted for the same
+/// keys and windows
+pub(crate) async fn emit_firing(
+    fired_schema: &ArroyoSchema,
+    results: Vec<RecordBatch>,
+    ctx: &mut ArrowContext,
+) -> Result<()> {
+    if results.is_empty() {
+        return Ok(());
+    }
+    let results = concat_batches(&results[0].schema(), results.iter())?;
+    let meta_index = results.schema().index_of(UPDATING_META_FIELD)?;
+    let mut fired_columns = vec![results.column(meta_index).as_struct().column(1).clone()];
+    fired_columns.extend(
+        results
+            .columns()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != meta_index)
+            .map(|(_, column)| column.clone()),
+    );
+    let fired = RecordBatch::try_new(fired_schema.schema.clone(), fired_columns)?;
+
+    let watermark = ctx.last_present_watermark();
+    let fired_table = ctx
+        .table_manager
+        .get_last_key_value_table("f", watermark)
+        .await?;
+    let mut output = vec![];
+    if let Some((prior, _filter)) = fired_table.get_current_matching_values(&fired)? {
+        let mut columns = prior.columns()[1..].to_vec();
+        columns.insert(
+            meta_index,
+            Arc::new(StructArray::new(
+                updating_meta_fields(),
+                vec![
+                    Arc::new(BooleanArray::from(vec![true; prior.num_rows()])),
+                    prior.column(0).clone(),
+                ],
+                None,
+            )),
+        );
+        output.push(RecordBatch::try_new(results.schema(), columns)?);
+    }
+    fired_table.insert_batch(fired).await?;
+    output.push(results);
+
+    ctx.collect(concat_batches(&output[0].schema(), output.iter())?)
+        .await;
+    Ok(())
 }
 
 pub struct TumblingAggregateWindowConstructor;
@@ -182,6 +355,19 @@ impl OperatorConstructor for TumblingAggregateWindowConstructor {
         let aggregate_with_timestamp_schema =
             add_timestamp_field_arrow(finish_execution_plan.schema());
 
+        let early_fire = Some(Duration::from_micros(config.early_fire_micros))
+            .filter(|interval| !interval.is_zero());
+        let allowed_lateness = Duration::from_micros(config.allowed_lateness_micros);
+
+        let fired_schema = if early_fire.is_some() || !allowed_lateness.is_zero() {
+            let Some(final_projection) = &final_projection_plan else {
+                bail!("windows with early firing or allowed lateness require a final projection");
+            };
+            Some(fired_schema(&final_projection.schema())?)
+        } else {
+            None
+        };
+
         Ok(OperatorNode::from_operator(Box::new(
             TumblingAggregatingWindowFunc {
                 width,
@@ -191,6 +377,9 @@ impl OperatorConstructor for TumblingAggregateWindowConstructor {
                 finish_execution_plan,
                 aggregate_with_timestamp_schema,
                 final_projection: final_projection_plan,
+                early_fire,
+                allowed_lateness,
+                fired_schema,
                 receiver,
                 final_batches_passer,
                 futures: Arc::new(Mutex::new(FuturesUnordered::new())),
@@ -211,6 +400,11 @@ impl ArrowOperator for TumblingAggregatingWindowFunc<SystemTime> {
             name: Cow::Borrowed("TumblingAggregatingWindowFunc"),
             fields: vec![
                 ("width", AsDisplayable::Debug(&self.width)),
+                ("early_fire", AsDisplayable::Debug(&self.early_fire)),
+                (
+                    "allowed_lateness",
+                    AsDisplayable::Debug(&self.allowed_lateness),
+                ),
                 (
                     "partial_aggregation_plan",
                     self.partial_aggregation_plan.as_ref().into(),
@@ -240,10 +434,18 @@ impl ArrowOperator for TumblingAggregatingWindowFunc<SystemTime> {
         for (timestamp, batch) in table.all_batches_for_watermark(watermark) {
             let bin = self.bin_start(*timestamp);
             let holder = self.execs.entry(bin).or_default();
+            // we don't know whether these were fired before the restore, so fire them again
+            holder.changed = true;
             batch
                 .iter()
                 .for_each(|batch| holder.finished_batches.push(batch.clone()));
         }
+        if self.fired_schema.is_some() {
+            ctx.table_manager
+                .get_last_key_value_table("f", watermark)
+                .await
+                .expect("should be able to load table");
+        }
     }
 
     async fn process_batch(&mut self, batch: RecordBatch, ctx: &mut ArrowContext) {
@@ -273,17 +475,20 @@ impl ArrowOperator for TumblingAggregatingWindowFunc<SystemTime> {
             let bin_start = from_nanos(typed_bin.value(range.start) as u128);
             let watermark = ctx.last_present_watermark();
 
-            if watermark.is_some() && bin_start < self.bin_start(watermark.unwrap()) {
+            if watermark.is_some() && bin_start < self.first_open_bin(watermark.unwrap()) {
                 warn!(
                     "bin start {} is before watermark {}, skipping",
                     print_time(bin_start),
                     print_time(watermark.unwrap())
                 );
+                ctx.drop_late_records(sorted.slice(range.start, range.end - range.start))
+                    .await;
                 continue;
             }
 
             let bin_batch = sorted.slice(range.start, range.end - range.start);
             let bin_exec = self.execs.entry(bin_start).or_default();
+            bin_exec.changed = true;
             if bin_exec.active_exec.is_none() {
                 let (unbounded_sender, unbounded_receiver) = unbounded_channel();
                 bin_exec.sender = Some(unbounded_sender);
@@ -314,71 +519,50 @@ impl ArrowOperator for TumblingAggregatingWindowFunc<SystemTime> {
         watermark: Watermark,
         ctx: &mut ArrowContext,
     ) -> Option<Watermark> {
-        if let Some(watermark) = ctx.last_present_watermark() {
-            let bin = self.bin_start(watermark);
-            while !self.execs.is_empty() {
-                let should_pop = {
-                    let Some((first_bin, _exec)) = self.execs.first_key_value() else {
-                        unreachable!("isn't empty")
-                    };
-                    *first_bin < bin
-                };
-                if should_pop {
-                    let Some((popped_bin, mut exec)) = self.execs.pop_first() else {
-                        unreachable!("should have an entry")
-                    };
-                    if let Some(mut active_exec) = exec.active_exec.take() {
-                        exec.sender.take();
-                        while let (_bin, Some((batch, new_exec))) = active_exec.await {
-                            active_exec = new_exec;
-                            let batch = batch.expect("should be able to compute batch");
-                            exec.finished_batches.push(batch);
-                        }
-                    }
-                    {
-                        let mut batches = self.final_batches_passer.write().unwrap();
-                        let finished_batches = mem::take(&mut exec.finished_batches);
-                        *batches = finished_batches;
-                    }
-                    self.finish_execution_plan
-                        .reset()
-                        .expect("reset execution plan");
-                    let mut final_exec = self
-                        .finish_execution_plan
-                        .execute(0, SessionContext::new().task_ctx())
-                        .unwrap();
-                    let mut aggregate_results = vec![];
-                    while let Some(batch) = final_exec.next().await {
-                        let batch = batch.expect("should be able to compute batch");
-                        let with_timestamp = Self::add_bin_start_as_timestamp(
-                            &batch,
-                            popped_bin,
-                            self.aggregate_with_timestamp_schema.clone(),
-                        )
-                        .expect("should be able to add timestamp");
-                        if self.final_projection.is_some() {
-                            aggregate_results.push(with_timestamp);
-                        } else {
-                            ctx.collect(with_timestamp).await;
-                        }
-                    }
-                    if let Some(final_projection) = self.final_projection.as_ref() {
-                        {
-                            let mut batches = self.final_batches_passer.write().unwrap();
-                            *batches = aggregate_results;
-                        }
-                        final_projection.reset().expect("reset execution plan");
-                        let mut final_projection_exec = final_projection
-                            .execute(0, SessionContext::new().task_ctx())
-                            .unwrap();
-                        while let Some(batch) = final_projection_exec.next().await {
-                            let batch = batch.expect("should be able to compute batch");
-                            ctx.collect(batch).await;
-                        }
-                    }
-                } else {
-                    break;
-                }
+        let Some(last_watermark) = ctx.last_present_watermark() else {
+            return Some(watermark);
+        };
+        if self.fired_schema.is_some() {
+            // fire the closed windows that changed, then drop those past their allowed lateness
+            let closed_bins: Vec<_> = self
+                .execs
+                .range(..self.bin_start(last_watermark))
+                .filter(|(_, exec)| exec.changed)
+                .map(|(bin, _)| *bin)
+                .collect();
+            for bin in closed_bins {
+                self.fire_bin(bin, ctx)
+                    .await
+                    .expect("should be able to fire bin");
+            }
+            let first_open_bin = self.first_open_bin(last_watermark);
+            self.execs = self.execs.split_off(&first_open_bin);
+            ctx.table_manager
+                .get_last_key_value_table("f", Some(last_watermark))
+                .await
+                .expect("should have fired table")
+                .expire(Some(last_watermark))
+                .expect("should expire fired table");
+            return Some(watermark);
+        }
+
+        let bin = self.bin_start(last_watermark);
+        while self
+            .execs
+            .first_key_value()
+            .is_some_and(|(first_bin, _exec)| *first_bin < bin)
+        {
+            let Some((popped_bin, mut exec)) = self.execs.pop_first() else {
+                unreachable!("should have an entry")
+            };
+            let new_batches = exec.drain_active_exec().await;
+            exec.finished_batches.extend(new_batches);
+            let results = self
+                .finish_bin(popped_bin, mem::take(&mut exec.finished_batches))
+                .await
+                .expect("should be able to compute bin results");
+            for batch in results {
+                ctx.collect(batch).await;
             }
         }
         Some(watermark)
@@ -435,13 +619,7 @@ impl ArrowOperator for TumblingAggregatingWindowFunc<SystemTime> {
 
         // This was a separate map just to the active execs, which could, in corner cases, be much smaller.
         for (bin, exec) in self.execs.iter_mut() {
-            exec.sender.take();
-            let Some(mut active_exec) = exec.active_exec.take() else {
-                continue;
-            };
-            while let (_bin_, Some((batch, next_exec))) = active_exec.await {
-                active_exec = next_exec;
-                let batch = batch.expect("should be able to compute batch");
+            for batch in exec.drain_active_exec().await {
                 let state_batch = Self::add_bin_start_as_timestamp(
                     &batch,
                     *bin,
@@ -456,17 +634,185 @@ impl ArrowOperator for TumblingAggregatingWindowFunc<SystemTime> {
     }
 
     fn tables(&self) -> HashMap<String, TableConfig> {
-        vec![(
+        let retention = self.width + self.allowed_lateness;
+        let mut tables = HashMap::new();
+        tables.insert(
             "t".to_string(),
             timestamp_table_config(
                 "t",
                 "tumbling_intermediate",
-                self.width,
+                retention,
                 false,
                 self.partial_schema.clone(),
             ),
-        )]
-        .into_iter()
-        .collect()
+        );
+        if let Some(fired_schema) = &self.fired_schema {
+            tables.insert(
+                "f".to_string(),
+                timestamp_table_config(
+                    "f",
+                    "tumbling_fired",
+                    retention,
+                    true,
+                    fired_schema.clone(),
+                ),
+            );
+        }
+        tables
+    }
+
+    fn tick_interval(&self) -> Option<Duration> {
+        self.early_fire
+    }
+
+    async fn handle_tick(&mut self, _tick: u64, ctx: &mut ArrowContext) {
+        let changed_bins: Vec<_> = self
+            .execs
+            .iter()
+            .filter(|(_, exec)| exec.changed)
+            .map(|(bin, _)| *bin)
+            .collect();
+        for bin in changed_bins {
+            self.fire_bin(bin, ctx)
+                .await
+                .expect("should be able to fire bin");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::arrow::test_utils::{nanos, OperatorHarness};
+    use arroyo_datastream::logical::OperatorName;
+    use arroyo_metrics::TaskCounters;
+    use serde_json::{json, Value};
+
+    const QUERY: &str = "
+        CREATE TABLE bids (
+            auction BIGINT,
+            bid_time TIMESTAMP
+        ) WITH (
+            connector = 'kafka',
+            bootstrap_servers = 'localhost:9092',
+            type = 'source',
+            topic = 'bids',
+            format = 'json',
+            event_time_field = 'bid_time'
+        );
+
+        SET window_early_fire = '1 second';
+        SET window_allowed_lateness = '20 seconds';
+
+        SELECT auction, tumble(interval '10 seconds') as window, count(*) as bids
+        FROM bids
+        GROUP BY 1, 2";
+
+    const LATE_RECORDS_QUERY: &str = "
+        CREATE TABLE bids (
+            auction BIGINT,
+            bid_time TIMESTAMP
+        ) WITH (
+            connector = 'kafka',
+            bootstrap_servers = 'localhost:9092',
+            type = 'source',
+            topic = 'bids',
+            format = 'json',
+            event_time_field = 'bid_time'
+        );
+
+        CREATE TABLE late_bids (
+            auction BIGINT,
+            bid_time TIMESTAMP
+        ) WITH (
+            connector = 'kafka',
+            bootstrap_servers = 'localhost:9092',
+            type = 'sink',
+            topic = 'late_bids',
+            format = 'json'
+        );
+
+        SET window_late_records_table = 'late_bids';
+
+        SELECT auction, tumble(interval '10 seconds') as window, count(*) as bids
+        FROM bids
+        GROUP BY 1, 2";
+
+    fn bid(auction: i64, secs: u64) -> Value {
+        json!({
+            "_key_auction": auction,
+            "auction": auction,
+            "bid_time": nanos(secs),
+            "_timestamp": nanos(secs),
+        })
+    }
+
+    /// The bid counts emitted for auction 1 in the window starting at 1000 seconds, as
+    /// (count, is_retract)
+    fn counts(rows: Vec<Value>) -> Vec<(i64, bool)> {
+        rows.iter()
+            .map(|row| {
+                assert_eq!(row["auction"], json!(1));
+                (
+                    row["bids"].as_i64().unwrap(),
+                    row["is_retract"].as_bool().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_early_firing_and_late_correction() {
+        let mut harness =
+            OperatorHarness::from_sql(QUERY, OperatorName::TumblingWindowAggregate).await;
+
+        harness.process(0, &[bid(1, 1001), bid(1, 1002)]).await;
+        harness.tick().await;
+        assert_eq!(counts(harness.output()), vec![(2, false)]);
+
+        // each firing retracts the results of the one before
+        harness.process(0, &[bid(1, 1003)]).await;
+        harness.tick().await;
+        assert_eq!(counts(harness.output()), vec![(2, true), (3, false)]);
+
+        // windows that haven't changed don't fire, on ticks or when they close
+        harness.tick().await;
+        harness.watermark(1015).await;
+        assert_eq!(counts(harness.output()), vec![]);
+
+        // a late record within the allowed lateness corrects the closed window
+        harness.process(0, &[bid(1, 1005)]).await;
+        harness.watermark(1016).await;
+        assert_eq!(counts(harness.output()), vec![(3, true), (4, false)]);
+
+        // once the watermark is past the allowed lateness, the window is gone for good
+        harness.watermark(1031).await;
+        harness.process(0, &[bid(1, 1008)]).await;
+        harness.tick().await;
+        harness.watermark(1032).await;
+        assert_eq!(counts(harness.output()), vec![]);
+    }
+
+    #[tokio::test]
+    async fn test_late_records_are_counted_and_sent_to_side_output() {
+        let mut harness =
+            OperatorHarness::from_sql(LATE_RECORDS_QUERY, OperatorName::TumblingWindowAggregate)
+                .await;
+
+        harness.process(0, &[bid(1, 1001)]).await;
+        harness.watermark(1010).await;
+        assert_eq!(harness.output().len(), 1);
+
+        // the window has closed, so the record is dropped and sent on without its key
+        harness.process(0, &[bid(1, 1005), bid(1, 1012)]).await;
+        assert_eq!(harness.counter(TaskCounters::LateRecordsDropped), 1);
+        let late = harness.side_output();
+        assert_eq!(late.len(), 1);
+        assert_eq!(late[0]["auction"], json!(1));
+        assert!(late[0].get("_key_auction").is_none());
+
+        // records for open windows are neither counted nor sent
+        harness.process(0, &[bid(1, 1013)]).await;
+        assert_eq!(harness.counter(TaskCounters::LateRecordsDropped), 1);
+        assert_eq!(harness.side_output(), Vec::<Value>::new());
     }
 }
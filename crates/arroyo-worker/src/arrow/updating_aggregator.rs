@@ -1,17 +1,22 @@
-use anyhow::{anyhow, Result};
-use arrow::compute::concat_batches;
+use anyhow::{anyhow, bail, Result};
+use arrow::compute::{concat_batches, filter, filter_record_batch, not, take};
+use arrow::row::{OwnedRow, RowConverter, SortField};
 use std::borrow::Cow;
 use std::{
     any::Any,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    mem,
     pin::Pin,
     sync::{Arc, RwLock},
 };
 
-use arrow_array::{Array, BooleanArray, RecordBatch, StructArray};
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, RecordBatch, RecordBatchOptions, StructArray, UInt32Array,
+};
 
 use arrow_array::cast::AsArray;
-use arrow_schema::SchemaRef;
+use arrow_array::types::Int64Type;
+use arrow_schema::{Schema, SchemaRef};
 use arroyo_df::physical::{ArroyoPhysicalExtensionCodec, DecodingContext};
 use arroyo_operator::{
     context::ArrowContext,
@@ -22,13 +27,15 @@ use arroyo_operator::{
 };
 use arroyo_rpc::df::ArroyoSchemaRef;
 use arroyo_rpc::grpc::{api::UpdatingAggregateOperator, rpc::TableConfig};
-use arroyo_rpc::{updating_meta_fields, UPDATING_META_FIELD};
+use arroyo_rpc::{updating_meta_fields, UPDATING_META_FIELD, UPDATING_ROW_COUNT_FIELD};
 use arroyo_state::timestamp_table_config;
 use arroyo_types::{CheckpointBarrier, SignalMessage, Watermark};
 use datafusion::execution::{
     runtime_env::{RuntimeConfig, RuntimeEnv},
     SendableRecordBatchStream,
 };
+use datafusion::physical_plan::aggregates::AggregateExec;
+use datafusion::scalar::ScalarValue;
 use datafusion::{execution::context::SessionContext, physical_plan::ExecutionPlan};
 use datafusion_proto::{physical_plan::AsExecutionPlan, protobuf::PhysicalPlanNode};
 use futures::{lock::Mutex, Future};
@@ -53,14 +60,15 @@ pub struct UpdatingAggregatingFunc {
     // In particular, if it is a global aggregate it will emit a record batch with 1 row initialized with the empty aggregate state,
     // while if it does have group by keys it will emit a record batch with 0 rows.
     exec: Arc<Mutex<Option<SendableRecordBatchStream>>>,
+    // retracted input rows, which are applied to the partial aggregates after the appends
+    retractions: Vec<RecordBatch>,
     ttl: Duration,
 }
 
 impl UpdatingAggregatingFunc {
-    async fn flush(&mut self, ctx: &mut ArrowContext) -> Result<()> {
-        if self.sender.is_none() {
-            return Ok(());
-        }
+    /// Combines the partial aggregates of the appended rows with those in state, returning the
+    /// new partials of the groups they touched.
+    async fn flush_appends(&mut self, ctx: &mut ArrowContext) -> Result<Vec<RecordBatch>> {
         {
             self.sender.take();
         }
@@ -111,17 +119,39 @@ impl UpdatingAggregatingFunc {
             final_input_batches.push(new_partial_batch);
         }
 
-        let final_input_batch = concat_batches(&self.partial_schema.schema, &final_input_batches)?;
-
-        let mut final_exec = {
-            let (sender, receiver) = unbounded_channel();
-            sender.send(final_input_batch)?;
-            self.receiver.write().unwrap().replace(receiver);
+        Ok(final_input_batches)
+    }
 
-            self.finish_execution_plan
-                .execute(0, SessionContext::new().task_ctx())?
+    async fn flush(&mut self, ctx: &mut ArrowContext) -> Result<()> {
+        if self.sender.is_none() && self.retractions.is_empty() {
+            return Ok(());
+        }
+        let final_input_batches = if self.sender.is_some() {
+            self.flush_appends(ctx).await?
+        } else {
+            vec![]
         };
 
+        let mut final_input_batch =
+            concat_batches(&self.partial_schema.schema, &final_input_batches)?;
+        let mut emptied_keys = None;
+
+        if let Some((retracted, emptied)) = self.apply_retractions(ctx).await? {
+            // the retracted partials are the latest for their groups, so replace them in the input
+            let keep = self.keys_not_in(&final_input_batch, &retracted)?;
+            final_input_batch = concat_batches(
+                &self.partial_schema.schema,
+                &[
+                    filter_record_batch(&final_input_batch, &keep)?,
+                    filter_record_batch(&retracted, &not(&emptied)?)?,
+                ],
+            )?;
+            emptied_keys = Some(
+                filter_record_batch(&retracted, &emptied)?
+                    .project(&(0..self.key_count()).collect::<Vec<_>>())?,
+            );
+        }
+
         let final_output_table = ctx
             .table_manager
             .get_last_key_value_table("f", ctx.last_present_watermark())
@@ -131,30 +161,61 @@ impl UpdatingAggregatingFunc {
 
         let out_schema = ctx.out_schema.as_ref().unwrap().schema.clone();
 
-        while let Some(results) = final_exec.next().await {
-            let results = results?;
-            let renamed_results = RecordBatch::try_new(
-                self.state_final_schema.schema.clone(),
-                results.columns().to_vec(),
-            )?;
-
-            if let Some((prior_batch, _filter)) =
-                final_output_table.get_current_matching_values(&renamed_results)?
-            {
-                batches_to_write.push(Self::set_retract_metadata(
-                    out_schema.clone(),
-                    prior_batch,
-                    true,
-                )?);
-            }
-
+        // groups that all of the rows have been retracted from no longer have an output, so their
+        // last output is retracted and they're removed from the final table
+        if let Some((prior_batch, _filter)) = emptied_keys
+            .map(|keys| {
+                final_output_table
+                    .get_current_values_for_keys(self.state_final_schema.schema.clone(), &keys)
+            })
+            .transpose()?
+            .flatten()
+        {
             final_output_table
-                .insert_batch(renamed_results.clone())
+                .insert_batch(self.with_is_retract(prior_batch.clone(), true)?)
                 .await?;
+            batches_to_write.push(Self::set_retract_metadata(
+                out_schema.clone(),
+                prior_batch,
+                true,
+            )?);
+        }
 
-            // Update the is_retract field within the _updating_meta struct for additions
-            let result_batch = Self::set_retract_metadata(out_schema.clone(), results, false)?;
-            batches_to_write.push(result_batch);
+        if final_input_batch.num_rows() > 0 {
+            let mut final_exec = {
+                let (sender, receiver) = unbounded_channel();
+                sender.send(final_input_batch)?;
+                self.receiver.write().unwrap().replace(receiver);
+
+                self.finish_execution_plan
+                    .execute(0, SessionContext::new().task_ctx())?
+            };
+
+            while let Some(results) = final_exec.next().await {
+                let results = results?;
+                let renamed_results = RecordBatch::try_new(
+                    self.state_final_schema.schema.clone(),
+                    results.columns().to_vec(),
+                )?;
+
+                if let Some((prior_batch, _filter)) =
+                    final_output_table.get_current_matching_values(&renamed_results)?
+                {
+                    batches_to_write.push(Self::set_retract_metadata(
+                        out_schema.clone(),
+                        prior_batch,
+                        true,
+                    )?);
+                }
+
+                final_output_table
+                    .insert_batch(renamed_results.clone())
+                    .await?;
+
+                // Update the is_retract field within the _updating_meta struct for additions
+                let result_batch = Self::set_retract_metadata(out_schema.clone(), results, false)?;
+                batches_to_write.push(result_batch);
+            }
         }
 
         if !batches_to_write.is_empty() {
@@ -168,6 +229,211 @@ impl UpdatingAggregatingFunc {
         Ok(())
     }
 
+    fn key_count(&self) -> usize {
+        self.state_partial_schema
+            .key_indices
+            .as_ref()
+            .map(|keys| keys.len())
+            .unwrap_or_default()
+    }
+
+    fn key_converter(&self) -> Result<RowConverter> {
+        let fields = self.partial_schema.schema.fields()[..self.key_count()]
+            .iter()
+            .map(|field| SortField::new(field.data_type().clone()))
+            .collect();
+        Ok(RowConverter::new(fields)?)
+    }
+
+    /// Returns which rows of `batch` are for groups that don't appear in `other`
+    fn keys_not_in(&self, batch: &RecordBatch, other: &RecordBatch) -> Result<BooleanArray> {
+        if self.key_count() == 0 {
+            // there's only a single group
+            return Ok(BooleanArray::from(vec![
+                other.num_rows() == 0;
+                batch.num_rows()
+            ]));
+        }
+        let converter = self.key_converter()?;
+        let other_rows = converter.convert_columns(&other.columns()[..self.key_count()])?;
+        let other_keys: HashSet<_> = other_rows.iter().collect();
+        let rows = converter.convert_columns(&batch.columns()[..self.key_count()])?;
+        Ok(rows
+            .iter()
+            .map(|row| Some(!other_keys.contains(&row)))
+            .collect())
+    }
+
+    /// Sets `is_retract` in the updating metadata of a batch in one of the state schemas, where it
+    /// follows the keys. Rows written to state with it set delete their keys.
+    fn with_is_retract(&self, batch: RecordBatch, is_retract: bool) -> Result<RecordBatch> {
+        let index = self.key_count();
+        let mut columns = batch.columns().to_vec();
+        columns[index] = Arc::new(StructArray::new(
+            updating_meta_fields(),
+            vec![
+                Arc::new(BooleanArray::from(vec![is_retract; batch.num_rows()])),
+                batch.column(index).as_struct().column(1).clone(),
+            ],
+            None,
+        ));
+        Ok(RecordBatch::try_new(batch.schema(), columns)?)
+    }
+
+    /// Retracts the buffered retracted rows from the partial aggregates of their groups, using the
+    /// accumulators' `retract_batch`. The new partials are returned in the partial schema, along
+    /// with which of the groups have had all of their rows retracted; those are deleted from state,
+    /// while the rest are written back to it.
+    async fn apply_retractions(
+        &mut self,
+        ctx: &mut ArrowContext,
+    ) -> Result<Option<(RecordBatch, BooleanArray)>> {
+        if self.retractions.is_empty() {
+            return Ok(None);
+        }
+        let retractions = mem::take(&mut self.retractions);
+        let batch = concat_batches(&retractions[0].schema(), &retractions)?;
+        let aggregate = self
+            .partial_aggregation_plan
+            .as_any()
+            .downcast_ref::<AggregateExec>()
+            .ok_or_else(|| anyhow!("partial aggregation plan should be an aggregate"))?;
+        let key_count = self.key_count();
+
+        let key_columns = aggregate.group_expr().expr()[..key_count]
+            .iter()
+            .map(|(expr, _)| expr.evaluate(&batch)?.into_array(batch.num_rows()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // group the retracted rows by key
+        let mut groups: Vec<Vec<u32>> = vec![];
+        if key_count == 0 {
+            groups.push((0..batch.num_rows() as u32).collect());
+        } else {
+            let rows = self.key_converter()?.convert_columns(&key_columns)?;
+            let mut group_indices: HashMap<OwnedRow, usize> = HashMap::new();
+            for (i, row) in rows.iter().enumerate() {
+                let group = *group_indices.entry(row.owned()).or_insert_with(|| {
+                    groups.push(vec![]);
+                    groups.len() - 1
+                });
+                groups[group].push(i as u32);
+            }
+        }
+
+        let first_indices = UInt32Array::from_iter_values(groups.iter().map(|group| group[0]));
+        let key_batch = RecordBatch::try_new_with_options(
+            Arc::new(Schema::new(
+                self.state_partial_schema.schema.fields()[..key_count].to_vec(),
+            )),
+            key_columns
+                .iter()
+                .map(|column| take(column, &first_indices, None))
+                .collect::<Result<_, _>>()?,
+            &RecordBatchOptions::new().with_row_count(Some(groups.len())),
+        )?;
+
+        let prior_partials = ctx
+            .table_manager
+            .get_last_key_value_table("p", ctx.last_present_watermark())
+            .await?;
+        // rows for groups that aren't in state (e.g., because they've expired) can't be retracted
+        let Some((prior_batch, found)) = prior_partials
+            .get_current_values_for_keys(self.state_partial_schema.schema.clone(), &key_batch)?
+        else {
+            return Ok(None);
+        };
+        let groups: Vec<_> = groups
+            .into_iter()
+            .zip(found.iter())
+            .filter_map(|(group, found)| found.unwrap_or(false).then_some(group))
+            .collect();
+
+        let mut columns = prior_batch.columns().to_vec();
+        // the state columns start after the keys and the updating metadata; the last aggregate is
+        // the max timestamp, which is left as is
+        let mut state_index = key_count + 1;
+        let mut row_count_index = None;
+        let aggregates = aggregate.aggr_expr();
+        for (aggregate_expr, filter_expr) in aggregates[..aggregates.len() - 1]
+            .iter()
+            .zip(aggregate.filter_expr())
+        {
+            let state_count = aggregate_expr.state_fields()?.len();
+            if aggregate_expr.name() == UPDATING_ROW_COUNT_FIELD {
+                row_count_index = Some(state_index);
+            }
+            let values = aggregate_expr
+                .expressions()
+                .iter()
+                .map(|expr| expr.evaluate(&batch)?.into_array(batch.num_rows()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mask = filter_expr
+                .as_ref()
+                .map(|expr| expr.evaluate(&batch)?.into_array(batch.num_rows()))
+                .transpose()?;
+
+            let mut states: Vec<Vec<ScalarValue>> = vec![vec![]; state_count];
+            for (row, group) in groups.iter().enumerate() {
+                let mut accumulator = aggregate_expr.create_accumulator()?;
+                if !accumulator.supports_retract_batch() {
+                    bail!(
+                        "aggregate {} does not support retractions",
+                        aggregate_expr.name()
+                    );
+                }
+                let prior_state: Vec<ArrayRef> = columns[state_index..state_index + state_count]
+                    .iter()
+                    .map(|column| column.slice(row, 1))
+                    .collect();
+                accumulator.merge_batch(&prior_state)?;
+                let indices = UInt32Array::from(group.clone());
+                let mut group_values = values
+                    .iter()
+                    .map(|values| take(values, &indices, None))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if let Some(mask) = &mask {
+                    let group_mask = take(mask, &indices, None)?;
+                    group_values = group_values
+                        .iter()
+                        .map(|values| filter(values, group_mask.as_boolean()))
+                        .collect::<Result<_, _>>()?;
+                }
+                accumulator.retract_batch(&group_values)?;
+                for (state, value) in states.iter_mut().zip(accumulator.state()?) {
+                    state.push(value);
+                }
+            }
+
+            for (i, state) in states.into_iter().enumerate() {
+                columns[state_index + i] = ScalarValue::iter_to_array(state)?;
+            }
+            state_index += state_count;
+        }
+
+        let emptied: BooleanArray = match row_count_index {
+            Some(index) => columns[index]
+                .as_primitive::<Int64Type>()
+                .iter()
+                .map(|count| Some(count.unwrap_or_default() == 0))
+                .collect(),
+            None => BooleanArray::from(vec![false; prior_batch.num_rows()]),
+        };
+
+        let state_batch =
+            RecordBatch::try_new(self.state_partial_schema.schema.clone(), columns.clone())?;
+        prior_partials
+            .insert_batch(filter_record_batch(&state_batch, &not(&emptied)?)?)
+            .await?;
+        prior_partials
+            .insert_batch(self.with_is_retract(filter_record_batch(&state_batch, &emptied)?, true)?)
+            .await?;
+        Ok(Some((
+            RecordBatch::try_new(self.partial_schema.schema.clone(), columns)?,
+            emptied,
+        )))
+    }
+
     fn set_retract_metadata(
         out_schema: SchemaRef,
         mut batch: RecordBatch,
@@ -243,6 +509,27 @@ impl ArrowOperator for UpdatingAggregatingFunc {
     }
 
     async fn process_batch(&mut self, batch: RecordBatch, _ctx: &mut ArrowContext) {
+        let batch = match batch.schema().index_of(UPDATING_META_FIELD) {
+            Ok(index) => {
+                let is_retract = batch
+                    .column(index)
+                    .as_struct()
+                    .column_by_name("is_retract")
+                    .expect("updating metadata should have is_retract")
+                    .as_boolean()
+                    .clone();
+                let retractions = filter_record_batch(&batch, &is_retract).unwrap();
+                let appends = filter_record_batch(&batch, &not(&is_retract).unwrap()).unwrap();
+                if retractions.num_rows() > 0 {
+                    self.retractions.push(retractions);
+                    if appends.num_rows() == 0 {
+                        return;
+                    }
+                }
+                appends
+            }
+            Err(_) => batch,
+        };
         if self.sender.is_none() {
             self.init_exec();
         }
@@ -429,8 +716,128 @@ impl OperatorConstructor for UpdatingAggregatingConstructor {
                 receiver,
                 sender: None,
                 exec: Arc::new(Mutex::new(None)),
+                retractions: vec![],
                 ttl: Duration::from_micros(ttl),
             },
         )))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrow::test_utils::{nanos, OperatorHarness};
+    use arrow_array::StringArray;
+    use arroyo_datastream::logical::OperatorName;
+    use arroyo_rpc::df::ArroyoSchema;
+    use serde_json::{json, Value};
+
+    const QUERY: &str = "
+        CREATE TABLE orders (
+            id int PRIMARY KEY,
+            category TEXT
+        ) WITH (
+            connector = 'kafka',
+            bootstrap_servers = 'localhost:9092',
+            type = 'source',
+            topic = 'orders',
+            format = 'debezium_json'
+        );
+
+        SELECT category, count(*) as orders FROM orders GROUP BY category";
+
+    fn order(id: i32, category: &str, is_retract: bool) -> Value {
+        json!({
+            "_key_category": category,
+            "id": id,
+            "category": category,
+            "_timestamp": nanos(id as u64),
+            "is_retract": is_retract,
+        })
+    }
+
+    /// The hidden row count in the partial aggregate of `category`, if it's in state
+    async fn row_count(harness: &mut OperatorHarness, category: &str) -> Option<i64> {
+        let config: UpdatingAggregateOperator = harness.config();
+        let schema: ArroyoSchema = config.state_partial_schema.unwrap().try_into().unwrap();
+        let keys = RecordBatch::try_from_iter([(
+            "category",
+            Arc::new(StringArray::from(vec![category])) as ArrayRef,
+        )])
+        .unwrap();
+
+        let watermark = harness.ctx.last_present_watermark();
+        let partials = harness
+            .ctx
+            .table_manager
+            .get_last_key_value_table("p", watermark)
+            .await
+            .unwrap();
+        let (batch, _) = partials
+            .get_current_values_for_keys(schema.schema.clone(), &keys)
+            .unwrap()?;
+        let index = schema
+            .schema
+            .fields()
+            .iter()
+            .position(|field| field.name().starts_with(UPDATING_ROW_COUNT_FIELD))
+            .expect("partial state should have the row count");
+        Some(batch.column(index).as_primitive::<Int64Type>().value(0))
+    }
+
+    fn counts(rows: &[Value]) -> Vec<(String, i64, bool)> {
+        rows.iter()
+            .map(|row| {
+                (
+                    row["category"].as_str().unwrap().to_string(),
+                    row["orders"].as_i64().unwrap(),
+                    row["is_retract"].as_bool().unwrap(),
+                )
+            })
+            .sorted()
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_emptied_group_is_removed_and_refilled() {
+        let mut harness = OperatorHarness::from_sql(QUERY, OperatorName::UpdatingAggregate).await;
+
+        harness
+            .process(
+                0,
+                &[
+                    order(1, "a", false),
+                    order(2, "a", false),
+                    order(3, "b", false),
+                ],
+            )
+            .await;
+        harness.tick().await;
+        assert_eq!(
+            counts(&harness.output()),
+            vec![("a".to_string(), 2, false), ("b".to_string(), 1, false)]
+        );
+        assert_eq!(row_count(&mut harness, "a").await, Some(2));
+
+        // retracting every row of a group retracts its output and removes it from state
+        harness
+            .process(0, &[order(1, "a", true), order(2, "a", true)])
+            .await;
+        harness.tick().await;
+        assert_eq!(counts(&harness.output()), vec![("a".to_string(), 2, true)]);
+        assert_eq!(row_count(&mut harness, "a").await, None);
+        assert_eq!(row_count(&mut harness, "b").await, Some(1));
+
+        // so a new row starts the group over, rather than retracting an output that's gone
+        harness.process(0, &[order(4, "a", false)]).await;
+        harness.tick().await;
+        assert_eq!(counts(&harness.output()), vec![("a".to_string(), 1, false)]);
+        assert_eq!(row_count(&mut harness, "a").await, Some(1));
+
+        // and the group's state survives a restore
+        harness.checkpoint().await;
+        let mut harness = harness.restore().await;
+        assert_eq!(row_count(&mut harness, "a").await, Some(1));
+        assert_eq!(row_count(&mut harness, "b").await, Some(1));
+    }
+}
@@ -13,10 +13,16 @@ use futures::StreamExt;
 use tracing::{info, warn};
 
 use crate::arrow::async_udf::AsyncUdfConstructor;
+use crate::arrow::dedup::DedupConstructor;
 use crate::arrow::instant_join::InstantJoinConstructor;
 use crate::arrow::join_with_expiration::JoinWithExpirationConstructor;
+use crate::arrow::match_recognize::MatchRecognizeConstructor;
 use crate::arrow::session_aggregating_window::SessionAggregatingWindowConstructor;
-use crate::arrow::sliding_aggregating_window::SlidingAggregatingWindowConstructor;
+use crate::arrow::sliding_aggregating_window::{
+    CumulativeAggregatingWindowConstructor, SlidingAggregatingWindowConstructor,
+};
+use crate::arrow::temporal_join::TemporalJoinConstructor;
+use crate::arrow::top_n::TopNConstructor;
 use crate::arrow::tumbling_aggregating_window::TumblingAggregateWindowConstructor;
 use crate::arrow::updating_aggregator::UpdatingAggregatingConstructor;
 use crate::arrow::watermark_generator::WatermarkGeneratorConstructor;
@@ -222,13 +228,16 @@ impl Program {
                 .map(|edge| edge.weight().schema.clone())
                 .collect();
 
+            // side outputs carry their own schema, so they don't determine the node's output
             let out_schema = logical
                 .edges_directed(idx, Direction::Outgoing)
+                .filter(|edge| !edge.weight().edge_type.is_side_output())
                 .map(|edge| edge.weight().schema.clone())
                 .next();
 
             let projection = logical
                 .edges_directed(idx, Direction::Outgoing)
+                .filter(|edge| !edge.weight().edge_type.is_side_output())
                 .map(|edge| edge.weight().projection.clone())
                 .next()
                 .unwrap_or_default();
@@ -295,7 +304,9 @@ impl Program {
                 }
                 LogicalEdgeType::Shuffle
                 | LogicalEdgeType::LeftJoin
-                | LogicalEdgeType::RightJoin => {
+                | LogicalEdgeType::RightJoin
+                | LogicalEdgeType::DeadLetter
+                | LogicalEdgeType::LateRecords => {
                     for f in &from_nodes {
                         for (idx, t) in to_nodes.iter().enumerate() {
                             let (tx, rx) = batch_bounded(queue_size);
@@ -675,6 +686,7 @@ impl Engine {
 
         let mut in_qs_map: BTreeMap<(LogicalEdgeType, usize), Vec<BatchReceiver>> = BTreeMap::new();
         let mut out_qs_map: BTreeMap<usize, BTreeMap<usize, BatchSender>> = BTreeMap::new();
+        let mut side_output_qs_map: BTreeMap<usize, BTreeMap<usize, BatchSender>> = BTreeMap::new();
         let task_info = {
             let mut graph = self.program.graph.write().unwrap();
             for edge in graph.edge_indices() {
@@ -699,7 +711,12 @@ impl Engine {
                 };
 
                 let tx = edge.weight().tx.as_ref().unwrap().clone();
-                out_qs_map
+                let qs_map = if edge.weight().edge.is_side_output() {
+                    &mut side_output_qs_map
+                } else {
+                    &mut out_qs_map
+                };
+                qs_map
                     .entry(edge.weight().out_logical_idx)
                     .or_default()
                     .insert(edge.weight().edge_idx, tx);
@@ -714,7 +731,7 @@ impl Engine {
         let tables = node.node.tables();
         let in_qs: Vec<_> = in_qs_map.into_values().flatten().collect();
 
-        let ctx = ArrowContext::new(
+        let mut ctx = ArrowContext::new(
             task_info,
             checkpoint_metadata.clone(),
             control_rx,
@@ -731,6 +748,15 @@ impl Engine {
         )
         .await;
 
+        if !side_output_qs_map.is_empty() {
+            ctx.set_side_output_qs(
+                side_output_qs_map
+                    .into_values()
+                    .map(|v| v.into_values().collect())
+                    .collect(),
+            );
+        }
+
         let operator = Box::new(node.node);
         let join_task = tokio::spawn(async move {
             operator.start(ctx, in_qs, ready).await;
@@ -763,12 +789,17 @@ pub fn construct_operator(
         OperatorName::AsyncUdf => Box::new(AsyncUdfConstructor),
         OperatorName::TumblingWindowAggregate => Box::new(TumblingAggregateWindowConstructor),
         OperatorName::SlidingWindowAggregate => Box::new(SlidingAggregatingWindowConstructor),
+        OperatorName::CumulativeWindowAggregate => Box::new(CumulativeAggregatingWindowConstructor),
         OperatorName::SessionWindowAggregate => Box::new(SessionAggregatingWindowConstructor),
         OperatorName::UpdatingAggregate => Box::new(UpdatingAggregatingConstructor),
         OperatorName::ExpressionWatermark => Box::new(WatermarkGeneratorConstructor),
         OperatorName::Join => Box::new(JoinWithExpirationConstructor),
         OperatorName::InstantJoin => Box::new(InstantJoinConstructor),
+        OperatorName::TemporalJoin => Box::new(TemporalJoinConstructor),
         OperatorName::WindowFunction => Box::new(WindowFunctionConstructor),
+        OperatorName::TopN => Box::new(TopNConstructor),
+        OperatorName::Dedup => Box::new(DedupConstructor),
+        OperatorName::MatchRecognize => Box::new(MatchRecognizeConstructor),
         OperatorName::ConnectorSource | OperatorName::ConnectorSink => {
             let op: api::ConnectorOp = prost::Message::decode(&mut config.as_slice()).unwrap();
             return connectors()
@@ -1,10 +1,8 @@
 #![allow(clippy::redundant_slicing)]
-use anyhow::{anyhow, bail};
-use arrow::buffer::MutableBuffer;
-use arrow::ipc::reader::read_record_batch;
 use arrow::ipc::writer::{DictionaryTracker, EncodedData, IpcDataGenerator, IpcWriteOptions};
 use arrow_array::RecordBatch;
 use arrow_schema::{ArrowError, SchemaRef};
+use arroyo_formats::ipc::IpcMessage;
 use arroyo_types::ArrowMessage;
 use bincode::config;
 use std::{collections::HashMap, mem::size_of, pin::Pin, sync::Arc, time::Duration};
@@ -456,36 +454,7 @@ pub async fn write_message_and_header<W: AsyncWrite + AsyncWriteExt>(
 }
 
 fn read_message(schema: SchemaRef, data: Vec<u8>) -> anyhow::Result<RecordBatch> {
-    let mut buf = &data[..];
-
-    // read the header size
-    let meta_size = buf.get_u32_le() as usize;
-    let mut meta_buffer = vec![0; meta_size];
-    std::io::Read::read_exact(&mut buf, &mut meta_buffer)?;
-
-    let message = arrow::ipc::root_as_message(&meta_buffer)
-        .map_err(|e| anyhow!("Unable to read IPC message: {:?}", e))?;
-
-    let arrow::ipc::MessageHeader::RecordBatch = message.header_type() else {
-        bail!("unexpected message type: {:?}", message.header_type());
-    };
-
-    let Some(batch) = message.header_as_record_batch() else {
-        bail!("Unable to read IPC message as record batch")
-    };
-
-    // read the block that makes up the record batch into a buffer
-    let mut batch_buf = MutableBuffer::from_len_zeroed(message.bodyLength() as usize);
-    std::io::Read::read_exact(&mut buf, &mut batch_buf)?;
-
-    Ok(read_record_batch(
-        &batch_buf.into(),
-        batch,
-        schema,
-        &HashMap::new(),
-        None,
-        &message.version(),
-    )?)
+    IpcMessage::read(&mut &data[..])?.record_batch(schema)
 }
 
 #[cfg(test)]
@@ -131,8 +131,12 @@ export type webhooks = Record<string, never>;
 
 export interface components {
   schemas: {
+    /** @description Messages are Arrow IPC streams, each holding one or more record batches */
+    ArrowIpcFormat: Record<string, never>;
     AvroFormat: {
       confluentSchemaRegistry?: boolean;
+      /** @description whether records are Debezium change envelopes (`before`, `after` and `op`) */
+      debezium?: boolean;
       intoUnstructuredJson?: boolean;
       rawDatums?: boolean;
       readerSchema?: string;
@@ -143,7 +147,18 @@ export interface components {
       fail: Record<string, never>;
     }, {
       drop: Record<string, never>;
+    }, {
+      dead_letter: {
+        table: string;
+      };
     }]>;
+    /**
+     * @description Change-data-capture envelopes other than Debezium's. Records in these envelopes are converted
+     * to Debezium's `before`/`after`/`op` form when they're deserialized, so tables using them are
+     * planned the same way as Debezium tables.
+     * @enum {string}
+     */
+    CdcEnvelope: "maxwell" | "canal";
     Checkpoint: {
       backend: string;
       /** Format: int32 */
@@ -238,6 +253,15 @@ export interface components {
     ConnectorCollection: {
       data: (components["schemas"]["Connector"])[];
     };
+    CsvFormat: Record<string, never>;
+    /** @description Messages are separated by an arbitrary sequence of bytes, like the ASCII record separator */
+    DelimitedFraming: {
+      delimiter: (number)[];
+      /** Format: int64 */
+      maxMessageLength?: number | null;
+    };
+    /** @enum {string} */
+    Endianness: "big" | "little";
     ErrorResp: {
       error: string;
     };
@@ -256,6 +280,10 @@ export interface components {
       protobuf: components["schemas"]["ProtobufFormat"];
     }, {
       parquet: components["schemas"]["ParquetFormat"];
+    }, {
+      csv: components["schemas"]["CsvFormat"];
+    }, {
+      arrow_ipc: components["schemas"]["ArrowIpcFormat"];
     }, {
       raw_string: components["schemas"]["RawStringFormat"];
     }, {
@@ -264,9 +292,15 @@ export interface components {
     Framing: {
       method: components["schemas"]["FramingMethod"];
     };
-    FramingMethod: {
+    FramingMethod: OneOf<[{
       newline: components["schemas"]["NewlineDelimitedFraming"];
-    };
+    }, {
+      octetCounting: components["schemas"]["OctetCountingFraming"];
+    }, {
+      lengthPrefixed: components["schemas"]["LengthPrefixedFraming"];
+    }, {
+      delimited: components["schemas"]["DelimitedFraming"];
+    }]>;
     GlobalUdf: {
       /** Format: int64 */
       createdAt: number;
@@ -320,6 +354,7 @@ export interface components {
       hasMore: boolean;
     };
     JsonFormat: {
+      cdcEnvelope?: components["schemas"]["CdcEnvelope"] | null;
       confluentSchemaRegistry?: boolean;
       debezium?: boolean;
       includeSchema?: boolean;
@@ -328,6 +363,17 @@ export interface components {
       timestampFormat?: components["schemas"]["TimestampFormat"];
       unstructured?: boolean;
     };
+    /**
+     * @description Each message is prefixed by its length in bytes, encoded as an unsigned integer of
+     * `prefix_bytes` bytes (1, 2, 4 or 8) with the configured byte order
+     */
+    LengthPrefixedFraming: {
+      endianness?: components["schemas"]["Endianness"];
+      /** Format: int64 */
+      maxMessageLength?: number | null;
+      /** Format: int32 */
+      prefixBytes: number;
+    };
     Metric: {
       /** Format: int64 */
       time: number;
@@ -344,6 +390,10 @@ export interface components {
       /** Format: int64 */
       maxLineLength?: number | null;
     };
+    OctetCountingFraming: {
+      /** Format: int64 */
+      maxMessageLength?: number | null;
+    };
     OperatorCheckpointGroup: {
       /** Format: int64 */
       bytes: number;
@@ -448,6 +498,8 @@ export interface components {
     };
     QueryValidationResult: {
       errors: (string)[];
+      /** @description The text plan of the pipeline, for queries prefixed with `EXPLAIN [VERBOSE]` */
+      explain?: string | null;
       graph?: components["schemas"]["PipelineGraph"] | null;
     };
     RawBytesFormat: Record<string, never>;
@@ -499,7 +551,16 @@ export interface components {
       message: string;
     };
     /** @enum {string} */
-    TimestampFormat: "rfc3339" | "unix_millis";
+    TimestampFormat: "rfc3339" | "unix_seconds" | "unix_millis" | "unix_micros" | "unix_nanos" | {
+      /**
+       * @description A strftime-style pattern, like `%Y-%m-%d %H:%M:%S%.f`. Timestamps that don't include an
+       * offset are in `timezone`, which defaults to UTC.
+       */
+      pattern: {
+        pattern: string;
+        timezone?: string | null;
+      };
+    };
     Udf: {
       definition: string;
       language?: components["schemas"]["UdfLanguage"];